@@ -75,6 +75,16 @@ fn benchmark(c: &mut Criterion, name: &str, (boxes, arena, handles): Inputs) {
         b.iter(|| sum_arena(black_box((&arena, &handles_sorted))));
     });
 
+    let handles_grouped = group_handles_by_type(&arena, &handles);
+
+    let _c = c.bench_function(&format!("{name} iterate arena grouped"), |b| {
+        b.iter(|| sum_arena(black_box((&arena, &handles_grouped))));
+    });
+
+    let _c = c.bench_function(&format!("{name} iterate arena dispatch"), |b| {
+        b.iter(|| sum_arena_dispatch(black_box(&arena)));
+    });
+
     let _c = c.bench_function(&format!("{name} clone   boxes"), |b| {
         b.iter(|| Clone::clone(black_box(&boxes)));
     });
@@ -95,6 +105,13 @@ fn sum_arena((arena, handles): (&Hato<dyn AsI32>, &[Handle])) -> i32 {
         .sum()
 }
 
+/// Visiting arenas of known types calls monomorphized code, without virtual dispatch.
+fn sum_arena_dispatch(arena: &Hato<dyn AsI32>) -> i32 {
+    let mut sum = 0;
+    hato::for_each_dispatch!(arena, [u8, i8], |x| sum += x.as_i32());
+    sum
+}
+
 /// Sorting helps with the jump target prediction and cache.
 fn sort_handles_by_type_and_offset(handles: &[Handle]) -> Vec<Handle> {
     let mut handles = handles.to_vec();
@@ -102,6 +119,12 @@ fn sort_handles_by_type_and_offset(handles: &[Handle]) -> Vec<Handle> {
     handles
 }
 
+/// Grouping by type only keeps most of the benefits, in linear time.
+fn group_handles_by_type(arena: &Hato<dyn AsI32>, handles: &[Handle]) -> Vec<Handle> {
+    let buckets = arena.group_handles_by_type(handles);
+    buckets.into_iter().flat_map(|(_, bucket)| bucket).collect()
+}
+
 // ? Wrap benchmark group declaration to fix missing documentation lint
 mod groups {
     criterion::criterion_group!(benches, super::generate_then_benchmark);
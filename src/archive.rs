@@ -0,0 +1,364 @@
+use alloc::borrow::ToOwned;
+use alloc::string::String;
+use alloc::vec::Vec;
+
+use core::ptr::{DynMetadata, Pointee};
+
+use aligned_vec::AVec;
+use serde::{Deserialize, Serialize};
+
+use crate::snapshot::{digest, resolve, Crc32, Restored};
+use crate::{Arena, Hato, Index, Kind, LiveSlots, Options, SnapshotError, Storage, Types};
+
+/// Serializable copy of the buffers of a [`Hato`], restored by copying each arena at once.
+///
+/// Where [`HatoSnapshot`](crate::HatoSnapshot) gathers live elements one by one and lays them
+/// out again on restore, archives keep the byte buffer of each arena whole, free slots and
+/// padding included, along with its free list. Restoring copies each buffer in one go,
+/// without going through insertions, so that large collections load in the time of a copy.
+/// Types are recorded by name as in snapshots, and mapped back to the virtual tables of the
+/// running build on restore. Free slots are handed out in the same order as in the archived
+/// collection, tombstones included. Arenas of oversized elements, see
+/// [`Hato::with_spill_threshold`], still get an allocation per element.
+///
+/// Only elements are captured: tag bytes, names and lists start out empty after a restore.
+///
+/// ```rust
+/// let mut arena = hato::Hato::<dyn core::fmt::Debug>::default();
+///
+/// let xs = (0..1000_u32).map(|i| arena.push(i)).collect::<Vec<_>>();
+/// arena.remove(xs[3]);
+///
+/// let types = hato::Types::default().register::<u32>();
+/// let archive = arena.archive(&types).unwrap();
+///
+/// let mut restored = hato::Hato::<dyn core::fmt::Debug>::default();
+/// unsafe { archive.restore(&mut restored, &types) }.unwrap();
+///
+/// assert!(!restored.contains(xs[3]));
+/// assert_eq!(format!("{:?}", unsafe { restored.get(xs[999]) }), "999");
+/// ```
+#[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+#[allow(clippy::unsafe_derive_deserialize)] // Restores are unsafe whatever the source of archives
+pub struct HatoArchive {
+    version: u32,
+    arenas: Vec<ArenaArchive>,
+
+    /// Checksum of the checksums of arenas, catching arenas lost or swapped along the way.
+    digest: u32,
+}
+
+/// Buffer of a single arena, along with the bookkeeping of its slots.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+struct ArenaArchive {
+    /// Names of the types admitted by the arena, starting with the one it was created for.
+    types: Vec<String>,
+
+    /// Distance between the offsets of consecutive slots, which handles depend on.
+    stride: usize,
+
+    /// Size of the elements, shared by all types of the arena.
+    size: usize,
+
+    /// Whether the arena is shared across types, see [`Hato::with_size_classes`].
+    shared: bool,
+
+    /// Whether elements each have their own allocation, see [`Hato::with_spill_threshold`].
+    spill: bool,
+
+    /// Whether each slot holds a live element.
+    occupied: Vec<bool>,
+
+    /// Position of the type of each slot in `types`, for shared arenas only.
+    kinds: Vec<usize>,
+
+    /// Offsets of free slots, the next one handed out last.
+    free: Vec<Index>,
+
+    /// Byte buffer of the arena, or elements back to back for arenas of oversized ones.
+    bytes: Vec<u8>,
+
+    /// Checksum of all of the above.
+    checksum: u32,
+}
+
+impl<Trait: ?Sized + Pointee<Metadata = DynMetadata<Trait>>, S: Storage> Hato<Trait, S> {
+    /// Copy the buffers of all arenas into a [`HatoArchive`].
+    ///
+    /// Returns `None` if an arena admits a type missing from `types`.
+    #[inline]
+    #[must_use]
+    pub fn archive(&self, types: &Types<Trait>) -> Option<HatoArchive> {
+        let arenas = self.arenas.iter().map(|arena| archive_arena(arena, types));
+        let arenas = arenas.collect::<Option<Vec<_>>>()?;
+
+        Some(HatoArchive {
+            version: types.version(),
+            digest: digest(arenas.iter().map(|arena| arena.checksum)),
+            arenas,
+        })
+    }
+}
+
+/// Copy the buffer of `arena`, or `None` if it admits a type missing from `types`.
+#[inline]
+fn archive_arena<Trait, S>(arena: &Arena<Trait, S>, types: &Types<Trait>) -> Option<ArenaArchive>
+where
+    Trait: ?Sized + Pointee<Metadata = DynMetadata<Trait>>,
+    S: Storage,
+{
+    let names = arena
+        .types
+        .iter()
+        .map(|(type_id, _)| types.name_of(*type_id).map(ToOwned::to_owned));
+    let names = names.collect::<Option<Vec<_>>>()?;
+
+    let position = |kind: &Kind<Trait>| arena.types.iter().position(|k| k.0 == kind.0);
+    let kinds = arena.kinds.iter().map(position).collect::<Option<_>>()?;
+
+    let size = arena.vtable.size_of();
+
+    let bytes = if arena.spill {
+        // Removed oversized elements have no allocation left, and stand as zeroes
+        let element = |slot: &AVec<u8>| -> Vec<u8> {
+            if slot.is_empty() {
+                alloc::vec![0; size]
+            } else {
+                slot.to_vec()
+            }
+        };
+
+        arena.spilled.iter().flat_map(element).collect()
+    } else {
+        // ! SAFETY: Buffer holds initialized bytes up to its length
+        unsafe { core::slice::from_raw_parts(arena.bytes.as_ptr(), arena.bytes.len()) }.to_vec()
+    };
+
+    let mut archive = ArenaArchive {
+        types: names,
+        stride: arena.stride,
+        size,
+        shared: arena.shared,
+        spill: arena.spill,
+        occupied: arena.occupied.clone(),
+        kinds,
+        free: arena.free_list().collect(),
+        bytes,
+        checksum: 0,
+    };
+
+    archive.checksum = archive.checksum_of();
+
+    Some(archive)
+}
+
+impl HatoArchive {
+    /// Version of the registry the archive was taken with, see [`Types::with_version`].
+    #[inline]
+    #[must_use]
+    pub const fn version(&self) -> u32 {
+        self.version
+    }
+
+    /// Recreate the archived arenas in `hato`, copying the buffer of each one at once.
+    ///
+    /// Elements end up at the same slots, so handles to the archived collection stay valid.
+    /// Types missing from `types` fail the restore even with [`Types::skip_unknown`],
+    /// as buffers are copied whole.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if `hato` is not empty, if the archive does not
+    /// match `types` and the layout of `hato`, or if it got corrupted since it was taken.
+    /// Arenas are checked before any is restored, leaving `hato` untouched on errors.
+    ///
+    /// # Safety
+    ///
+    /// The archive must come from a collection of the same types, as their bytes are copied
+    /// as is.
+    #[inline]
+    pub unsafe fn restore<Trait, S>(
+        &self,
+        hato: &mut Hato<Trait, S>,
+        types: &Types<Trait>,
+    ) -> Result<(), SnapshotError>
+    where
+        Trait: ?Sized + Pointee<Metadata = DynMetadata<Trait>>,
+        S: Storage,
+    {
+        if !hato.arenas.is_empty() {
+            return Err(SnapshotError::NotEmpty);
+        }
+
+        if digest(self.arenas.iter().map(|arena| arena.checksum)) != self.digest {
+            return Err(SnapshotError::Digest);
+        }
+
+        let arenas = self.arenas.iter().enumerate();
+        let arenas = arenas.map(|(index, archive)| archive.restore(index, types, hato.options));
+
+        for (arena, kinds) in arenas.collect::<Result<Vec<_>, _>>()? {
+            hato.append_restored(arena, &kinds);
+        }
+
+        Ok(())
+    }
+}
+
+impl ArenaArchive {
+    /// Rebuild the arena at `index` from its buffer, once checked, along with its kinds.
+    #[inline]
+    fn restore<Trait, S>(
+        &self,
+        index: usize,
+        types: &Types<Trait>,
+        options: Options,
+    ) -> Result<Restored<Trait, S>, SnapshotError>
+    where
+        Trait: ?Sized + Pointee<Metadata = DynMetadata<Trait>>,
+        S: Storage,
+    {
+        let corrupt = || SnapshotError::Corrupt {
+            arena: index,
+            types: self.types.clone(),
+        };
+
+        if !self.is_consistent() || self.checksum_of() != self.checksum {
+            return Err(corrupt());
+        }
+
+        let kinds = resolve(index, &self.types, types)?;
+
+        // Buffers are copied whole, so elements of unknown types cannot be left out
+        let kinds = kinds.into_iter().enumerate().map(|(position, kind)| {
+            kind.ok_or_else(|| SnapshotError::UnknownType {
+                arena: index,
+                name: self.types[position].clone(),
+            })
+        });
+
+        let kinds = kinds.collect::<Result<Vec<_>, _>>()?;
+
+        let (type_id, vtable) = *kinds.first().ok_or_else(corrupt)?;
+        let mut arena = Arena::<Trait, S>::empty(type_id, vtable, options);
+
+        for kind in &kinds[1..] {
+            arena.register(*kind);
+        }
+
+        // Offsets of elements, and thus handles, depend on the layout of their arena
+        let layout = (arena.stride, vtable.size_of(), arena.shared, arena.spill);
+
+        if layout != (self.stride, self.size, self.shared, self.spill) {
+            return Err(SnapshotError::Layout {
+                arena: index,
+                types: self.types.clone(),
+            });
+        }
+
+        if arena.spill {
+            let elements = self.bytes.chunks_exact(self.size.max(1));
+            let elements = elements.zip(&self.occupied).map(|(bytes, occupied)| {
+                if *occupied {
+                    AVec::from_slice(arena.bytes.align(), bytes)
+                } else {
+                    AVec::new(arena.bytes.align())
+                }
+            });
+
+            arena.spilled = elements.collect();
+        } else {
+            arena.bytes.extend_from_slice(&self.bytes);
+        }
+
+        arena.occupied.clone_from(&self.occupied);
+        arena.slots.clone_from(&self.free);
+        arena.live = self.occupied.iter().filter(|occupied| **occupied).count();
+        arena.tags.resize(self.occupied.len() * arena.tag_bytes, 0);
+
+        if self.shared {
+            arena.kinds = self.kinds.iter().map(|position| kinds[*position]).collect();
+        }
+
+        for slot in LiveSlots::new(&self.occupied) {
+            if self.shared {
+                *arena.count_mut(kinds[self.kinds[slot]]) += 1;
+            }
+
+            arena.stamp(slot);
+        }
+
+        // Catch accesses to free slots, in sanitized builds
+        arena.set_poisoned(0..self.occupied.len(), true);
+
+        Ok((arena, kinds.into_iter().map(Some).collect()))
+    }
+
+    /// Check whether the bookkeeping of slots agrees with itself and with the buffer.
+    #[inline]
+    fn is_consistent(&self) -> bool {
+        let slots = self.occupied.len();
+
+        // Zero-sized elements occupy no bytes, and oversized ones are stored without padding
+        let len = match self.size {
+            0 => 0,
+            size if self.spill => slots * size,
+            _ => slots * self.stride,
+        };
+
+        let free = self.free.iter().all(|offset| {
+            let (offset, stride) = (*offset as usize, self.stride.max(1));
+            let occupied = self.occupied.get(offset / stride).copied();
+
+            offset.is_multiple_of(stride) && occupied == Some(false)
+        });
+
+        let kinds = if self.shared {
+            let known = self.kinds.iter().all(|kind| *kind < self.types.len());
+            known && self.kinds.len() == slots
+        } else {
+            self.kinds.is_empty()
+        };
+
+        self.bytes.len() == len && free && kinds
+    }
+
+    /// Checksum of the contents of the arena.
+    #[inline]
+    fn checksum_of(&self) -> u32 {
+        let mut crc = Crc32::new();
+
+        crc.update_len(self.types.len());
+
+        for name in &self.types {
+            crc.update_len(name.len());
+            crc.update(name.as_bytes());
+        }
+
+        crc.update_len(self.stride);
+        crc.update_len(self.size);
+        crc.update_len(usize::from(self.shared));
+        crc.update_len(usize::from(self.spill));
+
+        crc.update_len(self.occupied.len());
+
+        for occupied in &self.occupied {
+            crc.update(&[u8::from(*occupied)]);
+        }
+
+        crc.update_len(self.kinds.len());
+
+        for kind in &self.kinds {
+            crc.update_len(*kind);
+        }
+
+        crc.update_len(self.free.len());
+
+        for offset in &self.free {
+            crc.update_len(*offset as usize);
+        }
+
+        crc.update(&self.bytes);
+        crc.finish()
+    }
+}
@@ -0,0 +1,272 @@
+use alloc::boxed::Box;
+use alloc::collections::{BTreeMap, BTreeSet};
+
+use core::fmt::{self, Debug, Formatter};
+use core::marker::Unsize;
+use core::mem::size_of;
+use core::ptr::{DynMetadata, Pointee};
+
+use unscrupulous::Unscrupulous;
+
+use crate::{Handle, Hato};
+
+/// Callback handed the handle and bytes of each element evicted from a [`HatoCache`].
+type Evictor = Box<dyn FnMut(CacheHandle, &[u8]) + Send + Sync>;
+
+/// Wrapper around [`Hato`] holding at most a budget of bytes, evicting cold elements past it.
+///
+/// Eviction follows the greedy-dual policy: each element is worth its cost, and elements
+/// that were not accessed for a while lose value relative to others. With equal costs,
+/// this amounts to evicting the least recently used element. Costs let elements that
+/// are expensive to rebuild stay longer. The budget counts the bytes of elements only,
+/// without padding nor bookkeeping.
+///
+/// Handles of evicted elements are detected, even once their slot holds another element.
+///
+/// ```rust
+/// let mut cache = hato::HatoCache::<dyn core::fmt::Debug>::new(8);
+///
+/// let x = cache.push(1_u32);
+/// let y = cache.push(2_u32);
+///
+/// // Accessing `x` makes `y` the coldest element, evicted to make room
+/// assert!(cache.get(x).is_some());
+/// let _ = cache.push(3_u32);
+///
+/// assert!(cache.get(y).is_none());
+/// assert_eq!(format!("{:?}", cache.get(x)), "Some(1)");
+/// ```
+pub struct HatoCache<Trait: ?Sized + Pointee<Metadata = DynMetadata<Trait>>> {
+    hato: Hato<Trait>,
+    budget: usize,
+    used: usize,
+    clock: u64,
+    tick: u64,
+    entries: BTreeMap<Handle, Entry>,
+    queue: BTreeSet<(u64, u64, Handle)>,
+    evictor: Option<Evictor>,
+}
+
+/// Bookkeeping of a cached element, ordered in the eviction queue by priority and last use.
+#[derive(Clone, Copy, Debug)]
+struct Entry {
+    stamp: u64,
+    cost: u64,
+    priority: u64,
+    used: u64,
+    size: usize,
+}
+
+impl<Trait: ?Sized + Pointee<Metadata = DynMetadata<Trait>>> Debug for HatoCache<Trait> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("HatoCache")
+            .field("len", &self.entries.len())
+            .field("budget", &self.budget)
+            .field("used", &self.used)
+            .field("evictor", &self.evictor.is_some())
+            .finish_non_exhaustive()
+    }
+}
+
+impl<Trait: ?Sized + Pointee<Metadata = DynMetadata<Trait>>> HatoCache<Trait> {
+    /// Create an empty cache, holding at most `budget` bytes worth of elements.
+    #[inline]
+    #[must_use]
+    pub fn new(budget: usize) -> Self {
+        Self {
+            hato: Hato::default(),
+            budget,
+            used: 0,
+            clock: 0,
+            tick: 0,
+            entries: BTreeMap::new(),
+            queue: BTreeSet::new(),
+            evictor: None,
+        }
+    }
+
+    /// Register `f` to be handed the [`CacheHandle`] and bytes of each evicted element.
+    ///
+    /// Removals through [`Self::remove`] are not reported.
+    #[inline]
+    pub fn on_evict(&mut self, f: impl FnMut(CacheHandle, &[u8]) + Send + Sync + 'static) {
+        self.evictor = Some(Box::new(f));
+    }
+
+    /// Underlying collection, to traverse cached elements without affecting eviction.
+    #[inline]
+    #[must_use]
+    pub const fn hato(&self) -> &Hato<Trait> {
+        &self.hato
+    }
+
+    /// Number of cached elements.
+    #[inline]
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Check whether the cache holds no element.
+    #[inline]
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Bytes of cached elements, at most the budget unless a single element exceeds it.
+    #[inline]
+    #[must_use]
+    pub const fn used_bytes(&self) -> usize {
+        self.used
+    }
+
+    /// Change the budget to `budget` bytes, evicting cold elements past it.
+    #[inline]
+    pub fn set_budget(&mut self, budget: usize) {
+        self.budget = budget;
+        self.evict_past(0);
+    }
+
+    /// Insert `x` with a cost of one, evicting cold elements to stay within the budget.
+    ///
+    /// # Panics
+    ///
+    /// This function will panic if the number of arenas overflows the index type.
+    #[inline]
+    pub fn push<T: Unsize<Trait> + Unscrupulous>(&mut self, x: T) -> CacheHandle {
+        self.push_with_cost(x, 1)
+    }
+
+    /// Insert `x`, worth `cost` in the eviction policy, evicting cold elements to make room.
+    ///
+    /// Elements larger than the whole budget evict all others, and are still inserted.
+    ///
+    /// # Panics
+    ///
+    /// This function will panic if the number of arenas overflows the index type.
+    #[inline]
+    pub fn push_with_cost<T: Unsize<Trait> + Unscrupulous>(
+        &mut self,
+        x: T,
+        cost: u64,
+    ) -> CacheHandle {
+        let size = size_of::<T>();
+        self.evict_past(size);
+
+        let handle = self.hato.push(x);
+        let stamp = self.next_tick();
+
+        let entry = Entry {
+            stamp,
+            cost,
+            priority: self.clock.saturating_add(cost),
+            used: stamp,
+            size,
+        };
+
+        let _ = self.queue.insert((entry.priority, entry.used, handle));
+        let _ = self.entries.insert(handle, entry);
+        self.used += size;
+
+        CacheHandle { handle, stamp }
+    }
+
+    /// Retrieve the element identified by `handle` and mark it as used, unless it was evicted.
+    #[inline]
+    pub fn get(&mut self, handle: CacheHandle) -> Option<&Trait> {
+        let (tick, clock) = (self.next_tick(), self.clock);
+        let entry = self.entry_mut(handle)?;
+
+        let old = (entry.priority, entry.used, handle.handle);
+        (entry.priority, entry.used) = (clock.saturating_add(entry.cost), tick);
+        let new = (entry.priority, entry.used, handle.handle);
+
+        let _ = self.queue.remove(&old);
+        let _ = self.queue.insert(new);
+
+        // ! SAFETY: Handle identifies a live element of the cached collection
+        Some(unsafe { self.hato.get(handle.handle) })
+    }
+
+    /// Retrieve the element identified by `handle` without marking it as used.
+    #[inline]
+    #[must_use]
+    pub fn peek(&self, handle: CacheHandle) -> Option<&Trait> {
+        let entry = self.entries.get(&handle.handle)?;
+
+        // ! SAFETY: Handle identifies a live element of the cached collection
+        (entry.stamp == handle.stamp).then(|| unsafe { self.hato.get(handle.handle) })
+    }
+
+    /// Remove the element identified by `handle`, returning whether it was still cached.
+    #[inline]
+    pub fn remove(&mut self, handle: CacheHandle) -> bool {
+        let Some(entry) = self.entry_mut(handle).copied() else {
+            return false;
+        };
+
+        self.forget(handle.handle, entry);
+        true
+    }
+
+    /// Evict elements, coldest first, until `size` more bytes fit within the budget.
+    #[inline]
+    fn evict_past(&mut self, size: usize) {
+        while self.used + size > self.budget {
+            let Some((priority, _, handle)) = self.queue.first().copied() else {
+                return;
+            };
+
+            // Age all remaining elements, relative to the value of the evicted one
+            self.clock = priority;
+
+            let entry = self.entries[&handle];
+
+            if let Some(f) = &mut self.evictor {
+                let evicted = CacheHandle {
+                    handle,
+                    stamp: entry.stamp,
+                };
+
+                // ! SAFETY: Handle identifies a live element of the cached collection
+                f(evicted, unsafe { self.hato.element_bytes(handle) });
+            }
+
+            self.forget(handle, entry);
+        }
+    }
+
+    /// Remove the live element `handle` from the collection and the bookkeeping.
+    #[inline]
+    fn forget(&mut self, handle: Handle, entry: Entry) {
+        let _ = self.queue.remove(&(entry.priority, entry.used, handle));
+        let _ = self.entries.remove(&handle);
+        self.used -= entry.size;
+
+        self.hato.remove(handle);
+    }
+
+    /// Bookkeeping of the element identified by `handle`, unless it was evicted.
+    #[inline]
+    fn entry_mut(&mut self, handle: CacheHandle) -> Option<&mut Entry> {
+        let entry = self.entries.get_mut(&handle.handle)?;
+
+        // Slots of evicted elements may hold newer ones, inserted at a later tick
+        (entry.stamp == handle.stamp).then_some(entry)
+    }
+
+    /// Advance the logical clock of insertions and accesses.
+    #[inline]
+    const fn next_tick(&mut self) -> u64 {
+        self.tick += 1;
+        self.tick
+    }
+}
+
+/// Index to access an element stored in a [`HatoCache`], detecting its eviction.
+#[derive(Clone, Copy, Debug, Eq, Ord, PartialEq, PartialOrd)]
+pub struct CacheHandle {
+    handle: Handle,
+    stamp: u64,
+}
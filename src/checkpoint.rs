@@ -0,0 +1,83 @@
+use alloc::vec::Vec;
+
+use core::ptr::{DynMetadata, Pointee};
+
+use crate::{index_of, Arena, Counted, Handle, Hato, LiveSlots, Storage};
+
+/// Number of slots of every arena at some point, to traverse elements appended since.
+///
+/// Obtained from [`Hato::checkpoint`], and consumed by [`Hato::iter_since`].
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct Checkpoint {
+    ends: Vec<usize>,
+}
+
+impl<Trait: ?Sized + Pointee<Metadata = DynMetadata<Trait>>, S: Storage> Hato<Trait, S> {
+    /// Mark the current end of every arena, for [`Self::iter_since`] to resume from.
+    #[inline]
+    #[must_use]
+    pub fn checkpoint(&self) -> Checkpoint {
+        Checkpoint {
+            ends: self
+                .arenas
+                .iter()
+                .map(|arena| arena.occupied.len())
+                .collect(),
+        }
+    }
+
+    /// Iterate over live elements appended to arenas since `checkpoint`, with their handle.
+    ///
+    /// Only slots past the end of each arena at the checkpoint are scanned, so incremental
+    /// pipelines can process new elements without traversing the whole collection again.
+    /// Elements taking the slot of one removed before the checkpoint are not yielded:
+    /// pair this with [`Self::with_tombstones`] for insertions to always append.
+    /// Operations moving elements, like compaction, leave checkpoints outdated.
+    ///
+    /// ```rust
+    /// let mut arena = hato::Hato::<dyn core::fmt::Debug>::default().with_tombstones();
+    ///
+    /// let x = arena.push(1_u8);
+    /// let checkpoint = arena.checkpoint();
+    ///
+    /// arena.remove(x);
+    /// let y = arena.push(2_u8);
+    /// let z = arena.push(3_u16);
+    ///
+    /// let new = arena.iter_since(&checkpoint).map(|(handle, _)| handle);
+    /// assert_eq!(new.collect::<Vec<_>>(), [y, z]);
+    /// ```
+    #[inline]
+    #[must_use]
+    pub fn iter_since<'a>(
+        &'a self,
+        checkpoint: &Checkpoint,
+    ) -> impl DoubleEndedIterator<Item = (Handle, &'a Trait)> + ExactSizeIterator + 'a {
+        // Arenas created since the checkpoint are scanned from their start
+        let ends = checkpoint.ends.clone();
+
+        // Slots appended since the checkpoint, as only some of them are live
+        let appended = move |index: usize, arena: &'a Arena<Trait, S>| {
+            let start = ends.get(index).copied().unwrap_or(0);
+            &arena.occupied[start.min(arena.occupied.len())..]
+        };
+
+        let arenas = self.arenas.iter().enumerate();
+        let len = arenas.clone().map(|(index, arena)| appended(index, arena));
+        let len = len.map(|occupied| LiveSlots::new(occupied).len()).sum();
+
+        let elements = arenas.flat_map(move |(index, arena)| {
+            let occupied = appended(index, arena);
+            let start = arena.occupied.len() - occupied.len();
+
+            let index = index_of(index);
+
+            LiveSlots::new(occupied).map(move |slot| {
+                let offset = arena.offset(start + slot);
+                (Handle { index, offset }, arena.get(offset))
+            })
+        });
+
+        Counted::new(elements, len)
+    }
+}
@@ -0,0 +1,118 @@
+use alloc::vec::Vec;
+
+use core::marker::Unsize;
+use core::mem::needs_drop;
+use core::ptr::{DynMetadata, Pointee};
+
+use unscrupulous::{as_slice_of_bytes, Unscrupulous};
+
+use crate::{get_metadata_of_ref, Handle, Hato, Kind, Storage};
+
+/// Queue of insertions and removals, recorded away from the collection and applied in one batch.
+///
+/// Systems can request new elements or removals while the collection is borrowed elsewhere,
+/// as in entity component systems spawning and despawning entities. Inserted values are
+/// copied into the queue right away, so they need not outlive the recording.
+///
+/// ```rust
+/// let mut arena = hato::Hato::<dyn core::fmt::Debug>::default();
+/// let x = arena.push(1_u8);
+///
+/// let mut commands = hato::HatoCommands::default();
+///
+/// for y in arena.handles() {
+///     let _ = commands.push(2_u16);
+///     commands.remove(y);
+/// }
+///
+/// let handles = commands.apply(&mut arena);
+///
+/// assert!(!arena.contains(x));
+/// assert_eq!(format!("{:?}", unsafe { arena.get(handles[0]) }), "2");
+/// ```
+#[derive(Debug)]
+pub struct HatoCommands<Trait: ?Sized + Pointee<Metadata = DynMetadata<Trait>>> {
+    commands: Vec<Command<Trait>>,
+    pushes: usize,
+}
+
+/// Modification recorded by [`HatoCommands`].
+#[derive(Debug)]
+enum Command<Trait: ?Sized + Pointee<Metadata = DynMetadata<Trait>>> {
+    Push(Vec<u8>, Kind<Trait>),
+    Remove(Handle),
+}
+
+impl<Trait: ?Sized + Pointee<Metadata = DynMetadata<Trait>>> Default for HatoCommands<Trait> {
+    fn default() -> Self {
+        Self {
+            commands: Vec::new(),
+            pushes: 0,
+        }
+    }
+}
+
+impl<Trait: ?Sized + Pointee<Metadata = DynMetadata<Trait>>> HatoCommands<Trait> {
+    /// Queue the insertion of `x`, returning the position of its handle in [`Self::apply`].
+    #[inline]
+    pub fn push<T: Unsize<Trait> + Unscrupulous>(&mut self, x: T) -> usize {
+        // Reject types whose destructor would silently be skipped
+        const { assert!(!needs_drop::<T>(), "destructors of elements never run") }
+
+        let kind = (typeid::of::<T>(), get_metadata_of_ref(&x));
+        let bytes = as_slice_of_bytes(&x).to_vec();
+
+        // Value now lives in the queue, as bytes
+        core::mem::forget(x);
+
+        self.commands.push(Command::Push(bytes, kind));
+        self.pushes += 1;
+
+        self.pushes - 1
+    }
+
+    /// Queue the removal of the element identified by `handle`, see [`Hato::try_remove`].
+    #[inline]
+    pub fn remove(&mut self, handle: Handle) {
+        self.commands.push(Command::Remove(handle));
+    }
+
+    /// Number of queued modifications.
+    #[inline]
+    #[must_use]
+    pub const fn len(&self) -> usize {
+        self.commands.len()
+    }
+
+    /// Check whether no modification is queued.
+    #[inline]
+    #[must_use]
+    pub const fn is_empty(&self) -> bool {
+        self.commands.is_empty()
+    }
+
+    /// Apply queued modifications to `hato` in order, returning the handles of insertions.
+    ///
+    /// The queue is left empty, ready to record the next batch.
+    ///
+    /// # Panics
+    ///
+    /// This function will panic if the number of arenas overflows the index type,
+    /// or if the quota of an inserted type is exhausted, see [`Hato::set_quota`].
+    #[inline]
+    pub fn apply<S: Storage>(&mut self, hato: &mut Hato<Trait, S>) -> Vec<Handle> {
+        let mut handles = Vec::with_capacity(self.pushes);
+
+        for command in self.commands.drain(..) {
+            match command {
+                Command::Push(bytes, kind) => handles.push(hato.push_kind(&bytes, kind)),
+                Command::Remove(handle) => {
+                    let _ = hato.try_remove(handle);
+                }
+            }
+        }
+
+        self.pushes = 0;
+        handles
+    }
+}
@@ -0,0 +1,387 @@
+use alloc::boxed::Box;
+use alloc::vec::Vec;
+
+use core::fmt::{self, Debug, Formatter};
+use core::ptr::{DynMetadata, Pointee};
+
+use crate::{index_of, Arena, Handle, Hato, Index, Remap, Storage};
+
+/// Callback notified of the handles of elements moved by automatic compaction.
+type Observer = Box<dyn FnMut(&Remap) + Send + Sync>;
+
+/// Policy to compact fragmented arenas, along with the hook reporting elements that moved.
+#[derive(Default)]
+pub struct Compaction {
+    percent: Option<u8>,
+    observer: Option<Observer>,
+    forwarding: bool,
+    forwards: Remap,
+
+    /// Whether a removal left an arena past the threshold since the last maintenance.
+    pending: bool,
+}
+
+impl Clone for Compaction {
+    fn clone(&self) -> Self {
+        // Observers track handles of the original collection, so clones start without one
+        Self {
+            percent: self.percent,
+            observer: None,
+            forwarding: self.forwarding,
+            forwards: self.forwards.clone(),
+            pending: self.pending,
+        }
+    }
+}
+
+impl Compaction {
+    /// Same policy, for a copy of the collection whose elements have new handles.
+    #[inline]
+    pub fn policy(&self) -> Self {
+        Self {
+            forwards: Remap::default(),
+            ..self.clone()
+        }
+    }
+
+    /// Forwarding entries of stale handles, unless there are none.
+    #[inline]
+    pub fn forwards(&self) -> Option<&Remap> {
+        (!self.forwards.is_empty()).then_some(&self.forwards)
+    }
+}
+
+impl Debug for Compaction {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Compaction")
+            .field("percent", &self.percent)
+            .field("observer", &self.observer.is_some())
+            .field("forwarding", &self.forwarding)
+            .field("forwards", &self.forwards)
+            .field("pending", &self.pending)
+            .finish()
+    }
+}
+
+impl<Trait: ?Sized + Pointee<Metadata = DynMetadata<Trait>>, S: Storage> Hato<Trait, S> {
+    /// Compact arenas once more than `percent` of their bytes lie in free slots.
+    ///
+    /// Removals that leave an arena past the threshold schedule its compaction for the next
+    /// [`Self::push`], or [`Self::maintain`], which move elements of fragmented arenas
+    /// into the free slots at their front, then release the tail. Elements that moved
+    /// are reported to the hook registered with [`Self::on_remap`], so that handles held
+    /// elsewhere can be updated. Arenas with tombstones are never compacted, since moving
+    /// elements would make stale handles alias them. Compacted elements should not belong
+    /// to any [`HandleList`](crate::HandleList).
+    ///
+    /// ```rust
+    /// use std::sync::{Arc, Mutex};
+    ///
+    /// let mut arena = hato::Hato::<dyn core::fmt::Debug>::default().with_compaction_threshold(30);
+    ///
+    /// let remaps = Arc::new(Mutex::new(hato::Remap::default()));
+    /// let observed = Arc::clone(&remaps);
+    /// arena.on_remap(move |remap| *observed.lock().unwrap() = remap.clone());
+    ///
+    /// let xs = (0..10_u32).map(|i| arena.push(i)).collect::<Vec<_>>();
+    ///
+    /// for x in &xs[..4] {
+    ///     arena.remove(*x);
+    /// }
+    ///
+    /// arena.maintain();
+    ///
+    /// let x = remaps.lock().unwrap().resolve(xs[9]);
+    /// assert_eq!(format!("{:?}", unsafe { arena.get(x) }), "9");
+    /// ```
+    #[inline]
+    #[must_use]
+    pub const fn with_compaction_threshold(mut self, percent: u8) -> Self {
+        self.compaction.percent = Some(percent);
+        self
+    }
+
+    /// Register `observer` to be notified of elements moved by automatic compaction.
+    ///
+    /// Replaces any previous observer. Clones of the collection start without one.
+    #[inline]
+    pub fn on_remap(&mut self, observer: impl FnMut(&Remap) + Send + Sync + 'static) {
+        self.compaction.observer = Some(Box::new(observer));
+    }
+
+    /// Leave forwarding entries behind compacted elements, so that stale handles still work.
+    ///
+    /// Methods of the collection taking handles resolve them through the entries first,
+    /// so compaction no longer breaks code that cannot update every stored handle at once.
+    /// Slots that elements moved out of are not reused until [`Self::flush_forwarding`],
+    /// which callers should invoke once they have rewritten their handles.
+    ///
+    /// ```rust
+    /// let mut arena = hato::Hato::<dyn core::fmt::Debug>::default()
+    ///     .with_compaction_threshold(30)
+    ///     .with_forwarding();
+    ///
+    /// let xs = (0..10_u32).map(|i| arena.push(i)).collect::<Vec<_>>();
+    ///
+    /// for x in &xs[..4] {
+    ///     arena.remove(*x);
+    /// }
+    ///
+    /// arena.maintain();
+    ///
+    /// // The stale handle still reaches the element, at its new location
+    /// assert_eq!(format!("{:?}", unsafe { arena.get(xs[9]) }), "9");
+    ///
+    /// arena.flush_forwarding();
+    /// assert!(!arena.contains(xs[9]));
+    /// ```
+    #[inline]
+    #[must_use]
+    pub const fn with_forwarding(mut self) -> Self {
+        self.compaction.forwarding = true;
+        self
+    }
+
+    /// Drop all forwarding entries, making slots that elements moved out of available again.
+    ///
+    /// Handles of elements moved by compaction must have been updated beforehand.
+    #[inline]
+    pub fn flush_forwarding(&mut self) {
+        if self.compaction.forwards.is_empty() {
+            return;
+        }
+
+        self.compaction.forwards = Remap::default();
+
+        for arena in self.arenas.iter_mut().filter(|arena| arena.pinned > 0) {
+            arena.pinned = 0;
+
+            // Slots that elements moved out of are missing from the free list, as tombstones
+            arena.reclaim_tombstones();
+        }
+    }
+
+    /// Current handle of the element `handle` identified, following forwarding entries.
+    #[inline]
+    pub(crate) fn forward(&self, handle: Handle) -> Handle {
+        if !self.forwards_handles() {
+            return handle;
+        }
+
+        self.compaction.forwards.resolve(handle)
+    }
+
+    /// Note a removal from arena `index`, scheduling compaction if it crossed the threshold.
+    #[inline]
+    pub(crate) fn removed_from(&mut self, index: usize) {
+        if let Some(percent) = self.compaction.percent {
+            self.compaction.pending |= self.arenas[index].fragmented(percent);
+        }
+    }
+
+    /// Compact fragmented arenas, if a removal left one past the threshold since the last time.
+    #[inline]
+    pub(crate) fn maintain_if_pending(&mut self) {
+        if self.compaction.pending {
+            self.maintain();
+        }
+    }
+
+    /// Forwarding entries of stale handles, unless there are none.
+    #[inline]
+    pub(crate) fn forwards(&self) -> Option<&Remap> {
+        self.compaction.forwards()
+    }
+
+    /// Move forwarding entries along with their arenas, `f` giving the new index of each one.
+    ///
+    /// Returns the entries as they were, to the handle their element has from now on,
+    /// so that callers rewriting handles with the moves of `moved` also rewrite stale ones.
+    #[inline]
+    pub(crate) fn reindex_forwards(&mut self, moved: &Remap, f: impl Fn(Index) -> Index) -> Remap {
+        let stale = self.compaction.forwards.moved_by(moved);
+        self.compaction.forwards = self.compaction.forwards.reindex(f);
+
+        stale
+    }
+
+    /// Drop forwarding entries to elements that `moved` took out of the collection.
+    ///
+    /// Returns the dropped entries, to the handle their element has in its new collection.
+    #[inline]
+    pub(crate) fn forwards_moved_out(&mut self, moved: &Remap) -> Remap {
+        let stale = self.compaction.forwards.moved_by(moved);
+        self.compaction
+            .forwards
+            .retain(|_, new| moved.get(new).is_none());
+
+        stale
+    }
+
+    /// Drop forwarding entries to removed elements, so that their stale handles stay dangling.
+    #[inline]
+    pub(crate) fn forget_removed_forwards(&mut self) {
+        let arenas = &self.arenas;
+        let live = |handle: Handle| arenas[handle.index as usize].contains(handle.offset);
+
+        self.compaction.forwards.retain(|_, new| live(new));
+    }
+
+    /// Check whether some stale handles are forwarded to the new location of their element.
+    #[inline]
+    pub(crate) fn forwards_handles(&self) -> bool {
+        !self.compaction.forwards.is_empty()
+    }
+
+    /// Compact arenas past the threshold set by [`Self::with_compaction_threshold`], if any.
+    ///
+    /// Elements that moved are reported to the observer registered with [`Self::on_remap`].
+    #[inline]
+    pub fn maintain(&mut self) {
+        let Some(percent) = self.compaction.percent else {
+            return;
+        };
+
+        self.compaction.pending = false;
+
+        let mut moves = Vec::new();
+        let Compaction {
+            forwarding,
+            forwards,
+            ..
+        } = &self.compaction;
+
+        for (index, arena) in self.arenas.iter_mut().enumerate() {
+            if !arena.fragmented(percent) {
+                continue;
+            }
+
+            let index = index_of(index);
+
+            // Keep slots that stale handles are forwarded from out of reach
+            let forwarded = |offset| forwards.get(Handle { index, offset }).is_some();
+
+            for (old, new) in arena.compact(*forwarding, forwarded) {
+                let old = Handle { index, offset: old };
+                let new = Handle { index, offset: new };
+
+                self.shadow.remove(old);
+                self.shadow.insert(new, || arena.element(new.offset));
+
+                moves.push((old, new));
+            }
+        }
+
+        if moves.is_empty() {
+            return;
+        }
+
+        let remap = moves.into_iter().collect();
+        self.names = self.names.remap(&remap);
+
+        if self.compaction.forwarding {
+            self.compaction.forwards = self.compaction.forwards.chain(&remap);
+        }
+
+        if let Some(observer) = &mut self.compaction.observer {
+            observer(&remap);
+        }
+    }
+}
+
+impl<Trait: ?Sized + Pointee<Metadata = DynMetadata<Trait>>, S: Storage> Arena<Trait, S> {
+    /// Check whether more than `percent` of the bytes of the arena lie in free slots.
+    #[inline]
+    fn fragmented(&self, percent: u8) -> bool {
+        // Without tombstones, all free slots but trailing ones are in the free list, along with
+        // offsets of slots cut off since, which never outnumber free slots
+        let free = self.slots.len().min(self.occupied.len() - self.live);
+        !self.tombstones && free * 100 > usize::from(percent) * self.occupied.len()
+    }
+
+    /// Move live elements from the back of the arena into free slots at the front.
+    ///
+    /// Returns the old and new offset of each element that moved. Free slots all end up
+    /// at the back, which is then released unless `pin` is set. Slots for which `forwarded`
+    /// returns `true` are never filled.
+    #[inline]
+    fn compact(&mut self, pin: bool, forwarded: impl Fn(Index) -> bool) -> Vec<(Index, Index)> {
+        let mut moves = Vec::new();
+        let (mut front, mut back) = (0, self.occupied.len());
+
+        loop {
+            // Find the first free slot, and the last live one
+            while front < back && (self.occupied[front] || forwarded(self.offset(front))) {
+                front += 1;
+            }
+
+            while back > front && !self.occupied[back - 1] {
+                back -= 1;
+            }
+
+            if front + 1 >= back {
+                break;
+            }
+
+            self.move_slot(back - 1, front);
+            moves.push((self.offset(back - 1), self.offset(front)));
+        }
+
+        // Free slots now all lie past the last live element, where the arena is cut
+        self.slots.clear();
+
+        if pin {
+            // Stale handles are forwarded from slots past the last live element, kept until flush
+            self.pinned = self.occupied.len();
+        }
+
+        self.truncate_free_tail();
+
+        moves
+    }
+
+    /// Move the live element of slot `from` into the free slot `to`, along with its sidecars.
+    #[inline]
+    fn move_slot(&mut self, from: usize, to: usize) {
+        self.set_poisoned(to..to + 1, false);
+
+        if self.spill {
+            self.spilled.swap(from, to);
+        } else {
+            let size = self.vtable.size_of();
+            let source = self.position(self.offset(from));
+            let target = self.position(self.offset(to));
+
+            // ! SAFETY: Both positions lie within the buffer, and slots of elements
+            // ! with a non-zero size never overlap
+            unsafe {
+                let ptr = self.bytes.as_mut_ptr();
+                core::ptr::copy_nonoverlapping(ptr.add(source), ptr.add(target), size);
+            }
+        }
+
+        self.occupied[to] = true;
+        self.occupied[from] = false;
+
+        if self.shared {
+            self.kinds[to] = self.kinds[from];
+        }
+
+        if self.aged {
+            self.ages[to] = self.ages[from];
+        }
+
+        // Let the element repair itself, from the slot it just left
+        let old = self.ptr(self.offset(from));
+        self.relocate(to, old);
+
+        // Carry tag bytes and list links over, leaving the old slot cleared
+        let (tag, start) = (self.tag_range(from), self.tag_range(to).start);
+        self.tags.copy_within(tag.clone(), start);
+        self.tags[tag].fill(0);
+
+        if from < self.links.len() {
+            self.links[to] = core::mem::take(&mut self.links[from]);
+        }
+    }
+}
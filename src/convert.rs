@@ -0,0 +1,181 @@
+use alloc::vec::Vec;
+
+use core::marker::Unsize;
+use core::ptr::{DynMetadata, Pointee};
+
+use unscrupulous::Unscrupulous;
+
+use crate::{get_metadata_of, Arena, Hato, Kind};
+
+/// Registry of types implementing both `Old` and `New`, to re-view a collection under `New`.
+///
+/// See [`Hato::convert`] for details.
+#[derive(Debug)]
+pub struct Conversion<Old, New>
+where
+    Old: ?Sized + Pointee<Metadata = DynMetadata<Old>>,
+    New: ?Sized + Pointee<Metadata = DynMetadata<New>>,
+{
+    vtables: Vec<(DynMetadata<Old>, DynMetadata<New>)>,
+}
+
+impl<Old, New> Default for Conversion<Old, New>
+where
+    Old: ?Sized + Pointee<Metadata = DynMetadata<Old>>,
+    New: ?Sized + Pointee<Metadata = DynMetadata<New>>,
+{
+    fn default() -> Self {
+        Self {
+            vtables: Vec::new(),
+        }
+    }
+}
+
+impl<Old, New> Conversion<Old, New>
+where
+    Old: ?Sized + Pointee<Metadata = DynMetadata<Old>>,
+    New: ?Sized + Pointee<Metadata = DynMetadata<New>>,
+{
+    /// Register type `T`, so that its elements can be viewed under `New`.
+    #[inline]
+    #[must_use]
+    pub fn register<T: Unsize<Old> + Unsize<New> + Unscrupulous>(mut self) -> Self {
+        let old = get_metadata_of::<T, Old>();
+        let new = get_metadata_of::<T, New>();
+
+        self.vtables.push((old, new));
+        self
+    }
+
+    /// Virtual table of `New` for the type whose virtual table of `Old` is `old`, if registered.
+    #[inline]
+    fn get(&self, old: DynMetadata<Old>) -> Option<DynMetadata<New>> {
+        self.vtables
+            .iter()
+            .find_map(|(o, n)| (*o == old).then_some(*n))
+    }
+
+    /// Virtual tables of `New` for all types of `arena`, if registered.
+    #[inline]
+    #[allow(clippy::type_complexity)] // Mirrors the sidecars of the arena
+    fn get_all(
+        &self,
+        arena: &Arena<Old>,
+    ) -> Option<(DynMetadata<New>, Vec<Kind<New>>, Vec<Kind<New>>)> {
+        let convert = |(type_id, vtable)| Some((type_id, self.get(vtable)?));
+
+        Some((
+            self.get(arena.vtable)?,
+            arena
+                .types
+                .iter()
+                .copied()
+                .map(convert)
+                .collect::<Option<_>>()?,
+            arena
+                .kinds
+                .iter()
+                .copied()
+                .map(convert)
+                .collect::<Option<_>>()?,
+        ))
+    }
+}
+
+impl<Trait: ?Sized + Pointee<Metadata = DynMetadata<Trait>>> Hato<Trait> {
+    /// Re-view the whole collection as trait objects of `New`, without copying any element.
+    ///
+    /// Only virtual tables are swapped, so all handles remain valid in the converted collection.
+    /// Fails and gives the collection back if some stored type was not registered.
+    ///
+    /// ```rust
+    /// use core::fmt::{Debug, Display};
+    ///
+    /// let mut arena = hato::Hato::<dyn Debug>::default();
+    /// let x = arena.push(4_u16);
+    ///
+    /// let conversion = hato::Conversion::<dyn Debug, dyn Display>::default().register::<u16>();
+    /// let Ok(arena) = arena.convert(&conversion) else {
+    ///     panic!("all types are registered");
+    /// };
+    ///
+    /// assert_eq!(unsafe { arena.get(x) }.to_string(), "4");
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// This function will return the collection unchanged if a type is missing from `conversion`.
+    #[inline]
+    #[allow(clippy::result_large_err)] // Collection is given back as is, rather than boxed
+    pub fn convert<New>(self, conversion: &Conversion<Trait, New>) -> Result<Hato<New>, Self>
+    where
+        New: ?Sized + Pointee<Metadata = DynMetadata<New>>,
+    {
+        // Check all types up front, so that failures leave the collection untouched
+        let Some(vtables) = self
+            .arenas
+            .iter()
+            .map(|arena| conversion.get_all(arena))
+            .collect::<Option<Vec<_>>>()
+        else {
+            return Err(self);
+        };
+
+        let arenas = self.arenas.into_iter().zip(vtables);
+
+        Ok(Hato {
+            arenas: arenas
+                .map(|(arena, (vtable, types, kinds))| arena.convert(vtable, types, kinds))
+                .collect(),
+            options: self.options,
+            names: self.names,
+            shadow: self.shadow,
+            compaction: self.compaction,
+            relocations: self.relocations,
+            quotas: self.quotas,
+            #[cfg(feature = "std")]
+            pool: self.pool,
+            #[cfg(feature = "std")]
+            deferred: self.deferred,
+        })
+    }
+}
+
+impl<Trait: ?Sized + Pointee<Metadata = DynMetadata<Trait>>> Arena<Trait> {
+    /// Move all storage to an arena of the same types, viewed through the tables of `New`.
+    #[inline]
+    fn convert<New>(
+        self,
+        vtable: DynMetadata<New>,
+        types: Vec<Kind<New>>,
+        kinds: Vec<Kind<New>>,
+    ) -> Arena<New>
+    where
+        New: ?Sized + Pointee<Metadata = DynMetadata<New>>,
+    {
+        Arena {
+            type_id: self.type_id,
+            vtable,
+            stride: self.stride,
+            exact: self.exact,
+            tombstones: self.tombstones,
+            bytes: self.bytes,
+            spill: self.spill,
+            spilled: self.spilled,
+            slots: self.slots,
+            occupied: self.occupied,
+            live: self.live,
+            links: self.links,
+            tag_bytes: self.tag_bytes,
+            tags: self.tags,
+            shared: self.shared,
+            types,
+            counts: self.counts,
+            kinds,
+            pinned: self.pinned,
+            relocations: self.relocations,
+            aged: self.aged,
+            ages: self.ages,
+        }
+    }
+}
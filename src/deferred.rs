@@ -0,0 +1,82 @@
+use std::sync::{Arc, Mutex, OnceLock, PoisonError};
+
+use core::ptr::{DynMetadata, Pointee};
+
+use crate::{Handle, Hato, Storage};
+
+/// Handles of elements to remove on the next [`Hato::apply_deferred`], shared with their guards.
+pub type Queue = Arc<Mutex<Vec<Handle>>>;
+
+/// Queue of a collection, only allocated once a removal is deferred or a guard is handed out.
+pub type Deferred = OnceLock<Queue>;
+
+impl<Trait: ?Sized + Pointee<Metadata = DynMetadata<Trait>>, S: Storage> Hato<Trait, S> {
+    /// Queue the removal of the element identified by `handle`, without touching storage.
+    ///
+    /// Only a shared reference is needed, so deaths can be flagged while iterating over
+    /// elements. Queued removals take effect on [`Self::apply_deferred`], at a safe point.
+    ///
+    /// ```rust
+    /// use core::ops::ControlFlow;
+    ///
+    /// let mut arena = hato::Hato::<dyn core::fmt::Debug>::default();
+    ///
+    /// let xs = (0..4_u8).map(|i| arena.push(i)).collect::<Vec<_>>();
+    ///
+    /// let _ = arena.try_for_each(|handle, x| {
+    ///     if format!("{x:?}") != "2" {
+    ///         arena.defer_remove(handle);
+    ///     }
+    ///
+    ///     ControlFlow::<()>::Continue(())
+    /// });
+    ///
+    /// assert!(arena.contains(xs[0]));
+    /// assert_eq!(arena.apply_deferred(), 3);
+    /// assert!(!arena.contains(xs[0]) && arena.contains(xs[2]));
+    /// ```
+    #[inline]
+    pub fn defer_remove(&self, handle: Handle) {
+        let mut deferred = self.queue().lock().unwrap_or_else(PoisonError::into_inner);
+        deferred.push(handle);
+    }
+
+    /// Remove all elements queued by [`Self::defer_remove`], returning how many were live.
+    ///
+    /// Handles queued several times count once. As with [`Self::try_remove`], handles of elements
+    /// removed since are skipped, unless their slot was handed to another element meanwhile.
+    #[inline]
+    pub fn apply_deferred(&mut self) -> usize {
+        // Collections never deferring a removal have nothing to apply
+        let Some(deferred) = self.deferred.get() else {
+            return 0;
+        };
+
+        let mut deferred = deferred.lock().unwrap_or_else(PoisonError::into_inner);
+        let mut handles = core::mem::take(&mut *deferred);
+        drop(deferred);
+
+        handles.sort_unstable();
+        handles.dedup();
+
+        handles
+            .into_iter()
+            .filter(|handle| self.try_remove(*handle))
+            .count()
+    }
+
+    /// Queue of deferred removals, allocated on first use.
+    #[inline]
+    pub(crate) fn queue(&self) -> &Queue {
+        self.deferred.get_or_init(Queue::default)
+    }
+}
+
+/// Copy a queue of deferred removals, along with its handles, for a clone of its collection.
+#[inline]
+pub fn clone_queue(deferred: &Deferred) -> Deferred {
+    deferred.get().map_or_else(Deferred::new, |queue| {
+        let handles = queue.lock().unwrap_or_else(PoisonError::into_inner);
+        Deferred::from(Arc::new(Mutex::new(handles.clone())))
+    })
+}
@@ -0,0 +1,175 @@
+use alloc::collections::BTreeSet;
+use alloc::vec::Vec;
+
+use core::any::TypeId;
+use core::ptr::{DynMetadata, Pointee};
+use core::sync::atomic::{AtomicU64, Ordering};
+
+use crate::{index_of, Arena, Handle, Hato, Storage};
+
+/// Sequence number of the latest insertion into an arena recording ages, in any collection.
+static SEQUENCE: AtomicU64 = AtomicU64::new(0);
+
+/// Leaked elements and free slots of a collection, as found by [`Hato::diagnose`].
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct Diagnostics {
+    /// Live elements absent from the handles known to the caller, in handle order.
+    pub orphans: Vec<Orphan>,
+
+    /// Counts of each type stored so far, in the order of arenas.
+    pub types: Vec<TypeDiagnostics>,
+}
+
+/// Live element that no handle known to the caller identifies.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Orphan {
+    /// Handle of the element.
+    pub handle: Handle,
+
+    /// Type of the element.
+    pub type_id: TypeId,
+
+    /// Sequence number of its insertion, if its arena records ages.
+    pub age: Option<u64>,
+}
+
+/// Counts of elements and slots of a single type.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct TypeDiagnostics {
+    /// Type counted.
+    pub type_id: TypeId,
+
+    /// Number of live elements.
+    pub live: usize,
+
+    /// Number of live elements among orphans.
+    pub orphans: usize,
+
+    /// Number of free slots, which last held an element of this type.
+    pub free: usize,
+
+    /// Smallest sequence number among orphans with a recorded age, if any.
+    pub oldest_orphan: Option<u64>,
+}
+
+impl<Trait: ?Sized + Pointee<Metadata = DynMetadata<Trait>>, S: Storage> Hato<Trait, S> {
+    /// Record the insertion sequence number of elements in arenas created from now on.
+    ///
+    /// Ages are then reported by [`Self::diagnose`], to tell leaks from recent elements.
+    /// Sequence numbers come from a counter shared by all collections, and thus also
+    /// order insertions across them.
+    #[inline]
+    #[must_use]
+    pub const fn with_diagnostics(mut self) -> Self {
+        self.options.aged = true;
+        self
+    }
+
+    /// Report live elements absent from `known`, along with free slots, per type.
+    ///
+    /// Since destructors never run, elements whose handles were dropped stay around
+    /// unnoticed. Handing all handles the application still holds finds such orphans.
+    ///
+    /// ```rust
+    /// let mut arena = hato::Hato::<dyn core::fmt::Debug>::default().with_diagnostics();
+    ///
+    /// let x = arena.push(1_u8);
+    /// let y = arena.push(2_u8);
+    /// let z = arena.push(3_u16);
+    ///
+    /// arena.remove(z);
+    ///
+    /// let report = arena.diagnose([x]);
+    ///
+    /// assert_eq!(report.orphans.len(), 1);
+    /// assert_eq!(report.orphans[0].handle, y);
+    /// assert_eq!((report.types[0].live, report.types[0].orphans), (2, 1));
+    /// ```
+    #[inline]
+    pub fn diagnose(&self, known: impl IntoIterator<Item = Handle>) -> Diagnostics {
+        let known = known
+            .into_iter()
+            .map(|handle| self.forward(handle))
+            .collect::<BTreeSet<_>>();
+
+        let mut report = Diagnostics::default();
+
+        for (index, arena) in self.arenas.iter().enumerate() {
+            let index = index_of(index);
+
+            for slot in 0..arena.occupied.len() {
+                let (type_id, _) = arena.kind(slot);
+                let counts = report.counts_mut(type_id);
+
+                if !arena.occupied[slot] {
+                    counts.free += 1;
+                    continue;
+                }
+
+                counts.live += 1;
+
+                let handle = Handle {
+                    index,
+                    offset: arena.offset(slot),
+                };
+
+                if known.contains(&handle) {
+                    continue;
+                }
+
+                let age = arena.ages.get(slot).copied();
+
+                counts.orphans += 1;
+                counts.oldest_orphan = counts.oldest_orphan.into_iter().chain(age).min();
+
+                report.orphans.push(Orphan {
+                    handle,
+                    type_id,
+                    age,
+                });
+            }
+        }
+
+        report
+    }
+}
+
+impl Diagnostics {
+    /// Counts of type `type_id`, starting from zero on its first occurrence.
+    #[inline]
+    fn counts_mut(&mut self, type_id: TypeId) -> &mut TypeDiagnostics {
+        let position = self.types.iter().position(|t| t.type_id == type_id);
+
+        let position = position.unwrap_or_else(|| {
+            self.types.push(TypeDiagnostics {
+                type_id,
+                live: 0,
+                orphans: 0,
+                free: 0,
+                oldest_orphan: None,
+            });
+
+            self.types.len() - 1
+        });
+
+        &mut self.types[position]
+    }
+}
+
+impl<Trait: ?Sized + Pointee<Metadata = DynMetadata<Trait>>, S: Storage> Arena<Trait, S> {
+    /// Record the insertion sequence number of the element in `slot`, if ages are tracked.
+    #[inline]
+    pub(crate) fn stamp(&mut self, slot: usize) {
+        if !self.aged {
+            return;
+        }
+
+        let age = SEQUENCE.fetch_add(1, Ordering::Relaxed);
+
+        if slot < self.ages.len() {
+            self.ages[slot] = age;
+        } else {
+            self.ages.push(age);
+        }
+    }
+}
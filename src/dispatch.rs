@@ -0,0 +1,129 @@
+use core::any::TypeId;
+use core::marker::Unsize;
+use core::ops::ControlFlow;
+use core::ptr::{DynMetadata, Pointee};
+
+use unscrupulous::Unscrupulous;
+
+use crate::{index_of, Handle, Hato, Storage};
+
+impl<Trait: ?Sized + Pointee<Metadata = DynMetadata<Trait>>, S: Storage> Hato<Trait, S> {
+    /// Call `f` on every element of type `T`, with direct access instead of virtual dispatch.
+    ///
+    /// Calls through `f` are monomorphized for `T`, so they can be inlined in the traversal.
+    /// See [`for_each_dispatch`](crate::for_each_dispatch) to visit a closed set of types.
+    ///
+    /// ```rust
+    /// let mut arena = hato::Hato::<dyn core::fmt::Debug>::default();
+    ///
+    /// let _ = arena.push(1_u8);
+    /// let _ = arena.push(2_i32);
+    /// let _ = arena.push(3_u8);
+    ///
+    /// let mut sum = 0;
+    /// arena.for_each_of::<u8>(|x| sum += x);
+    ///
+    /// assert_eq!(sum, 4);
+    /// ```
+    #[inline]
+    pub fn for_each_of<T: Unsize<Trait> + Unscrupulous>(&self, mut f: impl FnMut(&T)) {
+        let type_id = typeid::of::<T>();
+
+        // Skip arenas that never admitted the type, to only scan the slots of relevant ones
+        let arenas = self.arenas.iter();
+        let arenas = arenas.filter(|arena| arena.kind_of(|(id, _)| id == type_id).is_some());
+
+        for arena in arenas {
+            let live = (0..arena.occupied.len()).filter(|slot| arena.occupied[*slot]);
+
+            for slot in live.filter(|slot| arena.kind(*slot).0 == type_id) {
+                // ! SAFETY: Slot holds a valid element of type `T`, as identified by its kind
+                f(unsafe { &*arena.ptr(arena.offset(slot)).cast::<T>() });
+            }
+        }
+    }
+
+    /// Call `f` on every element whose type is not among `types`, through virtual dispatch.
+    ///
+    /// Types are identified with [`typeid::of`], which matches [`TypeId::of`] for `'static` types.
+    #[inline]
+    pub fn for_each_excluding(&self, types: &[TypeId], mut f: impl FnMut(&Trait)) {
+        for arena in &self.arenas {
+            let live = (0..arena.occupied.len()).filter(|slot| arena.occupied[*slot]);
+
+            for slot in live.filter(|slot| !types.contains(&arena.kind(*slot).0)) {
+                f(arena.get(arena.offset(slot)));
+            }
+        }
+    }
+
+    /// Call `f` on every element along with its handle, stopping at the first break.
+    ///
+    /// Returns the value of the break, so searches and validation passes can end early
+    /// without visiting the rest of the collection.
+    ///
+    /// ```rust
+    /// use core::ops::ControlFlow;
+    ///
+    /// let mut arena = hato::Hato::<dyn core::fmt::Debug>::default();
+    ///
+    /// let _ = arena.push(1_u8);
+    /// let x = arena.push(2_u8);
+    /// let _ = arena.push(3_u16);
+    ///
+    /// let found = arena.try_for_each(|handle, x| {
+    ///     if format!("{x:?}") == "2" {
+    ///         ControlFlow::Break(handle)
+    ///     } else {
+    ///         ControlFlow::Continue(())
+    ///     }
+    /// });
+    ///
+    /// assert_eq!(found, ControlFlow::Break(x));
+    /// ```
+    #[inline]
+    pub fn try_for_each<B>(
+        &self,
+        mut f: impl FnMut(Handle, &Trait) -> ControlFlow<B>,
+    ) -> ControlFlow<B> {
+        for (index, arena) in self.arenas.iter().enumerate() {
+            for slot in (0..arena.occupied.len()).filter(|slot| arena.occupied[*slot]) {
+                let index = index_of(index);
+
+                let offset = arena.offset(slot);
+                f(Handle { index, offset }, arena.get(offset))?;
+            }
+        }
+
+        ControlFlow::Continue(())
+    }
+
+    /// Call `f` on every element mutably along with its handle, stopping at the first break.
+    ///
+    /// See [`Self::try_for_each`] for details.
+    #[inline]
+    pub fn try_for_each_mut<B>(
+        &mut self,
+        mut f: impl FnMut(Handle, &mut Trait) -> ControlFlow<B>,
+    ) -> ControlFlow<B> {
+        for (index, arena) in self.arenas.iter_mut().enumerate() {
+            for slot in 0..arena.occupied.len() {
+                if !arena.occupied[slot] {
+                    continue;
+                }
+
+                let index = index_of(index);
+
+                let offset = arena.offset(slot);
+                let handle = Handle { index, offset };
+
+                // Element may be modified through the reference, past what the model can follow
+                self.shadow.touch(handle);
+
+                f(handle, arena.get_mut(offset))?;
+            }
+        }
+
+        ControlFlow::Continue(())
+    }
+}
@@ -0,0 +1,161 @@
+use core::marker::Unsize;
+use core::ptr::{drop_in_place, DynMetadata, Pointee};
+
+use unscrupulous::Unscrupulous;
+
+use crate::{Handle, Hato};
+
+/// Wrapper around [`Hato`] running the destructors of its elements.
+///
+/// Types owning resources can then be stored, as their destructor runs on removal,
+/// on [`Self::clear`], and when the wrapper itself is dropped. Destructors are reached
+/// through the vtable of each element, so no extra bookkeeping is needed.
+///
+/// ```rust
+/// use core::sync::atomic::{AtomicUsize, Ordering};
+///
+/// static CLOSED: AtomicUsize = AtomicUsize::new(0);
+///
+/// // Stand-in for a resource, such as a file descriptor
+/// #[derive(Debug)]
+/// struct Descriptor(i32);
+///
+/// impl Drop for Descriptor {
+///     fn drop(&mut self) {
+///         let _ = CLOSED.fetch_add(1, Ordering::Relaxed);
+///     }
+/// }
+///
+/// unsafe impl unscrupulous::Unscrupulous for Descriptor {}
+///
+/// let mut arena = hato::HatoDrop::<dyn core::fmt::Debug>::default();
+///
+/// let x = arena.push(Descriptor(3));
+/// let _ = arena.push(Descriptor(4));
+///
+/// assert!(arena.remove(x));
+/// assert_eq!(CLOSED.load(Ordering::Relaxed), 1);
+///
+/// drop(arena);
+/// assert_eq!(CLOSED.load(Ordering::Relaxed), 2);
+/// ```
+#[derive(Debug)]
+pub struct HatoDrop<Trait: ?Sized + Pointee<Metadata = DynMetadata<Trait>>> {
+    hato: Hato<Trait>,
+}
+
+impl<Trait: ?Sized + Pointee<Metadata = DynMetadata<Trait>>> Default for HatoDrop<Trait> {
+    fn default() -> Self {
+        Self::new(Hato::default())
+    }
+}
+
+impl<Trait: ?Sized + Pointee<Metadata = DynMetadata<Trait>>> Drop for HatoDrop<Trait> {
+    fn drop(&mut self) {
+        // A panicking destructor leaks the remaining elements, as the collection never drops them
+        drop_elements(&mut self.hato);
+    }
+}
+
+impl<Trait: ?Sized + Pointee<Metadata = DynMetadata<Trait>>> HatoDrop<Trait> {
+    /// Take ownership of the elements of `hato`, which will be dropped along with later ones.
+    #[inline]
+    #[must_use]
+    pub const fn new(hato: Hato<Trait>) -> Self {
+        Self { hato }
+    }
+
+    /// Wrapped collection.
+    #[inline]
+    #[must_use]
+    pub const fn hato(&self) -> &Hato<Trait> {
+        &self.hato
+    }
+
+    /// Insert `x`, whose destructor runs once it is removed.
+    ///
+    /// # Panics
+    ///
+    /// This function will panic if the number of arenas overflows the index type,
+    /// or if the quota of `T` is exhausted, see [`Hato::set_quota`].
+    #[inline]
+    pub fn push<T: Unsize<Trait> + Unscrupulous>(&mut self, x: T) -> Handle {
+        self.hato.push_no_drop(x)
+    }
+
+    /// Retrieve the element identified by `handle` as a trait object.
+    ///
+    /// # Safety
+    ///
+    /// The handle must originate from the same instance of `HatoDrop`.
+    #[inline]
+    #[must_use]
+    pub unsafe fn get(&self, handle: Handle) -> &Trait {
+        // ! SAFETY: Handle originates from the wrapped collection, as required from the caller
+        unsafe { self.hato.get(handle) }
+    }
+
+    /// Retrieve the element identified by `handle` as a mutable trait object.
+    #[inline]
+    #[must_use]
+    pub fn get_mut(&mut self, handle: Handle) -> &mut Trait {
+        self.hato.get_mut(handle)
+    }
+
+    /// Remove the element identified by `handle` and run its destructor, if it is live.
+    ///
+    /// Returns whether the element was live. As with [`Hato::try_remove`], handles of removed
+    /// elements are skipped, unless their slot was handed to another element meanwhile.
+    #[inline]
+    pub fn remove(&mut self, handle: Handle) -> bool {
+        if !self.hato.contains(handle) {
+            return false;
+        }
+
+        // Slot is detached even if the destructor panics, so that it cannot run twice
+        let detach = Detach {
+            hato: &mut self.hato,
+            handle,
+        };
+
+        // ! SAFETY: Handle identifies a live element, which is removed right after
+        unsafe { drop_in_place(detach.hato.get_mut(handle)) };
+
+        drop(detach);
+        true
+    }
+
+    /// Remove all elements and run their destructors, releasing memory of the collection.
+    ///
+    /// Options of the collection are kept, but handles of removed elements may be handed out again.
+    #[inline]
+    pub fn clear(&mut self) {
+        let empty = self.hato.empty_like();
+        let mut hato = core::mem::replace(&mut self.hato, empty);
+
+        // Elements are detached first, so that a panicking destructor leaks others instead of
+        // leaving dropped elements behind
+        drop_elements(&mut hato);
+    }
+}
+
+/// Guard removing the element identified by `handle` once dropped, without running its destructor.
+struct Detach<'a, Trait: ?Sized + Pointee<Metadata = DynMetadata<Trait>>> {
+    hato: &'a mut Hato<Trait>,
+    handle: Handle,
+}
+
+impl<Trait: ?Sized + Pointee<Metadata = DynMetadata<Trait>>> Drop for Detach<'_, Trait> {
+    fn drop(&mut self) {
+        self.hato.remove(self.handle);
+    }
+}
+
+/// Run the destructors of all elements of `hato`, leaving their bytes in place.
+#[inline]
+fn drop_elements<Trait: ?Sized + Pointee<Metadata = DynMetadata<Trait>>>(hato: &mut Hato<Trait>) {
+    for (_, element) in hato.iter_mut() {
+        // ! SAFETY: Each element is live and dropped once, as the collection is discarded after
+        unsafe { drop_in_place(element) };
+    }
+}
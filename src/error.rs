@@ -0,0 +1,39 @@
+use core::fmt::{self, Display, Formatter};
+
+/// Failure of a fallible operation, as returned by `try_*` and `checked_*` methods.
+///
+/// These methods never panic, so that collections can be embedded in code
+/// that must not unwind, like safety-critical systems or foreign function interfaces.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+#[non_exhaustive]
+pub enum Error {
+    /// The handle does not identify a live element of the collection.
+    InvalidHandle,
+
+    /// The collection or one of its arenas would outgrow the index types of handles.
+    CapacityOverflow,
+
+    /// Memory backing elements could not be allocated.
+    AllocationFailure,
+
+    /// The type of the element already has as many live elements as its quota allows.
+    QuotaExceeded,
+
+    /// The buffer is sealed read-only, and cannot grow until it is unsealed.
+    Sealed,
+}
+
+impl Display for Error {
+    #[inline]
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::InvalidHandle => "handle does not identify a live element",
+            Self::CapacityOverflow => "capacity overflows the index types of handles",
+            Self::AllocationFailure => "memory allocation failed",
+            Self::QuotaExceeded => "quota of elements of this type exceeded",
+            Self::Sealed => "buffer is sealed read-only",
+        })
+    }
+}
+
+impl core::error::Error for Error {}
@@ -0,0 +1,337 @@
+use alloc::collections::TryReserveError;
+use alloc::vec::Vec;
+
+use core::any::TypeId;
+use core::marker::Unsize;
+use core::mem::needs_drop;
+use core::ptr::{DynMetadata, Pointee};
+
+use aligned_vec::AVec;
+use unscrupulous::{as_slice_of_bytes, Unscrupulous};
+
+use crate::{
+    get_metadata_of, get_metadata_of_ref, Arena, Error, Handle, Hato, Index, Kind, Options, Storage,
+};
+
+impl<Trait: ?Sized + Pointee<Metadata = DynMetadata<Trait>>, S: Storage> Hato<Trait, S> {
+    /// Insert `x` into the arena for its specific type, reporting failures instead of panicking.
+    ///
+    /// ```rust
+    /// let mut arena = hato::Hato::<dyn core::fmt::Debug>::default();
+    ///
+    /// let x = arena.try_push(4_u16).unwrap();
+    /// assert_eq!(format!("{:?}", arena.checked_get(x).unwrap()), "4");
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the number of arenas overflows the index type,
+    /// or if memory for the element cannot be allocated.
+    #[inline]
+    pub fn try_push<T: Unsize<Trait> + Unscrupulous>(&mut self, x: T) -> Result<Handle, Error> {
+        // Reject types whose destructor would silently be skipped
+        const { assert!(!needs_drop::<T>(), "destructors of elements never run") }
+
+        // Compact fragmented arenas first, so that the new handle stays valid afterwards
+        self.maintain_if_pending();
+
+        let kind = (typeid::of::<T>(), get_metadata_of_ref(&x));
+        let handle = self.try_push_kind(as_slice_of_bytes(&x), kind);
+
+        // Element now lives in its arena, or was never copied on failure
+        core::mem::forget(x);
+
+        handle
+    }
+
+    /// Make room for `bytes` worth of elements of type `T`, without panicking.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the number of arenas overflows the index type,
+    /// or if memory for the elements cannot be allocated.
+    #[inline]
+    pub fn try_reserve_bytes<T: Unsize<Trait> + Unscrupulous>(
+        &mut self,
+        bytes: usize,
+    ) -> Result<(), Error> {
+        let vtable = get_metadata_of::<T, Trait>();
+
+        let index = self.try_index_with_room(typeid::of::<T>(), vtable)?;
+        let arena = &mut self.arenas[index as usize];
+
+        let count = arena.slots_for_bytes(bytes);
+
+        arena.try_reserve_slots(count)?;
+        arena
+            .slots
+            .try_reserve_exact(count)
+            .map_err(|_| Error::AllocationFailure)
+    }
+
+    /// Retrieve the element identified by `handle`, checking that it is live.
+    ///
+    /// Unlike [`Self::get`], this is safe to call with handles of any origin.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if `handle` does not identify a live element.
+    #[inline]
+    pub fn checked_get(&self, handle: Handle) -> Result<&Trait, Error> {
+        if !self.contains(handle) {
+            return Err(Error::InvalidHandle);
+        }
+
+        // ! SAFETY: Handle identifies a live element of this collection
+        Ok(unsafe { self.get(handle) })
+    }
+
+    /// Retrieve the element identified by `handle` mutably, checking that it is live.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if `handle` does not identify a live element.
+    #[inline]
+    pub fn checked_get_mut(&mut self, handle: Handle) -> Result<&mut Trait, Error> {
+        if !self.contains(handle) {
+            return Err(Error::InvalidHandle);
+        }
+
+        Ok(self.get_mut(handle))
+    }
+
+    /// Retrieve the element identified by `handle`, or `None` if it is not live.
+    ///
+    /// Handles out of bounds or of removed elements are rejected, unless their slot was handed
+    /// to another element meanwhile, which [`HatoVersioned`](crate::HatoVersioned) catches.
+    ///
+    /// ```rust
+    /// let mut arena = hato::Hato::<dyn core::fmt::Debug>::default();
+    ///
+    /// let x = arena.push(4_u16);
+    /// assert_eq!(format!("{:?}", arena.try_get(x)), "Some(4)");
+    ///
+    /// arena.remove(x);
+    /// assert!(arena.try_get(x).is_none());
+    /// ```
+    #[inline]
+    #[must_use]
+    pub fn try_get(&self, handle: Handle) -> Option<&Trait> {
+        self.checked_get(handle).ok()
+    }
+
+    /// Retrieve the element identified by `handle` mutably, or `None` if it is not live.
+    ///
+    /// See [`Self::try_get`] for the handles rejected.
+    #[inline]
+    #[must_use]
+    pub fn try_get_mut(&mut self, handle: Handle) -> Option<&mut Trait> {
+        self.checked_get_mut(handle).ok()
+    }
+
+    /// Remove the element identified by `handle`, checking that it is live.
+    ///
+    /// ```rust
+    /// let mut arena = hato::Hato::<dyn core::fmt::Debug>::default();
+    ///
+    /// let x = arena.try_push(4_u16).unwrap();
+    ///
+    /// assert_eq!(arena.checked_remove(x), Ok(()));
+    /// assert_eq!(arena.checked_remove(x), Err(hato::Error::InvalidHandle));
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if `handle` does not identify a live element,
+    /// or if the free list of its arena cannot grow.
+    #[inline]
+    pub fn checked_remove(&mut self, handle: Handle) -> Result<(), Error> {
+        if !self.contains(handle) {
+            return Err(Error::InvalidHandle);
+        }
+
+        // Make room in the free list, which removal may push the slot onto
+        let arena = &mut self.arenas[handle.index as usize];
+        arena
+            .slots
+            .try_reserve(1)
+            .map_err(|_| Error::AllocationFailure)?;
+
+        self.remove(handle);
+
+        Ok(())
+    }
+
+    /// Insert the bytes of an element of type `kind`, which must be [`Unscrupulous`].
+    ///
+    /// All insertions of single elements go through here, allocating everything up front
+    /// so that the insertion itself cannot fail.
+    #[inline]
+    pub(crate) fn try_push_kind(
+        &mut self,
+        bytes: &[u8],
+        (type_id, vtable): Kind<Trait>,
+    ) -> Result<Handle, Error> {
+        let index = self.try_index_with_room(type_id, vtable)?;
+        let arena = &mut self.arenas[index as usize];
+
+        arena.try_reserve(1)?;
+
+        let offset = if arena.spill {
+            let mut element = AVec::new(arena.bytes.align());
+            element
+                .try_reserve_exact(bytes.len())
+                .map_err(|_| Error::AllocationFailure)?;
+
+            element.extend_from_slice(bytes);
+            arena.push_spilled(element, (type_id, vtable))
+        } else {
+            arena.push_bytes(bytes, (type_id, vtable))
+        };
+
+        let handle = Handle { index, offset };
+        self.shadow
+            .insert(handle, || self.arenas[index as usize].element(offset));
+
+        Ok(handle)
+    }
+
+    /// Find an arena for elements of type `type_id` with room for one more, or create one.
+    #[inline]
+    fn try_index_with_room(
+        &mut self,
+        type_id: TypeId,
+        vtable: DynMetadata<Trait>,
+    ) -> Result<Index, Error> {
+        if !self.within_quota(type_id, 1) {
+            return Err(Error::QuotaExceeded);
+        }
+
+        let found = self
+            .arenas
+            .iter()
+            .position(|arena| arena.admits(vtable) && arena.has_room(1));
+
+        if let Some(index) = found {
+            let arena = &mut self.arenas[index];
+
+            // Make room to remember the type and its callback, in arenas shared across types
+            arena
+                .types
+                .try_reserve(1)
+                .and_then(|()| arena.counts.try_reserve(1))
+                .and_then(|()| arena.relocations.try_reserve(1))
+                .map_err(|_| Error::AllocationFailure)?;
+            arena.register((type_id, vtable));
+            self.attach_relocation(index, type_id);
+
+            return Index::try_from(index).map_err(|_| Error::CapacityOverflow);
+        }
+
+        // Bound the number of different types to limit the size of handles
+        let index = Index::try_from(self.arenas.len()).map_err(|_| Error::CapacityOverflow)?;
+
+        let mut arena = Arena::try_new(type_id, vtable, self.options)?;
+
+        arena
+            .relocations
+            .try_reserve(1)
+            .and_then(|()| self.arenas.try_reserve(1))
+            .map_err(|_| Error::AllocationFailure)?;
+        self.arenas.push(arena);
+        self.attach_relocation(self.arenas.len() - 1, type_id);
+
+        #[cfg(feature = "std")]
+        if self.pool.is_some() {
+            self.adopt_pooled(self.arenas.len() - 1);
+        }
+
+        Ok(index)
+    }
+}
+
+impl<Trait: ?Sized + Pointee<Metadata = DynMetadata<Trait>>, S: Storage> Arena<Trait, S> {
+    /// Create an arena as [`Self::new`] does, reporting failures instead of panicking.
+    #[inline]
+    fn try_new(
+        type_id: TypeId,
+        vtable: DynMetadata<Trait>,
+        options: Options,
+    ) -> Result<Self, Error> {
+        let mut arena = Self::try_empty(type_id, vtable, options)?;
+
+        arena.try_reserve_slots(arena.slots_for_bytes(options.capacity_bytes))?;
+        Ok(arena)
+    }
+
+    /// Make room for `count` more slots as [`Self::reserve_slots`] does, without panicking.
+    #[inline]
+    fn try_reserve_slots(&mut self, count: usize) -> Result<(), Error> {
+        self.try_grow_by(count, true)
+    }
+
+    /// Make room for `count` more slots following the growth mode of the arena,
+    /// as insertions do, without panicking.
+    #[inline]
+    fn try_reserve(&mut self, count: usize) -> Result<(), Error> {
+        self.try_grow_by(count, self.exact)
+    }
+
+    /// Make room for `count` more slots, exactly or growing geometrically to amortize copies.
+    #[inline]
+    fn try_grow_by(&mut self, count: usize, exact: bool) -> Result<(), Error> {
+        // Zero-sized and spilled types occupy no bytes of the buffer at all
+        let bytes = if self.vtable.size_of() == 0 || self.spill {
+            0
+        } else {
+            count
+                .checked_mul(self.stride)
+                .ok_or(Error::CapacityOverflow)?
+        };
+
+        let (moved, anchor) = (self.prepare_growth(bytes), self.anchor());
+
+        let grown = if exact {
+            self.bytes.try_reserve_exact(bytes)
+        } else {
+            self.bytes.try_reserve(bytes)
+        };
+
+        self.relocate_all(anchor);
+        self.finish_growth(moved);
+        grown?;
+
+        let tags = count
+            .checked_mul(self.tag_bytes)
+            .ok_or(Error::CapacityOverflow)?;
+
+        let spilled = if self.spill { count } else { 0 };
+
+        try_reserve_vec(&mut self.spilled, spilled, exact)
+            .and_then(|()| try_reserve_vec(&mut self.occupied, count, exact))
+            .and_then(|()| try_reserve_vec(&mut self.tags, tags, exact))
+            .and_then(|()| {
+                let kinds = if self.shared { count } else { 0 };
+                try_reserve_vec(&mut self.kinds, kinds, exact)
+            })
+            .and_then(|()| {
+                let ages = if self.aged { count } else { 0 };
+                try_reserve_vec(&mut self.ages, ages, exact)
+            })
+            .map_err(|_| Error::AllocationFailure)
+    }
+}
+
+/// Make room for `additional` more items in `vec`, exactly or growing geometrically.
+#[inline]
+fn try_reserve_vec<T>(
+    vec: &mut Vec<T>,
+    additional: usize,
+    exact: bool,
+) -> Result<(), TryReserveError> {
+    if exact {
+        vec.try_reserve_exact(additional)
+    } else {
+        vec.try_reserve(additional)
+    }
+}
@@ -0,0 +1,201 @@
+use alloc::vec::Vec;
+
+use core::future::poll_fn;
+use core::pin::Pin;
+use core::ptr::{DynMetadata, Pointee};
+use core::task::{Context, Poll};
+
+use std::io;
+
+use futures_core::Stream;
+
+use crate::snapshot::{read_header, restore_from, Encoder, Source};
+use crate::{Hato, SnapshotError, Storage, Types};
+
+impl<Trait: ?Sized + Pointee<Metadata = DynMetadata<Trait>>, S: Storage> Hato<Trait, S> {
+    /// Stream all elements as a snapshot, in frames of at least `frame` bytes but the last.
+    ///
+    /// Frames follow the format of [`Self::write_snapshot`], and concatenate to the same bytes.
+    /// Each frame is encoded as it is polled, so async runtimes regain control between frames
+    /// instead of being blocked for the whole snapshot. Frames only exceed `frame` bytes
+    /// by the size of the last element they hold. Read them back with an [`AsyncSnapshotReader`].
+    ///
+    /// ```rust
+    /// use core::pin::pin;
+    /// use core::task::{Context, Poll, Waker};
+    ///
+    /// use futures_core::Stream;
+    ///
+    /// let mut arena = hato::Hato::<dyn core::fmt::Debug>::default();
+    ///
+    /// let x = arena.push(1_u8);
+    /// let y = arena.push(2_u16);
+    ///
+    /// let types = hato::Types::default().register::<u8>().register::<u16>();
+    ///
+    /// // Frames are ready as soon as they are polled, which an async runtime would do
+    /// let mut frames = pin!(arena.snapshot_frames(&types, 16));
+    /// let mut context = Context::from_waker(Waker::noop());
+    ///
+    /// let mut bytes = Vec::new();
+    ///
+    /// while let Poll::Ready(Some(frame)) = frames.as_mut().poll_next(&mut context) {
+    ///     bytes.extend(frame.unwrap());
+    /// }
+    ///
+    /// let mut written = Vec::new();
+    /// arena.write_snapshot(&types, &mut written).unwrap();
+    ///
+    /// assert_eq!(bytes, written);
+    /// ```
+    #[inline]
+    #[must_use]
+    pub const fn snapshot_frames<'a>(
+        &'a self,
+        types: &'a Types<Trait>,
+        frame: usize,
+    ) -> SnapshotFrames<'a, Trait, S> {
+        SnapshotFrames {
+            encoder: Encoder::new(self, types),
+            frame,
+        }
+    }
+}
+
+/// Stream of the frames of a snapshot, built by [`Hato::snapshot_frames`].
+///
+/// Frames hold [`io::Error`]s of kind [`InvalidInput`](io::ErrorKind::InvalidInput)
+/// if an arena admits a type missing from the registry, after which the stream ends.
+#[derive(Debug)]
+pub struct SnapshotFrames<'a, Trait: ?Sized + Pointee<Metadata = DynMetadata<Trait>>, S: Storage> {
+    encoder: Encoder<'a, Trait, S>,
+    frame: usize,
+}
+
+impl<Trait: ?Sized + Pointee<Metadata = DynMetadata<Trait>>, S: Storage> Stream
+    for SnapshotFrames<'_, Trait, S>
+{
+    type Item = io::Result<Vec<u8>>;
+
+    #[inline]
+    fn poll_next(self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+        let mut frame = Vec::with_capacity(this.frame);
+
+        while frame.len() < this.frame.max(1) {
+            match this.encoder.step(&mut frame) {
+                Ok(true) => {}
+                Ok(false) => break,
+                Err(error) => return Poll::Ready(Some(Err(error))),
+            }
+        }
+
+        Poll::Ready((!frame.is_empty()).then_some(Ok(frame)))
+    }
+}
+
+/// Reader of snapshots streamed as frames, restoring them as frames arrive.
+///
+/// Frames may be split anywhere, for instance as they come off a socket, and only the elements
+/// of one slot are buffered at a time. Frames come from a [`Stream`] of byte buffers, or of
+/// [`io::Error`]s to abort the restore. It must be [`Unpin`], which [`Box::pin`] provides.
+/// As with [`SnapshotReader`](crate::SnapshotReader), the header is read first, for
+/// applications to look up the [`version`](Self::version) before picking their types.
+#[derive(Debug)]
+pub struct AsyncSnapshotReader<St, B> {
+    frames: Frames<St, B>,
+    version: u32,
+}
+
+impl<St: Stream<Item = io::Result<B>> + Unpin, B: AsRef<[u8]>> AsyncSnapshotReader<St, B> {
+    /// Read the header of the snapshot streamed as `frames`.
+    ///
+    /// # Errors
+    ///
+    /// This function will return [`SnapshotError::Io`] if `frames` yields an error or ends
+    /// early, or [`SnapshotError::Format`] if it does not stream a snapshot in a format
+    /// this build understands.
+    #[inline]
+    pub async fn new(frames: St) -> Result<Self, SnapshotError> {
+        let mut frames = Frames {
+            stream: frames,
+            frame: None,
+            position: 0,
+        };
+
+        let version = read_header(&mut frames).await?;
+
+        Ok(Self { frames, version })
+    }
+
+    /// Version of the registry the snapshot was taken with, see [`Types::with_version`].
+    #[inline]
+    #[must_use]
+    pub const fn version(&self) -> u32 {
+        self.version
+    }
+
+    /// Recreate the streamed elements in `hato`, at the slots they were taken from.
+    ///
+    /// Follows the rules of [`SnapshotReader::restore`](crate::SnapshotReader::restore).
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the stream fails, if the snapshot is malformed
+    /// or corrupt, or if it does not match `types` and the layout of `hato`.
+    ///
+    /// # Safety
+    ///
+    /// The snapshot must come from a collection of the same types, as their bytes are copied
+    /// as is.
+    #[inline]
+    pub async unsafe fn restore<Trait, S>(
+        mut self,
+        hato: &mut Hato<Trait, S>,
+        types: &Types<Trait>,
+    ) -> Result<(), SnapshotError>
+    where
+        Trait: ?Sized + Pointee<Metadata = DynMetadata<Trait>>,
+        S: Storage,
+    {
+        // ! SAFETY: Caller guarantees the snapshot comes from a collection of the same types
+        unsafe { restore_from(&mut self.frames, hato, types) }.await
+    }
+}
+
+/// Source reading from a stream of frames, waiting for the next one once a frame is consumed.
+#[derive(Debug)]
+struct Frames<St, B> {
+    stream: St,
+    frame: Option<B>,
+
+    /// Number of bytes of the current frame already read.
+    position: usize,
+}
+
+impl<St: Stream<Item = io::Result<B>> + Unpin, B: AsRef<[u8]>> Source for Frames<St, B> {
+    #[inline]
+    async fn read_exact(&mut self, mut buffer: &mut [u8]) -> io::Result<()> {
+        while !buffer.is_empty() {
+            let available = self.frame.as_ref().map_or(&[][..], |frame| frame.as_ref());
+            let available = &available[self.position..];
+
+            if available.is_empty() {
+                let next = poll_fn(|context| Pin::new(&mut self.stream).poll_next(context)).await;
+
+                self.frame = Some(next.ok_or(io::ErrorKind::UnexpectedEof)??);
+                self.position = 0;
+
+                continue;
+            }
+
+            let len = available.len().min(buffer.len());
+            buffer[..len].copy_from_slice(&available[..len]);
+
+            buffer = &mut buffer[len..];
+            self.position += len;
+        }
+
+        Ok(())
+    }
+}
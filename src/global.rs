@@ -0,0 +1,115 @@
+use std::sync::{OnceLock, PoisonError, RwLock, RwLockReadGuard, RwLockWriteGuard};
+
+use core::marker::Unsize;
+use core::ptr::{DynMetadata, Pointee};
+
+use unscrupulous::Unscrupulous;
+
+use crate::{Error, Handle, Hato};
+
+/// Lazily initialized [`Hato`] behind a lock, meant to be stored in a `static`.
+///
+/// Plugin registries or interners can then insert and access elements from anywhere,
+/// instead of threading a mutable reference through every call site. Accesses take a
+/// read lock, and modifications a write lock. Read-mostly collections may prefer
+/// the lock-free snapshots of `HatoSwap`, behind the `arc-swap` feature.
+///
+/// ```rust
+/// use core::fmt::Debug;
+///
+/// static REGISTRY: hato::GlobalHato<dyn Debug + Send + Sync> = hato::GlobalHato::new();
+///
+/// let x = REGISTRY.push(4_u16);
+///
+/// assert_eq!(REGISTRY.get(x, |x| format!("{x:?}")), Ok("4".to_owned()));
+/// assert!(REGISTRY.remove(x));
+/// ```
+///
+/// Elements are shared across threads, so trait objects must be [`Send`] and [`Sync`]:
+///
+/// ```rust,compile_fail
+/// use core::fmt::Debug;
+///
+/// static REGISTRY: hato::GlobalHato<dyn Debug> = hato::GlobalHato::new();
+/// ```
+#[derive(Debug)]
+pub struct GlobalHato<Trait: ?Sized + Send + Sync + Pointee<Metadata = DynMetadata<Trait>>>(
+    OnceLock<RwLock<Hato<Trait>>>,
+);
+
+impl<Trait> Default for GlobalHato<Trait>
+where
+    Trait: ?Sized + Send + Sync + Pointee<Metadata = DynMetadata<Trait>>,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<Trait> GlobalHato<Trait>
+where
+    Trait: ?Sized + Send + Sync + Pointee<Metadata = DynMetadata<Trait>>,
+{
+    /// Create a wrapper whose collection is only built on first use.
+    #[inline]
+    #[must_use]
+    pub const fn new() -> Self {
+        Self(OnceLock::new())
+    }
+
+    /// Insert `x` into the collection, see [`Hato::push`].
+    ///
+    /// # Panics
+    ///
+    /// This function will panic if the number of arenas overflows the index type.
+    #[inline]
+    pub fn push<T: Unsize<Trait> + Unscrupulous>(&self, x: T) -> Handle {
+        self.write().push(x)
+    }
+
+    /// Call `f` on the element identified by `handle`, checking that it is live.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if `handle` does not identify a live element.
+    #[inline]
+    pub fn get<R>(&self, handle: Handle, f: impl FnOnce(&Trait) -> R) -> Result<R, Error> {
+        self.read().checked_get(handle).map(f)
+    }
+
+    /// Remove the element identified by `handle`, see [`Hato::try_remove`].
+    #[inline]
+    pub fn remove(&self, handle: Handle) -> bool {
+        self.write().try_remove(handle)
+    }
+
+    /// Call `f` on the collection, holding a read lock.
+    #[inline]
+    pub fn with<R>(&self, f: impl FnOnce(&Hato<Trait>) -> R) -> R {
+        f(&self.read())
+    }
+
+    /// Call `f` on the collection mutably, holding a write lock.
+    #[inline]
+    pub fn with_mut<R>(&self, f: impl FnOnce(&mut Hato<Trait>) -> R) -> R {
+        f(&mut self.write())
+    }
+
+    /// Lock the collection for reading, building it on first use.
+    #[inline]
+    fn read(&self) -> RwLockReadGuard<'_, Hato<Trait>> {
+        let lock = self.0.get_or_init(RwLock::default);
+
+        // Collections stay consistent even if a holder of the lock panicked
+        lock.read().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Lock the collection for writing, building it on first use.
+    #[inline]
+    fn write(&self) -> RwLockWriteGuard<'_, Hato<Trait>> {
+        let lock = self.0.get_or_init(RwLock::default);
+
+        // Collections stay consistent even if a holder of the lock panicked
+        lock.write().unwrap_or_else(PoisonError::into_inner)
+    }
+}
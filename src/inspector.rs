@@ -0,0 +1,102 @@
+use core::fmt::Debug;
+use core::ptr::{DynMetadata, Pointee};
+
+use egui::{CollapsingHeader, Grid, Response, ScrollArea, Ui, Widget};
+
+use crate::{Arena, Hato};
+
+/// Maximum number of elements listed per arena, to keep frames cheap on large collections.
+const MAX_ELEMENTS: usize = 256;
+
+/// Widget browsing the arenas of a collection, their slots, elements and memory usage.
+///
+/// ```rust,no_run
+/// # fn show(ui: &mut egui::Ui, arena: &hato::Hato<dyn core::fmt::Debug>) {
+/// ui.add(hato::Inspector::new(arena));
+/// # }
+/// ```
+#[derive(Clone, Copy, Debug)]
+pub struct Inspector<'a, Trait: ?Sized + Pointee<Metadata = DynMetadata<Trait>>> {
+    hato: &'a Hato<Trait>,
+}
+
+impl<'a, Trait: ?Sized + Pointee<Metadata = DynMetadata<Trait>>> Inspector<'a, Trait> {
+    /// Inspect `hato`, whose elements are displayed through their [`Debug`] implementation.
+    #[inline]
+    #[must_use]
+    pub const fn new(hato: &'a Hato<Trait>) -> Self {
+        Self { hato }
+    }
+}
+
+impl<Trait> Widget for Inspector<'_, Trait>
+where
+    Trait: ?Sized + Pointee<Metadata = DynMetadata<Trait>> + Debug,
+{
+    fn ui(self, ui: &mut Ui) -> Response {
+        let arenas = &self.hato.arenas;
+
+        let used = arenas.iter().map(|arena| arena.bytes.len()).sum::<usize>();
+        let reserved = arenas
+            .iter()
+            .map(|arena| arena.bytes.capacity())
+            .sum::<usize>();
+
+        ui.vertical(|ui| {
+            let summary = format!("{} arenas, {used} of {reserved} bytes used", arenas.len());
+            drop(ui.label(summary));
+
+            for (index, arena) in arenas.iter().enumerate() {
+                let live = arena.live;
+                let free = arena.occupied.len() - live;
+
+                let header =
+                    CollapsingHeader::new(format!("Arena {index}: {live} live, {free} free"));
+                drop(
+                    header
+                        .id_salt(index)
+                        .show(ui, |ui| show_arena(ui, index, arena, live)),
+                );
+            }
+        })
+        .response
+    }
+}
+
+/// Display memory statistics of `arena`, followed by its first live elements.
+fn show_arena<Trait>(ui: &mut Ui, index: usize, arena: &Arena<Trait>, live: usize)
+where
+    Trait: ?Sized + Pointee<Metadata = DynMetadata<Trait>> + Debug,
+{
+    drop(Grid::new(("hato-stats", index)).show(ui, |ui| {
+        let rows = [
+            ("Element size", arena.vtable.size_of().to_string()),
+            ("Stride", arena.stride.to_string()),
+            ("Bytes used", arena.bytes.len().to_string()),
+            ("Bytes reserved", arena.bytes.capacity().to_string()),
+        ];
+
+        for (name, value) in rows {
+            drop(ui.label(name));
+            drop(ui.label(value));
+            ui.end_row();
+        }
+    }));
+
+    let scroll = ScrollArea::vertical()
+        .id_salt(("hato-elements", index))
+        .max_height(200.0);
+
+    let _ = scroll.show(ui, |ui| {
+        let slots = (0..arena.occupied.len()).filter(|slot| arena.occupied[*slot]);
+
+        for slot in slots.take(MAX_ELEMENTS) {
+            let offset = arena.offset(slot);
+            drop(ui.monospace(format!("[{offset}] {:?}", arena.get(offset))));
+        }
+
+        if live > MAX_ELEMENTS {
+            drop(ui.label(format!("... and {} more", live - MAX_ELEMENTS)));
+        }
+    });
+}
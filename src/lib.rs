@@ -1,23 +1,253 @@
 // Unstable features necessary to avoid macros
 #![feature(ptr_metadata, unsize)]
+// Detect builds with sanitizers, to poison free slots
+#![feature(cfg_sanitize)]
 // Use `README.md` as documentation home page, to reduce duplication
 #![doc = include_str!("../README.md")]
+// Only rely on an allocator, unless locks, threads or I/O are needed
+#![cfg_attr(not(feature = "std"), no_std)]
 
-#[cfg(test)]
+extern crate alloc;
+
+#[cfg(feature = "rayon")]
+mod par;
+
+#[cfg(feature = "serde")]
+mod archive;
+
+mod cache;
+
+mod checkpoint;
+
+mod commands;
+
+mod compaction;
+
+mod convert;
+
+#[cfg(feature = "std")]
+mod deferred;
+
+mod diagnostics;
+
+mod dispatch;
+
+mod dropping;
+
+mod error;
+
+mod fallible;
+
+#[cfg(feature = "futures")]
+mod frames;
+
+#[cfg(feature = "std")]
+mod global;
+
+#[cfg(feature = "egui")]
+mod inspector;
+
+mod list;
+
+mod macros;
+
+mod names;
+
+#[cfg(feature = "std")]
+mod owned;
+
+#[cfg(feature = "std")]
+mod paged;
+
+#[cfg(feature = "oplog")]
+mod oplog;
+
+mod order;
+
+mod partition;
+
+mod persistent;
+
+mod poison;
+
+mod pool;
+
+#[cfg(all(feature = "protect", unix))]
+mod protect;
+
+#[cfg(all(feature = "protect", not(unix)))]
+compile_error!("the `protect` feature relies on `mprotect`, which is only available on Unix");
+
+mod query;
+
+mod quota;
+
+#[cfg(feature = "std")]
+mod recycle;
+
+mod relocate;
+
+#[cfg(feature = "bevy_reflect")]
+mod reflect;
+
+mod remap;
+
+mod resolver;
+
+mod sequence;
+
+mod shadow;
+
+#[cfg(any(feature = "get-size", feature = "malloc_size_of"))]
+mod size;
+
+#[cfg(feature = "serde")]
+mod snapshot;
+
+mod split;
+
+mod stats;
+
+mod steal;
+
+mod storage;
+
+#[cfg(feature = "arc-swap")]
+mod swap;
+
+#[cfg(all(test, feature = "std"))]
 mod tests;
 
+mod text;
+
+#[cfg(feature = "compress")]
+mod tiered;
+
+#[cfg(feature = "std")]
+mod threads;
+
+mod trace;
+
+mod typed;
+
+#[cfg(any(feature = "oplog", feature = "serde"))]
+mod types;
+
+mod versioned;
+
+mod view;
+
+#[cfg(feature = "wal")]
+mod wal;
+
+use alloc::alloc::{alloc, handle_alloc_error};
+use alloc::boxed::Box;
+use alloc::vec;
+use alloc::vec::Vec;
+
+use core::any::TypeId;
 use core::marker::Unsize;
-use core::mem::align_of;
-use core::ptr::{from_raw_parts, from_raw_parts_mut, from_ref, metadata, DynMetadata, Pointee};
+use core::mem::{needs_drop, size_of};
+use core::ptr::{
+    from_raw_parts, from_raw_parts_mut, from_ref, metadata, null, without_provenance_mut,
+    DynMetadata, Pointee,
+};
 
-use aligned_vec::AVec;
+use aligned_vec::{AVec, CACHELINE_ALIGN};
 use unscrupulous::{as_slice_of_bytes, Unscrupulous};
 
+use compaction::Compaction;
+use list::Link;
+use names::Names;
+use shadow::Shadow;
+
+pub use cache::{CacheHandle, HatoCache};
+pub use checkpoint::Checkpoint;
+pub use commands::HatoCommands;
+pub use convert::Conversion;
+pub use diagnostics::{Diagnostics, Orphan, TypeDiagnostics};
+pub use dropping::HatoDrop;
+pub use error::Error;
+pub use list::HandleList;
+pub use partition::HatoPartition;
+pub use persistent::HatoPersistent;
+pub use pool::{Pool, PoolHandle};
+pub use query::Query;
+pub use relocate::Relocate;
+pub use remap::Remap;
+pub use resolver::HandleResolver;
+pub use sequence::{Sequence, SequenceHandle};
+pub use stats::{Stats, TypeStats};
+pub use storage::{InlineStorage, Storage};
+pub use text::ParseHandleError;
+pub use typed::TypedHandle;
+pub use versioned::{HatoVersioned, VersionedHandle};
+pub use view::ReadOnlyView;
+
+#[cfg(feature = "std")]
+pub use global::GlobalHato;
+
+#[cfg(feature = "std")]
+pub use owned::OwnedHandle;
+
+#[cfg(feature = "std")]
+pub use paged::HatoPaged;
+
+#[cfg(feature = "std")]
+pub use recycle::HatoPool;
+
+#[cfg(feature = "egui")]
+pub use inspector::Inspector;
+
+#[cfg(feature = "futures")]
+pub use frames::{AsyncSnapshotReader, SnapshotFrames};
+
+#[cfg(feature = "oplog")]
+pub use oplog::{OpLog, Recorder};
+
+#[cfg(all(feature = "protect", unix))]
+pub use protect::{HatoSealed, ProtectedStorage};
+
+#[cfg(feature = "serde")]
+pub use archive::HatoArchive;
+
+#[cfg(feature = "serde")]
+pub use snapshot::{HatoSnapshot, SnapshotError};
+
+#[cfg(all(feature = "serde", feature = "std"))]
+pub use snapshot::SnapshotReader;
+
+#[cfg(feature = "seal")]
+pub use snapshot::Aead;
+
+#[cfg(feature = "arc-swap")]
+pub use swap::HatoSwap;
+
+#[cfg(feature = "compress")]
+pub use tiered::{HatoTiered, TieredHandle};
+
+#[cfg(any(feature = "oplog", feature = "serde"))]
+pub use types::Types;
+
+#[cfg(feature = "wal")]
+pub use wal::Wal;
+
 /// Arenas of heterogeneous trait objects, stored by type in separate vectors.
 ///
 /// As with bump allocators, [`Drop`] implementations will **not** be invoked on deallocation
-/// or calls to `remove`. If you need to run the logic contained in destructors,
-/// you can acquire a mutable reference with `get_mut`, and then call [`core::ptr::drop_in_place`].
+/// or calls to `remove`. Types that need to be dropped are thus rejected by `push`,
+/// unless explicitly inserted with `push_no_drop`. If you need to run the logic contained
+/// in destructors, you can acquire a mutable reference with `get_mut`,
+/// and then call [`core::ptr::drop_in_place`], or store elements in a [`HatoDrop`] instead.
+///
+/// Trait objects do not need to be `'static`: `Hato<dyn Trait + 'a>` stores elements of types
+/// that outlive `'a`. However, elements cannot hold references themselves,
+/// since the [`Unscrupulous`] bound excludes them. Types can still be tied to the lifetime
+/// of borrowed data with a [`PhantomData`](core::marker::PhantomData) marker,
+/// storing plain indices into it instead of references.
+///
+/// Elements of each type are stored in a heap-allocated byte buffer by default.
+/// Other backends, like fixed or memory-mapped regions, are supplied through [`Storage`].
 ///
 /// This type is subject to the [ABA problem](https://en.wikipedia.org/wiki/ABA_problem).
 /// Using handles of previously removed elements will **not** trigger errors but will return
@@ -27,201 +257,2627 @@ use unscrupulous::{as_slice_of_bytes, Unscrupulous};
 /// // Initialize the collection
 /// let mut arena = hato::Hato::<dyn core::fmt::Debug>::default();
 ///
-/// // Insert an element...
+/// // Insert an element, followed by another...
 /// let x = arena.push(5_u8);
+/// let _ = arena.push(7_u8);
 ///
-/// // ... then remove it
+/// // ... then remove the first one
 /// arena.remove(x);
 ///
 /// // ! We can still use the handle to access it
-/// assert_eq!(format!("{:?}", arena.get(x)), "5");
+/// assert_eq!(format!("{:?}", unsafe { arena.get(x) }), "5");
 ///
 /// // Insert a new element into the arena
 /// let _y = arena.push(9_u8);
 ///
 /// // ! The old handle accesses the repurposed capacity
-/// assert_eq!(format!("{:?}", arena.get(x)), "9");
+/// assert_eq!(format!("{:?}", unsafe { arena.get(x) }), "9");
 /// ```
+///
+/// Memory of removed elements is only ever given back at the end of arenas, when trailing
+/// slots are truncated on removal, or by [`Self::shrink_to_fit`] and [`Self::retain_types`].
+/// Accessing elements through stale handles past the end of their arena then panics,
+/// instead of reading memory the collection no longer owns.
+///
+/// Builds with the address sanitizer poison slots of removed elements, until they are reused.
+/// Accesses through stale handles are then reported right away, rather than going unnoticed.
+/// [`HatoVersioned`] detects them in all builds, with handles carrying the generation of their slot.
 #[derive(Debug)]
-pub struct Hato<Trait: ?Sized + Pointee<Metadata = DynMetadata<Trait>>>(Vec<Arena<Trait>>);
+pub struct Hato<Trait: ?Sized + Pointee<Metadata = DynMetadata<Trait>>, S: Storage = AVec<u8>> {
+    arenas: Vec<Arena<Trait, S>>,
+    options: Options,
+    names: Names,
+    shadow: Shadow,
+    compaction: Compaction,
+    relocations: Vec<(TypeId, Relocate)>,
+    quotas: Vec<(TypeId, usize)>,
+    #[cfg(feature = "std")]
+    pool: Option<HatoPool<S>>,
+    #[cfg(feature = "std")]
+    deferred: deferred::Deferred,
+}
 
-impl<Trait: ?Sized + Pointee<Metadata = DynMetadata<Trait>>> Default for Hato<Trait> {
+impl<Trait: ?Sized + Pointee<Metadata = DynMetadata<Trait>>, S: Storage> Default
+    for Hato<Trait, S>
+{
     fn default() -> Self {
-        Self(Vec::default())
+        Self {
+            arenas: Vec::default(),
+            options: Options::default(),
+            names: Names::default(),
+            shadow: Shadow::default(),
+            compaction: Compaction::default(),
+            relocations: Vec::new(),
+            quotas: Vec::new(),
+            #[cfg(feature = "std")]
+            pool: None,
+            #[cfg(feature = "std")]
+            deferred: deferred::Deferred::new(),
+        }
     }
 }
 
-impl<Trait: ?Sized + Pointee<Metadata = DynMetadata<Trait>>> Clone for Hato<Trait> {
+impl<Trait: ?Sized + Pointee<Metadata = DynMetadata<Trait>>, S: Storage + Clone> Clone
+    for Hato<Trait, S>
+{
     fn clone(&self) -> Self {
-        Self(self.0.clone())
+        Self {
+            arenas: self.arenas.clone(),
+            options: self.options,
+            names: self.names.clone(),
+            shadow: self.shadow.clone(),
+            compaction: self.compaction.clone(),
+            relocations: self.relocations.clone(),
+            quotas: self.quotas.clone(),
+            #[cfg(feature = "std")]
+            pool: self.pool.clone(),
+            #[cfg(feature = "std")]
+            deferred: deferred::clone_queue(&self.deferred),
+        }
     }
 }
 
-impl<Trait: ?Sized + Pointee<Metadata = DynMetadata<Trait>>> Hato<Trait> {
-    /// Insert `x` into the arena for its specific type.
+impl<Trait: ?Sized + Pointee<Metadata = DynMetadata<Trait>>, S: Storage> Hato<Trait, S> {
+    /// Pad elements of arenas created from now on to a multiple of the cache line size.
+    ///
+    /// Each element then starts on its own cache line, so that threads mutating neighboring
+    /// elements do not contend on the same line. This trades memory for parallel throughput,
+    /// and slightly degrades sequential traversals. Elements over-aligned beyond
+    /// the cache line size are not supported, with or without padding.
+    ///
+    /// ```rust
+    /// let mut arena = hato::Hato::<dyn core::fmt::Debug>::default().with_cache_line_padding();
+    ///
+    /// let x = arena.push(1_u8);
+    /// let y = arena.push(2_u8);
+    ///
+    /// let (px, py) = unsafe { (arena.get(x), arena.get(y)) };
+    /// let distance = (&raw const *py).addr() - (&raw const *px).addr();
+    ///
+    /// assert_eq!(distance % aligned_vec::CACHELINE_ALIGN, 0);
+    /// ```
+    #[inline]
+    #[must_use]
+    pub const fn with_cache_line_padding(mut self) -> Self {
+        self.options.padded = true;
+        self
+    }
+
+    /// Grow arenas created from now on by exactly the space needed, instead of doubling it.
+    ///
+    /// Memory usage stays minimal, at the cost of more frequent reallocations.
+    /// Use [`Self::slack`] to inspect how much memory arenas reserve beyond their elements.
+    ///
+    /// ```rust
+    /// let mut arena = hato::Hato::<dyn core::fmt::Debug>::default().with_exact_fit();
+    ///
+    /// for i in 0..100_u32 {
+    ///     let _ = arena.push(i);
+    /// }
+    ///
+    /// assert!(arena.slack().all(|bytes| bytes == 0));
+    /// ```
+    #[inline]
+    #[must_use]
+    pub const fn with_exact_fit(mut self) -> Self {
+        self.options.exact = true;
+        self
+    }
+
+    /// Reserve `bytes` of storage up front in each arena created from now on.
+    ///
+    /// Combined with [`Self::reserve_bytes`] for types known in advance, an application with
+    /// a memory budget can commit all of its storage at startup, and not reallocate afterwards.
+    ///
+    /// ```rust
+    /// let mut arena = hato::Hato::<dyn core::fmt::Debug>::default().with_capacity_bytes(64);
+    ///
+    /// let _ = arena.push(1_u32);
+    /// assert!(arena.slack().eq([60]));
+    /// ```
+    #[inline]
+    #[must_use]
+    pub const fn with_capacity_bytes(mut self, bytes: usize) -> Self {
+        self.options.capacity_bytes = bytes;
+        self
+    }
+
+    /// Make room for `bytes` worth of elements of type `T`, without reallocating on insertion.
+    ///
+    /// The arena for `T` is created if needed. Its free list gets room as well,
+    /// so that removing reserved elements does not allocate either.
     ///
     /// # Panics
     ///
     /// This function will panic if the number of arenas overflows the index type.
     #[inline]
-    pub fn push<T: Unsize<Trait> + Unscrupulous>(&mut self, x: T) -> Handle {
-        // Identify individual types at runtime using their virtual table pointer
-        let vtable = get_metadata_of_ref(&x);
+    pub fn reserve_bytes<T: Unsize<Trait> + Unscrupulous>(&mut self, bytes: usize) {
+        let vtable = get_metadata_of::<T, Trait>();
 
-        // Index of arena that contains elements of type `T` and is not full
-        let index_as_usize = self
-            .0
-            .iter()
-            .position(|arena| arena.vtable == vtable && !arena.is_full())
-            .unwrap_or_else(|| {
-                // Create a new arena to store elements of type `T`
-                self.0.push(Arena::new::<T>(vtable));
+        // Reserve in the arena that will receive the next elements of this type
+        let index = self.index_with_room(typeid::of::<T>(), vtable, 1);
+        let arena = &mut self.arenas[index as usize];
 
-                // Point to arena that was just created
-                self.0.len() - 1
-            });
+        let count = arena.slots_for_bytes(bytes);
+
+        arena.reserve_slots(count);
+        arena.slots.reserve_exact(count);
+    }
 
-        // Bound the number of different types to limit the size of handles
-        let index = u32::try_from(index_as_usize)
-            .unwrap_or_else(|_| panic!("got more than `{}` arenas", u32::MAX));
+    /// Touch reserved but unused memory of all arenas, so that it is mapped ahead of time.
+    ///
+    /// Operating systems usually map pages on first access, and the first insertions
+    /// into a large reservation would otherwise pay for page faults. Pair this with
+    /// [`Self::with_capacity_bytes`] or [`Self::reserve_bytes`] during loading screens.
+    #[inline]
+    pub fn prewarm(&mut self) {
+        for arena in &mut self.arenas {
+            arena.prewarm();
+        }
+    }
 
-        // Insert element into the arena
-        let offset = self.0[index_as_usize].push(x);
+    /// Touch reserved but unused memory of arenas for elements of type `T`.
+    ///
+    /// See [`Self::prewarm`] for details.
+    #[inline]
+    pub fn prewarm_type<T: Unsize<Trait> + Unscrupulous>(&mut self) {
+        let vtable = get_metadata_of::<T, Trait>();
 
-        // Return handle for caller so they can access the element
-        Handle { index, offset }
+        for arena in self.arenas.iter_mut().filter(|a| a.admits(vtable)) {
+            arena.prewarm();
+        }
     }
 
-    /// Retrieve the element identified by `handle` as a trait object.
+    /// Never reuse slots of removed elements in arenas created from now on, until reclaimed.
     ///
-    /// # Safety
+    /// Removed slots become tombstones, so that stale handles can be detected with
+    /// [`Self::contains`] instead of silently aliasing new elements. Memory of tombstones
+    /// is only recovered by [`Self::reclaim_tombstones`], after which stale handles alias again.
     ///
-    /// The handle must originate from the same instance of `Hato`.
+    /// ```rust
+    /// let mut arena = hato::Hato::<dyn core::fmt::Debug>::default().with_tombstones();
+    ///
+    /// let x = arena.push(1_u8);
+    /// arena.remove(x);
+    ///
+    /// let y = arena.push(2_u8);
+    ///
+    /// assert_ne!(x, y);
+    /// assert!(!arena.contains(x));
+    /// ```
     #[inline]
     #[must_use]
-    pub unsafe fn get(&self, handle: Handle) -> &Trait {
-        self.0[handle.index as usize].get(handle.offset)
+    pub const fn with_tombstones(mut self) -> Self {
+        self.options.tombstones = true;
+        self
     }
 
-    /// Retrieve the element identified by `handle` as a mutable trait object.
+    /// Make slots of removed elements available again, in all arenas with tombstones.
     ///
-    /// # Safety
+    /// Trailing tombstones are released, and others are handed out to future insertions.
+    /// Handles to removed elements must not be used past this point.
+    #[inline]
+    pub fn reclaim_tombstones(&mut self) {
+        for arena in self.arenas.iter_mut().filter(|arena| arena.tombstones) {
+            arena.reclaim_tombstones();
+        }
+    }
+
+    /// Store `count` extra bytes alongside each element of arenas created from now on.
     ///
-    /// The handle must originate from the same instance of `Hato`.
+    /// Tags hold small per-element metadata, such as flags or generation counters.
+    /// They start zeroed on insertion, and are accessed with [`Self::tag`] and [`Self::tag_mut`].
+    ///
+    /// ```rust
+    /// let mut arena = hato::Hato::<dyn core::fmt::Debug>::default().with_tag_bytes(1);
+    ///
+    /// let x = arena.push(4_u16);
+    /// arena.tag_mut(x)[0] |= 0b10;
+    ///
+    /// assert_eq!(arena.tag(x), [0b10]);
+    /// ```
     #[inline]
     #[must_use]
-    pub fn get_mut(&mut self, handle: Handle) -> &mut Trait {
-        self.0[handle.index as usize].get_mut(handle.offset)
+    pub const fn with_tag_bytes(mut self, count: usize) -> Self {
+        self.options.tag_bytes = count;
+        self
     }
 
-    /// Remove the element identified by `handle` from the collection.
+    /// Store elements larger than `bytes` in individual allocations, in arenas created from now on.
+    ///
+    /// Arenas of oversized types then only grow a vector of pointers, which keeps reallocations
+    /// cheap and lifts the limit of 4GB per arena to one of 4 billion elements, with the default
+    /// [`Index`] type. Memory of removed elements is freed right away. Access and removal go
+    /// through handles as usual.
+    ///
+    /// ```rust
+    /// let mut arena = hato::Hato::<dyn core::fmt::Debug>::default().with_spill_threshold(1024);
+    ///
+    /// let blob = arena.push([7_u8; 4096]);
+    /// let small = arena.push(3_u16);
+    ///
+    /// assert_eq!(format!("{:?}", unsafe { arena.get(small) }), "3");
+    /// assert!(format!("{:?}", unsafe { arena.get(blob) }).starts_with("[7, 7"));
+    ///
+    /// arena.remove(blob);
+    /// ```
     #[inline]
-    pub fn remove(&mut self, handle: Handle) {
-        self.0[handle.index as usize].remove(handle.offset);
+    #[must_use]
+    pub const fn with_spill_threshold(mut self, bytes: usize) -> Self {
+        self.options.spill_threshold = Some(bytes);
+        self
     }
-}
-
-#[derive(Debug)]
-struct Arena<Trait: ?Sized + Pointee<Metadata = DynMetadata<Trait>>> {
-    vtable: DynMetadata<Trait>,
-    bytes: AVec<u8>,
-    slots: Vec<u32>,
-}
 
-impl<Trait: ?Sized + Pointee<Metadata = DynMetadata<Trait>>> Clone for Arena<Trait> {
-    fn clone(&self) -> Self {
-        Self {
-            vtable: self.vtable,
-            bytes: self.bytes.clone(),
-            slots: self.slots.clone(),
-        }
+    /// Share arenas created from now on between all types of the same size and alignment.
+    ///
+    /// Codebases with hundreds of tiny types then need far fewer arenas, at the cost
+    /// of a virtual table and type identifier stored per slot, in a sidecar.
+    /// Elements of different types are interleaved in shared arenas, so traversals
+    /// restricted to a single type skip over the others.
+    ///
+    /// ```rust
+    /// let mut arena = hato::Hato::<dyn core::fmt::Debug>::default().with_size_classes();
+    ///
+    /// let x = arena.push(1_u32);
+    /// let y = arena.push(2.5_f32);
+    ///
+    /// assert_eq!(arena.slack().len(), 1);
+    /// assert_eq!(format!("{:?}", unsafe { (arena.get(x), arena.get(y)) }), "(1, 2.5)");
+    /// ```
+    #[inline]
+    #[must_use]
+    pub const fn with_size_classes(mut self) -> Self {
+        self.options.shared = true;
+        self
     }
-}
 
-impl<Trait: ?Sized + Pointee<Metadata = DynMetadata<Trait>>> Arena<Trait> {
+    /// Number of bytes reserved by each arena beyond those used by its slots, in index order.
     #[inline]
-    fn new<T>(vtable: DynMetadata<Trait>) -> Self {
-        // ! SAFETY: Force base pointer alignment so individual elements are always
-        // ! stored at valid addresses, even on re-allocation events
-        let bytes = AVec::new(align_of::<T>());
+    #[must_use]
+    pub fn slack(&self) -> impl DoubleEndedIterator<Item = usize> + ExactSizeIterator + '_ {
+        self.arenas
+            .iter()
+            .map(|arena| arena.bytes.capacity() - arena.bytes.len())
+    }
 
-        Self {
-            vtable,
-            bytes,
-            slots: Vec::new(),
+    /// Give memory reserved beyond the elements of each arena back to the allocator.
+    ///
+    /// Arenas left without elements release all of their memory, while staying in the directory
+    /// so that handles to other arenas remain valid. Others shrink their buffer to the end
+    /// of their last slot, which may move elements, as reported to [`Self::on_relocate`].
+    /// Free slots between elements are kept, as handles depend on their offsets.
+    /// Backends of fixed size keep their capacity, see [`Storage::shrink_to_fit`].
+    ///
+    /// ```rust
+    /// let mut arena = hato::Hato::<dyn core::fmt::Debug>::default();
+    ///
+    /// let xs = (0..100_u32).map(|i| arena.push(i)).collect::<Vec<_>>();
+    /// let y = arena.push(1_u8);
+    ///
+    /// for x in xs {
+    ///     arena.remove(x);
+    /// }
+    ///
+    /// arena.shrink_to_fit();
+    ///
+    /// assert!(arena.slack().all(|slack| slack == 0));
+    /// assert_eq!(format!("{:?}", unsafe { arena.get(y) }), "1");
+    /// ```
+    #[inline]
+    pub fn shrink_to_fit(&mut self) {
+        for arena in &mut self.arenas {
+            arena.shrink_to_fit();
         }
+
+        self.arenas.shrink_to_fit();
     }
 
+    /// Insert `x` into the arena for its specific type.
+    ///
+    /// Since destructors are never invoked, types that need to be dropped are rejected
+    /// at compile time. Use [`Self::push_no_drop`] if skipping their destructor is intended.
+    ///
+    /// ```rust,compile_fail
+    /// struct Noisy(u8);
+    ///
+    /// impl Drop for Noisy {
+    ///     fn drop(&mut self) {}
+    /// }
+    ///
+    /// unsafe impl unscrupulous::Unscrupulous for Noisy {}
+    ///
+    /// let mut arena = hato::Hato::<dyn core::any::Any>::default();
+    /// let _x = arena.push(Noisy(4)); // ! Does not compile
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// This function will panic if the number of arenas overflows the index type,
+    /// or if the quota of `T` is exhausted, see [`Self::set_quota`].
     #[inline]
-    fn is_full(&self) -> bool {
-        u32::try_from(self.bytes.len()).is_err()
+    pub fn push<T: Unsize<Trait> + Unscrupulous>(&mut self, x: T) -> Handle {
+        // Reject types whose destructor would silently be skipped
+        const { assert!(!needs_drop::<T>(), "destructors of elements never run") }
+
+        self.push_no_drop(x)
     }
 
+    /// Insert `x` into the arena for its specific type, even if it needs to be dropped.
+    ///
+    /// The destructor of `x` will **not** be invoked, unless done manually.
+    ///
+    /// # Panics
+    ///
+    /// This function will panic if the number of arenas overflows the index type,
+    /// or if the quota of `T` is exhausted, see [`Self::set_quota`].
     #[inline]
-    fn push<T: Unsize<Trait> + Unscrupulous>(&mut self, x: T) -> u32 {
-        // Check caller is inserting an element of the correct type
-        debug_assert_eq!(self.vtable, get_metadata_of_ref(&x));
-
-        // Reinterpret object as a slice of bytes to be copied to buffer
-        let slice = as_slice_of_bytes(&x);
+    pub fn push_no_drop<T: Unsize<Trait> + Unscrupulous>(&mut self, x: T) -> Handle {
+        // Compact fragmented arenas first, so that the new handle stays valid afterwards
+        self.maintain_if_pending();
 
-        // Position of the element in the buffer
-        let offset = if let Some(offset) = self.slots.pop() {
-            // Offset is a valid `usize` by initial construction in previous `push`
-            #[allow(clippy::cast_possible_truncation)]
-            let offset_as_usize = offset as usize;
+        // Identify individual types at runtime using their virtual table pointer
+        let kind = (typeid::of::<T>(), get_metadata_of_ref(&x));
 
-            // Copy object over to buffer, overwriting previous element
-            self.bytes[offset_as_usize..offset_as_usize + align_of::<T>()].copy_from_slice(slice);
+        // Reinterpret object as a slice of bytes to be copied to its arena
+        let handle = self.push_kind(as_slice_of_bytes(&x), kind);
 
-            offset
-        } else {
-            // Fit byte offset in a `u32` to limit the size of handles
-            let offset = u32::try_from(self.bytes.len())
-                .expect("individual arenas should hold less than 4GB of data");
+        // Prevent destructor from running on scope end
+        core::mem::forget(x);
 
-            // Copy object over to buffer, valid thanks to `Unscrupulous` trait bound
-            self.bytes.extend_from_slice(slice);
+        handle
+    }
 
-            offset
-        };
+    /// Insert a copy of `x`, leaving the original with the caller.
+    ///
+    /// Elements are copied bit by bit thanks to the [`Unscrupulous`] bound, straight from
+    /// the reference into the arena, so a prototype can be inserted many times over
+    /// without cloning or moving it first.
+    ///
+    /// ```rust
+    /// let mut arena = hato::Hato::<dyn core::fmt::Debug>::default();
+    /// let prototype = [7_u8; 16];
+    ///
+    /// let handles = (0..4).map(|_| arena.push_copy(&prototype)).collect::<Vec<_>>();
+    ///
+    /// assert_eq!(format!("{:?}", unsafe { arena.get(handles[3]) }), format!("{prototype:?}"));
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// This function will panic if the number of arenas overflows the index type,
+    /// or if the quota of `T` is exhausted, see [`Self::set_quota`].
+    #[inline]
+    pub fn push_copy<T: Unsize<Trait> + Unscrupulous>(&mut self, x: &T) -> Handle {
+        // Reject types whose destructor would silently be skipped
+        const { assert!(!needs_drop::<T>(), "destructors of elements never run") }
 
-        // Prevent destructor from running on scope end
-        core::mem::forget(x);
+        self.maintain_if_pending();
 
-        offset
+        let kind = (typeid::of::<T>(), get_metadata_of_ref(x));
+        self.push_kind(as_slice_of_bytes(x), kind)
     }
 
+    /// Insert a copy of `x`, known only as a trait object, without naming its concrete type.
+    ///
+    /// Elements are copied bit by bit thanks to the [`Unscrupulous`] bound, so the trait
+    /// needs no cloning method. The type of `x` must already have an arena in the collection,
+    /// which proves it is [`Unscrupulous`]. Returns `None` otherwise.
+    ///
+    /// ```rust
+    /// let mut prefabs = hato::Hato::<dyn core::fmt::Debug>::default();
+    /// let prefab = prefabs.push(4_u16);
+    ///
+    /// let mut arena = hato::Hato::<dyn core::fmt::Debug>::default();
+    /// let _ = arena.push(0_u16);
+    ///
+    /// let x = arena.push_dyn_clone(unsafe { prefabs.get(prefab) }).unwrap();
+    /// assert_eq!(format!("{:?}", unsafe { arena.get(x) }), "4");
+    ///
+    /// assert!(arena.push_dyn_clone(&1_u8).is_none());
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// This function will panic if the number of arenas overflows the index type,
+    /// or if the quota of its type is exhausted, see [`Self::set_quota`].
     #[inline]
-    fn get(&self, offset: u32) -> &Trait {
-        unsafe {
-            // ! SAFETY: Trait object points to a valid byte representation of this type
-            &*from_raw_parts(self.bytes.as_ptr().add(offset as usize).cast(), self.vtable)
-        }
+    pub fn push_dyn_clone(&mut self, x: &Trait) -> Option<Handle> {
+        let vtable = metadata(x);
+
+        // Only types already stored are known to be safe to copy bit by bit
+        let (type_id, _) = self
+            .arenas
+            .iter()
+            .find_map(|arena| arena.kind_of(|(_, v)| v == vtable))?;
+
+        // ! SAFETY: Trait object spans exactly the size of its type from its address
+        let ptr = from_ref(x).cast::<u8>();
+        let slice = unsafe { core::slice::from_raw_parts(ptr, vtable.size_of()) };
+
+        Some(self.push_kind(slice, (type_id, vtable)))
     }
 
+    /// Insert an element from its raw `bytes`, given the [`TypeId`] of its type.
+    ///
+    /// This skips the typed path of [`Self::push`], for foreign function interfaces
+    /// or deserializers that only know elements as bytes. The type must already have an arena
+    /// in the collection, which can be created up front with [`Self::reserve_bytes`].
+    /// Its identifier thus acts as a token, registered along with the virtual table of the type.
+    /// Virtual tables are not compared directly, as they may be duplicated across codegen units.
+    ///
+    /// ```rust
+    /// use core::any::TypeId;
+    ///
+    /// let mut arena = hato::Hato::<dyn core::fmt::Debug>::default();
+    /// arena.reserve_bytes::<u16>(0);
+    ///
+    /// let x = unsafe { arena.push_raw(&7_u16.to_ne_bytes(), TypeId::of::<u16>()) };
+    /// assert_eq!(format!("{:?}", unsafe { arena.get(x) }), "7");
+    /// ```
+    ///
+    /// # Safety
+    ///
+    /// The bytes must be a valid element of the type identified by `type_id`.
+    ///
+    /// # Panics
+    ///
+    /// This function will panic if the type has no arena, or if the length of `bytes`
+    /// differs from its size.
     #[inline]
-    fn get_mut(&mut self, offset: u32) -> &mut Trait {
-        unsafe {
-            // ! SAFETY: Trait object points to a valid byte representation of this type
-            let ptr = self.bytes.as_mut_ptr().add(offset as usize).cast();
-            &mut *from_raw_parts_mut(ptr, self.vtable)
-        }
+    pub unsafe fn push_raw(&mut self, bytes: &[u8], type_id: TypeId) -> Handle {
+        let (_, vtable) = self
+            .arenas
+            .iter()
+            .find_map(|arena| arena.kind_of(|(id, _)| id == type_id))
+            .expect("type should have an arena");
+
+        assert_eq!(
+            bytes.len(),
+            vtable.size_of(),
+            "bytes should match the type size"
+        );
+
+        self.push_kind(bytes, (type_id, vtable))
     }
 
+    /// Insert the bytes of an element of type `kind`, which must be [`Unscrupulous`].
     #[inline]
-    fn remove(&mut self, offset: u32) {
-        self.slots.push(offset);
+    pub(crate) fn push_kind(&mut self, bytes: &[u8], kind: Kind<Trait>) -> Handle {
+        self.try_push_kind(bytes, kind)
+            .unwrap_or_else(|error| panic!("failed to insert element: {error}"))
     }
-}
+
+    /// Insert all elements of `xs` at once, in a single bulk copy.
+    ///
+    /// Elements are stored contiguously in the same arena, and identified
+    /// by the returned range of handles, in the order of the vector.
+    ///
+    /// ```rust
+    /// let mut arena = hato::Hato::<dyn core::fmt::Debug>::default();
+    ///
+    /// let handles = arena.absorb_vec(vec![1_u8, 2, 3]);
+    /// assert_eq!(handles.len(), 3);
+    ///
+    /// for (h, expected) in handles.zip(["1", "2", "3"]) {
+    ///     assert_eq!(format!("{:?}", unsafe { arena.get(h) }), expected);
+    /// }
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// This function will panic if the elements do not fit in a single arena,
+    /// or if the number of arenas overflows the index type.
+    #[inline]
+    pub fn absorb_vec<T: Unsize<Trait> + Unscrupulous>(&mut self, xs: Vec<T>) -> HandleRange {
+        // Reject types whose destructor would silently be skipped
+        const { assert!(!needs_drop::<T>(), "destructors of elements never run") }
+
+        // Identify type without requiring an instance, as the vector may be empty
+        let vtable = get_metadata_of::<T, Trait>();
+
+        // Index of arena that has room for all elements at its end
+        let index = self.index_with_room(typeid::of::<T>(), vtable, xs.len());
+
+        // Copy all elements over at once
+        let (start, end) = self.arenas[index as usize].extend(&xs);
+
+        // Elements were moved into the arena, only the vector's buffer remains to be freed
+        drop(xs);
+
+        let handles = HandleRange {
+            index,
+            start,
+            end,
+            stride: self.arenas[index as usize].stride,
+        };
+
+        #[cfg(feature = "shadow")]
+        for handle in handles.clone() {
+            let arena = &self.arenas[index as usize];
+            self.shadow.insert(handle, || arena.element(handle.offset));
+        }
+
+        handles
+    }
+
+    /// Build a collection from all elements of `xs`, in a single bulk copy.
+    ///
+    /// See [`Self::absorb_vec`] for details.
+    ///
+    /// # Panics
+    ///
+    /// This function will panic if the elements do not fit in a single arena.
+    #[inline]
+    #[must_use]
+    pub fn from_vec<T: Unsize<Trait> + Unscrupulous>(xs: Vec<T>) -> (Self, HandleRange) {
+        let mut hato = Self::default();
+        let handles = hato.absorb_vec(xs);
+
+        (hato, handles)
+    }
+
+    /// Move all elements of type `T` out of the collection, emptying their arenas.
+    ///
+    /// Arenas without free slots are moved out with a single copy. Handles to extracted
+    /// elements are invalidated, and their slots will be reused by future insertions.
+    ///
+    /// ```rust
+    /// let mut arena = hato::Hato::<dyn core::fmt::Debug>::default();
+    ///
+    /// let _ = arena.push(1_u8);
+    /// let _ = arena.push(2_i32);
+    /// let _ = arena.push(3_u8);
+    ///
+    /// assert_eq!(arena.extract_all::<u8>(), [1, 3]);
+    /// assert_eq!(arena.extract_all::<u8>(), []);
+    /// ```
+    #[inline]
+    pub fn extract_all<T: Unsize<Trait> + Unscrupulous>(&mut self) -> Vec<T> {
+        let vtable = get_metadata_of::<T, Trait>();
+
+        let mut xs = Vec::new();
+
+        for arena in self.arenas.iter_mut().filter(|arena| arena.admits(vtable)) {
+            arena.drain_into(&mut xs);
+        }
+
+        // Forget names of extracted elements
+        let arenas = &self.arenas;
+        self.names
+            .retain(|handle| arenas[handle.index as usize].contains(handle.offset));
+        self.shadow
+            .retain(|handle| arenas[handle.index as usize].contains(handle.offset));
+
+        xs
+    }
+
+    /// Remove all elements of type `T` for which `f` returns `false`, leaving other types alone.
+    ///
+    /// The predicate gets direct mutable access to elements, without virtual dispatch,
+    /// and only arenas of `T` are visited.
+    ///
+    /// ```rust
+    /// let mut arena = hato::Hato::<dyn core::fmt::Debug>::default();
+    ///
+    /// let x = arena.push(1_u8);
+    /// let y = arena.push(2_u8);
+    /// let z = arena.push(3_i32);
+    ///
+    /// arena.retain_of::<u8>(|x| {
+    ///     *x *= 10;
+    ///     *x > 10
+    /// });
+    ///
+    /// assert!(!arena.contains(x));
+    /// assert_eq!(format!("{:?}", unsafe { arena.get(y) }), "20");
+    /// assert_eq!(format!("{:?}", unsafe { arena.get(z) }), "3");
+    /// ```
+    #[inline]
+    pub fn retain_of<T: Unsize<Trait> + Unscrupulous>(
+        &mut self,
+        mut f: impl FnMut(&mut T) -> bool,
+    ) {
+        let vtable = get_metadata_of::<T, Trait>();
+
+        for (index, arena) in self.arenas.iter_mut().enumerate() {
+            if !arena.admits(vtable) {
+                continue;
+            }
+
+            let index = index_of(index);
+
+            let live = (0..arena.occupied.len()).filter(|slot| arena.occupied[*slot]);
+            let live = live.filter(|slot| arena.kind(*slot).1 == vtable);
+            let offsets = live.map(|slot| arena.offset(slot)).collect::<Vec<_>>();
+
+            for offset in offsets {
+                // ! SAFETY: Slot holds a valid element of type `T`, as the slot's vtable is
+                // ! its own
+                let x = unsafe { &mut *arena.ptr_mut(offset).cast::<T>() };
+
+                let handle = Handle { index, offset };
+
+                if f(x) {
+                    // Element may have been modified, past what the model can follow
+                    self.shadow.touch(handle);
+                } else {
+                    arena.remove(offset);
+                    self.names.remove(handle);
+                    self.shadow.remove(handle);
+                }
+            }
+        }
+
+        for index in 0..self.arenas.len() {
+            self.removed_from(index);
+        }
+
+        self.forget_removed_forwards();
+    }
+
+    /// Release all elements of types for which `f` returns `false`, along with their memory.
+    ///
+    /// Types are identified with [`typeid::of`], which matches [`TypeId::of`] for `'static` types.
+    /// The predicate is evaluated once per arena, regardless of the number of elements,
+    /// or once per element of arenas shared across types with [`Self::with_size_classes`].
+    /// Emptied arenas stay in place so that handles to other elements remain valid.
+    ///
+    /// ```rust
+    /// use core::any::TypeId;
+    ///
+    /// let mut arena = hato::Hato::<dyn core::fmt::Debug>::default();
+    ///
+    /// let _ = arena.push(1_u8);
+    /// let x = arena.push(2_i32);
+    ///
+    /// arena.retain_types(|id| id != TypeId::of::<u8>());
+    ///
+    /// assert_eq!(arena.extract_all::<u8>(), []);
+    /// assert_eq!(format!("{:?}", unsafe { arena.get(x) }), "2");
+    /// ```
+    #[inline]
+    pub fn retain_types(&mut self, mut f: impl FnMut(TypeId) -> bool) {
+        for arena in &mut self.arenas {
+            if !arena.shared {
+                if !f(arena.type_id) {
+                    arena.release();
+                }
+
+                continue;
+            }
+
+            // Elements of shared arenas are released one by one, keeping other types around
+            let live = (0..arena.occupied.len()).filter(|slot| arena.occupied[*slot]);
+            let dropped = live.filter(|slot| !f(arena.kind(*slot).0));
+            let offsets = dropped.map(|slot| arena.offset(slot)).collect::<Vec<_>>();
+
+            for offset in offsets {
+                arena.remove(offset);
+            }
+        }
+
+        for index in 0..self.arenas.len() {
+            self.removed_from(index);
+        }
+
+        self.forget_removed_forwards();
+
+        // Forget names of released elements
+        let arenas = &self.arenas;
+        self.names
+            .retain(|handle| arenas[handle.index as usize].contains(handle.offset));
+        self.shadow
+            .retain(|handle| arenas[handle.index as usize].contains(handle.offset));
+    }
+
+    /// Remove all elements, keeping arenas and the memory backing them for later insertions.
+    ///
+    /// Collections reused across frames then stop reallocating once they reach their peak size.
+    /// Arenas keep their directory index, so types map to the same arenas as before. Offsets
+    /// start over, which hands out handles of removed elements again, even with tombstones.
+    /// Names, forwarding entries and queued removals are discarded along with the elements.
+    ///
+    /// ```rust
+    /// let mut arena = hato::Hato::<dyn core::fmt::Debug>::default();
+    ///
+    /// let x = arena.push(1_u32);
+    /// let _ = arena.push(2_u32);
+    ///
+    /// arena.clear();
+    /// assert!(arena.is_empty());
+    ///
+    /// // Slots are handed out from the start again, in the same arena
+    /// assert_eq!(arena.push(3_u32), x);
+    /// ```
+    #[inline]
+    pub fn clear(&mut self) {
+        for arena in &mut self.arenas {
+            arena.clear();
+        }
+
+        self.names = Names::default();
+        self.shadow.retain(|_| false);
+        self.flush_forwarding();
+
+        // Guards of earlier elements keep the previous queue, so their removals are dropped
+        #[cfg(feature = "std")]
+        {
+            self.deferred = deferred::Deferred::new();
+        }
+    }
+
+    /// Retrieve the element identified by `handle` as a trait object.
+    ///
+    /// # Safety
+    ///
+    /// The handle must originate from the same instance of `Hato`. Stale handles are allowed,
+    /// and return whatever element took their slot since, as the type documentation shows.
+    ///
+    /// # Panics
+    ///
+    /// This function will panic if `handle` is stale and points past the end of its arena,
+    /// or to a removed element that was spilled, see [`Self::with_spill_threshold`].
+    #[inline]
+    #[must_use]
+    pub unsafe fn get(&self, handle: Handle) -> &Trait {
+        let handle = self.forward(handle);
+
+        let arena = &self.arenas[handle.index as usize];
+        self.shadow.check(handle, || arena.element(handle.offset));
+
+        arena.get(handle.offset)
+    }
+
+    /// Retrieve the elements identified by `handles` as trait objects, appending them to `out`.
+    ///
+    /// Elements are appended in the order of `handles`. Consecutive handles to the same arena
+    /// share a single directory lookup, so sorting handles beforehand speeds up resolution.
+    ///
+    /// ```rust
+    /// let mut arena = hato::Hato::<dyn core::fmt::Debug>::default();
+    ///
+    /// let handles = [arena.push(1_u8), arena.push(2_u8), arena.push(3_u16)];
+    ///
+    /// let mut out = Vec::new();
+    /// unsafe { arena.get_batch(&handles, &mut out) };
+    ///
+    /// assert_eq!(format!("{out:?}"), "[1, 2, 3]");
+    /// ```
+    ///
+    /// # Safety
+    ///
+    /// All handles must originate from the same instance of `Hato`.
+    #[inline]
+    pub unsafe fn get_batch<'a>(&'a self, handles: &[Handle], out: &mut Vec<&'a Trait>) {
+        out.reserve(handles.len());
+
+        // Runs of stale handles may span several arenas, so resolve them one at a time
+        if self.forwards_handles() {
+            for handle in handles {
+                // ! SAFETY: Caller guarantees all handles originate from this collection
+                out.push(unsafe { self.get(*handle) });
+            }
+
+            return;
+        }
+
+        for run in handles.chunk_by(|a, b| a.index == b.index) {
+            let arena = &self.arenas[run[0].index as usize];
+
+            for handle in run {
+                self.shadow.check(*handle, || arena.element(handle.offset));
+            }
+
+            out.extend(run.iter().map(|handle| arena.get(handle.offset)));
+        }
+    }
+
+    /// Bucket `handles` by the type of their element, in a single pass.
+    ///
+    /// Buckets follow the order in which types were first inserted, and keep handles
+    /// in their original order. Processing elements one type at a time, for instance
+    /// with [`Self::get_batch`], avoids jumping between arenas.
+    ///
+    /// ```rust
+    /// use core::any::TypeId;
+    ///
+    /// let mut arena = hato::Hato::<dyn core::fmt::Debug>::default();
+    ///
+    /// let [x, y, z] = [arena.push(1_u8), arena.push(2_u16), arena.push(3_u8)];
+    ///
+    /// assert_eq!(
+    ///     arena.group_handles_by_type(&[y, z, x]),
+    ///     [(TypeId::of::<u8>(), vec![z, x]), (TypeId::of::<u16>(), vec![y])]
+    /// );
+    /// ```
+    #[inline]
+    #[must_use]
+    pub fn group_handles_by_type(&self, handles: &[Handle]) -> Vec<(TypeId, Vec<Handle>)> {
+        let mut buckets = vec![Vec::new(); self.arenas.len()];
+
+        for handle in handles {
+            buckets[handle.index as usize].push(*handle);
+        }
+
+        let mut groups = Vec::new();
+
+        // Leave out types without any handle
+        for (arena, bucket) in self.arenas.iter().zip(buckets) {
+            if !arena.shared {
+                if !bucket.is_empty() {
+                    groups.push((arena.type_id, bucket));
+                }
+
+                continue;
+            }
+
+            // Split buckets of shared arenas further, by the type of each element
+            let start = groups.len();
+
+            for handle in bucket {
+                let (type_id, _) = arena.kind(arena.slot(handle.offset));
+
+                match groups[start..].iter_mut().find(|(id, _)| *id == type_id) {
+                    Some((_, group)) => group.push(handle),
+                    None => groups.push((type_id, vec![handle])),
+                }
+            }
+        }
+
+        groups
+    }
+
+    /// Iterate over raw pointers to live elements, one inner iterator per arena in index order.
+    ///
+    /// Pointers carry their virtual table, and are meant for hand-written unsafe loops,
+    /// with custom prefetching or gathers, where references would get in the way.
+    /// Elements of an arena are yielded in increasing address order.
+    ///
+    /// Pointers are valid for reads until the collection is next modified: insertions may move
+    /// buffers of arenas, and removals may release the memory backing elements.
+    /// Writing through them is undefined behavior, as they derive from a shared borrow.
+    ///
+    /// ```rust
+    /// let mut arena = hato::Hato::<dyn core::fmt::Debug>::default();
+    ///
+    /// let _ = arena.push(1_u8);
+    /// let _ = arena.push(2_u16);
+    /// let _ = arena.push(3_u8);
+    ///
+    /// let mut seen = Vec::new();
+    ///
+    /// for ptrs in arena.iter_ptrs() {
+    ///     for ptr in ptrs {
+    ///         // ! SAFETY: The collection is not modified while pointers are in use
+    ///         seen.push(format!("{:?}", unsafe { &*ptr }));
+    ///     }
+    /// }
+    ///
+    /// assert_eq!(seen, ["1", "3", "2"]);
+    /// ```
+    #[inline]
+    #[must_use]
+    pub fn iter_ptrs(
+        &self,
+    ) -> impl DoubleEndedIterator<
+        Item = impl DoubleEndedIterator<Item = *const Trait> + ExactSizeIterator + '_,
+    > + ExactSizeIterator
+           + '_ {
+        self.arenas.iter().map(|arena| {
+            let live = LiveSlots::new(&arena.occupied);
+            live.map(|slot| from_raw_parts(arena.ptr(arena.offset(slot)), arena.kind(slot).1))
+        })
+    }
+
+    /// Iterate over all live elements, with their handle, arena by arena.
+    ///
+    /// Slots of removed elements are skipped, so no list of handles needs to be kept aside.
+    ///
+    /// ```rust
+    /// let mut arena = hato::Hato::<dyn core::fmt::Debug>::default();
+    ///
+    /// let x = arena.push(1_u8);
+    /// let y = arena.push(2_u16);
+    /// let z = arena.push(3_u8);
+    ///
+    /// arena.remove(x);
+    ///
+    /// let seen = arena.iter().map(|(handle, x)| (handle, format!("{x:?}")));
+    /// assert_eq!(seen.collect::<Vec<_>>(), [(z, "3".into()), (y, "2".into())]);
+    /// ```
+    #[inline]
+    #[must_use]
+    pub fn iter(
+        &self,
+    ) -> impl DoubleEndedIterator<Item = (Handle, &Trait)> + ExactSizeIterator + '_ {
+        let elements = self.arenas.iter().enumerate().flat_map(|(index, arena)| {
+            let index = index_of(index);
+
+            LiveSlots::new(&arena.occupied).map(move |slot| {
+                let offset = arena.offset(slot);
+                (Handle { index, offset }, arena.get(offset))
+            })
+        });
+
+        Counted::new(elements, self.len())
+    }
+
+    /// Iterate over all live elements as mutable trait objects, with their handle, arena by arena.
+    ///
+    /// Each element is yielded once, so references to distinct elements can be held together.
+    ///
+    /// ```rust
+    /// let mut arena = hato::Hato::<dyn core::any::Any>::default();
+    ///
+    /// let x = arena.push(1_u8);
+    /// let y = arena.push(2_u8);
+    ///
+    /// for (handle, element) in arena.iter_mut() {
+    ///     if handle == x {
+    ///         *element.downcast_mut::<u8>().unwrap() = 3;
+    ///     }
+    /// }
+    ///
+    /// assert_eq!(unsafe { arena.get(x) }.downcast_ref::<u8>(), Some(&3));
+    /// assert_eq!(unsafe { arena.get(y) }.downcast_ref::<u8>(), Some(&2));
+    /// ```
+    #[inline]
+    #[must_use]
+    pub fn iter_mut(
+        &mut self,
+    ) -> impl DoubleEndedIterator<Item = (Handle, &mut Trait)> + ExactSizeIterator + '_ {
+        let len = self.len();
+        let shadow = &mut self.shadow;
+
+        let elements = self
+            .arenas
+            .iter_mut()
+            .enumerate()
+            .flat_map(|(index, arena)| {
+                let index = index_of(index);
+
+                let bases = arena.bases();
+                let arena = &*arena;
+
+                LiveSlots::new(&arena.occupied).map(move |slot| {
+                    let offset = arena.offset(slot);
+
+                    // ! SAFETY: Slot holds a valid element, and each slot is yielded once
+                    let x = unsafe { arena.element_mut(&bases, slot) };
+
+                    (Handle { index, offset }, x)
+                })
+            });
+
+        // Elements may be modified through the references, past what the model can follow
+        let elements = elements.map(|(handle, x)| {
+            shadow.touch(handle);
+            (handle, x)
+        });
+
+        Counted::new(elements, len)
+    }
+
+    /// Retrieve the element identified by `handle` as a mutable trait object.
+    ///
+    /// # Panics
+    ///
+    /// This function will panic if `handle` is stale and points past the end of its arena,
+    /// or to a removed element that was spilled, see [`Self::with_spill_threshold`].
+    #[inline]
+    #[must_use]
+    pub fn get_mut(&mut self, handle: Handle) -> &mut Trait {
+        let handle = self.forward(handle);
+
+        // Element may be modified through the reference, past what the model can follow
+        self.shadow.touch(handle);
+
+        self.arenas[handle.index as usize].get_mut(handle.offset)
+    }
+
+    /// Copy the element identified by `handle` out of the collection, into its own allocation.
+    ///
+    /// The box has the size and alignment of the concrete type of the element, so it can be
+    /// handed to code expecting standard boxed trait objects. Unlike elements of the collection,
+    /// the copy runs the destructor of its type when dropped.
+    ///
+    /// ```rust
+    /// let mut arena = hato::Hato::<dyn core::fmt::Debug>::default();
+    /// let x = arena.push(4_u16);
+    ///
+    /// let boxed: Box<dyn core::fmt::Debug> = unsafe { arena.clone_out(x) };
+    /// arena.remove(x);
+    ///
+    /// assert_eq!(format!("{boxed:?}"), "4");
+    /// ```
+    ///
+    /// # Safety
+    ///
+    /// The handle must originate from the same instance of `Hato`.
+    #[inline]
+    #[must_use]
+    pub unsafe fn clone_out(&self, handle: Handle) -> Box<Trait> {
+        let handle = self.forward(handle);
+
+        let arena = &self.arenas[handle.index as usize];
+        self.shadow.check(handle, || arena.element(handle.offset));
+
+        let (_, vtable) = arena.kind(arena.slot(handle.offset));
+        let layout = vtable.layout();
+
+        // Zero-sized types need no allocation, only a well-aligned address
+        let ptr = if layout.size() == 0 {
+            without_provenance_mut(layout.align())
+        } else {
+            // ! SAFETY: Layout has a non-zero size
+            let ptr = unsafe { alloc(layout) };
+
+            if ptr.is_null() {
+                handle_alloc_error(layout);
+            }
+
+            ptr
+        };
+
+        // ! SAFETY: Allocation spans exactly the size of the element, valid as bytes
+        // ! thanks to `Unscrupulous` bound, and is owned by the box from now on
+        unsafe {
+            core::ptr::copy_nonoverlapping(arena.ptr(handle.offset), ptr, layout.size());
+            Box::from_raw(from_raw_parts_mut(ptr, vtable))
+        }
+    }
+
+    /// Remove the element identified by `handle`, handing it back as a value of type `T`.
+    ///
+    /// The element can then move to another container, and its destructor runs as usual
+    /// once the value is dropped. Its slot is handed out to future insertions.
+    ///
+    /// ```rust
+    /// let mut arena = hato::Hato::<dyn core::fmt::Debug>::default();
+    ///
+    /// let x = arena.push([1_u8, 2, 3]);
+    /// let elsewhere = vec![arena.take::<[u8; 3]>(x)];
+    ///
+    /// assert_eq!(elsewhere, [[1, 2, 3]]);
+    /// assert!(!arena.contains(x));
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// This function will panic if `handle` does not identify a live element,
+    /// or if the element is not of type `T`.
+    #[inline]
+    pub fn take<T: Unsize<Trait> + Unscrupulous>(&mut self, handle: Handle) -> T {
+        assert!(
+            self.contains(handle),
+            "handle should identify a live element"
+        );
+
+        let current = self.forward(handle);
+
+        let arena = &self.arenas[current.index as usize];
+        self.shadow.check(current, || arena.element(current.offset));
+
+        let slot = arena.slot(current.offset);
+        assert_eq!(
+            arena.kind(slot).0,
+            typeid::of::<T>(),
+            "element type mismatch"
+        );
+
+        // ! SAFETY: Slot holds a valid element of type `T`, duplicated by copying bits
+        // ! thanks to `Unscrupulous` bound, and the original is discarded below
+        let x = unsafe { arena.ptr(current.offset).cast::<T>().read() };
+
+        self.remove(handle);
+        x
+    }
+
+    /// Raw bytes backing the element identified by `handle`, without padding up to the next slot.
+    ///
+    /// Elements can thus be hashed, checksummed or fed to custom codecs as they are stored.
+    ///
+    /// ```rust
+    /// let mut arena = hato::Hato::<dyn core::fmt::Debug>::default().with_cache_line_padding();
+    /// let x = arena.push(0x0102_u16);
+    ///
+    /// assert_eq!(unsafe { arena.element_bytes(x) }, 0x0102_u16.to_ne_bytes());
+    /// ```
+    ///
+    /// # Safety
+    ///
+    /// The handle must originate from the same instance of `Hato`.
+    #[inline]
+    #[must_use]
+    pub unsafe fn element_bytes(&self, handle: Handle) -> &[u8] {
+        let handle = self.forward(handle);
+
+        let arena = &self.arenas[handle.index as usize];
+        self.shadow.check(handle, || arena.element(handle.offset));
+
+        arena.element(handle.offset)
+    }
+
+    /// Tag bytes stored alongside the element identified by `handle`.
+    ///
+    /// The slice is empty unless tags were enabled with [`Self::with_tag_bytes`].
+    #[inline]
+    #[must_use]
+    pub fn tag(&self, handle: Handle) -> &[u8] {
+        let handle = self.forward(handle);
+        let arena = &self.arenas[handle.index as usize];
+        &arena.tags[arena.tag_range(arena.slot(handle.offset))]
+    }
+
+    /// Mutable tag bytes stored alongside the element identified by `handle`.
+    #[inline]
+    #[must_use]
+    pub fn tag_mut(&mut self, handle: Handle) -> &mut [u8] {
+        let handle = self.forward(handle);
+        let arena = &mut self.arenas[handle.index as usize];
+        let range = arena.tag_range(arena.slot(handle.offset));
+
+        &mut arena.tags[range]
+    }
+
+    /// Move the element identified by `handle` into `dest`, returning its handle there.
+    ///
+    /// The element is copied to the arena of its type in `dest`, and its slot is freed in `self`.
+    /// Its tag bytes follow along, as many as both collections store. The element should not
+    /// belong to any [`HandleList`] when moved.
+    ///
+    /// ```rust
+    /// let mut active = hato::Hato::<dyn core::fmt::Debug>::default();
+    /// let mut archived = hato::Hato::<dyn core::fmt::Debug>::default();
+    ///
+    /// let x = active.push(4_u16);
+    /// let y = active.transfer(x, &mut archived);
+    ///
+    /// assert!(!active.try_remove(x));
+    /// assert_eq!(format!("{:?}", unsafe { archived.get(y) }), "4");
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// This function will panic if the number of arenas of `dest` overflows the index type.
+    #[inline]
+    pub fn transfer(&mut self, handle: Handle, dest: &mut Self) -> Handle {
+        let handle = self.forward(handle);
+
+        let arena = &self.arenas[handle.index as usize];
+
+        // Catch transfers of removed elements, which would duplicate garbage
+        debug_assert!(arena.contains(handle.offset), "element was already removed");
+
+        let kind = arena.kind(arena.slot(handle.offset));
+        let index = dest.index_with_room(kind.0, kind.1, 1);
+
+        // Copy the element's bytes over, valid in any arena of the same type
+        let offset = dest.arenas[index as usize].push_bytes(arena.element(handle.offset), kind);
+
+        let moved = Handle { index, offset };
+        dest.shadow
+            .insert(moved, || dest.arenas[index as usize].element(offset));
+
+        // Carry over the common prefix of tag bytes
+        let (old, new) = (self.tag(handle), dest.tag_mut(moved));
+        let len = old.len().min(new.len());
+        new[..len].copy_from_slice(&old[..len]);
+
+        self.remove(handle);
+
+        moved
+    }
+
+    /// Build a densely packed copy holding only live elements, along with their new handles.
+    ///
+    /// The original is left untouched and remains valid, until the copy replaces it.
+    /// Tag bytes are carried over, but elements start out of any [`HandleList`].
+    ///
+    /// ```rust
+    /// let mut arena = hato::Hato::<dyn core::fmt::Debug>::default();
+    ///
+    /// let x = arena.push(1_u8);
+    /// let y = arena.push(2_u8);
+    ///
+    /// arena.remove(x);
+    ///
+    /// let (compacted, remap) = arena.compact_clone();
+    ///
+    /// assert_eq!(format!("{:?}", unsafe { compacted.get(remap.resolve(y)) }), "2");
+    /// assert_eq!(remap.get(y), Some(x));
+    /// ```
+    #[inline]
+    #[must_use]
+    pub fn compact_clone(&self) -> (Self, Remap) {
+        let mut compacted = self.empty_like();
+        let mut moves = Vec::new();
+
+        for (index, arena) in self.arenas.iter().enumerate() {
+            for slot in (0..arena.occupied.len()).filter(|slot| arena.occupied[*slot]) {
+                let old = Handle {
+                    index: index_of(index),
+                    offset: arena.offset(slot),
+                };
+                let new = self.copy_element(old, &mut compacted);
+
+                if old != new {
+                    moves.push((old, new));
+                }
+            }
+        }
+
+        let remap = moves.into_iter().collect();
+        compacted.names = self.names.remap(&remap);
+
+        (compacted, remap)
+    }
+
+    /// Copy the layout of the collection, without any element.
+    ///
+    /// Arenas keep their index, admitted types and reserved capacity, so rebuilds of
+    /// per-frame or per-request collections start pre-shaped and pre-allocated.
+    /// Handles of elements inserted in the same order as in the original then match.
+    /// Options and hooks follow as with [`Self::compact_clone`], while names do not.
+    ///
+    /// ```rust
+    /// let mut arena = hato::Hato::<dyn core::fmt::Debug>::default();
+    ///
+    /// let _ = arena.push(1_u8);
+    /// let x = arena.push(2_u16);
+    ///
+    /// let mut frame = arena.clone_empty();
+    /// assert!(frame.handles().next().is_none());
+    ///
+    /// // Arena order is kept, even when inserting types in another order
+    /// assert_eq!(frame.push(3_u16), x);
+    /// ```
+    #[inline]
+    #[must_use]
+    pub fn clone_empty(&self) -> Self {
+        let mut empty = self.empty_like();
+        empty.arenas = self.arenas.iter().map(Arena::clone_empty).collect();
+
+        for (index, arena) in empty.arenas.iter().enumerate() {
+            if !arena.relocations.is_empty() {
+                empty.shadow.exempt(index);
+            }
+        }
+
+        empty
+    }
+
+    /// Empty collection with the same options and hooks, except for the remap observer.
+    #[inline]
+    fn empty_like(&self) -> Self {
+        Self {
+            arenas: Vec::new(),
+            options: self.options,
+            names: Names::default(),
+            shadow: Shadow::default(),
+            compaction: self.compaction.policy(),
+            relocations: self.relocations.clone(),
+            quotas: self.quotas.clone(),
+            #[cfg(feature = "std")]
+            pool: self.pool.clone(),
+            #[cfg(feature = "std")]
+            deferred: deferred::Deferred::new(),
+        }
+    }
+
+    /// Copy the live element `handle` to `dest` along with its tag bytes, returning its handle.
+    #[inline]
+    fn copy_element(&self, handle: Handle, dest: &mut Self) -> Handle {
+        let arena = &self.arenas[handle.index as usize];
+        let slot = arena.slot(handle.offset);
+
+        let (slice, kind) = (arena.element(handle.offset), arena.kind(slot));
+
+        let index = dest.index_with_room(kind.0, kind.1, 1);
+        let offset = dest.arenas[index as usize].push_bytes(slice, kind);
+
+        let copied = Handle { index, offset };
+        dest.shadow.insert(copied, || slice);
+
+        dest.tag_mut(copied)
+            .copy_from_slice(&arena.tags[arena.tag_range(slot)]);
+
+        copied
+    }
+
+    /// Number of live elements in the collection.
+    ///
+    /// Arenas keep count of their elements, so this only visits the directory.
+    ///
+    /// ```rust
+    /// let mut arena = hato::Hato::<dyn core::fmt::Debug>::default();
+    /// assert!(arena.is_empty());
+    ///
+    /// let x = arena.push(1_u8);
+    /// let _ = arena.push(2_u16);
+    /// arena.remove(x);
+    ///
+    /// assert_eq!(arena.len(), 1);
+    /// assert_eq!(arena.arena_lens().collect::<Vec<_>>(), [0, 1]);
+    /// ```
+    #[inline]
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.arenas.iter().map(|arena| arena.live).sum()
+    }
+
+    /// Check whether the collection holds no live element.
+    #[inline]
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.arenas.iter().all(|arena| arena.live == 0)
+    }
+
+    /// Number of live elements of each arena, in the order of their directory index.
+    ///
+    /// Arenas hold elements of a single type, unless shared with [`Self::with_size_classes`].
+    #[inline]
+    #[must_use]
+    pub fn arena_lens(&self) -> impl ExactSizeIterator<Item = usize> + '_ {
+        self.arenas.iter().map(|arena| arena.live)
+    }
+
+    /// Check whether `handle` identifies a live element of this collection.
+    ///
+    /// Slots of removed elements may have been reused, unless tombstones are enabled.
+    #[inline]
+    #[must_use]
+    pub fn contains(&self, handle: Handle) -> bool {
+        let handle = self.forward(handle);
+
+        self.arenas
+            .get(handle.index as usize)
+            .is_some_and(|arena| arena.contains(handle.offset))
+    }
+
+    /// Remove the element identified by `handle` from the collection.
+    ///
+    /// Removing the same element twice hands its slot to two future insertions.
+    /// Use [`Self::try_remove`] when the element might already have been removed.
+    #[inline]
+    pub fn remove(&mut self, handle: Handle) {
+        let handle = self.forward(handle);
+
+        let arena = &mut self.arenas[handle.index as usize];
+
+        // Catch double removals, which corrupt the free list
+        debug_assert!(arena.contains(handle.offset), "element was already removed");
+
+        arena.remove(handle.offset);
+        self.names.remove(handle);
+        self.shadow.remove(handle);
+        self.removed_from(handle.index as usize);
+    }
+
+    /// Remove the element identified by `handle`, unless its slot is already free.
+    ///
+    /// Returns `false` if the element was already removed, or if the handle is not
+    /// from this collection. This does **not** protect against the ABA problem:
+    /// if the slot was reused by another element, that element will be removed instead.
+    #[inline]
+    pub fn try_remove(&mut self, handle: Handle) -> bool {
+        let handle = self.forward(handle);
+
+        let Some(arena) = self.arenas.get_mut(handle.index as usize) else {
+            return false;
+        };
+
+        let contains = arena.contains(handle.offset);
+
+        if contains {
+            arena.remove(handle.offset);
+            self.names.remove(handle);
+            self.shadow.remove(handle);
+            self.removed_from(handle.index as usize);
+        }
+
+        contains
+    }
+
+    /// Find an arena for elements of type `type_id` with room for `count` more, or create one.
+    #[inline]
+    fn index_with_room(
+        &mut self,
+        type_id: TypeId,
+        vtable: DynMetadata<Trait>,
+        count: usize,
+    ) -> Index {
+        assert!(
+            self.within_quota(type_id, count),
+            "quota of elements of this type exceeded"
+        );
+
+        let found = self
+            .arenas
+            .iter()
+            .position(|arena| arena.admits(vtable) && arena.has_room(count));
+
+        let index_as_usize = found.unwrap_or(self.arenas.len());
+
+        // Bound the number of different types to limit the size of handles,
+        // before creating an arena that could not be reached
+        let index = Index::try_from(index_as_usize)
+            .unwrap_or_else(|_| panic!("got more than `{}` arenas", Index::MAX));
+
+        if found.is_none() {
+            // Create a new arena to store elements of this type
+            self.arenas.push(Arena::new(type_id, vtable, self.options));
+        }
+
+        // Buffers of recycled collections only exist alongside locks of the standard library,
+        // and collections without a pool skip the lookup entirely
+        #[cfg(feature = "std")]
+        if found.is_none() && self.pool.is_some() {
+            self.adopt_pooled(index_as_usize);
+        }
+
+        self.arenas[index_as_usize].register((type_id, vtable));
+        self.attach_relocation(index_as_usize, type_id);
+
+        index
+    }
+}
+
+/// Smallest page size of common platforms, so that prewarming touches every page.
+const PAGE_SIZE: usize = 4096;
+
+/// Layout and growth options, applied to arenas on creation.
+#[derive(Clone, Copy, Debug, Default)]
+#[allow(clippy::struct_excessive_bools)] // Independent flags, rather than states of a machine
+struct Options {
+    padded: bool,
+    exact: bool,
+    tombstones: bool,
+    tag_bytes: usize,
+    spill_threshold: Option<usize>,
+    capacity_bytes: usize,
+    shared: bool,
+    aged: bool,
+}
+
+#[derive(Debug)]
+#[allow(clippy::struct_excessive_bools)] // Independent flags, rather than states of a machine
+struct Arena<Trait: ?Sized + Pointee<Metadata = DynMetadata<Trait>>, S: Storage = AVec<u8>> {
+    type_id: TypeId,
+    vtable: DynMetadata<Trait>,
+    stride: usize,
+    exact: bool,
+    tombstones: bool,
+    bytes: S,
+    spill: bool,
+    spilled: Vec<AVec<u8>>,
+    slots: Vec<Index>,
+    occupied: Vec<bool>,
+    live: usize,
+    links: Vec<Link>,
+    tag_bytes: usize,
+    tags: Vec<u8>,
+    shared: bool,
+    types: Vec<Kind<Trait>>,
+
+    /// Live elements of each type of `types`, only counted by arenas shared across types.
+    counts: Vec<usize>,
+
+    kinds: Vec<Kind<Trait>>,
+    pinned: usize,
+    relocations: Vec<(TypeId, Relocate)>,
+    aged: bool,
+    ages: Vec<u64>,
+}
+
+/// Type and virtual table of an element, stored per slot by arenas shared across types.
+type Kind<Trait> = (TypeId, DynMetadata<Trait>);
+
+impl<Trait: ?Sized + Pointee<Metadata = DynMetadata<Trait>>, S: Storage + Clone> Clone
+    for Arena<Trait, S>
+{
+    fn clone(&self) -> Self {
+        // Copy free slots along with live ones, without the sanitizer reporting it
+        self.set_poisoned(0..self.occupied.len(), false);
+
+        let clone = Self {
+            type_id: self.type_id,
+            vtable: self.vtable,
+            stride: self.stride,
+            exact: self.exact,
+            tombstones: self.tombstones,
+            bytes: self.bytes.clone(),
+            spill: self.spill,
+            spilled: self.spilled.clone(),
+            slots: self.slots.clone(),
+            occupied: self.occupied.clone(),
+            live: self.live,
+            links: self.links.clone(),
+            tag_bytes: self.tag_bytes,
+            tags: self.tags.clone(),
+            shared: self.shared,
+            types: self.types.clone(),
+            counts: self.counts.clone(),
+            kinds: self.kinds.clone(),
+            pinned: self.pinned,
+            relocations: self.relocations.clone(),
+            aged: self.aged,
+            ages: self.ages.clone(),
+        };
+
+        self.set_poisoned(0..self.occupied.len(), true);
+        clone.set_poisoned(0..clone.occupied.len(), true);
+
+        clone
+    }
+}
+
+impl<Trait: ?Sized + Pointee<Metadata = DynMetadata<Trait>>, S: Storage> Arena<Trait, S> {
+    #[inline]
+    fn new(type_id: TypeId, vtable: DynMetadata<Trait>, options: Options) -> Self {
+        let mut arena = Self::empty(type_id, vtable, options);
+
+        arena.reserve_slots(arena.slots_for_bytes(options.capacity_bytes));
+        arena
+    }
+
+    /// Create an arena without reserving any capacity, whatever the options say.
+    #[inline]
+    fn empty(type_id: TypeId, vtable: DynMetadata<Trait>, options: Options) -> Self {
+        Self::try_empty(type_id, vtable, options)
+            .unwrap_or_else(|error| panic!("failed to create arena: {error}"))
+    }
+
+    /// Create an arena as [`Self::empty`] does, reporting failures instead of panicking.
+    #[inline]
+    fn try_empty(
+        type_id: TypeId,
+        vtable: DynMetadata<Trait>,
+        options: Options,
+    ) -> Result<Self, Error> {
+        // ! SAFETY: Force base pointer alignment so individual elements are always
+        // ! stored at valid addresses, even on re-allocation events
+        let bytes = S::new(vtable.align_of());
+
+        // Oversized elements each get their own allocation, out of the byte buffer
+        let spill = options
+            .spill_threshold
+            .is_some_and(|threshold| vtable.size_of() > threshold);
+
+        // Offsets of zero-sized and spilled types count slots, as they are not in the buffer
+        let stride = match vtable.size_of() {
+            _ if spill => 1,
+            0 => 1,
+            size if options.padded => size.next_multiple_of(CACHELINE_ALIGN),
+            size => size,
+        };
+
+        // Types admitted by the arena, starting with the one it was created for
+        let mut types = Vec::new();
+        let mut counts = Vec::new();
+
+        types
+            .try_reserve_exact(1)
+            .and_then(|()| counts.try_reserve_exact(1))
+            .map_err(|_| Error::AllocationFailure)?;
+
+        types.push((type_id, vtable));
+        counts.push(0);
+
+        Ok(Self {
+            type_id,
+            vtable,
+            stride,
+            exact: options.exact,
+            tombstones: options.tombstones,
+            bytes,
+            spill,
+            spilled: Vec::new(),
+            slots: Vec::new(),
+            occupied: Vec::new(),
+            live: 0,
+            links: Vec::new(),
+            tag_bytes: options.tag_bytes,
+            tags: Vec::new(),
+            shared: options.shared,
+            types,
+            counts,
+            kinds: Vec::new(),
+            pinned: 0,
+            relocations: Vec::new(),
+            aged: options.aged,
+            ages: Vec::new(),
+        })
+    }
+
+    /// Create an arena with the same layout, admitted types and capacity, but no elements.
+    #[inline]
+    fn clone_empty(&self) -> Self {
+        let mut arena = Self {
+            type_id: self.type_id,
+            vtable: self.vtable,
+            stride: self.stride,
+            exact: self.exact,
+            tombstones: self.tombstones,
+            bytes: S::new(self.bytes.align()),
+            spill: self.spill,
+            spilled: Vec::new(),
+            slots: Vec::new(),
+            occupied: Vec::new(),
+            live: 0,
+            links: Vec::new(),
+            tag_bytes: self.tag_bytes,
+            tags: Vec::new(),
+            shared: self.shared,
+            types: self.types.clone(),
+            counts: vec![0; self.types.len()],
+            kinds: Vec::new(),
+            pinned: 0,
+            relocations: self.relocations.clone(),
+            aged: self.aged,
+            ages: Vec::new(),
+        };
+
+        // Capacity of the buffer in slots, or of bookkeeping for elements out of the buffer
+        let slots = (self.bytes.capacity() / self.stride).max(self.occupied.capacity());
+        arena.reserve_slots(slots);
+
+        arena
+    }
+
+    /// Check whether `count` more elements can be appended without overflowing offsets.
+    #[inline]
+    fn has_room(&self, count: usize) -> bool {
+        let end = count
+            .checked_mul(self.stride)
+            .and_then(|n| n.checked_add(self.end()));
+        end.is_some_and(|end| Index::try_from(end).is_ok())
+    }
+
+    #[inline]
+    fn push<T: Unsize<Trait> + Unscrupulous>(&mut self, x: T) -> Index {
+        let kind = (typeid::of::<T>(), get_metadata_of_ref(&x));
+
+        // Reinterpret object as a slice of bytes to be copied to buffer
+        let offset = self.push_bytes(as_slice_of_bytes(&x), kind);
+
+        // Prevent destructor from running on scope end
+        core::mem::forget(x);
+
+        offset
+    }
+
+    /// Insert the byte representation of an element of type `kind`, admitted by this arena.
+    #[inline]
+    fn push_bytes(&mut self, slice: &[u8], kind: Kind<Trait>) -> Index {
+        // Check caller is inserting an element of the correct type
+        debug_assert!(self.admits(kind.1));
+
+        if self.spill {
+            // Copy object over to its own allocation, leaving the buffer untouched
+            return self.push_spilled(AVec::from_slice(self.bytes.align(), slice), kind);
+        }
+
+        // Position of the element in the buffer
+        if let Some(offset) = self.pop_free() {
+            let slot = self.slot(offset);
+
+            let ptr = self.ptr_mut(offset);
+            self.set_poisoned(slot..slot + 1, false);
+
+            // ! SAFETY: Copy object over to buffer, overwriting previous element of same size
+            unsafe { core::ptr::copy_nonoverlapping(slice.as_ptr(), ptr, slice.len()) };
+
+            // Flag the slot as holding a live element again
+            self.occupied[slot] = true;
+            self.live += 1;
+            self.set_kind(slot, kind);
+            self.stamp(slot);
+
+            offset
+        } else {
+            // Fit offset in an `Index` to limit the size of handles
+            let offset = Index::try_from(self.end())
+                .expect("offsets of individual arenas should fit in the index type");
+
+            let moved = self.prepare_growth(self.stride);
+            self.reserve(1);
+
+            // Copy object over to buffer, valid thanks to `Unscrupulous` trait bound
+            let anchor = self.anchor();
+            self.bytes.extend_from_slice(slice);
+            self.relocate_all(anchor);
+
+            self.occupied.push(true);
+            self.live += 1;
+            self.tags.resize(self.occupied.len() * self.tag_bytes, 0);
+            self.set_kind(self.occupied.len() - 1, kind);
+            self.stamp(self.occupied.len() - 1);
+
+            // Fill padding up to the next slot, zero-sized types occupying no bytes at all
+            if !slice.is_empty() {
+                self.bytes.resize_zeroed(self.end());
+            }
+
+            self.finish_growth(moved);
+
+            offset
+        }
+    }
+
+    /// Insert an element already copied to its own allocation, for arenas of oversized types.
+    #[inline]
+    fn push_spilled(&mut self, element: AVec<u8>, kind: Kind<Trait>) -> Index {
+        if let Some(offset) = self.pop_free() {
+            let slot = self.slot(offset);
+
+            // Hand the slot an allocation again, as removal freed it
+            self.spilled[slot] = element;
+            self.occupied[slot] = true;
+            self.live += 1;
+            self.set_kind(slot, kind);
+            self.stamp(slot);
+
+            offset
+        } else {
+            // Offsets count slots, and fit in an `Index` to limit the size of handles
+            let offset = Index::try_from(self.end())
+                .expect("offsets of individual arenas should fit in the index type");
+
+            self.reserve(1);
+
+            self.spilled.push(element);
+            self.occupied.push(true);
+            self.live += 1;
+            self.tags.resize(self.occupied.len() * self.tag_bytes, 0);
+            self.set_kind(self.occupied.len() - 1, kind);
+            self.stamp(self.occupied.len() - 1);
+
+            offset
+        }
+    }
+
+    /// Append all elements of `xs`, returning the range of their offsets.
+    #[inline]
+    fn extend<T: Unsize<Trait> + Unscrupulous>(&mut self, xs: &[T]) -> (Index, Index) {
+        let start = Index::try_from(self.end())
+            .expect("offsets of individual arenas should fit in the index type");
+
+        let moved = self.prepare_growth(xs.len() * self.stride);
+        self.reserve(xs.len());
+
+        let anchor = self.anchor();
+
+        // ! SAFETY: Elements are contiguous, and valid as bytes thanks to `Unscrupulous` bound
+        let slice = unsafe { core::slice::from_raw_parts(xs.as_ptr().cast(), size_of_val(xs)) };
+
+        if self.spill {
+            // Copy objects one by one, each to its own allocation
+            let align = self.bytes.align();
+            let spilled = slice
+                .chunks(size_of::<T>())
+                .map(|x| AVec::from_slice(align, x));
+
+            self.spilled.extend(spilled);
+        } else if self.stride == size_of::<T>() || size_of::<T>() == 0 {
+            // Copy all objects over to buffer at once
+            self.bytes.extend_from_slice(slice);
+        } else {
+            // Copy objects one by one, filling padding in between
+            for x in slice.chunks(size_of::<T>()) {
+                self.bytes.extend_from_slice(x);
+                self.bytes
+                    .resize_zeroed(self.bytes.len() + self.stride - x.len());
+            }
+        }
+
+        // Report elements moved by growth of the buffer, before counting new ones as live
+        self.relocate_all(anchor);
+
+        let first = self.occupied.len();
+
+        self.occupied.resize(first + xs.len(), true);
+        self.live += xs.len();
+        self.tags.resize(self.occupied.len() * self.tag_bytes, 0);
+
+        for slot in first..self.occupied.len() {
+            self.stamp(slot);
+        }
+
+        if self.shared {
+            let kind = (typeid::of::<T>(), get_metadata_of::<T, Trait>());
+            self.kinds.resize(self.occupied.len(), kind);
+            *self.count_mut(kind) += xs.len();
+        }
+
+        self.finish_growth(moved);
+
+        let end = Index::try_from(self.end())
+            .expect("offsets of individual arenas should fit in the index type");
+
+        (start, end)
+    }
+
+    /// Make room for `count` more slots, growing by exactly that much in exact-fit mode.
+    #[inline]
+    fn reserve(&mut self, count: usize) {
+        if self.exact {
+            self.reserve_slots(count);
+        }
+    }
+
+    /// Make room for exactly `count` more slots, whatever the growth mode.
+    #[inline]
+    fn reserve_slots(&mut self, count: usize) {
+        // Zero-sized and spilled types occupy no bytes of the buffer at all
+        let bytes = if self.vtable.size_of() == 0 || self.spill {
+            0
+        } else {
+            count * self.stride
+        };
+
+        let (moved, anchor) = (self.prepare_growth(bytes), self.anchor());
+        self.bytes.reserve_exact(bytes);
+        self.relocate_all(anchor);
+        self.finish_growth(moved);
+
+        self.spilled
+            .reserve_exact(if self.spill { count } else { 0 });
+        self.occupied.reserve_exact(count);
+        self.tags.reserve_exact(count * self.tag_bytes);
+        self.kinds
+            .reserve_exact(if self.shared { count } else { 0 });
+        self.ages.reserve_exact(if self.aged { count } else { 0 });
+    }
+
+    /// Write to each page of spare capacity of the buffer, forcing it to be mapped.
+    #[inline]
+    fn prewarm(&mut self) {
+        let ptr = self.bytes.as_mut_ptr();
+
+        for position in (self.bytes.len()..self.bytes.capacity()).step_by(PAGE_SIZE) {
+            // ! SAFETY: Position lies within the allocation, past live bytes,
+            // ! and volatile writes are not optimized away
+            unsafe { ptr.add(position).write_volatile(0) };
+        }
+    }
+
+    /// Number of slots whose elements fit in `bytes`, rounded up.
+    #[inline]
+    fn slots_for_bytes(&self, bytes: usize) -> usize {
+        match self.vtable.size_of() {
+            // Zero-sized types never need any storage for their elements
+            0 => 0,
+            size if self.spill => bytes.div_ceil(size),
+            _ => bytes.div_ceil(self.stride),
+        }
+    }
+
+    /// Move all live elements into `xs`, then empty the arena while keeping its capacity.
+    ///
+    /// Arenas shared across types only give up their elements of type `T`.
+    #[inline]
+    fn drain_into<T: Unsize<Trait> + Unscrupulous>(&mut self, xs: &mut Vec<T>) {
+        let vtable = get_metadata_of::<T, Trait>();
+
+        // Check caller is extracting elements of the correct type
+        debug_assert!(self.admits(vtable));
+
+        if self.shared {
+            let live = (0..self.occupied.len()).filter(|slot| self.occupied[*slot]);
+            let live = live.filter(|slot| self.kind(*slot).1 == vtable);
+
+            for offset in live.map(|slot| self.offset(slot)).collect::<Vec<_>>() {
+                // ! SAFETY: Slot holds a valid element of type `T`, duplicated by copying bits
+                // ! thanks to `Unscrupulous` bound, and the original is discarded right after
+                xs.push(unsafe { self.ptr(offset).cast::<T>().read() });
+                self.remove(offset);
+            }
+
+            return;
+        }
+
+        // Tombstones are missing from the free list, so check occupancy of slots directly
+        let dense = !self.occupied.contains(&false);
+
+        if dense && self.stride == size_of::<T>() && !self.spill {
+            // Dense arena without padding, all elements can be copied at once
+            xs.reserve(self.occupied.len());
+
+            unsafe {
+                // ! SAFETY: Buffer holds valid contiguous elements, duplicated by copying bits
+                // ! thanks to `Unscrupulous` bound, and originals are discarded below
+                let dst = xs.as_mut_ptr().add(xs.len()).cast::<u8>();
+                core::ptr::copy_nonoverlapping(self.bytes.as_ptr(), dst, self.bytes.len());
+
+                xs.set_len(xs.len() + self.occupied.len());
+            }
+        } else {
+            // Pick out live elements one by one, skipping free slots
+            for slot in (0..self.occupied.len()).filter(|slot| self.occupied[*slot]) {
+                let ptr = self.ptr(self.offset(slot));
+
+                // ! SAFETY: Slot holds a valid element, duplicated by copying bits
+                // ! thanks to `Unscrupulous` bound, and originals are discarded below
+                xs.push(unsafe { ptr.cast::<T>().read() });
+            }
+        }
+
+        self.clear();
+    }
+
+    /// Discard all elements, keeping the memory backing them for later insertions.
+    #[inline]
+    fn clear(&mut self) {
+        self.set_poisoned(0..self.occupied.len(), false);
+
+        self.bytes.clear();
+        self.spilled.clear();
+        self.slots.clear();
+        self.occupied.clear();
+        self.live = 0;
+        self.links.clear();
+        self.tags.clear();
+        self.counts.fill(0);
+        self.kinds.clear();
+        self.ages.clear();
+    }
+
+    /// Discard all elements and free the memory backing them.
+    #[inline]
+    fn release(&mut self) {
+        self.set_poisoned(0..self.occupied.len(), false);
+
+        self.bytes = S::new(self.bytes.align());
+        self.spilled = Vec::new();
+        self.slots = Vec::new();
+        self.occupied = Vec::new();
+        self.live = 0;
+        self.links = Vec::new();
+        self.tags = Vec::new();
+        self.counts.fill(0);
+        self.kinds = Vec::new();
+        self.ages = Vec::new();
+    }
+
+    /// Release capacity past the last slot, or all memory once no element is left.
+    #[inline]
+    fn shrink_to_fit(&mut self) {
+        // Offsets of tombstones and pinned slots must stay out of circulation
+        if self.live == 0 && !self.tombstones && self.pinned == 0 {
+            self.release();
+            return;
+        }
+
+        // Shrinking copies free slots along with live ones, without the sanitizer reporting it
+        self.set_poisoned(0..self.occupied.len(), false);
+
+        let anchor = self.anchor();
+        self.bytes.shrink_to_fit();
+        self.relocate_all(anchor);
+
+        self.set_poisoned(0..self.occupied.len(), true);
+
+        self.spilled.shrink_to_fit();
+        self.trim_free_list();
+        self.slots.shrink_to_fit();
+        self.occupied.shrink_to_fit();
+        self.links.shrink_to_fit();
+        self.tags.shrink_to_fit();
+        self.kinds.shrink_to_fit();
+        self.ages.shrink_to_fit();
+    }
+
+    #[inline]
+    fn get(&self, offset: Index) -> &Trait {
+        let vtable = self.vtable_of(offset);
+
+        // ! SAFETY: Trait object points to a valid byte representation of this type
+        unsafe { &*from_raw_parts(self.ptr(offset), vtable) }
+    }
+
+    #[inline]
+    fn get_mut(&mut self, offset: Index) -> &mut Trait {
+        let vtable = self.vtable_of(offset);
+
+        // ! SAFETY: Trait object points to a valid byte representation of this type
+        unsafe { &mut *from_raw_parts_mut(self.ptr_mut(offset), vtable) }
+    }
+
+    /// Virtual table of the element identified by `offset`.
+    #[inline]
+    fn vtable_of(&self, offset: Index) -> DynMetadata<Trait> {
+        // Arenas dedicated to a single type need neither the slot nor the sidecar
+        if self.shared {
+            self.kind(self.slot(offset)).1
+        } else {
+            self.vtable
+        }
+    }
+
+    /// Type and virtual table of the element in `slot`.
+    #[inline]
+    fn kind(&self, slot: usize) -> Kind<Trait> {
+        // Arenas dedicated to a single type keep no sidecar
+        self.kinds
+            .get(slot)
+            .copied()
+            .unwrap_or((self.type_id, self.vtable))
+    }
+
+    /// Record the type of the element in `slot`, for arenas shared across types.
+    #[inline]
+    fn set_kind(&mut self, slot: usize, kind: Kind<Trait>) {
+        if !self.shared {
+            return;
+        }
+
+        *self.count_mut(kind) += 1;
+
+        if slot < self.kinds.len() {
+            self.kinds[slot] = kind;
+        } else {
+            self.kinds.push(kind);
+        }
+    }
+
+    /// First type admitted so far by the arena for which `f` returns `true`, if any.
+    #[inline]
+    fn kind_of(&self, mut f: impl FnMut(Kind<Trait>) -> bool) -> Option<Kind<Trait>> {
+        self.types.iter().copied().find(|kind| f(*kind))
+    }
+
+    /// Remember that elements of type `kind` may be stored in this arena.
+    #[inline]
+    fn register(&mut self, kind: Kind<Trait>) {
+        if !self.types.contains(&kind) {
+            self.types.push(kind);
+            self.counts.push(0);
+        }
+    }
+
+    /// Number of live elements of type `kind`, registering it first if needed.
+    #[inline]
+    fn count_mut(&mut self, kind: Kind<Trait>) -> &mut usize {
+        self.register(kind);
+
+        let position = self.types.iter().position(|k| *k == kind);
+        &mut self.counts[position.unwrap_or_default()]
+    }
+
+    /// Number of live elements of type `type_id`, in constant time for a given set of types.
+    #[inline]
+    fn live_of(&self, type_id: TypeId) -> usize {
+        if !self.shared {
+            return if self.type_id == type_id {
+                self.live
+            } else {
+                0
+            };
+        }
+
+        let counts = self.types.iter().zip(&self.counts);
+        counts
+            .filter(|((id, _), _)| *id == type_id)
+            .map(|(_, count)| count)
+            .sum()
+    }
+
+    /// Check whether elements with virtual table `vtable` may be stored in this arena.
+    #[inline]
+    fn admits(&self, vtable: DynMetadata<Trait>) -> bool {
+        if self.shared {
+            self.vtable.layout() == vtable.layout()
+        } else {
+            self.vtable == vtable
+        }
+    }
+
+    /// Bytes of the element identified by `offset`.
+    #[inline]
+    fn element(&self, offset: Index) -> &[u8] {
+        // ! SAFETY: Element spans exactly the size of its type from its address
+        unsafe { core::slice::from_raw_parts(self.ptr(offset), self.vtable.size_of()) }
+    }
+
+    /// Address of the element identified by `offset`.
+    ///
+    /// # Panics
+    ///
+    /// This function will panic if the element lies past the end of the buffer,
+    /// or if it was spilled and removed since.
+    #[inline]
+    fn ptr(&self, offset: Index) -> *const u8 {
+        if self.spill {
+            self.spilled[self.checked_spill(offset)].as_ptr()
+        } else {
+            let position = self.checked_position(offset);
+
+            // ! SAFETY: Position lies within the buffer, as checked above
+            unsafe { self.bytes.as_ptr().add(position) }
+        }
+    }
+
+    /// Mutable address of the element identified by `offset`.
+    ///
+    /// # Panics
+    ///
+    /// This function will panic if the element lies past the end of the buffer,
+    /// or if it was spilled and removed since.
+    #[inline]
+    fn ptr_mut(&mut self, offset: Index) -> *mut u8 {
+        if self.spill {
+            let slot = self.checked_spill(offset);
+            self.spilled[slot].as_mut_ptr()
+        } else {
+            let position = self.checked_position(offset);
+
+            // ! SAFETY: Position lies within the buffer, as checked above
+            unsafe { self.bytes.as_mut_ptr().add(position) }
+        }
+    }
+
+    /// Slot of the spilled element identified by `offset`, checked to still be allocated.
+    ///
+    /// Spilled elements are freed on removal, so stale handles to them are stopped here.
+    #[inline]
+    fn checked_spill(&self, offset: Index) -> usize {
+        let slot = offset as usize;
+
+        assert!(
+            self.occupied.get(slot).copied().unwrap_or(false),
+            "stale handle to a removed spilled element"
+        );
+
+        slot
+    }
+
+    /// Position of the element identified by `offset`, checked to lie within the buffer.
+    ///
+    /// Stale handles point past the end once trailing slots are truncated or released,
+    /// so their accesses are stopped here rather than reading freed memory.
+    #[inline]
+    fn checked_position(&self, offset: Index) -> usize {
+        // Buffers end on a slot boundary, so slots starting within them lie within them
+        if (offset as usize) < self.bytes.len() {
+            return offset as usize;
+        }
+
+        // Zero-sized types all live at the aligned base address of the buffer
+        assert!(
+            self.vtable.size_of() == 0,
+            "stale handle past the end of its arena"
+        );
+
+        0
+    }
+
+    #[inline]
+    fn remove(&mut self, offset: Index) {
+        let slot = self.slot(offset);
+
+        // Removing a free slot again leaves the count alone
+        self.live -= usize::from(self.occupied[slot]);
+
+        if self.shared && self.occupied[slot] {
+            let kind = self.kind(slot);
+            *self.count_mut(kind) -= 1;
+        }
+
+        self.occupied[slot] = false;
+
+        // Catch accesses through handles of the element from now on, in sanitized builds
+        self.set_poisoned(slot..slot + 1, true);
+
+        // Free oversized elements right away, rather than on reuse of their slot
+        if self.spill {
+            self.spilled[slot] = AVec::new(self.bytes.align());
+        }
+
+        // Elements taking the slot later on start out of any list
+        if let Some(link) = self.links.get_mut(slot) {
+            *link = Link::default();
+        }
+
+        // Elements taking the slot later on start with a cleared tag
+        let tag = self.tag_range(slot);
+        self.tags[tag].fill(0);
+
+        if self.tombstones {
+            // Leave the slot out of circulation until tombstones are reclaimed
+        } else if slot + 1 == self.occupied.len() {
+            // Shrink buffer instead of growing the free list, along with free slots before it
+            self.truncate_free_tail();
+        } else {
+            if self.exact {
+                self.slots.reserve_exact(1);
+            }
+
+            self.slots.push(offset);
+        }
+    }
+
+    /// Drop free slots at the end of the arena, along with the memory backing them.
+    ///
+    /// Slots pinned by forwarding entries are kept, so that their offsets are not handed out.
+    #[inline]
+    fn truncate_free_tail(&mut self) {
+        let pinned = self.pinned.min(self.occupied.len());
+
+        // Only look past pinned slots, so that each slot is scanned once before being cut off
+        let len = self.occupied[pinned..]
+            .iter()
+            .rposition(|occupied| *occupied)
+            .map_or(pinned, |s| pinned + s + 1);
+
+        // Bytes past the end may be written again by later insertions
+        self.set_poisoned(len..self.occupied.len(), false);
+
+        self.occupied.truncate(len);
+        self.links.truncate(len);
+        self.tags.truncate(len * self.tag_bytes);
+        self.kinds.truncate(len);
+        self.ages.truncate(len);
+        self.spilled.truncate(len);
+        self.bytes.truncate(self.end());
+
+        // Forget about free slots that were cut off once they make up most of the free list,
+        // so that stack-like removals do not walk it every time
+        if self.slots.len() > 2 * (self.occupied.len() - self.live) {
+            self.trim_free_list();
+        }
+    }
+
+    /// Drop offsets of slots that were cut off from the free list.
+    #[inline]
+    fn trim_free_list(&mut self) {
+        let end = self.end();
+        self.slots.retain(|offset| (*offset as usize) < end);
+    }
+
+    /// Take the most recently freed slot, skipping those that were cut off since.
+    #[inline]
+    fn pop_free(&mut self) -> Option<Index> {
+        while let Some(offset) = self.slots.pop() {
+            if (offset as usize) < self.end() {
+                return Some(offset);
+            }
+        }
+
+        None
+    }
+
+    /// Offsets of free slots, in the order they were freed.
+    #[inline]
+    fn free_list(&self) -> impl DoubleEndedIterator<Item = Index> + '_ {
+        let end = self.end();
+        self.slots
+            .iter()
+            .copied()
+            .filter(move |offset| (*offset as usize) < end)
+    }
+
+    /// Hand tombstoned slots out to future insertions again.
+    #[inline]
+    fn reclaim_tombstones(&mut self) {
+        self.truncate_free_tail();
+
+        // Rebuild the free list from scratch, as tombstones were never added to it
+        let free = (0..self.occupied.len()).filter(|slot| !self.occupied[*slot]);
+        self.slots = free.rev().map(|slot| self.offset(slot)).collect();
+    }
+
+    /// Check whether `offset` identifies a slot that holds a live element.
+    #[inline]
+    fn contains(&self, offset: Index) -> bool {
+        let slot = self.slot(offset);
+
+        // Reject offsets pointing inside an element, which do not come from this arena
+        self.offset(slot) == offset && self.occupied.get(slot).copied().unwrap_or(false)
+    }
+
+    /// Range of the tag bytes of `slot` in the sidecar.
+    #[inline]
+    const fn tag_range(&self, slot: usize) -> core::ops::Range<usize> {
+        slot * self.tag_bytes..(slot + 1) * self.tag_bytes
+    }
+
+    /// Index of the slot identified by `offset`, to track its occupancy.
+    #[inline]
+    const fn slot(&self, offset: Index) -> usize {
+        offset as usize / self.stride
+    }
+
+    /// Offset identifying the element stored in `slot`, as found in handles.
+    #[inline]
+    const fn offset(&self, slot: usize) -> Index {
+        // Offsets of existing slots fit in an `Index` by construction in `push`
+        #[allow(clippy::cast_possible_truncation)]
+        let offset = (slot * self.stride) as Index;
+
+        offset
+    }
+
+    /// Offset that the next appended slot would get, which may not fit in an `Index`.
+    #[inline]
+    const fn end(&self) -> usize {
+        self.occupied.len() * self.stride
+    }
+
+    /// Position in the byte buffer of the element identified by `offset`.
+    #[inline]
+    fn position(&self, offset: Index) -> usize {
+        match self.vtable.size_of() {
+            // Zero-sized types all live at the aligned base address of the buffer
+            0 => 0,
+            _ => offset as usize,
+        }
+    }
+
+    /// Mutable addresses of the elements of the arena, taken up front so that the arena
+    /// can be shared afterwards, while [`Self::element_mut`] hands out its elements.
+    #[inline]
+    fn bases(&mut self) -> Vec<Base> {
+        if self.spill {
+            self.spilled
+                .iter_mut()
+                .map(|x| Base(x.as_mut_ptr()))
+                .collect()
+        } else {
+            vec![Base(self.bytes.as_mut_ptr())]
+        }
+    }
+
+    /// Element of the `slot`-th slot as a mutable trait object, through the addresses of `bases`.
+    ///
+    /// # Safety
+    ///
+    /// `bases` must come from [`Self::bases`] on this arena, which must not have been modified
+    /// since. The slot must hold a live element, not borrowed elsewhere for the lifetime `'a`.
+    #[inline]
+    unsafe fn element_mut<'a>(&self, bases: &[Base], slot: usize) -> &'a mut Trait {
+        let ptr = if self.spill {
+            bases[slot].0
+        } else {
+            // ! SAFETY: Position lies within the buffer for offsets of this arena
+            unsafe { bases[0].0.add(self.position(self.offset(slot))) }
+        };
+
+        // ! SAFETY: Slot holds a valid element, which the caller borrows once
+        unsafe { &mut *from_raw_parts_mut(ptr, self.kind(slot).1) }
+    }
+}
+
+/// Mutable address of an element or buffer, handed out for slots that are each visited once.
+#[derive(Clone, Copy, Debug)]
+struct Base(*mut u8);
+
+// ! SAFETY: Addresses are only dereferenced for the slots a task or thread was handed,
+// ! which never overlap
+unsafe impl Send for Base {}
+
+// ! SAFETY: Same as above, addresses being shared by tasks visiting the same arena
+unsafe impl Sync for Base {}
+
+/// Integer type of the fields of handles, which bounds the number of arenas and their size.
+///
+/// Offsets are in bytes, so each arena holds less than 4GB of data. Targets with 16-bit
+/// pointers get 16-bit handles instead, limiting arenas to 64KB of data. The width only
+/// depends on the target, so that all crates sharing handles agree on it.
+#[cfg(not(target_pointer_width = "16"))]
+pub type Index = u32;
+
+/// Integer type of the fields of handles, which bounds the number of arenas and their size.
+#[cfg(target_pointer_width = "16")]
+pub type Index = u16;
+
+// Offsets index buffers, so conversions to `usize` must never truncate them
+const _: () = assert!(
+    Index::BITS <= usize::BITS,
+    "handle fields are wider than pointers"
+);
+
+/// Position of an arena in the directory, as handles to its elements store it.
+#[inline]
+const fn index_of(index: usize) -> Index {
+    // Directory indices fit in an `Index`, as they come from handles
+    #[allow(clippy::cast_possible_truncation)]
+    let index = index as Index;
+
+    index
+}
 
 /// Index to access an element stored in the arena.
-#[derive(Clone, Copy, Debug, Eq, Ord, PartialEq, PartialOrd)]
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
 pub struct Handle {
-    index: u32,
-    offset: u32,
+    index: Index,
+    offset: Index,
+}
+
+/// Contiguous range of handles to elements of the same arena, as returned by bulk insertions.
+#[derive(Clone, Debug)]
+pub struct HandleRange {
+    index: Index,
+    start: Index,
+    end: Index,
+    stride: usize,
+}
+
+impl Iterator for HandleRange {
+    type Item = Handle;
+
+    #[inline]
+    fn next(&mut self) -> Option<Handle> {
+        (self.start < self.end).then(|| {
+            let handle = Handle {
+                index: self.index,
+                offset: self.start,
+            };
+
+            // Stride fits in an `Index`, as it separates offsets of the same arena
+            #[allow(clippy::cast_possible_truncation)]
+            let stride = self.stride as Index;
+
+            self.start += stride;
+
+            handle
+        })
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = (self.end - self.start) as usize / self.stride;
+        (len, Some(len))
+    }
+}
+
+impl DoubleEndedIterator for HandleRange {
+    #[inline]
+    fn next_back(&mut self) -> Option<Handle> {
+        (self.start < self.end).then(|| {
+            // Stride fits in an `Index`, as it separates offsets of the same arena
+            #[allow(clippy::cast_possible_truncation)]
+            let stride = self.stride as Index;
+
+            self.end -= stride;
+
+            Handle {
+                index: self.index,
+                offset: self.end,
+            }
+        })
+    }
+}
+
+impl ExactSizeIterator for HandleRange {}
+
+/// Slots of an arena that hold live elements, counted up front to report an exact size.
+#[derive(Clone, Debug)]
+struct LiveSlots<'a> {
+    occupied: &'a [bool],
+    front: usize,
+    back: usize,
+    len: usize,
+}
+
+impl<'a> LiveSlots<'a> {
+    #[inline]
+    fn new(occupied: &'a [bool]) -> Self {
+        Self {
+            occupied,
+            front: 0,
+            back: occupied.len(),
+            len: occupied.iter().filter(|occupied| **occupied).count(),
+        }
+    }
+}
+
+impl Iterator for LiveSlots<'_> {
+    type Item = usize;
+
+    #[inline]
+    fn next(&mut self) -> Option<usize> {
+        while self.front < self.back {
+            let slot = self.front;
+            self.front += 1;
+
+            if self.occupied[slot] {
+                self.len -= 1;
+                return Some(slot);
+            }
+        }
+
+        None
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.len, Some(self.len))
+    }
+}
+
+impl DoubleEndedIterator for LiveSlots<'_> {
+    #[inline]
+    fn next_back(&mut self) -> Option<usize> {
+        while self.front < self.back {
+            self.back -= 1;
+
+            if self.occupied[self.back] {
+                self.len -= 1;
+                return Some(self.back);
+            }
+        }
+
+        None
+    }
+}
+
+impl ExactSizeIterator for LiveSlots<'_> {}
+
+/// Iterator over items counted up front, such as live elements, to report an exact size.
+#[derive(Clone, Debug)]
+struct Counted<I> {
+    inner: I,
+    len: usize,
+}
+
+impl<I> Counted<I> {
+    /// Wrap `inner`, which yields exactly `len` items.
+    #[inline]
+    const fn new(inner: I, len: usize) -> Self {
+        Self { inner, len }
+    }
+}
+
+impl<I: Iterator> Iterator for Counted<I> {
+    type Item = I::Item;
+
+    #[inline]
+    fn next(&mut self) -> Option<I::Item> {
+        let item = self.inner.next()?;
+        self.len -= 1;
+
+        Some(item)
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.len, Some(self.len))
+    }
+}
+
+impl<I: DoubleEndedIterator> DoubleEndedIterator for Counted<I> {
+    #[inline]
+    fn next_back(&mut self) -> Option<I::Item> {
+        let item = self.inner.next_back()?;
+        self.len -= 1;
+
+        Some(item)
+    }
 }
 
+impl<I: Iterator> ExactSizeIterator for Counted<I> {}
+
 /// Extract pointer to the virtual table of a specific type's implementation of `Trait`.
 const fn get_metadata_of_ref<T, Trait>(ptr: &T) -> DynMetadata<Trait>
 where
@@ -230,3 +2886,12 @@ where
 {
     metadata(from_ref::<Trait>(ptr))
 }
+
+/// Extract pointer to the virtual table of `T`'s implementation of `Trait`, without an instance.
+const fn get_metadata_of<T, Trait>() -> DynMetadata<Trait>
+where
+    T: Sized + Unscrupulous + Unsize<Trait>,
+    Trait: ?Sized + Pointee<Metadata = DynMetadata<Trait>>,
+{
+    metadata(null::<T>() as *const Trait)
+}
@@ -0,0 +1,269 @@
+use core::ptr::{DynMetadata, Pointee};
+
+use crate::{Handle, Hato, Storage};
+
+/// Ordered sequence of elements, linked through handles stored alongside them.
+///
+/// Links live in a sidecar of each arena maintained by the crate, so queues and lists
+/// of elements need no external `Vec<Handle>`. Only the two ends are stored in the list.
+///
+/// An element belongs to at most one list at a time, and should be unlinked before removal.
+///
+/// ```rust
+/// let mut arena = hato::Hato::<dyn core::fmt::Debug>::default();
+/// let mut queue = hato::HandleList::new();
+///
+/// let x = arena.push(4_u16);
+/// let y = arena.push(2_u8);
+///
+/// queue.push_back(&mut arena, y);
+/// queue.push_back(&mut arena, x);
+///
+/// assert_eq!(queue.pop_front(&mut arena), Some(y));
+/// assert_eq!(queue.front(), Some(x));
+/// ```
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct HandleList {
+    head: Option<Handle>,
+    tail: Option<Handle>,
+    len: usize,
+}
+
+/// Neighbors of an element in the list it belongs to.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Link {
+    prev: Option<Handle>,
+    next: Option<Handle>,
+}
+
+impl HandleList {
+    /// Create an empty list.
+    #[inline]
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            head: None,
+            tail: None,
+            len: 0,
+        }
+    }
+
+    /// Number of elements in the list.
+    #[inline]
+    #[must_use]
+    pub const fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Check whether the list holds no element.
+    #[inline]
+    #[must_use]
+    pub const fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// First element of the list, if any.
+    #[inline]
+    #[must_use]
+    pub const fn front(&self) -> Option<Handle> {
+        self.head
+    }
+
+    /// Last element of the list, if any.
+    #[inline]
+    #[must_use]
+    pub const fn back(&self) -> Option<Handle> {
+        self.tail
+    }
+
+    /// Append element `handle` of `hato` at the end of the list.
+    #[inline]
+    pub fn push_back<Trait, S: Storage>(&mut self, hato: &mut Hato<Trait, S>, handle: Handle)
+    where
+        Trait: ?Sized + Pointee<Metadata = DynMetadata<Trait>>,
+    {
+        *hato.link_mut(handle) = Link {
+            prev: self.tail,
+            next: None,
+        };
+
+        match self.tail {
+            Some(tail) => hato.link_mut(tail).next = Some(handle),
+            None => self.head = Some(handle),
+        }
+
+        self.tail = Some(handle);
+        self.len += 1;
+    }
+
+    /// Prepend element `handle` of `hato` at the start of the list.
+    #[inline]
+    pub fn push_front<Trait, S: Storage>(&mut self, hato: &mut Hato<Trait, S>, handle: Handle)
+    where
+        Trait: ?Sized + Pointee<Metadata = DynMetadata<Trait>>,
+    {
+        *hato.link_mut(handle) = Link {
+            prev: None,
+            next: self.head,
+        };
+
+        match self.head {
+            Some(head) => hato.link_mut(head).prev = Some(handle),
+            None => self.tail = Some(handle),
+        }
+
+        self.head = Some(handle);
+        self.len += 1;
+    }
+
+    /// Detach element `handle` from the list, which must contain it.
+    #[inline]
+    pub fn unlink<Trait, S: Storage>(&mut self, hato: &mut Hato<Trait, S>, handle: Handle)
+    where
+        Trait: ?Sized + Pointee<Metadata = DynMetadata<Trait>>,
+    {
+        let Link { prev, next } = core::mem::take(hato.link_mut(handle));
+
+        match prev {
+            Some(prev) => hato.link_mut(prev).next = next,
+            None => self.head = next,
+        }
+
+        match next {
+            Some(next) => hato.link_mut(next).prev = prev,
+            None => self.tail = prev,
+        }
+
+        self.len -= 1;
+    }
+
+    /// Detach and return the first element of the list, if any.
+    #[inline]
+    pub fn pop_front<Trait, S: Storage>(&mut self, hato: &mut Hato<Trait, S>) -> Option<Handle>
+    where
+        Trait: ?Sized + Pointee<Metadata = DynMetadata<Trait>>,
+    {
+        let head = self.head?;
+        self.unlink(hato, head);
+
+        Some(head)
+    }
+
+    /// Detach and return the last element of the list, if any.
+    #[inline]
+    pub fn pop_back<Trait, S: Storage>(&mut self, hato: &mut Hato<Trait, S>) -> Option<Handle>
+    where
+        Trait: ?Sized + Pointee<Metadata = DynMetadata<Trait>>,
+    {
+        let tail = self.tail?;
+        self.unlink(hato, tail);
+
+        Some(tail)
+    }
+
+    /// Iterate over handles of the list, from front to back.
+    ///
+    /// ```rust
+    /// let mut arena = hato::Hato::<dyn core::fmt::Debug>::default();
+    /// let mut list = hato::HandleList::new();
+    ///
+    /// let xs = [arena.push(1_u8), arena.push(2_u8), arena.push(3_u8)];
+    ///
+    /// for x in xs {
+    ///     list.push_back(&mut arena, x);
+    /// }
+    ///
+    /// assert!(list.iter(&arena).rev().eq(xs.into_iter().rev()));
+    /// ```
+    #[inline]
+    #[must_use]
+    pub fn iter<'a, Trait, S: Storage>(
+        &self,
+        hato: &'a Hato<Trait, S>,
+    ) -> impl DoubleEndedIterator<Item = Handle> + ExactSizeIterator + 'a
+    where
+        Trait: ?Sized + Pointee<Metadata = DynMetadata<Trait>>,
+    {
+        Iter {
+            hato,
+            front: self.head,
+            back: self.tail,
+            len: self.len,
+        }
+    }
+}
+
+/// Handles of a list, walked from both ends through the links of its elements.
+struct Iter<'a, Trait: ?Sized + Pointee<Metadata = DynMetadata<Trait>>, S: Storage> {
+    hato: &'a Hato<Trait, S>,
+    front: Option<Handle>,
+    back: Option<Handle>,
+    len: usize,
+}
+
+impl<Trait: ?Sized + Pointee<Metadata = DynMetadata<Trait>>, S: Storage> Iterator
+    for Iter<'_, Trait, S>
+{
+    type Item = Handle;
+
+    #[inline]
+    fn next(&mut self) -> Option<Handle> {
+        // Stop once both ends met, as handles past them were already yielded
+        let handle = self.front.filter(|_| self.len > 0)?;
+
+        self.front = self.hato.link(handle).next;
+        self.len -= 1;
+
+        Some(handle)
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.len, Some(self.len))
+    }
+}
+
+impl<Trait: ?Sized + Pointee<Metadata = DynMetadata<Trait>>, S: Storage> DoubleEndedIterator
+    for Iter<'_, Trait, S>
+{
+    #[inline]
+    fn next_back(&mut self) -> Option<Handle> {
+        let handle = self.back.filter(|_| self.len > 0)?;
+
+        self.back = self.hato.link(handle).prev;
+        self.len -= 1;
+
+        Some(handle)
+    }
+}
+
+impl<Trait: ?Sized + Pointee<Metadata = DynMetadata<Trait>>, S: Storage> ExactSizeIterator
+    for Iter<'_, Trait, S>
+{
+}
+
+impl<Trait: ?Sized + Pointee<Metadata = DynMetadata<Trait>>, S: Storage> Hato<Trait, S> {
+    /// Links of element `handle`, which default to none until set.
+    #[inline]
+    fn link(&self, handle: Handle) -> Link {
+        let arena = &self.arenas[handle.index as usize];
+
+        let slot = arena.slot(handle.offset);
+        arena.links.get(slot).copied().unwrap_or_default()
+    }
+
+    /// Mutable links of element `handle`, growing the sidecar of its arena on demand.
+    #[inline]
+    fn link_mut(&mut self, handle: Handle) -> &mut Link {
+        let arena = &mut self.arenas[handle.index as usize];
+        debug_assert!(arena.contains(handle.offset), "element is not in the arena");
+
+        let slot = arena.slot(handle.offset);
+
+        if arena.links.len() <= slot {
+            arena.links.resize(arena.occupied.len(), Link::default());
+        }
+
+        &mut arena.links[slot]
+    }
+}
@@ -0,0 +1,49 @@
+/// Build a [`Hato`](crate::Hato) from a list of elements, returning it with their handles in order.
+///
+/// ```rust
+/// use core::fmt::Debug;
+///
+/// let (arena, [x, y]) = hato::hato![dyn Debug; 1_u8, 2.5_f32];
+///
+/// assert_eq!(format!("{:?}", unsafe { arena.get(x) }), "1");
+/// assert_eq!(format!("{:?}", unsafe { arena.get(y) }), "2.5");
+/// ```
+#[macro_export]
+macro_rules! hato {
+    ($trait:ty; $($x:expr),* $(,)?) => {{
+        #[allow(unused_mut)]
+        let mut hato = $crate::Hato::<$trait>::default();
+        let handles: [$crate::Handle; _] = [$(hato.push($x)),*];
+
+        (hato, handles)
+    }};
+}
+
+/// Call an expression on every element of a [`Hato`](crate::Hato), specialized for listed types.
+///
+/// Elements of each listed type are visited with their concrete type, so that calls made
+/// on them are resolved statically, as with an enum over the closed set of types.
+/// Elements of other types are visited last, as trait objects. Elements are thus visited
+/// grouped by type, and listed types must be `'static`.
+///
+/// ```rust
+/// let mut arena = hato::Hato::<dyn core::fmt::Display>::default();
+///
+/// let _ = arena.push(1_u8);
+/// let _ = arena.push(2_i32);
+/// let _ = arena.push('c');
+///
+/// let mut text = String::new();
+/// hato::for_each_dispatch!(arena, [u8, i32], |x| text += &x.to_string());
+///
+/// assert_eq!(text, "12c");
+/// ```
+#[macro_export]
+macro_rules! for_each_dispatch {
+    ($hato:expr, [$($ty:ty),* $(,)?], |$x:ident| $body:expr) => {{
+        let hato = &$hato;
+
+        $(hato.for_each_of::<$ty>(|$x: &$ty| $body);)*
+        hato.for_each_excluding(&[$(::core::any::TypeId::of::<$ty>()),*], |$x| $body);
+    }};
+}
@@ -0,0 +1,101 @@
+use alloc::collections::BTreeMap;
+use alloc::string::String;
+
+use core::ptr::{DynMetadata, Pointee};
+
+use crate::{Handle, Hato, Remap, Storage};
+
+/// Bidirectional registry between string identifiers and handles, one name per element.
+#[derive(Clone, Debug, Default)]
+pub struct Names {
+    handles: BTreeMap<String, Handle>,
+    names: BTreeMap<Handle, String>,
+}
+
+impl Names {
+    /// Forget the name of element `handle`, if it has one.
+    #[inline]
+    pub fn remove(&mut self, handle: Handle) {
+        if let Some(name) = self.names.remove(&handle) {
+            let _ = self.handles.remove(&name);
+        }
+    }
+
+    /// Forget names of all elements for which `f` returns `false`.
+    #[inline]
+    pub fn retain(&mut self, mut f: impl FnMut(Handle) -> bool) {
+        self.names.retain(|handle, _| f(*handle));
+        self.handles.retain(|_, handle| f(*handle));
+    }
+
+    /// Point names to the new location of their element.
+    #[inline]
+    pub fn remap(&self, remap: &Remap) -> Self {
+        let mut names = Self::default();
+
+        for (name, handle) in &self.handles {
+            let handle = remap.resolve(*handle);
+
+            let _ = names.handles.insert(name.clone(), handle);
+            drop(names.names.insert(handle, name.clone()));
+        }
+
+        names
+    }
+}
+
+impl<Trait: ?Sized + Pointee<Metadata = DynMetadata<Trait>>, S: Storage> Hato<Trait, S> {
+    /// Refer to element `handle` by `name`, returning the element previously under that name.
+    ///
+    /// Each element has at most one name, so any previous name of `handle` is forgotten.
+    /// Names are forgotten as well when their element is removed.
+    ///
+    /// ```rust
+    /// let mut arena = hato::Hato::<dyn core::fmt::Debug>::default();
+    ///
+    /// let x = arena.push(4_u16);
+    /// let _ = arena.insert_named("player", x);
+    ///
+    /// assert_eq!(arena.get_named("player"), Some(x));
+    /// assert_eq!(arena.name_of(x), Some("player"));
+    ///
+    /// arena.remove(x);
+    /// assert_eq!(arena.get_named("player"), None);
+    /// ```
+    #[inline]
+    pub fn insert_named(&mut self, name: impl Into<String>, handle: Handle) -> Option<Handle> {
+        let name = name.into();
+
+        // Free the name and the handle from previous associations
+        let previous = self.remove_named(&name);
+        self.names.remove(handle);
+
+        drop(self.names.names.insert(handle, name.clone()));
+        let _ = self.names.handles.insert(name, handle);
+
+        previous
+    }
+
+    /// Handle of the element under `name`, if any.
+    #[inline]
+    #[must_use]
+    pub fn get_named(&self, name: &str) -> Option<Handle> {
+        self.names.handles.get(name).copied()
+    }
+
+    /// Name of element `handle`, if it has one.
+    #[inline]
+    #[must_use]
+    pub fn name_of(&self, handle: Handle) -> Option<&str> {
+        self.names.names.get(&handle).map(String::as_str)
+    }
+
+    /// Forget `name`, returning the handle of the element it referred to, if any.
+    #[inline]
+    pub fn remove_named(&mut self, name: &str) -> Option<Handle> {
+        let handle = self.names.handles.remove(name)?;
+        drop(self.names.names.remove(&handle));
+
+        Some(handle)
+    }
+}
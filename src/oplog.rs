@@ -0,0 +1,346 @@
+use alloc::borrow::ToOwned;
+use alloc::string::String;
+use alloc::vec::Vec;
+
+use core::any::type_name;
+use core::marker::Unsize;
+use core::ptr::{DynMetadata, Pointee};
+
+use unscrupulous::Unscrupulous;
+
+use crate::{Handle, Hato, Index, Types};
+
+/// Wrapper around [`Hato`] recording every modification into an [`OpLog`].
+///
+/// Replaying the log onto a collection in the same initial state reproduces identical handles,
+/// for bug reports or deterministic lockstep debugging.
+///
+/// ```rust
+/// let mut recorder = hato::Recorder::new(hato::Hato::<dyn core::fmt::Debug>::default());
+///
+/// let x = recorder.push(1_u8);
+/// recorder.update(x, |_| {});
+///
+/// let bytes = recorder.log().encode();
+///
+/// let log = hato::OpLog::decode(&bytes).unwrap();
+/// let types = hato::Types::default().register::<u8>();
+///
+/// let mut replayed = hato::Hato::<dyn core::fmt::Debug>::default();
+/// assert!(unsafe { log.replay(&mut replayed, &types) });
+/// assert_eq!(format!("{:?}", unsafe { replayed.get(x) }), "1");
+/// ```
+#[derive(Clone, Debug)]
+pub struct Recorder<Trait: ?Sized + Pointee<Metadata = DynMetadata<Trait>>> {
+    hato: Hato<Trait>,
+    log: OpLog,
+}
+
+/// Sequence of modifications recorded by a [`Recorder`], with the bytes of their elements.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct OpLog(Vec<Op>);
+
+/// Single modification of a collection.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Op {
+    Push { type_name: String, bytes: Vec<u8> },
+    Remove { handle: Handle },
+    Write { handle: Handle, bytes: Vec<u8> },
+}
+
+impl<Trait: ?Sized + Pointee<Metadata = DynMetadata<Trait>>> Recorder<Trait> {
+    /// Start recording modifications of `hato`, whose state replays need to start from.
+    #[inline]
+    #[must_use]
+    pub fn new(hato: Hato<Trait>) -> Self {
+        Self {
+            hato,
+            log: OpLog::default(),
+        }
+    }
+
+    /// Recorded collection.
+    #[inline]
+    #[must_use]
+    pub const fn hato(&self) -> &Hato<Trait> {
+        &self.hato
+    }
+
+    /// Modifications recorded so far.
+    #[inline]
+    #[must_use]
+    pub const fn log(&self) -> &OpLog {
+        &self.log
+    }
+
+    /// Stop recording, giving back the collection and its log.
+    #[inline]
+    #[must_use]
+    pub fn into_parts(self) -> (Hato<Trait>, OpLog) {
+        (self.hato, self.log)
+    }
+
+    /// Insert `x` into the collection, recording its type and bytes.
+    ///
+    /// # Panics
+    ///
+    /// This function will panic if the number of arenas overflows the index type.
+    #[inline]
+    pub fn push<T: Unsize<Trait> + Unscrupulous>(&mut self, x: T) -> Handle {
+        let handle = self.hato.push(x);
+
+        self.log.0.push(Op::Push {
+            type_name: type_name::<T>().to_owned(),
+            bytes: self.bytes(handle).to_vec(),
+        });
+
+        handle
+    }
+
+    /// Remove the element identified by `handle`, recording the removal.
+    #[inline]
+    pub fn remove(&mut self, handle: Handle) {
+        self.hato.remove(handle);
+        self.log.0.push(Op::Remove { handle });
+    }
+
+    /// Apply `f` to the element identified by `handle`, recording its bytes afterwards.
+    #[inline]
+    pub fn update<R>(&mut self, handle: Handle, f: impl FnOnce(&mut Trait) -> R) -> R {
+        let output = f(self.hato.get_mut(handle));
+
+        self.log.0.push(Op::Write {
+            handle,
+            bytes: self.bytes(handle).to_vec(),
+        });
+
+        output
+    }
+
+    /// Bytes of the element identified by `handle`.
+    #[inline]
+    fn bytes(&self, handle: Handle) -> &[u8] {
+        self.hato.arenas[handle.index as usize].element(handle.offset)
+    }
+}
+
+impl OpLog {
+    /// Number of recorded modifications.
+    #[inline]
+    #[must_use]
+    pub const fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    /// Check whether no modification was recorded.
+    #[inline]
+    #[must_use]
+    pub const fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Serialize the log into a compact binary representation.
+    #[inline]
+    #[must_use]
+    pub fn encode(&self) -> Vec<u8> {
+        let mut out = Vec::new();
+
+        for op in &self.0 {
+            op.encode(&mut out);
+        }
+
+        out
+    }
+
+    /// Deserialize a log produced by [`Self::encode`], or `None` if `bytes` are malformed.
+    #[inline]
+    #[must_use]
+    pub fn decode(mut bytes: &[u8]) -> Option<Self> {
+        let mut ops = Vec::new();
+
+        while !bytes.is_empty() {
+            ops.push(Op::decode(&mut bytes)?);
+        }
+
+        Some(Self(ops))
+    }
+
+    /// Deserialize the longest prefix of `bytes` holding whole modifications.
+    ///
+    /// Returns the log along with the length of that prefix. Tails torn by a crash
+    /// while appending, as with [`Wal`](crate::Wal), are thus left out.
+    #[inline]
+    #[must_use]
+    pub fn decode_intact(bytes: &[u8]) -> (Self, usize) {
+        let (mut ops, mut rest) = (Vec::new(), bytes);
+        let mut next = rest;
+
+        // Only move past modifications that decode whole
+        while let Some(op) = Op::decode(&mut next) {
+            ops.push(op);
+            rest = next;
+        }
+
+        (Self(ops), bytes.len() - rest.len())
+    }
+
+    /// Apply all recorded modifications to `hato`, in order.
+    ///
+    /// Handles match those of the recording if `hato` starts in the same state, with the
+    /// same options. Returns `false` and stops at the first modification that cannot apply,
+    /// such as insertions of types missing from `types`.
+    ///
+    /// # Safety
+    ///
+    /// The log must come from a recording of the same types, as their bytes are copied as is.
+    #[inline]
+    #[must_use]
+    pub unsafe fn replay<Trait>(&self, hato: &mut Hato<Trait>, types: &Types<Trait>) -> bool
+    where
+        Trait: ?Sized + Pointee<Metadata = DynMetadata<Trait>>,
+    {
+        for op in &self.0 {
+            match op {
+                Op::Push { type_name, bytes } => {
+                    let Some((type_id, vtable)) = types.find(type_name) else {
+                        return false;
+                    };
+
+                    if bytes.len() != vtable.size_of() {
+                        return false;
+                    }
+
+                    let index = hato.index_with_room(type_id, vtable, 1);
+                    let kind = (type_id, vtable);
+                    let offset = hato.arenas[index as usize].push_bytes(bytes, kind);
+
+                    let arena = &hato.arenas[index as usize];
+                    hato.shadow
+                        .insert(Handle { index, offset }, || arena.element(offset));
+                }
+                Op::Remove { handle } => {
+                    if !hato.try_remove(*handle) {
+                        return false;
+                    }
+                }
+                Op::Write { handle, bytes } => {
+                    if !hato.contains(*handle) {
+                        return false;
+                    }
+
+                    let arena = &mut hato.arenas[handle.index as usize];
+
+                    if bytes.len() != arena.vtable.size_of() {
+                        return false;
+                    }
+
+                    // ! SAFETY: Element spans exactly the size of its type from its address,
+                    // ! and caller guarantees bytes are a valid representation of it
+                    let ptr = arena.ptr_mut(handle.offset);
+                    unsafe { core::ptr::copy_nonoverlapping(bytes.as_ptr(), ptr, bytes.len()) };
+
+                    hato.shadow.touch(*handle);
+                }
+            }
+        }
+
+        true
+    }
+}
+
+impl Op {
+    /// Append the binary representation of the modification to `out`.
+    #[inline]
+    pub fn encode(&self, out: &mut Vec<u8>) {
+        match self {
+            Self::Push { type_name, bytes } => {
+                out.push(0);
+                encode_bytes(out, type_name.as_bytes());
+                encode_bytes(out, bytes);
+            }
+            Self::Remove { handle } => {
+                out.push(1);
+                encode_handle(out, *handle);
+            }
+            Self::Write { handle, bytes } => {
+                out.push(2);
+                encode_handle(out, *handle);
+                encode_bytes(out, bytes);
+            }
+        }
+    }
+
+    /// Split a modification off the front of `bytes`, or `None` if they are malformed.
+    #[inline]
+    pub fn decode(bytes: &mut &[u8]) -> Option<Self> {
+        let (tag, rest) = bytes.split_first()?;
+        *bytes = rest;
+
+        let op = match tag {
+            0 => Self::Push {
+                type_name: String::from_utf8(decode_bytes(bytes)?.to_vec()).ok()?,
+                bytes: decode_bytes(bytes)?.to_vec(),
+            },
+            1 => Self::Remove {
+                handle: decode_handle(bytes)?,
+            },
+            2 => Self::Write {
+                handle: decode_handle(bytes)?,
+                bytes: decode_bytes(bytes)?.to_vec(),
+            },
+            _ => return None,
+        };
+
+        Some(op)
+    }
+}
+
+/// Append `bytes` to `out`, prefixed with their length.
+fn encode_bytes(out: &mut Vec<u8>, bytes: &[u8]) {
+    // Elements and type names are smaller than the 4GB limit of arenas with the widest index
+    #[allow(clippy::cast_possible_truncation)]
+    let len = bytes.len() as u32;
+
+    out.extend_from_slice(&len.to_le_bytes());
+    out.extend_from_slice(bytes);
+}
+
+/// Append both fields of `handle` to `out`.
+fn encode_handle(out: &mut Vec<u8>, handle: Handle) {
+    out.extend_from_slice(&handle.index.to_le_bytes());
+    out.extend_from_slice(&handle.offset.to_le_bytes());
+}
+
+/// Split a length-prefixed slice of bytes off the front of `bytes`.
+fn decode_bytes<'a>(bytes: &mut &'a [u8]) -> Option<&'a [u8]> {
+    let len = decode_u32(bytes)? as usize;
+
+    let (head, rest) = bytes.split_at_checked(len)?;
+    *bytes = rest;
+
+    Some(head)
+}
+
+/// Split a handle off the front of `bytes`.
+fn decode_handle(bytes: &mut &[u8]) -> Option<Handle> {
+    Some(Handle {
+        index: decode_index(bytes)?,
+        offset: decode_index(bytes)?,
+    })
+}
+
+/// Split a little-endian [`Index`] off the front of `bytes`.
+fn decode_index(bytes: &mut &[u8]) -> Option<Index> {
+    let (head, rest) = bytes.split_first_chunk::<{ size_of::<Index>() }>()?;
+    *bytes = rest;
+
+    Some(Index::from_le_bytes(*head))
+}
+
+/// Split a little-endian `u32` off the front of `bytes`.
+fn decode_u32(bytes: &mut &[u8]) -> Option<u32> {
+    let (head, rest) = bytes.split_first_chunk::<4>()?;
+    *bytes = rest;
+
+    Some(u32::from_le_bytes(*head))
+}
@@ -0,0 +1,98 @@
+use alloc::vec;
+use alloc::vec::Vec;
+
+use core::any::TypeId;
+use core::ptr::{DynMetadata, Pointee};
+
+use crate::{index_of, Handle, Hato, Remap, Storage};
+
+impl<Trait: ?Sized + Pointee<Metadata = DynMetadata<Trait>>, S: Storage> Hato<Trait, S> {
+    /// Reorder arenas by the key `f` returns for the type of their elements.
+    ///
+    /// Traversals visit arenas in index order, so types can follow a caller-chosen order,
+    /// like the update order of systems. Hot types can also be moved to the lowest indices,
+    /// for a more cache-friendly directory. The sort is stable, and arenas shared across types
+    /// are keyed by the type they were created for.
+    ///
+    /// Elements keep their offset, but handles to elements of arenas that moved change
+    /// their index, as reported by the returned table. Stale handles forwarded by compaction,
+    /// see [`Self::with_forwarding`], map to the new handle of their element as well.
+    /// Reordered elements should not belong to any [`HandleList`](crate::HandleList).
+    ///
+    /// ```rust
+    /// use core::any::TypeId;
+    ///
+    /// let mut arena = hato::Hato::<dyn core::fmt::Debug>::default();
+    ///
+    /// let x = arena.push(1_u8);
+    /// let _ = arena.push(2_u16);
+    ///
+    /// let remap = arena.sort_arenas_by_key(|id| id != TypeId::of::<u16>());
+    ///
+    /// let mut seen = Vec::new();
+    /// arena.for_each_excluding(&[], |x| seen.push(format!("{x:?}")));
+    ///
+    /// assert_eq!(seen, ["2", "1"]);
+    /// assert_eq!(format!("{:?}", unsafe { arena.get(remap.resolve(x)) }), "1");
+    /// ```
+    #[inline]
+    pub fn sort_arenas_by_key<K: Ord>(&mut self, mut f: impl FnMut(TypeId) -> K) -> Remap {
+        let mut order = (0..self.arenas.len()).collect::<Vec<_>>();
+        order.sort_by_cached_key(|index| f(self.arenas[*index].type_id));
+
+        let mut moves = Vec::new();
+
+        for (new, old) in order.iter().copied().enumerate() {
+            if new == old {
+                continue;
+            }
+
+            let arena = &self.arenas[old];
+
+            for slot in (0..arena.occupied.len()).filter(|slot| arena.occupied[*slot]) {
+                let offset = arena.offset(slot);
+
+                let (old, new) = (index_of(old), index_of(new));
+
+                moves.push((Handle { index: old, offset }, Handle { index: new, offset }));
+            }
+        }
+
+        // Move arenas to their new index, without copying any of their elements
+        let mut arenas = core::mem::take(&mut self.arenas)
+            .into_iter()
+            .map(Some)
+            .collect::<Vec<_>>();
+
+        self.arenas = order
+            .iter()
+            .filter_map(|index| arenas[*index].take())
+            .collect();
+
+        // Forget all old handles first, as they may be reused by other arenas
+        for (old, _) in &moves {
+            self.shadow.remove(*old);
+        }
+
+        for (_, new) in &moves {
+            let arena = &self.arenas[new.index as usize];
+            self.shadow.insert(*new, || arena.element(new.offset));
+        }
+
+        let remap = moves.into_iter().collect::<Remap>();
+        self.names = self.names.remap(&remap);
+
+        // Stale handles keep being forwarded within their arena, at its new index
+        let mut indices = vec![0; order.len()];
+
+        for (new, old) in order.iter().copied().enumerate() {
+            let new = index_of(new);
+
+            indices[old] = new;
+        }
+
+        let stale = self.reindex_forwards(&remap, |index| indices[index as usize]);
+
+        remap.union(stale)
+    }
+}
@@ -0,0 +1,98 @@
+use std::sync::{Arc, PoisonError};
+
+use core::marker::Unsize;
+use core::ptr::{DynMetadata, Pointee};
+
+use unscrupulous::Unscrupulous;
+
+use crate::deferred::Queue;
+use crate::{Handle, Hato, Storage};
+
+/// Handle owning its element, whose removal is queued once the guard is dropped.
+///
+/// Elements then live as long as some scope, without a full reference counting scheme.
+/// Guards do not borrow their collection: dropping them queues the removal as
+/// [`Hato::defer_remove`] does, until the next [`Hato::apply_deferred`]. As everywhere
+/// in the crate, destructors of elements never run.
+///
+/// ```rust
+/// let mut arena = hato::Hato::<dyn core::fmt::Debug>::default();
+///
+/// let x = arena.push_owned(1_u8);
+/// let handle = x.handle();
+///
+/// {
+///     let _y = arena.push_owned(2_u8);
+/// }
+///
+/// assert_eq!(arena.apply_deferred(), 1);
+/// assert!(arena.contains(handle));
+///
+/// // Escape the guard, leaving the element in place
+/// assert_eq!(x.into_raw(), handle);
+/// assert_eq!(arena.apply_deferred(), 0);
+/// ```
+#[derive(Debug)]
+pub struct OwnedHandle {
+    handle: Handle,
+    queue: Option<Queue>,
+}
+
+impl Drop for OwnedHandle {
+    fn drop(&mut self) {
+        // Guards released by hand leave their element in place
+        if let Some(queue) = &self.queue {
+            let mut queue = queue.lock().unwrap_or_else(PoisonError::into_inner);
+            queue.push(self.handle);
+        }
+    }
+}
+
+impl OwnedHandle {
+    /// Handle of the owned element, valid until the guard is dropped.
+    #[inline]
+    #[must_use]
+    pub const fn handle(&self) -> Handle {
+        self.handle
+    }
+
+    /// Release the guard without removing its element, returning the handle.
+    #[inline]
+    #[must_use]
+    pub fn into_raw(mut self) -> Handle {
+        self.queue = None;
+        self.handle
+    }
+
+    /// Release the guard without removing its element, which stays until removed by hand.
+    #[inline]
+    pub fn forget(self) {
+        let _ = self.into_raw();
+    }
+}
+
+impl<Trait: ?Sized + Pointee<Metadata = DynMetadata<Trait>>, S: Storage> Hato<Trait, S> {
+    /// Insert `x`, returning a guard that queues its removal when dropped.
+    ///
+    /// See [`OwnedHandle`] for details.
+    ///
+    /// # Panics
+    ///
+    /// This function will panic if the number of arenas overflows the index type,
+    /// or if the quota of `T` is exhausted, see [`Self::set_quota`].
+    #[inline]
+    pub fn push_owned<T: Unsize<Trait> + Unscrupulous>(&mut self, x: T) -> OwnedHandle {
+        let handle = self.push(x);
+        self.own(handle)
+    }
+
+    /// Take ownership of the element identified by `handle`, see [`OwnedHandle`].
+    #[inline]
+    #[must_use]
+    pub fn own(&self, handle: Handle) -> OwnedHandle {
+        OwnedHandle {
+            handle,
+            queue: Some(Arc::clone(self.queue())),
+        }
+    }
+}
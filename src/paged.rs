@@ -0,0 +1,363 @@
+use std::io::{self, Read, Seek, SeekFrom, Write};
+
+use alloc::vec::Vec;
+
+use core::any::TypeId;
+use core::marker::Unsize;
+use core::ptr::{DynMetadata, Pointee};
+use core::slice;
+
+use aligned_vec::AVec;
+use unscrupulous::Unscrupulous;
+
+use crate::{get_metadata_of, Handle, Hato, Storage};
+
+/// Wrapper around [`Hato`] writing cold arenas out to a file, for data sets larger than memory.
+///
+/// Once arenas take more than a resident budget of bytes, the least recently used ones
+/// are written to the file, and their buffer is freed. Accessing an element of a paged out
+/// arena reads it back whole, paging others out in turn. Arenas keep their place in the
+/// directory meanwhile, so handles stay valid throughout. Arenas that were only read since
+/// they were paged in are not written again, as the file still holds their bytes.
+///
+/// Arenas holding pinned types, see [`Self::with_pinned`], always stay in memory.
+/// The budget counts the buffers of arenas only, without bookkeeping nor oversized elements,
+/// see [`Hato::with_spill_threshold`]. The file is typically a temporary one, although
+/// any seekable buffer works. Its contents only make sense to this collection.
+///
+/// ```rust
+/// use std::io::Cursor;
+///
+/// let mut paged = hato::HatoPaged::<dyn core::fmt::Debug, _>::new(Cursor::new(Vec::new()), 8);
+///
+/// let x = paged.push([1_u32; 2]).unwrap();
+/// let y = paged.push(2_u16).unwrap();
+///
+/// // Inserting `y` paged the arena of `x` out, which comes back on access
+/// assert!(!paged.is_resident(x) && paged.is_resident(y));
+/// assert_eq!(format!("{:?}", unsafe { paged.get(x) }.unwrap()), "[1, 1]");
+/// assert!(paged.is_resident(x) && !paged.is_resident(y));
+/// ```
+#[derive(Debug)]
+pub struct HatoPaged<Trait: ?Sized + Pointee<Metadata = DynMetadata<Trait>>, F> {
+    hato: Hato<Trait>,
+    file: F,
+
+    /// Bookkeeping of each arena, in the order of the directory.
+    pages: Vec<Page>,
+
+    resident: usize,
+    pinned: Vec<TypeId>,
+    tick: u64,
+
+    /// End of the regions of the file handed out to arenas so far.
+    end: u64,
+}
+
+/// Location of the bytes of an arena in the file, and state of its buffer.
+#[derive(Debug, Default)]
+struct Page {
+    /// Logical time of the last access to the arena.
+    used: u64,
+
+    /// Region of the file reserved for the arena, if it was ever paged out.
+    position: u64,
+    capacity: usize,
+
+    /// Number of bytes of the buffer of the arena, once paged out.
+    len: usize,
+
+    /// Whether the buffer of the arena only lives in the file.
+    paged: bool,
+
+    /// Whether the file still holds the bytes of the arena, which was not modified since.
+    clean: bool,
+}
+
+impl<Trait, F> HatoPaged<Trait, F>
+where
+    Trait: ?Sized + Pointee<Metadata = DynMetadata<Trait>>,
+    F: Read + Write + Seek,
+{
+    /// Create an empty collection, paging arenas out to `file` past `resident` bytes.
+    #[inline]
+    #[must_use]
+    pub fn new(file: F, resident: usize) -> Self {
+        Self {
+            hato: Hato::default(),
+            file,
+            pages: Vec::new(),
+            resident,
+            pinned: Vec::new(),
+            tick: 0,
+            end: 0,
+        }
+    }
+
+    /// Keep arenas holding elements of type `T` in memory, whatever the resident budget.
+    #[inline]
+    #[must_use]
+    pub fn with_pinned<T: Unsize<Trait>>(mut self) -> Self {
+        self.pinned.push(typeid::of::<T>());
+        self
+    }
+
+    /// Number of live elements, resident or paged out.
+    #[inline]
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.hato.len()
+    }
+
+    /// Check whether the collection holds no live element.
+    #[inline]
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.hato.is_empty()
+    }
+
+    /// Bytes of the buffers of resident arenas, at most the resident budget unless
+    /// pinned arenas or the last one accessed exceed it.
+    #[inline]
+    #[must_use]
+    pub fn resident_bytes(&self) -> usize {
+        self.hato.arenas.iter().map(|arena| arena.bytes.len()).sum()
+    }
+
+    /// Bytes of the buffers of arenas paged out to the file.
+    #[inline]
+    #[must_use]
+    pub fn paged_bytes(&self) -> usize {
+        self.pages
+            .iter()
+            .filter(|page| page.paged)
+            .map(|page| page.len)
+            .sum()
+    }
+
+    /// Check whether the arena of `handle` is in memory, without affecting its recency.
+    #[inline]
+    #[must_use]
+    pub fn is_resident(&self, handle: Handle) -> bool {
+        self.pages
+            .get(handle.index as usize)
+            .is_none_or(|page| !page.paged)
+    }
+
+    /// Change the resident budget to `resident` bytes, paging arenas out past it.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if arenas cannot be written to the file.
+    #[inline]
+    pub fn set_resident(&mut self, resident: usize) -> io::Result<()> {
+        self.resident = resident;
+        self.page_out_cold(None)
+    }
+
+    /// Insert `x` into the arena for its specific type, paging it in first if needed.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if arenas cannot be read from or written to the file.
+    ///
+    /// # Panics
+    ///
+    /// This function will panic if the number of arenas overflows the index type.
+    #[inline]
+    pub fn push<T: Unsize<Trait> + Unscrupulous>(&mut self, x: T) -> io::Result<Handle> {
+        let vtable = get_metadata_of::<T, Trait>();
+
+        // Insertions may land in any arena admitting the type, so they all come back first
+        for index in 0..self.hato.arenas.len() {
+            if self.hato.arenas[index].admits(vtable) {
+                self.page_in(index)?;
+            }
+        }
+
+        let handle = self.hato.push(x);
+        self.pages
+            .resize_with(self.hato.arenas.len(), Page::default);
+
+        self.touch(handle.index as usize, true);
+        self.page_out_cold(Some(handle.index as usize))?;
+
+        Ok(handle)
+    }
+
+    /// Retrieve the element identified by `handle`, paging its arena in first if needed.
+    ///
+    /// The arena becomes the most recently used one, which may page others out.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if arenas cannot be read from or written to the file.
+    ///
+    /// # Safety
+    ///
+    /// Same as [`Hato::get`], the handle must originate from this collection.
+    ///
+    /// # Panics
+    ///
+    /// This function will panic under the conditions of [`Hato::get`].
+    #[inline]
+    pub unsafe fn get(&mut self, handle: Handle) -> io::Result<&Trait> {
+        self.access(handle, false)?;
+
+        // ! SAFETY: Caller guarantees the handle originates from this collection
+        Ok(unsafe { self.hato.get(handle) })
+    }
+
+    /// Retrieve the element identified by `handle` mutably, paging its arena in first if needed.
+    ///
+    /// The arena becomes the most recently used one, which may page others out.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if arenas cannot be read from or written to the file.
+    ///
+    /// # Panics
+    ///
+    /// This function will panic under the conditions of [`Hato::get_mut`].
+    #[inline]
+    pub fn get_mut(&mut self, handle: Handle) -> io::Result<&mut Trait> {
+        self.access(handle, true)?;
+        Ok(self.hato.get_mut(handle))
+    }
+
+    /// Remove the element identified by `handle`, paging its arena in first if needed.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if arenas cannot be read from or written to the file.
+    #[inline]
+    pub fn remove(&mut self, handle: Handle) -> io::Result<()> {
+        self.access(handle, true)?;
+        self.hato.remove(handle);
+
+        Ok(())
+    }
+
+    /// Page all arenas back in, and return the underlying collection along with the file.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if arenas cannot be read from the file.
+    #[inline]
+    pub fn into_inner(mut self) -> io::Result<(Hato<Trait>, F)> {
+        for index in 0..self.pages.len() {
+            self.page_in(index)?;
+        }
+
+        Ok((self.hato, self.file))
+    }
+
+    /// Page the arena of `handle` in and mark it as most recently used, then page others out.
+    #[inline]
+    fn access(&mut self, handle: Handle, modified: bool) -> io::Result<()> {
+        let index = handle.index as usize;
+
+        self.page_in(index)?;
+        self.touch(index, modified);
+
+        self.page_out_cold(Some(index))
+    }
+
+    /// Mark the arena at `index` as most recently used, and as modified if it is.
+    #[inline]
+    fn touch(&mut self, index: usize, modified: bool) {
+        self.tick += 1;
+
+        let page = &mut self.pages[index];
+
+        page.used = self.tick;
+        page.clean &= !modified;
+    }
+
+    /// Page the least recently used arenas out until the resident ones fit within the budget.
+    ///
+    /// The arena at `keep` stays in memory, so that it can be accessed.
+    #[inline]
+    fn page_out_cold(&mut self, keep: Option<usize>) -> io::Result<()> {
+        let mut resident = self.resident_bytes();
+
+        while resident > self.resident {
+            let coldest = (0..self.pages.len())
+                .filter(|index| Some(*index) != keep && !self.is_pinned(*index))
+                .filter(|index| !self.hato.arenas[*index].bytes.is_empty())
+                .min_by_key(|index| self.pages[*index].used);
+
+            let Some(index) = coldest else {
+                return Ok(());
+            };
+
+            resident -= self.hato.arenas[index].bytes.len();
+            self.page_out(index)?;
+        }
+
+        Ok(())
+    }
+
+    /// Write the buffer of the arena at `index` to the file unless it holds it already,
+    /// and free it.
+    #[inline]
+    fn page_out(&mut self, index: usize) -> io::Result<()> {
+        let bytes = &self.hato.arenas[index].bytes;
+        let page = &mut self.pages[index];
+
+        if !page.clean {
+            // Arenas grown past their region move to the end of the file
+            if bytes.len() > page.capacity {
+                page.position = self.end;
+                page.capacity = bytes.len();
+
+                self.end += bytes.len() as u64;
+            }
+
+            // ! SAFETY: Buffer holds initialized bytes up to its length
+            let slice = unsafe { slice::from_raw_parts(bytes.as_ptr(), bytes.len()) };
+
+            let _ = self.file.seek(SeekFrom::Start(page.position))?;
+            self.file.write_all(slice)?;
+        }
+
+        page.len = bytes.len();
+        page.paged = true;
+        page.clean = true;
+
+        self.hato.arenas[index].bytes = AVec::new(bytes.align());
+
+        Ok(())
+    }
+
+    /// Read the buffer of the arena at `index` back from the file, if it was paged out.
+    #[inline]
+    fn page_in(&mut self, index: usize) -> io::Result<()> {
+        let page = &mut self.pages[index];
+
+        if !page.paged {
+            return Ok(());
+        }
+
+        let mut bytes = AVec::new(self.hato.arenas[index].bytes.align());
+        bytes.resize_zeroed(page.len);
+
+        // ! SAFETY: Buffer was just zeroed up to its length
+        let slice = unsafe { slice::from_raw_parts_mut(bytes.as_mut_ptr(), bytes.len()) };
+
+        let _ = self.file.seek(SeekFrom::Start(page.position))?;
+        self.file.read_exact(slice)?;
+
+        page.paged = false;
+        self.hato.arenas[index].bytes = bytes;
+
+        Ok(())
+    }
+
+    /// Check whether the arena at `index` holds elements of a pinned type.
+    #[inline]
+    fn is_pinned(&self, index: usize) -> bool {
+        let arena = &self.hato.arenas[index];
+        arena.kind_of(|(id, _)| self.pinned.contains(&id)).is_some()
+    }
+}
@@ -0,0 +1,163 @@
+use std::sync::Arc;
+
+use core::ptr::{DynMetadata, Pointee};
+
+use rayon::prelude::{IndexedParallelIterator, IntoParallelRefIterator, ParallelIterator};
+use rayon::slice::ParallelSlice;
+
+use crate::{index_of, Base, Handle, Hato, LiveSlots, Storage};
+
+/// Number of consecutive slots evaluated by a single task, to amortize scheduling costs.
+const CHUNK: usize = 1024;
+
+impl<Trait: ?Sized + Pointee<Metadata = DynMetadata<Trait>> + Sync, S: Storage> Hato<Trait, S> {
+    /// Iterate over all live elements in parallel, with their handle.
+    ///
+    /// Work is split per arena and by chunks of slots within each arena, so that
+    /// large arenas are spread over all threads of the pool.
+    ///
+    /// ```rust
+    /// use rayon::iter::ParallelIterator;
+    ///
+    /// let mut arena = hato::Hato::<dyn core::fmt::Debug + Sync>::default();
+    ///
+    /// for i in 0..10_000_u32 {
+    ///     let _ = arena.push(i);
+    /// }
+    ///
+    /// let len = arena.par_iter().map(|(_, x)| format!("{x:?}").len()).sum::<usize>();
+    /// assert_eq!(len, 38_890);
+    /// ```
+    #[inline]
+    #[must_use]
+    pub fn par_iter(&self) -> impl ParallelIterator<Item = (Handle, &Trait)> + '_
+    where
+        S: Sync,
+    {
+        self.arenas
+            .par_iter()
+            .enumerate()
+            .flat_map(|(index, arena)| {
+                let index = index_of(index);
+
+                let chunks = arena.occupied.par_chunks(CHUNK).enumerate();
+
+                chunks.flat_map_iter(move |(chunk, occupied)| {
+                    let first = chunk * CHUNK;
+
+                    LiveSlots::new(occupied).map(move |slot| {
+                        let offset = arena.offset(first + slot);
+                        (Handle { index, offset }, arena.get(offset))
+                    })
+                })
+            })
+    }
+
+    /// Iterate over all live elements in parallel as mutable trait objects, with their handle.
+    ///
+    /// Work is split as with [`Self::par_iter`]. Each element is yielded once, so tasks
+    /// never share an element.
+    ///
+    /// ```rust
+    /// use rayon::iter::ParallelIterator;
+    ///
+    /// let mut arena = hato::Hato::<dyn core::any::Any + Send + Sync>::default();
+    ///
+    /// let xs = (0..10_000_u32).map(|i| arena.push(i)).collect::<Vec<_>>();
+    ///
+    /// arena.par_iter_mut().for_each(|(_, x)| *x.downcast_mut::<u32>().unwrap() *= 2);
+    ///
+    /// assert_eq!(unsafe { arena.get(xs[21]) }.downcast_ref::<u32>(), Some(&42));
+    /// ```
+    #[inline]
+    #[must_use]
+    pub fn par_iter_mut(&mut self) -> impl ParallelIterator<Item = (Handle, &mut Trait)> + '_
+    where
+        Trait: Send,
+        S: Sync,
+    {
+        // Elements may be modified through the references, past what the model can follow
+        self.touch_all();
+
+        // Take mutable addresses up front, so that tasks only share the arenas afterwards
+        let bases = self
+            .arenas
+            .iter_mut()
+            .map(|arena| Arc::<[Base]>::from(arena.bases()))
+            .collect::<Vec<_>>();
+
+        let arenas = &self.arenas;
+
+        arenas
+            .par_iter()
+            .zip(bases)
+            .enumerate()
+            .flat_map(|(index, (arena, bases))| {
+                let index = index_of(index);
+
+                let chunks = arena.occupied.par_chunks(CHUNK).enumerate();
+
+                chunks.flat_map_iter(move |(chunk, occupied)| {
+                    let (first, bases) = (chunk * CHUNK, Arc::clone(&bases));
+
+                    LiveSlots::new(occupied).map(move |slot| {
+                        let slot = first + slot;
+                        let offset = arena.offset(slot);
+
+                        // ! SAFETY: Slot holds a valid element, and each slot is yielded once
+                        let x = unsafe { arena.element_mut(&bases, slot) };
+
+                        (Handle { index, offset }, x)
+                    })
+                })
+            })
+    }
+
+    /// Remove all elements for which `f` returns `false`, evaluating it in parallel.
+    ///
+    /// The predicate runs concurrently across arenas and chunks of slots within each arena.
+    /// Removals are collected per chunk, then applied once every element has been evaluated.
+    #[inline]
+    pub fn par_retain(&mut self, f: impl Fn(&Trait) -> bool + Sync)
+    where
+        S: Sync,
+    {
+        let f = &f;
+
+        let removals = self
+            .arenas
+            .par_iter()
+            .enumerate()
+            .flat_map(|(index, arena)| {
+                arena
+                    .occupied
+                    .par_chunks(CHUNK)
+                    .enumerate()
+                    .map(move |(chunk, occupied)| {
+                        let first = chunk * CHUNK;
+
+                        // Evaluate predicate on live elements of this chunk only
+                        (first..first + occupied.len())
+                            .filter(|slot| occupied[slot - first])
+                            .map(|slot| arena.offset(slot))
+                            .filter(|offset| !f(arena.get(*offset)))
+                            .map(|offset| (index, offset))
+                            .collect::<Vec<_>>()
+                    })
+            })
+            .collect::<Vec<_>>();
+
+        // Apply removals sequentially, since they mutate free lists
+        for (index, offset) in removals.into_iter().flatten() {
+            self.arenas[index].remove(offset);
+            self.removed_from(index);
+
+            let index = index_of(index);
+
+            self.names.remove(Handle { index, offset });
+            self.shadow.remove(Handle { index, offset });
+        }
+
+        self.forget_removed_forwards();
+    }
+}
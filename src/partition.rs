@@ -0,0 +1,223 @@
+use alloc::vec::Vec;
+
+use core::marker::PhantomData;
+use core::num::NonZeroUsize;
+use core::ptr::{from_raw_parts_mut, DynMetadata, Pointee};
+use core::slice;
+
+use aligned_vec::AVec;
+
+use crate::{index_of, Arena, Handle, Hato, Index, Kind, Remap, Storage};
+
+/// Disjoint share of the elements of a collection, to be mutated from its own thread.
+///
+/// Obtained from [`Hato::partitions_mut`], which splits the collection by whole arenas
+/// or ranges of their slots. Partitions can be sent to other threads as long as elements can,
+/// and the collection is available again once all of them are dropped.
+#[derive(Debug)]
+pub struct HatoPartition<'a, Trait: ?Sized + Pointee<Metadata = DynMetadata<Trait>>> {
+    pieces: Vec<Piece<'a, Trait>>,
+    forwards: Option<&'a Remap>,
+    marker: PhantomData<&'a mut Trait>,
+}
+
+/// Range of slots of a single arena, along with the storage backing their elements.
+#[derive(Debug)]
+struct Piece<'a, Trait: ?Sized + Pointee<Metadata = DynMetadata<Trait>>> {
+    index: Index,
+    first: usize,
+    occupied: &'a [bool],
+    kinds: &'a [Kind<Trait>],
+    bytes: &'a mut [u8],
+    spilled: &'a mut [AVec<u8>],
+    stride: usize,
+    vtable: DynMetadata<Trait>,
+}
+
+impl<Trait: ?Sized + Pointee<Metadata = DynMetadata<Trait>>, S: Storage> Hato<Trait, S> {
+    /// Split the collection into `count` disjoint partitions of about the same number of slots.
+    ///
+    /// Each partition can be mutated from its own thread, without any locking.
+    ///
+    /// ```rust
+    /// let mut arena = hato::Hato::<dyn core::fmt::Debug + Send>::default();
+    ///
+    /// let _ = arena.push(1_u8);
+    /// let y = arena.push(2_u32);
+    ///
+    /// let count = core::num::NonZeroUsize::new(2).unwrap();
+    ///
+    /// std::thread::scope(|scope| {
+    ///     for mut partition in arena.partitions_mut(count) {
+    ///         let _ = scope.spawn(move || partition.for_each_mut(|_| ()));
+    ///     }
+    /// });
+    ///
+    /// assert_eq!(format!("{:?}", unsafe { arena.get(y) }), "2");
+    /// ```
+    #[inline]
+    #[must_use]
+    pub fn partitions_mut(&mut self, count: NonZeroUsize) -> Vec<HatoPartition<'_, Trait>> {
+        // Elements may be modified through the partitions, past what the model can follow
+        self.touch_all();
+
+        let total = self
+            .arenas
+            .iter()
+            .map(|arena| arena.occupied.len())
+            .sum::<usize>();
+        let size = total.div_ceil(count.get()).max(1);
+
+        // Stale handles of compacted elements resolve through the entries of the collection
+        let forwards = self.compaction.forwards();
+
+        let mut partitions = Vec::with_capacity(count.get());
+        let mut pieces = Vec::new();
+        let mut len = 0;
+
+        for (index, arena) in self.arenas.iter_mut().enumerate() {
+            let in_buffer = !arena.spill && arena.vtable.size_of() > 0;
+            let Arena {
+                bytes,
+                spilled,
+                occupied,
+                kinds,
+                stride,
+                spill,
+                vtable,
+                ..
+            } = arena;
+
+            // ! SAFETY: Buffer holds initialized bytes up to its length,
+            // ! which stay borrowed mutably along with the collection
+            let mut bytes = unsafe { slice::from_raw_parts_mut(bytes.as_mut_ptr(), bytes.len()) };
+            let mut spilled = spilled.as_mut_slice();
+            let occupied: &[bool] = occupied;
+            let kinds: &[Kind<Trait>] = kinds;
+
+            let mut first = 0;
+
+            while first < occupied.len() {
+                let taken = (size - len).min(occupied.len() - first);
+
+                let (head, tail) = bytes.split_at_mut(if in_buffer { taken * *stride } else { 0 });
+                bytes = tail;
+
+                let (head_spilled, tail_spilled) =
+                    spilled.split_at_mut(if *spill { taken } else { 0 });
+                spilled = tail_spilled;
+
+                let index = index_of(index);
+
+                pieces.push(Piece {
+                    index,
+                    first,
+                    occupied: &occupied[first..first + taken],
+                    kinds: kinds.get(first..first + taken).unwrap_or_default(),
+                    bytes: head,
+                    spilled: head_spilled,
+                    stride: *stride,
+                    vtable: *vtable,
+                });
+
+                first += taken;
+                len += taken;
+
+                if len == size {
+                    partitions.push(HatoPartition::new(core::mem::take(&mut pieces), forwards));
+                    len = 0;
+                }
+            }
+        }
+
+        if !pieces.is_empty() {
+            partitions.push(HatoPartition::new(pieces, forwards));
+        }
+
+        // Hand out exactly as many partitions as requested, some of which may be empty
+        partitions.resize_with(count.get(), || HatoPartition::new(Vec::new(), forwards));
+
+        partitions
+    }
+}
+
+impl<'a, Trait: ?Sized + Pointee<Metadata = DynMetadata<Trait>>> HatoPartition<'a, Trait> {
+    #[inline]
+    const fn new(pieces: Vec<Piece<'a, Trait>>, forwards: Option<&'a Remap>) -> Self {
+        Self {
+            pieces,
+            forwards,
+            marker: PhantomData,
+        }
+    }
+
+    /// Number of live elements in the partition.
+    #[inline]
+    #[must_use]
+    pub fn len(&self) -> usize {
+        let occupied = self.pieces.iter().flat_map(|piece| piece.occupied);
+        occupied.filter(|occupied| **occupied).count()
+    }
+
+    /// Check whether the partition holds no live element.
+    #[inline]
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Retrieve the element identified by `handle` mutably, if it belongs to the partition.
+    ///
+    /// Handles made stale by compaction are followed to the current slot of their element.
+    #[inline]
+    #[must_use]
+    pub fn get_mut(&mut self, handle: Handle) -> Option<&mut Trait> {
+        let handle = self
+            .forwards
+            .map_or(handle, |forwards| forwards.resolve(handle));
+
+        self.pieces.iter_mut().find_map(|piece| {
+            let slot = (handle.offset as usize / piece.stride).checked_sub(piece.first)?;
+
+            // Reject offsets pointing inside an element, which do not come from the arena
+            let aligned = (handle.offset as usize).is_multiple_of(piece.stride);
+            let live = piece.occupied.get(slot).copied().unwrap_or(false);
+
+            (piece.index == handle.index && aligned && live).then(|| piece.get_mut(slot))
+        })
+    }
+
+    /// Call `f` on every element of the partition mutably.
+    #[inline]
+    pub fn for_each_mut(&mut self, mut f: impl FnMut(&mut Trait)) {
+        for piece in &mut self.pieces {
+            for slot in (0..piece.occupied.len()).filter(|slot| piece.occupied[*slot]) {
+                f(piece.get_mut(slot));
+            }
+        }
+    }
+}
+
+impl<Trait: ?Sized + Pointee<Metadata = DynMetadata<Trait>>> Piece<'_, Trait> {
+    /// Element of the `slot`-th slot of the piece, which must hold a live element.
+    #[inline]
+    fn get_mut(&mut self, slot: usize) -> &mut Trait {
+        let ptr = if !self.spilled.is_empty() {
+            self.spilled[slot].as_mut_ptr()
+        } else if self.bytes.is_empty() {
+            // Zero-sized types all live at the aligned base address of the buffer
+            self.bytes.as_mut_ptr()
+        } else {
+            self.bytes[slot * self.stride..].as_mut_ptr()
+        };
+
+        // Arenas shared across types record the virtual table of each slot
+        let vtable = self
+            .kinds
+            .get(slot)
+            .map_or(self.vtable, |(_, vtable)| *vtable);
+
+        // ! SAFETY: Slot holds a valid element, borrowed mutably through the partition only
+        unsafe { &mut *from_raw_parts_mut(ptr, vtable) }
+    }
+}
@@ -0,0 +1,372 @@
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+
+use core::any::TypeId;
+use core::marker::Unsize;
+use core::mem::needs_drop;
+use core::ptr::{from_raw_parts, DynMetadata, Pointee};
+
+use aligned_vec::AVec;
+use unscrupulous::{as_slice_of_bytes, Unscrupulous};
+
+use crate::{get_metadata_of_ref, Arena, Handle, Hato, Index, LiveSlots, Options};
+
+/// Number of slots per chunk, the unit of copy when a version modifies shared elements.
+const CHUNK_SLOTS: usize = 64;
+
+/// Immutable variant of [`Hato`], where modifications produce a new version of the collection.
+///
+/// Arenas are split into chunks of a fixed number of slots, which versions share. A modification
+/// copies the chunk it touches, along with the list of chunks of its arena, and shares the rest.
+/// Many versions can thus coexist cheaply, for undo stacks or speculative exploration.
+/// Handles of a version remain valid in all versions derived from it.
+///
+/// ```rust
+/// let empty = hato::HatoPersistent::<dyn core::fmt::Debug>::default();
+///
+/// let (first, x) = empty.push(4_u16);
+/// let (second, y) = first.push(2_u8);
+///
+/// let third = second.remove(x);
+///
+/// // Older versions are left untouched
+/// assert_eq!(format!("{:?}", unsafe { first.get(x) }), "4");
+/// assert_eq!(format!("{:?}", unsafe { third.get(y) }), "2");
+/// ```
+#[derive(Debug)]
+pub struct HatoPersistent<Trait: ?Sized + Pointee<Metadata = DynMetadata<Trait>>> {
+    arenas: Vec<Arc<Chunked<Trait>>>,
+    options: Options,
+}
+
+/// Arena of a persistent collection, with its slots spread over shared chunks.
+#[derive(Debug)]
+struct Chunked<Trait: ?Sized + Pointee<Metadata = DynMetadata<Trait>>> {
+    vtable: DynMetadata<Trait>,
+    shared: bool,
+    tombstones: bool,
+
+    /// Distance between the offsets of consecutive slots, as in the arenas of [`Hato`].
+    stride: usize,
+
+    chunks: Vec<Arc<Chunk<Trait>>>,
+
+    /// Number of slots laid out so far, live or free.
+    len: usize,
+
+    /// Free slots, as a stack whose tail is shared with earlier versions.
+    free: Option<Arc<Free>>,
+}
+
+/// Fixed run of slots, shared by versions until one of them modifies it.
+#[derive(Debug)]
+struct Chunk<Trait: ?Sized + Pointee<Metadata = DynMetadata<Trait>>> {
+    bytes: AVec<u8>,
+
+    /// Virtual table of the last element stored in each slot.
+    vtables: [DynMetadata<Trait>; CHUNK_SLOTS],
+}
+
+/// Node of the stack of free slots.
+#[derive(Debug)]
+struct Free {
+    slot: usize,
+    next: Option<Arc<Self>>,
+}
+
+impl<Trait: ?Sized + Pointee<Metadata = DynMetadata<Trait>>> Default for HatoPersistent<Trait> {
+    fn default() -> Self {
+        Self::from(Hato::default())
+    }
+}
+
+impl<Trait: ?Sized + Pointee<Metadata = DynMetadata<Trait>>> Clone for HatoPersistent<Trait> {
+    fn clone(&self) -> Self {
+        Self {
+            arenas: self.arenas.clone(),
+            options: self.options,
+        }
+    }
+}
+
+impl<Trait: ?Sized + Pointee<Metadata = DynMetadata<Trait>>> Clone for Chunked<Trait> {
+    fn clone(&self) -> Self {
+        Self {
+            vtable: self.vtable,
+            shared: self.shared,
+            tombstones: self.tombstones,
+            stride: self.stride,
+            chunks: self.chunks.clone(),
+            len: self.len,
+            free: self.free.clone(),
+        }
+    }
+}
+
+impl<Trait: ?Sized + Pointee<Metadata = DynMetadata<Trait>>> Clone for Chunk<Trait> {
+    fn clone(&self) -> Self {
+        Self {
+            bytes: self.bytes.clone(),
+            vtables: self.vtables,
+        }
+    }
+}
+
+impl<Trait: ?Sized + Pointee<Metadata = DynMetadata<Trait>>> From<Hato<Trait>>
+    for HatoPersistent<Trait>
+{
+    /// Freeze `hato` into a first version, keeping its handles and options.
+    fn from(hato: Hato<Trait>) -> Self {
+        Self {
+            arenas: hato
+                .arenas
+                .iter()
+                .map(|a| Arc::new(Chunked::from(a)))
+                .collect(),
+            options: hato.options,
+        }
+    }
+}
+
+impl<Trait: ?Sized + Pointee<Metadata = DynMetadata<Trait>>> HatoPersistent<Trait> {
+    /// Build the version with `x` inserted into the arena for its specific type.
+    ///
+    /// # Panics
+    ///
+    /// This function will panic if the number of arenas overflows the index type.
+    #[inline]
+    #[must_use]
+    pub fn push<T: Unsize<Trait> + Unscrupulous>(&self, x: T) -> (Self, Handle) {
+        // Reject types whose destructor would silently be skipped
+        const { assert!(!needs_drop::<T>(), "destructors of elements never run") }
+
+        let mut next = self.clone();
+
+        // Identify individual types at runtime using their virtual table pointer
+        let vtable = get_metadata_of_ref(&x);
+
+        let index_as_usize = next
+            .arenas
+            .iter()
+            .position(|arena| arena.admits(vtable) && arena.has_room())
+            .unwrap_or_else(|| {
+                // Create a new arena to store elements of type `T`
+                let arena = Chunked::new(typeid::of::<T>(), vtable, next.options);
+                next.arenas.push(Arc::new(arena));
+
+                next.arenas.len() - 1
+            });
+
+        // Bound the number of different types to limit the size of handles
+        let index = Index::try_from(index_as_usize)
+            .unwrap_or_else(|_| panic!("got more than `{}` arenas", Index::MAX));
+
+        // Copy the list of chunks only if other versions still share the arena
+        let arena = Arc::make_mut(&mut next.arenas[index_as_usize]);
+        let offset = arena.push(as_slice_of_bytes(&x), vtable);
+
+        // Prevent destructor from running on scope end
+        core::mem::forget(x);
+
+        (next, Handle { index, offset })
+    }
+
+    /// Build the version with the element identified by `handle` removed.
+    #[inline]
+    #[must_use]
+    pub fn remove(&self, handle: Handle) -> Self {
+        let mut next = self.clone();
+
+        Arc::make_mut(&mut next.arenas[handle.index as usize]).remove(handle.offset);
+        next
+    }
+
+    /// Retrieve the element identified by `handle` as a trait object.
+    ///
+    /// # Safety
+    ///
+    /// The handle must originate from this version, or one it was derived from.
+    ///
+    /// # Panics
+    ///
+    /// This function will panic if `handle` points past the slots of its arena.
+    #[inline]
+    #[must_use]
+    pub unsafe fn get(&self, handle: Handle) -> &Trait {
+        self.arenas[handle.index as usize].get(handle.offset)
+    }
+}
+
+impl<Trait: ?Sized + Pointee<Metadata = DynMetadata<Trait>>> Chunked<Trait> {
+    /// Create an arena laid out as [`Hato`] would lay it out with `options`.
+    #[inline]
+    fn new(type_id: TypeId, vtable: DynMetadata<Trait>, options: Options) -> Self {
+        Self::from(&Arena::empty(type_id, vtable, options))
+    }
+
+    /// Check whether elements with virtual table `vtable` may be stored in this arena.
+    #[inline]
+    fn admits(&self, vtable: DynMetadata<Trait>) -> bool {
+        if self.shared {
+            self.vtable.layout() == vtable.layout()
+        } else {
+            self.vtable == vtable
+        }
+    }
+
+    /// Check whether a slot is free, or one more keeps offsets within the index type.
+    #[inline]
+    fn has_room(&self) -> bool {
+        let end = (self.len + 1).checked_mul(self.stride);
+        self.free.is_some() || end.is_some_and(|end| Index::try_from(end).is_ok())
+    }
+
+    /// Store `bytes` of an element with virtual table `vtable` in a free slot, or a new one.
+    #[inline]
+    fn push(&mut self, bytes: &[u8], vtable: DynMetadata<Trait>) -> Index {
+        let slot = if let Some(free) = self.free.take() {
+            self.free.clone_from(&free.next);
+            free.slot
+        } else {
+            if self.len.is_multiple_of(CHUNK_SLOTS) {
+                let chunk = Chunk::new(self.vtable);
+                self.chunks.push(Arc::new(chunk));
+            }
+
+            self.len += 1;
+            self.len - 1
+        };
+
+        let (chunk, within) = Self::locate(slot);
+        let position = within * self.vtable.size_of();
+
+        // Copy the chunk only if other versions still share it
+        let chunk = Arc::make_mut(&mut self.chunks[chunk]);
+
+        chunk.bytes[position..position + bytes.len()].copy_from_slice(bytes);
+        chunk.vtables[within] = vtable;
+
+        // Offsets of existing slots fit in an `Index`, as checked by `has_room`
+        #[allow(clippy::cast_possible_truncation)]
+        let offset = (slot * self.stride) as Index;
+
+        offset
+    }
+
+    #[inline]
+    fn remove(&mut self, offset: Index) {
+        let slot = offset as usize / self.stride;
+
+        // Catch double removals, which corrupt the free list
+        debug_assert!(
+            slot < self.len && !self.is_free(slot),
+            "element was already removed"
+        );
+
+        if self.tombstones {
+            // Leave the slot out of circulation, without touching shared chunks
+            return;
+        }
+
+        self.free = Some(Arc::new(Free {
+            slot,
+            next: self.free.take(),
+        }));
+    }
+
+    #[inline]
+    fn get(&self, offset: Index) -> &Trait {
+        let slot = offset as usize / self.stride;
+
+        assert!(slot < self.len, "stale handle past the end of its arena");
+
+        let (chunk, within) = Self::locate(slot);
+        let chunk = &self.chunks[chunk];
+
+        let position = within * self.vtable.size_of();
+
+        // ! SAFETY: Trait object points to a valid byte representation of this type
+        unsafe { &*from_raw_parts(chunk.bytes.as_ptr().add(position), chunk.vtables[within]) }
+    }
+
+    /// Check whether `slot` is on the free list, walking it from the top.
+    #[inline]
+    fn is_free(&self, slot: usize) -> bool {
+        let mut free = self.free.as_deref();
+
+        while let Some(node) = free {
+            if node.slot == slot {
+                return true;
+            }
+
+            free = node.next.as_deref();
+        }
+
+        false
+    }
+
+    /// Chunk holding `slot`, and position of the slot in it.
+    #[inline]
+    const fn locate(slot: usize) -> (usize, usize) {
+        (slot / CHUNK_SLOTS, slot % CHUNK_SLOTS)
+    }
+}
+
+impl<Trait: ?Sized + Pointee<Metadata = DynMetadata<Trait>>> From<&Arena<Trait>>
+    for Chunked<Trait>
+{
+    /// Copy the elements of `arena` into chunks, keeping their offsets and its free list.
+    fn from(arena: &Arena<Trait>) -> Self {
+        let size = arena.vtable.size_of();
+
+        let count = arena.occupied.len().div_ceil(CHUNK_SLOTS);
+        let mut chunks = (0..count)
+            .map(|_| Chunk::new(arena.vtable))
+            .collect::<Vec<_>>();
+
+        for slot in LiveSlots::new(&arena.occupied) {
+            let (chunk, within) = Self::locate(slot);
+            let position = within * size;
+
+            let chunk = &mut chunks[chunk];
+
+            chunk.bytes[position..position + size]
+                .copy_from_slice(arena.element(arena.offset(slot)));
+            chunk.vtables[within] = arena.kind(slot).1;
+        }
+
+        // Hand free slots out in the same order as the arena would have
+        let mut free = None;
+
+        for offset in arena.free_list() {
+            free = Some(Arc::new(Free {
+                slot: arena.slot(offset),
+                next: free,
+            }));
+        }
+
+        Self {
+            vtable: arena.vtable,
+            shared: arena.shared,
+            tombstones: arena.tombstones,
+            stride: arena.stride,
+            chunks: chunks.into_iter().map(Arc::new).collect(),
+            len: arena.occupied.len(),
+            free,
+        }
+    }
+}
+
+impl<Trait: ?Sized + Pointee<Metadata = DynMetadata<Trait>>> Chunk<Trait> {
+    /// Create a chunk of zeroed slots, for elements with virtual table `vtable`.
+    #[inline]
+    fn new(vtable: DynMetadata<Trait>) -> Self {
+        let mut bytes = AVec::new(vtable.align_of());
+        bytes.resize(CHUNK_SLOTS * vtable.size_of(), 0);
+
+        Self {
+            bytes,
+            vtables: [vtable; CHUNK_SLOTS],
+        }
+    }
+}
@@ -0,0 +1,75 @@
+use core::ops::Range;
+use core::ptr::{DynMetadata, Pointee};
+
+use crate::{Arena, Storage};
+
+#[cfg(sanitize = "address")]
+unsafe extern "C" {
+    fn __asan_poison_memory_region(addr: *const u8, size: usize);
+    fn __asan_unpoison_memory_region(addr: *const u8, size: usize);
+}
+
+impl<Trait: ?Sized + Pointee<Metadata = DynMetadata<Trait>>, S: Storage> Arena<Trait, S> {
+    /// Flag the bytes of free slots among `slots` for the address sanitizer, if enabled.
+    ///
+    /// Accesses to poisoned slots, like those through handles of removed elements,
+    /// are then reported right away. Without the sanitizer, this does nothing.
+    #[cfg_attr(
+        not(sanitize = "address"),
+        allow(clippy::unused_self, clippy::missing_const_for_fn, unused_variables)
+    )]
+    #[inline]
+    pub fn set_poisoned(&self, slots: Range<usize>, poisoned: bool) {
+        // Only elements of the byte buffer are poisoned, others having their own allocation
+        #[cfg(sanitize = "address")]
+        if !self.spill && self.vtable.size_of() > 0 && !self.inline() {
+            for slot in slots.filter(|slot| !self.occupied[*slot]) {
+                let ptr = self.ptr(self.offset(slot));
+
+                // ! SAFETY: Slot lies within the allocation of the buffer
+                unsafe {
+                    if poisoned {
+                        __asan_poison_memory_region(ptr, self.vtable.size_of());
+                    } else {
+                        __asan_unpoison_memory_region(ptr, self.vtable.size_of());
+                    }
+                }
+            }
+        }
+    }
+
+    /// Check whether the buffer is stored within the arena itself, see [`crate::InlineStorage`].
+    ///
+    /// Such buffers are never poisoned, as the directory copies them whole when it moves arenas.
+    #[cfg(sanitize = "address")]
+    #[inline]
+    fn inline(&self) -> bool {
+        let start = core::ptr::from_ref(self).cast::<u8>();
+        let end = start.wrapping_add(size_of::<Self>());
+
+        (start..end).contains(&self.bytes.as_ptr())
+    }
+
+    /// Unpoison free slots if `bytes` more would move the buffer, returning whether it would.
+    ///
+    /// Re-allocations copy the whole buffer, which the sanitizer would report as an access
+    /// to poisoned slots. Pass the result to [`Self::finish_growth`] once the buffer grew.
+    #[inline]
+    pub fn prepare_growth(&self, bytes: usize) -> bool {
+        let moves = cfg!(sanitize = "address") && self.bytes.len() + bytes > self.bytes.capacity();
+
+        if moves {
+            self.set_poisoned(0..self.occupied.len(), false);
+        }
+
+        moves
+    }
+
+    /// Poison free slots again, after growth of the buffer prepared with [`Self::prepare_growth`].
+    #[inline]
+    pub fn finish_growth(&self, moved: bool) {
+        if moved {
+            self.set_poisoned(0..self.occupied.len(), true);
+        }
+    }
+}
@@ -0,0 +1,173 @@
+use alloc::vec::Vec;
+
+use core::marker::PhantomData;
+use core::mem::needs_drop;
+
+use unscrupulous::Unscrupulous;
+
+use crate::{get_metadata_of, Arena, Index, Options};
+
+/// Homogeneous collection of elements of type `T`, backed by the arena of [`Hato`](crate::Hato).
+///
+/// Elements are stored contiguously, and slots of removed elements are reused by later insertions.
+/// Handles are a single [`Index`](crate::Index) offset, and follow the same rules
+/// as those of [`Hato`](crate::Hato).
+///
+/// ```rust
+/// let mut pool = hato::Pool::<u32>::default();
+///
+/// let x = pool.push(4);
+/// let ys = pool.absorb_vec(vec![5, 6]);
+///
+/// pool.remove(x);
+///
+/// assert_eq!(ys.map(|y| unsafe { *pool.get(y) }).collect::<Vec<_>>(), [5, 6]);
+/// assert_eq!(pool.extract_all(), [5, 6]);
+/// ```
+pub struct Pool<T> {
+    arena: Arena<dyn Erased>,
+    marker: PhantomData<T>,
+}
+
+/// Trait implemented by all types, to reuse the trait object machinery of arenas.
+trait Erased {}
+
+impl<T> Erased for T {}
+
+impl<T: Unscrupulous + 'static> Default for Pool<T> {
+    fn default() -> Self {
+        Self {
+            arena: Arena::new(
+                typeid::of::<T>(),
+                get_metadata_of::<T, dyn Erased>(),
+                Options::default(),
+            ),
+            marker: PhantomData,
+        }
+    }
+}
+
+impl<T> core::fmt::Debug for Pool<T> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("Pool")
+            .field("stride", &self.arena.stride)
+            .field("occupied", &self.arena.occupied)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<T> Clone for Pool<T> {
+    fn clone(&self) -> Self {
+        Self {
+            arena: self.arena.clone(),
+            marker: PhantomData,
+        }
+    }
+}
+
+impl<T: Unscrupulous + 'static> Pool<T> {
+    /// Insert `x` into the pool, reusing the slot of a removed element if any.
+    ///
+    /// # Panics
+    ///
+    /// This function will panic if offsets of the pool outgrow the [`Index`](crate::Index) type.
+    #[inline]
+    pub fn push(&mut self, x: T) -> PoolHandle {
+        // Reject types whose destructor would silently be skipped
+        const { assert!(!needs_drop::<T>(), "destructors of elements never run") }
+
+        PoolHandle {
+            offset: self.arena.push(x),
+        }
+    }
+
+    /// Insert all elements of `xs` at once, in a single bulk copy.
+    ///
+    /// # Panics
+    ///
+    /// This function will panic if offsets of the pool outgrow the [`Index`](crate::Index) type.
+    #[inline]
+    pub fn absorb_vec(
+        &mut self,
+        xs: Vec<T>,
+    ) -> impl DoubleEndedIterator<Item = PoolHandle> + ExactSizeIterator {
+        // Reject types whose destructor would silently be skipped
+        const { assert!(!needs_drop::<T>(), "destructors of elements never run") }
+
+        assert!(
+            self.arena.has_room(xs.len()),
+            "offsets of individual arenas should fit in the index type"
+        );
+
+        let (start, _) = self.arena.extend(&xs);
+        let (len, stride) = (xs.len(), self.arena.stride);
+
+        // Elements were moved into the pool, only the vector's buffer remains to be freed
+        drop(xs);
+
+        (0..len).map(move |i| {
+            // Offsets of appended slots fit in an `Index`, as checked above
+            #[allow(clippy::cast_possible_truncation)]
+            let offset = start + (i * stride) as Index;
+
+            PoolHandle { offset }
+        })
+    }
+
+    /// Move all elements out of the pool, emptying it while keeping its capacity.
+    #[inline]
+    pub fn extract_all(&mut self) -> Vec<T> {
+        let mut xs = Vec::new();
+        self.arena.drain_into(&mut xs);
+
+        xs
+    }
+
+    /// Retrieve the element identified by `handle`.
+    ///
+    /// # Safety
+    ///
+    /// The handle must originate from the same instance of `Pool`.
+    #[inline]
+    #[must_use]
+    pub unsafe fn get(&self, handle: PoolHandle) -> &T {
+        let ptr: *const dyn Erased = self.arena.get(handle.offset);
+
+        // ! SAFETY: Pool only holds elements of type `T`
+        unsafe { &*ptr.cast::<T>() }
+    }
+
+    /// Retrieve the element identified by `handle` mutably.
+    ///
+    /// # Safety
+    ///
+    /// The handle must originate from the same instance of `Pool`.
+    #[inline]
+    #[must_use]
+    pub unsafe fn get_mut(&mut self, handle: PoolHandle) -> &mut T {
+        let ptr: *mut dyn Erased = self.arena.get_mut(handle.offset);
+
+        // ! SAFETY: Pool only holds elements of type `T`
+        unsafe { &mut *ptr.cast::<T>() }
+    }
+
+    /// Remove the element identified by `handle` from the pool.
+    ///
+    /// Removing the same element twice hands its slot to two future insertions.
+    #[inline]
+    pub fn remove(&mut self, handle: PoolHandle) {
+        // Catch double removals, which corrupt the free list
+        debug_assert!(
+            self.arena.contains(handle.offset),
+            "element was already removed"
+        );
+
+        self.arena.remove(handle.offset);
+    }
+}
+
+/// Index to access an element stored in a [`Pool`].
+#[derive(Clone, Copy, Debug, Eq, Ord, PartialEq, PartialOrd)]
+pub struct PoolHandle {
+    offset: Index,
+}
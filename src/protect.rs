@@ -0,0 +1,297 @@
+use core::ptr::{self, DynMetadata, Pointee};
+
+use crate::{Error, Handle, Hato, Storage};
+
+/// Buffer of whole pages mapped from the operating system, which can be made read-only.
+///
+/// Sealed buffers fault on any write, so stray writes through stale pointers derived from
+/// [`Hato::get_mut`], or through unsafe code, crash right away instead of silently corrupting
+/// elements. Buffers are not shared with other allocations, so protecting their pages
+/// leaves the rest of the process untouched. Capacity grows a page at a time at least,
+/// for elements aligned to at most the size of pages. Buffers are sealed through
+/// [`Hato::seal`], which only hands out read access until they are unsealed.
+///
+/// ```rust
+/// let mut arena = hato::Hato::<dyn core::fmt::Debug, hato::ProtectedStorage>::default();
+///
+/// let x = arena.push(1_u32);
+///
+/// // Sealed collections only expose reads
+/// let sealed = arena.seal();
+/// assert_eq!(format!("{:?}", sealed.checked_get(x).unwrap()), "1");
+///
+/// let mut arena = sealed.unseal();
+/// let _ = arena.push(2_u32);
+/// ```
+#[derive(Debug)]
+pub struct ProtectedStorage {
+    ptr: *mut u8,
+    len: usize,
+    capacity: usize,
+    align: usize,
+    sealed: bool,
+}
+
+// ! SAFETY: Buffers own their mapping, which no other value points to
+unsafe impl Send for ProtectedStorage {}
+
+// ! SAFETY: Shared references only read the mapping
+unsafe impl Sync for ProtectedStorage {}
+
+impl Drop for ProtectedStorage {
+    fn drop(&mut self) {
+        if self.capacity > 0 {
+            // ! SAFETY: Mapping spans the capacity from the base address, and is not used anymore
+            let _ = unsafe { libc::munmap(self.ptr.cast(), self.capacity) };
+        }
+    }
+}
+
+impl Clone for ProtectedStorage {
+    fn clone(&self) -> Self {
+        let mut clone = Self::new(self.align);
+        clone.extend_from_slice(self.bytes());
+
+        if self.sealed {
+            clone.seal();
+        }
+
+        clone
+    }
+}
+
+impl ProtectedStorage {
+    /// Check whether the buffer is read-only.
+    #[inline]
+    #[must_use]
+    pub const fn is_sealed(&self) -> bool {
+        self.sealed
+    }
+
+    /// Make the pages of the buffer read-only, so that writes to them fault.
+    #[inline]
+    fn seal(&mut self) {
+        self.protect(libc::PROT_READ);
+        self.sealed = true;
+    }
+
+    /// Make the pages of the buffer writable again.
+    #[inline]
+    fn unseal(&mut self) {
+        self.protect(libc::PROT_READ | libc::PROT_WRITE);
+        self.sealed = false;
+    }
+
+    /// Change the protection of the pages of the buffer to `protection`, if it has any.
+    #[inline]
+    fn protect(&self, protection: libc::c_int) {
+        if self.capacity == 0 {
+            return;
+        }
+
+        // ! SAFETY: Mapping spans the capacity from the base address, which is page-aligned
+        let result = unsafe { libc::mprotect(self.ptr.cast(), self.capacity, protection) };
+
+        assert_eq!(result, 0, "pages of the buffer should change protection");
+    }
+
+    /// Bytes in use.
+    #[inline]
+    const fn bytes(&self) -> &[u8] {
+        // ! SAFETY: Bytes are initialized up to the length
+        unsafe { core::slice::from_raw_parts(self.ptr, self.len) }
+    }
+}
+
+// ! SAFETY: Mappings start on a page boundary, which is aligned to any supported alignment,
+// ! and growing copies the bytes in use over to the new mapping
+unsafe impl Storage for ProtectedStorage {
+    #[inline]
+    fn new(align: usize) -> Self {
+        assert!(align <= page_size(), "alignment should not exceed pages");
+
+        Self {
+            ptr: ptr::without_provenance_mut(align),
+            len: 0,
+            capacity: 0,
+            align,
+            sealed: false,
+        }
+    }
+
+    #[inline]
+    fn align(&self) -> usize {
+        self.align
+    }
+
+    #[inline]
+    fn len(&self) -> usize {
+        self.len
+    }
+
+    #[inline]
+    fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    #[inline]
+    fn as_ptr(&self) -> *const u8 {
+        self.ptr
+    }
+
+    #[inline]
+    fn as_mut_ptr(&mut self) -> *mut u8 {
+        self.ptr
+    }
+
+    #[inline]
+    fn grow(&mut self, capacity: usize) {
+        if let Err(error) = self.try_grow(capacity) {
+            panic!("failed to grow protected buffer: {error}");
+        }
+    }
+
+    #[inline]
+    fn try_grow(&mut self, capacity: usize) -> Result<(), Error> {
+        // Growing would move bytes to a writable mapping, defeating the seal
+        if self.sealed {
+            return Err(Error::Sealed);
+        }
+
+        if capacity <= self.capacity {
+            return Ok(());
+        }
+
+        let capacity = capacity.checked_next_multiple_of(page_size());
+        let capacity = capacity.ok_or(Error::CapacityOverflow)?;
+
+        // ! SAFETY: Anonymous private mappings have no preconditions
+        let mapped = unsafe {
+            libc::mmap(
+                ptr::null_mut(),
+                capacity,
+                libc::PROT_READ | libc::PROT_WRITE,
+                libc::MAP_PRIVATE | libc::MAP_ANONYMOUS,
+                -1,
+                0,
+            )
+        };
+
+        if mapped == libc::MAP_FAILED {
+            return Err(Error::AllocationFailure);
+        }
+
+        let mapped = mapped.cast::<u8>();
+
+        // ! SAFETY: New mapping is larger than the bytes in use, and distinct from the old one
+        unsafe { ptr::copy_nonoverlapping(self.ptr, mapped, self.len) };
+
+        if self.capacity > 0 {
+            // ! SAFETY: Old mapping spans its capacity, and its bytes were moved out
+            let _ = unsafe { libc::munmap(self.ptr.cast(), self.capacity) };
+        }
+
+        self.ptr = mapped;
+        self.capacity = capacity;
+
+        Ok(())
+    }
+
+    #[inline]
+    unsafe fn set_len(&mut self, len: usize) {
+        self.len = len;
+    }
+}
+
+impl<Trait: ?Sized + Pointee<Metadata = DynMetadata<Trait>>> Hato<Trait, ProtectedStorage> {
+    /// Make the buffers of all arenas read-only, see [`ProtectedStorage`].
+    ///
+    /// The collection can only be read until [`HatoSealed::unseal`] hands it back.
+    ///
+    /// # Panics
+    ///
+    /// This function will panic if the operating system refuses to protect the pages.
+    #[inline]
+    #[must_use]
+    pub fn seal(mut self) -> HatoSealed<Trait> {
+        for arena in &mut self.arenas {
+            arena.bytes.seal();
+        }
+
+        HatoSealed { hato: self }
+    }
+}
+
+/// Collection whose buffers are read-only, as returned by [`Hato::seal`].
+///
+/// Only reads are exposed, so that the sealed pages are never written to. Elements
+/// with interior mutability must not be modified through shared references meanwhile,
+/// as such writes fault as well.
+#[derive(Debug)]
+pub struct HatoSealed<Trait: ?Sized + Pointee<Metadata = DynMetadata<Trait>>> {
+    hato: Hato<Trait, ProtectedStorage>,
+}
+
+impl<Trait: ?Sized + Pointee<Metadata = DynMetadata<Trait>>> Clone for HatoSealed<Trait> {
+    fn clone(&self) -> Self {
+        // Clones of sealed buffers are sealed as well
+        Self {
+            hato: self.hato.clone(),
+        }
+    }
+}
+
+impl<Trait: ?Sized + Pointee<Metadata = DynMetadata<Trait>>> HatoSealed<Trait> {
+    /// Sealed collection.
+    #[inline]
+    #[must_use]
+    pub const fn hato(&self) -> &Hato<Trait, ProtectedStorage> {
+        &self.hato
+    }
+
+    /// Retrieve the element identified by `handle`, see [`Hato::get`].
+    ///
+    /// # Safety
+    ///
+    /// The handle must originate from the sealed collection.
+    #[inline]
+    #[must_use]
+    pub unsafe fn get(&self, handle: Handle) -> &Trait {
+        // ! SAFETY: Handle originates from the sealed collection, as required from the caller
+        unsafe { self.hato.get(handle) }
+    }
+
+    /// Retrieve the element identified by `handle`, checking that it is live.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if `handle` does not identify a live element.
+    #[inline]
+    pub fn checked_get(&self, handle: Handle) -> Result<&Trait, Error> {
+        self.hato.checked_get(handle)
+    }
+
+    /// Make the buffers of all arenas writable again, handing the collection back.
+    ///
+    /// # Panics
+    ///
+    /// This function will panic if the operating system refuses to unprotect the pages.
+    #[inline]
+    #[must_use]
+    pub fn unseal(mut self) -> Hato<Trait, ProtectedStorage> {
+        for arena in &mut self.hato.arenas {
+            arena.bytes.unseal();
+        }
+
+        self.hato
+    }
+}
+
+/// Size of pages of the operating system.
+#[inline]
+fn page_size() -> usize {
+    // ! SAFETY: Querying configuration values has no preconditions
+    let size = unsafe { libc::sysconf(libc::_SC_PAGESIZE) };
+
+    usize::try_from(size).expect("page size should be positive")
+}
@@ -0,0 +1,101 @@
+use alloc::vec;
+use alloc::vec::Vec;
+
+use core::any::TypeId;
+use core::marker::Unsize;
+use core::ptr::{DynMetadata, Pointee};
+
+use unscrupulous::Unscrupulous;
+
+use crate::{index_of, Counted, Handle, Hato, Storage};
+
+/// Tuple of concrete types, selecting the elements visited by [`Hato::query`].
+///
+/// Implemented for tuples of up to eight types.
+pub trait Query<Trait: ?Sized> {
+    /// Identifiers of the listed types.
+    fn type_ids() -> Vec<TypeId>;
+}
+
+/// Implement [`Query`] for a tuple of the given type parameters.
+macro_rules! impl_query {
+    ($($ty:ident),+) => {
+        impl<Trait: ?Sized, $($ty: Unsize<Trait> + Unscrupulous),+> Query<Trait> for ($($ty,)+) {
+            #[inline]
+            fn type_ids() -> Vec<TypeId> {
+                vec![$(typeid::of::<$ty>()),+]
+            }
+        }
+    };
+}
+
+impl_query!(A);
+impl_query!(A, B);
+impl_query!(A, B, C);
+impl_query!(A, B, C, D);
+impl_query!(A, B, C, D, E);
+impl_query!(A, B, C, D, E, F);
+impl_query!(A, B, C, D, E, F, G);
+impl_query!(A, B, C, D, E, F, G, H);
+
+impl<Trait: ?Sized + Pointee<Metadata = DynMetadata<Trait>>, S: Storage> Hato<Trait, S> {
+    /// Iterate over live elements whose type is listed in the tuple `Q`, with their handle.
+    ///
+    /// Only arenas that admit one of these types are traversed, arena by arena.
+    /// Elements are yielded as trait objects, whose concrete type is among those listed.
+    ///
+    /// ```rust
+    /// let mut arena = hato::Hato::<dyn core::fmt::Debug>::default();
+    ///
+    /// let x = arena.push(1_u8);
+    /// let _ = arena.push(2_u16);
+    /// let z = arena.push(3_u32);
+    ///
+    /// let handles = arena.query::<(u8, u32)>().map(|(handle, _)| handle);
+    /// assert_eq!(handles.collect::<Vec<_>>(), [x, z]);
+    /// ```
+    #[inline]
+    #[must_use]
+    pub fn query<Q: Query<Trait>>(
+        &self,
+    ) -> impl DoubleEndedIterator<Item = (Handle, &Trait)> + ExactSizeIterator + '_ {
+        let mut type_ids = Q::type_ids();
+
+        // Types listed twice still match their elements once
+        type_ids.sort_unstable();
+        type_ids.dedup();
+
+        let len = self.arenas.iter().map(|arena| {
+            let live = type_ids.iter().map(|type_id| arena.live_of(*type_id));
+            live.sum::<usize>()
+        });
+
+        let len = len.sum();
+
+        let elements = self
+            .arenas
+            .iter()
+            .enumerate()
+            .flat_map(move |(index, arena)| {
+                let index = index_of(index);
+
+                // Skip arenas admitting none of the types, without visiting their slots
+                let admitted = arena.kind_of(|(id, _)| type_ids.contains(&id)).is_some();
+                let end = if admitted { arena.occupied.len() } else { 0 };
+
+                let type_ids = type_ids.clone();
+
+                // Arenas shared across types also hold elements of types left out
+                let slots = (0..end).filter(move |slot| {
+                    arena.occupied[*slot] && type_ids.contains(&arena.kind(*slot).0)
+                });
+
+                slots.map(move |slot| {
+                    let offset = arena.offset(slot);
+                    (Handle { index, offset }, arena.get(offset))
+                })
+            });
+
+        Counted::new(elements, len)
+    }
+}
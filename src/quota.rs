@@ -0,0 +1,76 @@
+use core::any::TypeId;
+use core::marker::Unsize;
+use core::mem::size_of;
+use core::ptr::{DynMetadata, Pointee};
+
+use unscrupulous::Unscrupulous;
+
+use crate::{Hato, Storage};
+
+impl<Trait: ?Sized + Pointee<Metadata = DynMetadata<Trait>>, S: Storage> Hato<Trait, S> {
+    /// Allow at most `count` live elements of type `T`, or lift its quota with `None`.
+    ///
+    /// Insertions past the quota panic, while `try_*` methods return
+    /// [`Error::QuotaExceeded`](crate::Error::QuotaExceeded) instead. Elements already stored
+    /// stay, even beyond a lowered quota. Insertions of types under a quota count their live
+    /// elements first, in time linear in the number of arenas.
+    ///
+    /// ```rust
+    /// let mut arena = hato::Hato::<dyn core::fmt::Debug>::default();
+    /// arena.set_quota::<u8>(Some(1));
+    ///
+    /// let x = arena.push(1_u8);
+    /// assert_eq!(arena.try_push(2_u8), Err(hato::Error::QuotaExceeded));
+    ///
+    /// // Removals give room back
+    /// arena.remove(x);
+    /// assert!(arena.try_push(3_u8).is_ok());
+    /// ```
+    #[inline]
+    pub fn set_quota<T: Unsize<Trait> + Unscrupulous>(&mut self, count: Option<usize>) {
+        let type_id = typeid::of::<T>();
+
+        self.quotas.retain(|(id, _)| *id != type_id);
+        self.quotas.extend(count.map(|count| (type_id, count)));
+    }
+
+    /// Allow elements of type `T` to span at most `bytes` in total, or lift its quota.
+    ///
+    /// This amounts to a quota of `bytes / size_of::<T>()` elements, see [`Self::set_quota`].
+    /// Zero-sized types span no bytes, and are thus never limited.
+    #[inline]
+    pub fn set_byte_quota<T: Unsize<Trait> + Unscrupulous>(&mut self, bytes: Option<usize>) {
+        let count = bytes.and_then(|bytes| bytes.checked_div(size_of::<T>()));
+        self.set_quota::<T>(count);
+    }
+
+    /// Number of live elements of type `T`.
+    #[inline]
+    #[must_use]
+    pub fn count_of<T: Unsize<Trait> + Unscrupulous>(&self) -> usize {
+        self.live_of(typeid::of::<T>())
+    }
+
+    /// Check whether `count` more elements of type `type_id` fit within its quota, if any.
+    #[inline]
+    pub(crate) fn within_quota(&self, type_id: TypeId, count: usize) -> bool {
+        // Collections without quotas skip the lookup entirely
+        if self.quotas.is_empty() {
+            return true;
+        }
+
+        let Some((_, quota)) = self.quotas.iter().find(|(id, _)| *id == type_id) else {
+            return true;
+        };
+
+        self.live_of(type_id)
+            .checked_add(count)
+            .is_some_and(|total| total <= *quota)
+    }
+
+    /// Number of live elements of type `type_id`, across arenas admitting it.
+    #[inline]
+    fn live_of(&self, type_id: TypeId) -> usize {
+        self.arenas.iter().map(|arena| arena.live_of(type_id)).sum()
+    }
+}
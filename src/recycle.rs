@@ -0,0 +1,139 @@
+use std::sync::{Arc, Mutex, PoisonError};
+
+use core::fmt::{self, Debug, Formatter};
+use core::ptr::{DynMetadata, Pointee};
+
+use aligned_vec::AVec;
+
+use crate::{Hato, Storage};
+
+/// Shared stock of arena buffers, recycled across collections instead of being freed.
+///
+/// Collections created with [`Hato::with_pool`] take buffers from the pool for their new arenas,
+/// and hand them back along with their capacity through [`Self::recycle`]. Short-lived
+/// collections, like per-frame scratch space, thus stop churning through the allocator.
+/// Clones of a pool, and of collections using it, share the same stock.
+///
+/// ```rust
+/// let pool = hato::HatoPool::default();
+///
+/// for frame in 0..3_u32 {
+///     let mut scratch = hato::Hato::<dyn core::fmt::Debug>::default().with_pool(&pool);
+///
+///     for i in 0..100 {
+///         let _ = scratch.push(frame + i);
+///     }
+///
+///     pool.recycle(scratch);
+///     assert_eq!(pool.len(), 1);
+/// }
+/// ```
+pub struct HatoPool<S: Storage = AVec<u8>> {
+    buffers: Arc<Mutex<Vec<S>>>,
+}
+
+impl<S: Storage> Default for HatoPool<S> {
+    fn default() -> Self {
+        Self {
+            buffers: Arc::default(),
+        }
+    }
+}
+
+impl<S: Storage> Clone for HatoPool<S> {
+    fn clone(&self) -> Self {
+        Self {
+            buffers: Arc::clone(&self.buffers),
+        }
+    }
+}
+
+impl<S: Storage> Debug for HatoPool<S> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("HatoPool")
+            .field("len", &self.len())
+            .finish()
+    }
+}
+
+impl<S: Storage> HatoPool<S> {
+    /// Number of buffers available.
+    #[inline]
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.lock().len()
+    }
+
+    /// Check whether no buffer is available.
+    #[inline]
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.lock().is_empty()
+    }
+
+    /// Drop `hato`, keeping the buffers of its arenas for later collections.
+    ///
+    /// Buffers without capacity are dropped along with the collection.
+    #[inline]
+    pub fn recycle<Trait>(&self, hato: Hato<Trait, S>)
+    where
+        Trait: ?Sized + Pointee<Metadata = DynMetadata<Trait>>,
+    {
+        let mut buffers = self.lock();
+
+        for mut arena in hato.arenas {
+            // Free slots are handed out whole, and must be accessible again
+            arena.set_poisoned(0..arena.occupied.len(), false);
+
+            if arena.bytes.capacity() > 0 {
+                arena.bytes.clear();
+                buffers.push(arena.bytes);
+            }
+        }
+    }
+
+    /// Take the largest buffer aligned to at least `align` bytes, holding `capacity` or more.
+    #[inline]
+    fn take(&self, align: usize, capacity: usize) -> Option<S> {
+        let mut buffers = self.lock();
+
+        let (position, _) = buffers
+            .iter()
+            .enumerate()
+            .filter(|(_, buffer)| buffer.align() >= align && buffer.capacity() >= capacity)
+            .max_by_key(|(_, buffer)| buffer.capacity())?;
+
+        Some(buffers.swap_remove(position))
+    }
+
+    /// Access the stock of buffers, which stays consistent even if a holder panicked.
+    #[inline]
+    fn lock(&self) -> std::sync::MutexGuard<'_, Vec<S>> {
+        self.buffers.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+impl<Trait: ?Sized + Pointee<Metadata = DynMetadata<Trait>>, S: Storage> Hato<Trait, S> {
+    /// Take buffers of new arenas from `pool`, see [`HatoPool`].
+    #[inline]
+    #[must_use]
+    pub fn with_pool(mut self, pool: &HatoPool<S>) -> Self {
+        self.pool = Some(pool.clone());
+        self
+    }
+
+    /// Back the arena at `index`, which was just created, with a buffer of the attached pool.
+    #[inline]
+    pub(crate) fn adopt_pooled(&mut self, index: usize) {
+        let Some(pool) = &self.pool else {
+            return;
+        };
+
+        let arena = &mut self.arenas[index];
+
+        // Buffers smaller than the capacity reserved up front stay in the pool
+        if let Some(buffer) = pool.take(arena.bytes.align(), arena.bytes.capacity()) {
+            arena.bytes = buffer;
+        }
+    }
+}
@@ -0,0 +1,76 @@
+use core::ptr::{DynMetadata, Pointee};
+
+use bevy_reflect::{Reflect, ReflectFromPtr, TypeRegistry};
+
+use crate::{Arena, Handle, Hato};
+
+impl<Trait: ?Sized + Pointee<Metadata = DynMetadata<Trait>>> Hato<Trait> {
+    /// Retrieve the element identified by `handle` through reflection, without copying it.
+    ///
+    /// Returns `None` if the element was removed, or if its type is missing from `registry`
+    /// or was registered without [`ReflectFromPtr`] type data.
+    ///
+    /// ```rust
+    /// let mut registry = bevy_reflect::TypeRegistry::default();
+    /// registry.register::<u16>();
+    ///
+    /// let mut arena = hato::Hato::<dyn core::fmt::Debug>::default();
+    /// let x = arena.push(4_u16);
+    ///
+    /// let reflected = arena.get_reflect(x, &registry).unwrap();
+    /// assert_eq!(reflected.downcast_ref::<u16>(), Some(&4));
+    /// ```
+    #[inline]
+    #[must_use]
+    pub fn get_reflect(&self, handle: Handle, registry: &TypeRegistry) -> Option<&dyn Reflect> {
+        if !self.contains(handle) {
+            return None;
+        }
+
+        let arena = &self.arenas[handle.index as usize];
+        let cast = caster(arena, handle, registry)?;
+
+        self.shadow.check(handle, || arena.element(handle.offset));
+
+        // ! SAFETY: Element is live and of the type the cast was generated for
+        Some(unsafe { &*cast(arena.ptr(handle.offset).cast_mut().cast()).cast_const() })
+    }
+
+    /// Retrieve the element identified by `handle` through mutable reflection.
+    ///
+    /// Returns `None` in the same cases as [`Self::get_reflect`].
+    #[inline]
+    #[must_use]
+    pub fn get_reflect_mut(
+        &mut self,
+        handle: Handle,
+        registry: &TypeRegistry,
+    ) -> Option<&mut dyn Reflect> {
+        if !self.contains(handle) {
+            return None;
+        }
+
+        let arena = &mut self.arenas[handle.index as usize];
+        let cast = caster(arena, handle, registry)?;
+
+        // Element may be modified through the reference, past what the model can follow
+        self.shadow.touch(handle);
+
+        // ! SAFETY: Element is live and of the type the cast was generated for
+        Some(unsafe { &mut *cast(arena.ptr_mut(handle.offset).cast()) })
+    }
+}
+
+/// Cast from element addresses to reflected references, for the type of `handle` in `arena`.
+#[inline]
+fn caster<Trait: ?Sized + Pointee<Metadata = DynMetadata<Trait>>>(
+    arena: &Arena<Trait>,
+    handle: Handle,
+    registry: &TypeRegistry,
+) -> Option<fn(*mut ()) -> *mut dyn Reflect> {
+    let (type_id, _) = arena.kind(arena.slot(handle.offset));
+    let from_ptr = registry.get_type_data::<ReflectFromPtr>(type_id)?;
+
+    // Registrations may carry type data generated for another type
+    (from_ptr.type_id() == type_id).then(|| from_ptr.raw_pointer_cast())
+}
@@ -0,0 +1,129 @@
+use core::any::TypeId;
+use core::marker::Unsize;
+use core::ptr::{DynMetadata, Pointee};
+
+use unscrupulous::Unscrupulous;
+
+use crate::{Arena, Hato, Storage};
+
+/// Callback repairing an element moved by the collection, from its old address to its new one.
+///
+/// The old address is only meant for fix-ups, as memory behind it may already be released.
+/// Bytes are those of the element at its new address.
+pub type Relocate = fn(old: *const u8, new: *const u8, bytes: &mut [u8]);
+
+impl<Trait: ?Sized + Pointee<Metadata = DynMetadata<Trait>>, S: Storage> Hato<Trait, S> {
+    /// Call `f` on every element of type `T` the collection moves in memory, from now on.
+    ///
+    /// Types caching pointers into themselves, or registered by address elsewhere, can thus
+    /// repair themselves when compaction moves them to another slot, or when the buffer of their
+    /// arena grows to another allocation. Elements stored in their own allocation never move.
+    /// Copies of elements, like clones of the collection, are not reported.
+    ///
+    /// ```rust
+    /// use core::fmt::Debug;
+    ///
+    /// #[derive(Debug)]
+    /// struct Anchored(usize);
+    ///
+    /// unsafe impl unscrupulous::Unscrupulous for Anchored {}
+    ///
+    /// let mut arena = hato::Hato::<dyn Debug>::default();
+    ///
+    /// arena.on_relocate::<Anchored>(|_, new, bytes| {
+    ///     bytes.copy_from_slice(&(new as usize).to_ne_bytes());
+    /// });
+    ///
+    /// let x = arena.push(Anchored(0));
+    ///
+    /// for _ in 0..100 {
+    ///     let _ = arena.push(Anchored(0));
+    /// }
+    ///
+    /// // The element was moved by growth of the buffer, and knows its new address
+    /// let ptr = unsafe { arena.element_bytes(x) }.as_ptr() as usize;
+    /// assert_eq!(format!("{:?}", unsafe { arena.get(x) }), format!("Anchored({ptr})"));
+    /// ```
+    #[inline]
+    pub fn on_relocate<T: Unsize<Trait> + Unscrupulous>(&mut self, f: Relocate) {
+        let type_id = typeid::of::<T>();
+
+        self.relocations.retain(|(id, _)| *id != type_id);
+        self.relocations.push((type_id, f));
+
+        for (index, arena) in self.arenas.iter_mut().enumerate() {
+            if arena.kind_of(|(id, _)| id == type_id).is_some() {
+                arena.attach(type_id, f);
+                self.shadow.exempt(index);
+            }
+        }
+    }
+
+    /// Hand the callback registered for `type_id`, if any, to the arena at `index`.
+    #[inline]
+    pub(crate) fn attach_relocation(&mut self, index: usize, type_id: TypeId) {
+        let found = self.relocations.iter().find(|(id, _)| *id == type_id);
+
+        if let Some((_, f)) = found {
+            self.arenas[index].attach(type_id, *f);
+            self.shadow.exempt(index);
+        }
+    }
+}
+
+impl<Trait: ?Sized + Pointee<Metadata = DynMetadata<Trait>>, S: Storage> Arena<Trait, S> {
+    /// Call `f` on elements of type `type_id` of this arena whenever they move.
+    #[inline]
+    fn attach(&mut self, type_id: TypeId, f: Relocate) {
+        self.relocations.retain(|(id, _)| *id != type_id);
+        self.relocations.push((type_id, f));
+    }
+
+    /// Base address of the buffer and number of slots, before an operation that may move them.
+    #[inline]
+    pub(crate) fn anchor(&self) -> (*const u8, usize) {
+        (self.bytes.as_ptr(), self.occupied.len())
+    }
+
+    /// Report elements moved by re-allocation of the buffer since `anchor` was taken.
+    #[inline]
+    pub(crate) fn relocate_all(&mut self, (base, count): (*const u8, usize)) {
+        if self.relocations.is_empty() || self.bytes.as_ptr() == base {
+            return;
+        }
+
+        for slot in 0..count {
+            if !self.occupied[slot] {
+                continue;
+            }
+
+            let old = base.wrapping_add(self.position(self.offset(slot)));
+            self.relocate(slot, old);
+        }
+    }
+
+    /// Report the element of `slot`, which was just moved there from `old`.
+    #[inline]
+    pub(crate) fn relocate(&mut self, slot: usize, old: *const u8) {
+        let (type_id, vtable) = self.kind(slot);
+
+        let Some((_, f)) = self.relocations.iter().find(|(id, _)| *id == type_id) else {
+            return;
+        };
+
+        let f = *f;
+
+        // Elements of the buffer move along with it, others having their own allocation
+        if self.spill || vtable.size_of() == 0 {
+            return;
+        }
+
+        let ptr = self.ptr_mut(self.offset(slot));
+
+        // ! SAFETY: Slot holds a live element of this size, valid as bytes
+        // ! thanks to `Unscrupulous` bound
+        let bytes = unsafe { core::slice::from_raw_parts_mut(ptr, vtable.size_of()) };
+
+        f(old, ptr, bytes);
+    }
+}
@@ -0,0 +1,148 @@
+use alloc::collections::BTreeMap;
+
+use crate::{Handle, Index};
+
+/// Table of handles to elements that moved, mapping their old handle to their new one.
+///
+/// Handles that are not in the table are left unchanged, as their element did not move.
+/// Helpers apply the table to external bookkeeping in a single pass.
+///
+/// ```rust
+/// let mut arena = hato::Hato::<dyn core::fmt::Debug>::default();
+///
+/// let x = arena.push(1_u8);
+/// let y = arena.push(2_u8);
+///
+/// let remap = hato::Remap::from_iter([(x, y)]);
+///
+/// let mut handles = vec![x, y];
+/// remap.apply(&mut handles);
+///
+/// assert_eq!(handles, [y, y]);
+/// assert_eq!(remap.get(y), None);
+/// ```
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct Remap(BTreeMap<Handle, Handle>);
+
+impl FromIterator<(Handle, Handle)> for Remap {
+    fn from_iter<I: IntoIterator<Item = (Handle, Handle)>>(iter: I) -> Self {
+        Self(iter.into_iter().collect())
+    }
+}
+
+impl Remap {
+    /// New handle of the element identified by `handle`, if it moved.
+    #[inline]
+    #[must_use]
+    pub fn get(&self, handle: Handle) -> Option<Handle> {
+        self.0.get(&handle).copied()
+    }
+
+    /// Current handle of the element identified by `handle`, whether it moved or not.
+    #[inline]
+    #[must_use]
+    pub fn resolve(&self, handle: Handle) -> Handle {
+        self.get(handle).unwrap_or(handle)
+    }
+
+    /// Number of elements that moved.
+    #[inline]
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    /// Check whether no element moved.
+    #[inline]
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Combine with `next`, a table of later moves, so that handles resolve through both.
+    ///
+    /// ```rust
+    /// let mut arena = hato::Hato::<dyn core::fmt::Debug>::default();
+    ///
+    /// let [x, y, z] = [arena.push(1_u8), arena.push(2_u8), arena.push(3_u8)];
+    ///
+    /// let first = hato::Remap::from_iter([(x, y)]);
+    /// let second = hato::Remap::from_iter([(y, z)]);
+    ///
+    /// let chained = first.chain(&second);
+    /// assert_eq!((chained.resolve(x), chained.resolve(y)), (z, z));
+    /// ```
+    #[inline]
+    #[must_use]
+    pub fn chain(&self, next: &Self) -> Self {
+        let earlier = self.0.iter().map(|(old, new)| (*old, next.resolve(*new)));
+        let later = next.0.iter().filter(|(old, _)| !self.0.contains_key(old));
+
+        Self(
+            earlier
+                .chain(later.map(|(old, new)| (*old, *new)))
+                .collect(),
+        )
+    }
+
+    /// Update all `handles` in place to point to the new location of their element.
+    #[inline]
+    pub fn apply(&self, handles: &mut [Handle]) {
+        for handle in handles {
+            *handle = self.resolve(*handle);
+        }
+    }
+
+    /// Re-key all entries of `map` to the new location of their element.
+    ///
+    /// Works with any map keyed by handles, such as [`BTreeMap`] or `HashMap`.
+    #[inline]
+    pub fn apply_map<V, M>(&self, map: &mut M)
+    where
+        M: Default + IntoIterator<Item = (Handle, V)> + FromIterator<(Handle, V)>,
+    {
+        *map = core::mem::take(map)
+            .into_iter()
+            .map(|(handle, value)| (self.resolve(handle), value))
+            .collect();
+    }
+
+    /// Entries whose element moved again in `next`, to their handle after both moves.
+    #[inline]
+    pub(crate) fn moved_by(&self, next: &Self) -> Self {
+        let moved = self
+            .0
+            .iter()
+            .filter_map(|(old, new)| Some((*old, next.get(*new)?)));
+        Self(moved.collect())
+    }
+
+    /// Entries of both tables, whose old handles must not overlap.
+    #[inline]
+    pub(crate) fn union(mut self, other: Self) -> Self {
+        self.0.extend(other.0);
+        self
+    }
+
+    /// Same entries, with the arena index of handles mapped by `f`.
+    #[inline]
+    pub(crate) fn reindex(&self, f: impl Fn(Index) -> Index) -> Self {
+        let reindex = |handle: Handle| Handle {
+            index: f(handle.index),
+            ..handle
+        };
+
+        Self(
+            self.0
+                .iter()
+                .map(|(old, new)| (reindex(*old), reindex(*new)))
+                .collect(),
+        )
+    }
+
+    /// Keep only the entries for which `f` returns `true`, given the old and new handle.
+    #[inline]
+    pub(crate) fn retain(&mut self, mut f: impl FnMut(Handle, Handle) -> bool) {
+        self.0.retain(|old, new| f(*old, *new));
+    }
+}
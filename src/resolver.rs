@@ -0,0 +1,130 @@
+use core::marker::PhantomData;
+use core::ptr::{from_raw_parts, DynMetadata, Pointee};
+
+use aligned_vec::AVec;
+
+use crate::{Handle, Hato, Index, Kind, Remap};
+
+/// Accessor bound to a single arena, to resolve many handles to elements of the same type.
+///
+/// Resolving a handle through [`Hato::get`] first looks up its arena in the directory.
+/// The resolver caches the arena's base pointer and virtual table instead,
+/// which pays off in tight loops over handles to elements of the same type.
+/// It borrows the collection, so any mutation statically invalidates it.
+///
+/// ```rust
+/// let mut arena = hato::Hato::<dyn core::fmt::Debug>::default();
+///
+/// let xs = [arena.push(1_u8), arena.push(2_u8)];
+/// let y = arena.push(3_i32);
+///
+/// let resolver = arena.resolver(xs[0]);
+///
+/// assert_eq!(format!("{:?}", unsafe { resolver.get(xs[1]) }.unwrap()), "2");
+/// assert!(unsafe { resolver.get(y) }.is_none());
+/// ```
+#[derive(Clone, Copy, Debug)]
+pub struct HandleResolver<'a, Trait: ?Sized + Pointee<Metadata = DynMetadata<Trait>>> {
+    index: Index,
+    base: *const u8,
+    spilled: Option<&'a [AVec<u8>]>,
+    occupied: &'a [bool],
+    len: usize,
+    size: usize,
+    stride: usize,
+    vtable: DynMetadata<Trait>,
+    kinds: &'a [Kind<Trait>],
+
+    /// Forwarding entries of stale handles, only looked up if there are some.
+    forwards: Option<&'a Remap>,
+
+    marker: PhantomData<&'a Hato<Trait>>,
+}
+
+impl<Trait: ?Sized + Pointee<Metadata = DynMetadata<Trait>>> Hato<Trait> {
+    /// Bind a resolver to the arena of the element identified by `handle`.
+    ///
+    /// # Panics
+    ///
+    /// This function will panic if the handle does not point to an arena of this collection.
+    #[inline]
+    #[must_use]
+    pub fn resolver(&self, handle: Handle) -> HandleResolver<'_, Trait> {
+        let handle = self.forward(handle);
+        let arena = &self.arenas[handle.index as usize];
+
+        HandleResolver {
+            index: handle.index,
+            base: arena.bytes.as_ptr(),
+            spilled: arena.spill.then_some(arena.spilled.as_slice()),
+            occupied: &arena.occupied,
+            len: arena.bytes.len(),
+            size: arena.vtable.size_of(),
+            stride: arena.stride,
+            vtable: arena.vtable,
+            kinds: &arena.kinds,
+            forwards: self.forwards(),
+            marker: PhantomData,
+        }
+    }
+}
+
+impl<'a, Trait: ?Sized + Pointee<Metadata = DynMetadata<Trait>>> HandleResolver<'a, Trait> {
+    /// Retrieve the element identified by `handle`, if it belongs to the bound arena.
+    ///
+    /// # Safety
+    ///
+    /// The handle must originate from the same instance of `Hato` as the resolver.
+    ///
+    /// # Panics
+    ///
+    /// This function will panic if `handle` is stale and points past the end of its arena,
+    /// or to a removed element that was spilled, as [`Hato::get`] does.
+    #[inline]
+    #[must_use]
+    pub unsafe fn get(&self, handle: Handle) -> Option<&'a Trait> {
+        // Compaction forwards stale handles within their arena, never to another one
+        let handle = self
+            .forwards
+            .map_or(handle, |forwards| forwards.resolve(handle));
+
+        (handle.index == self.index).then(|| {
+            let slot = handle.offset as usize / self.stride;
+
+            let ptr = match self.spilled {
+                // Oversized elements live in their own allocation, freed on removal
+                Some(spilled) => {
+                    let live = self.occupied.get(slot).copied().unwrap_or(false);
+                    assert!(live, "stale handle to a removed spilled element");
+
+                    spilled[slot].as_ptr()
+                }
+
+                // Zero-sized types all live at the aligned base address of the buffer
+                None if self.size == 0 => self.base,
+
+                None => {
+                    let position = handle.offset as usize;
+
+                    assert!(
+                        position + self.size <= self.len,
+                        "stale handle past the end of its arena"
+                    );
+
+                    // ! SAFETY: Position lies within the buffer, as checked above
+                    unsafe { self.base.add(position) }
+                }
+            };
+
+            // Arenas shared across types record the virtual table of each slot
+            let vtable = self
+                .kinds
+                .get(slot)
+                .map_or(self.vtable, |(_, vtable)| *vtable);
+
+            // ! SAFETY: Trait object points to a valid byte representation of this type,
+            // ! and the buffer cannot be reallocated while the collection is borrowed
+            unsafe { &*from_raw_parts(ptr, vtable) }
+        })
+    }
+}
@@ -0,0 +1,308 @@
+use core::marker::{PhantomData, Unsize};
+use core::mem::{align_of, needs_drop, size_of};
+use core::ptr::{from_raw_parts, from_raw_parts_mut, DynMetadata, Pointee};
+
+use aligned_vec::{AVec, CACHELINE_ALIGN};
+use unscrupulous::{as_slice_of_bytes, Unscrupulous};
+
+use crate::{get_metadata_of_ref, Counted, Index, Storage};
+
+/// Heterogeneous collection storing elements of all types back-to-back, in insertion order.
+///
+/// Each element is preceded by a header holding its virtual table, in a single buffer.
+/// Compared to [`Hato`](crate::Hato), this trades a few bytes per element for a tiny
+/// directory and iteration in insertion order, as for event logs. Slots of removed elements
+/// are never reused, until [`Self::clear`]. Handles are a single [`Index`] offset, and follow
+/// the same rules as those of [`Hato`](crate::Hato).
+///
+/// ```rust
+/// let mut events = hato::Sequence::<dyn core::fmt::Debug>::default();
+///
+/// let _ = events.push(1_u8);
+/// let x = events.push(2_i64);
+/// let _ = events.push(3.0_f32);
+///
+/// events.remove(x);
+///
+/// assert_eq!(format!("{:?}", events.iter().collect::<Vec<_>>()), "[1, 3.0]");
+/// ```
+pub struct Sequence<Trait: ?Sized + Pointee<Metadata = DynMetadata<Trait>>> {
+    bytes: AVec<u8>,
+    len: usize,
+    marker: PhantomData<DynMetadata<Trait>>,
+}
+
+/// Metadata stored in front of each element of a [`Sequence`].
+///
+/// Both fields are pointer-sized, so that headers hold no padding bytes.
+#[repr(C)]
+struct Header<Trait: ?Sized + Pointee<Metadata = DynMetadata<Trait>>> {
+    vtable: Option<DynMetadata<Trait>>,
+    next: usize,
+}
+
+impl<Trait: ?Sized + Pointee<Metadata = DynMetadata<Trait>>> Clone for Header<Trait> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<Trait: ?Sized + Pointee<Metadata = DynMetadata<Trait>>> Copy for Header<Trait> {}
+
+impl<Trait: ?Sized + Pointee<Metadata = DynMetadata<Trait>>> Default for Sequence<Trait> {
+    fn default() -> Self {
+        Self {
+            // ! SAFETY: Force base pointer alignment so headers and elements are always
+            // ! stored at valid addresses, even on re-allocation events
+            bytes: AVec::new(CACHELINE_ALIGN),
+            len: 0,
+            marker: PhantomData,
+        }
+    }
+}
+
+impl<Trait: ?Sized + Pointee<Metadata = DynMetadata<Trait>>> Clone for Sequence<Trait> {
+    fn clone(&self) -> Self {
+        Self {
+            bytes: self.bytes.clone(),
+            len: self.len,
+            marker: PhantomData,
+        }
+    }
+}
+
+impl<Trait: ?Sized + Pointee<Metadata = DynMetadata<Trait>>> core::fmt::Debug for Sequence<Trait> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("Sequence")
+            .field("len", &self.len)
+            .field("bytes", &self.bytes.len())
+            .finish_non_exhaustive()
+    }
+}
+
+impl<Trait: ?Sized + Pointee<Metadata = DynMetadata<Trait>>> Sequence<Trait> {
+    /// Append `x` after all elements inserted so far.
+    ///
+    /// # Panics
+    ///
+    /// This function will panic if offsets of the sequence outgrow the [`Index`] type,
+    /// or if `T` is aligned to more than a cache line.
+    #[inline]
+    pub fn push<T: Unsize<Trait> + Unscrupulous>(&mut self, x: T) -> SequenceHandle {
+        // Reject types whose destructor would silently be skipped
+        const { assert!(!needs_drop::<T>(), "destructors of elements never run") }
+
+        // Alignment of the buffer bounds that of its elements
+        const { assert!(align_of::<T>() <= CACHELINE_ALIGN, "over-aligned type") }
+
+        let header = self.bytes.len();
+        let element = (header + size_of::<Header<Trait>>()).next_multiple_of(align_of::<T>());
+        let end = (element + size_of::<T>()).next_multiple_of(align_of::<Header<Trait>>());
+
+        let Ok(offset) = Index::try_from(header) else {
+            panic!("offsets of individual arenas should fit in the index type");
+        };
+
+        self.bytes.resize_zeroed(end);
+
+        let ptr = self.bytes.as_mut_ptr();
+        let bytes = as_slice_of_bytes(&x);
+
+        // ! SAFETY: Buffer was just grown past both positions, and the element one is aligned
+        // ! for its type since the buffer is aligned to a cache line
+        unsafe {
+            ptr.add(header)
+                .cast::<Header<Trait>>()
+                .write_unaligned(Header {
+                    vtable: Some(get_metadata_of_ref(&x)),
+                    next: end,
+                });
+
+            core::ptr::copy_nonoverlapping(bytes.as_ptr(), ptr.add(element), bytes.len());
+        }
+
+        // Prevent destructor from running on scope end
+        core::mem::forget(x);
+
+        self.len += 1;
+
+        SequenceHandle { offset }
+    }
+
+    /// Number of live elements.
+    #[inline]
+    #[must_use]
+    pub const fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Check whether the sequence holds no live element.
+    #[inline]
+    #[must_use]
+    pub const fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Retrieve the element identified by `handle`.
+    ///
+    /// # Safety
+    ///
+    /// The handle must originate from the same instance of `Sequence`, and its element
+    /// must not have been removed.
+    #[inline]
+    #[must_use]
+    pub unsafe fn get(&self, handle: SequenceHandle) -> &Trait {
+        let (position, vtable) = self.element(handle);
+        let ptr = self.bytes.as_ptr().wrapping_add(position);
+
+        // ! SAFETY: Caller guarantees handle identifies a live element of this sequence
+        unsafe { &*from_raw_parts(ptr, vtable) }
+    }
+
+    /// Retrieve the element identified by `handle` mutably.
+    ///
+    /// # Safety
+    ///
+    /// The handle must originate from the same instance of `Sequence`, and its element
+    /// must not have been removed.
+    #[inline]
+    #[must_use]
+    pub unsafe fn get_mut(&mut self, handle: SequenceHandle) -> &mut Trait {
+        let (position, vtable) = self.element(handle);
+        let ptr = self.bytes.as_mut_ptr().wrapping_add(position);
+
+        // ! SAFETY: Caller guarantees handle identifies a live element of this sequence
+        unsafe { &mut *from_raw_parts_mut(ptr, vtable) }
+    }
+
+    /// Remove the element identified by `handle`, leaving its bytes in place.
+    ///
+    /// # Panics
+    ///
+    /// This function will panic if `handle` does not originate from this sequence.
+    #[inline]
+    pub fn remove(&mut self, handle: SequenceHandle) {
+        let offset = handle.offset as usize;
+
+        assert!(
+            offset + size_of::<Header<Trait>>() <= self.bytes.len(),
+            "handle should originate from this sequence"
+        );
+
+        let mut header = self.header(offset);
+
+        // Catch double removals, which would throw the count of live elements off
+        debug_assert!(header.vtable.is_some(), "element was already removed");
+
+        // Removed elements are marked by dropping their virtual table
+        if header.vtable.take().is_some() {
+            self.len -= 1;
+        }
+
+        // ! SAFETY: Header lies within the buffer
+        unsafe {
+            let ptr = self.bytes.as_mut_ptr().add(offset);
+            ptr.cast::<Header<Trait>>().write_unaligned(header);
+        }
+    }
+
+    /// Iterate over live elements, in insertion order.
+    #[inline]
+    #[must_use]
+    pub fn iter(&self) -> impl ExactSizeIterator<Item = &Trait> + '_ {
+        self.handles().map(|handle| {
+            // ! SAFETY: Handles come from walking the live elements of this sequence
+            unsafe { self.get(handle) }
+        })
+    }
+
+    /// Call `f` on every live element mutably, in insertion order.
+    #[inline]
+    pub fn for_each_mut(&mut self, mut f: impl FnMut(&mut Trait)) {
+        let mut offset = 0;
+
+        while offset < self.bytes.len() {
+            let header = self.header(offset);
+
+            // Offsets fit in an `Index`, as checked on insertion
+            #[allow(clippy::cast_possible_truncation)]
+            let handle = SequenceHandle {
+                offset: offset as Index,
+            };
+
+            if header.vtable.is_some() {
+                // ! SAFETY: Handle identifies a live element of this sequence
+                f(unsafe { self.get_mut(handle) });
+            }
+
+            offset = header.next;
+        }
+    }
+
+    /// Iterate over the handles of live elements, in insertion order.
+    #[inline]
+    #[must_use]
+    pub fn handles(&self) -> impl ExactSizeIterator<Item = SequenceHandle> + '_ {
+        let mut offset = 0;
+
+        let handles = core::iter::from_fn(move || {
+            while offset < self.bytes.len() {
+                let header = self.header(offset);
+
+                // Offsets fit in an `Index`, as checked on insertion
+                #[allow(clippy::cast_possible_truncation)]
+                let handle = SequenceHandle {
+                    offset: offset as Index,
+                };
+
+                offset = header.next;
+
+                if header.vtable.is_some() {
+                    return Some(handle);
+                }
+            }
+
+            None
+        });
+
+        Counted::new(handles, self.len)
+    }
+
+    /// Remove all elements, keeping the capacity.
+    #[inline]
+    pub fn clear(&mut self) {
+        self.bytes.clear();
+        self.len = 0;
+    }
+
+    /// Position and virtual table of the element identified by `handle`.
+    #[inline]
+    fn element(&self, handle: SequenceHandle) -> (usize, DynMetadata<Trait>) {
+        let offset = handle.offset as usize;
+        let vtable = self.header(offset).vtable.expect("element was removed");
+
+        let position = offset + size_of::<Header<Trait>>();
+        (position.next_multiple_of(vtable.align_of()), vtable)
+    }
+
+    /// Header of the element starting at `offset`.
+    #[inline]
+    fn header(&self, offset: usize) -> Header<Trait> {
+        debug_assert!(offset + size_of::<Header<Trait>>() <= self.bytes.len());
+
+        // ! SAFETY: Headers are written within the buffer on insertion
+        unsafe {
+            self.bytes
+                .as_ptr()
+                .add(offset)
+                .cast::<Header<Trait>>()
+                .read_unaligned()
+        }
+    }
+}
+
+/// Index to access an element stored in a [`Sequence`].
+#[derive(Clone, Copy, Debug, Eq, Ord, PartialEq, PartialOrd)]
+pub struct SequenceHandle {
+    offset: Index,
+}
@@ -0,0 +1,164 @@
+#[cfg(feature = "shadow")]
+use alloc::collections::{BTreeMap, BTreeSet};
+#[cfg(feature = "shadow")]
+use alloc::vec::Vec;
+
+use crate::{index_of, Handle, LiveSlots};
+
+#[cfg(feature = "shadow")]
+use crate::Index;
+
+/// Model of the collection, mirroring every operation to cross-check the arenas against it.
+///
+/// The model keeps a plain copy of the bytes of each live element, keyed by handle.
+/// Without the `shadow` feature, it holds nothing and all checks compile away.
+#[derive(Clone, Debug, Default)]
+pub struct Shadow {
+    #[cfg(feature = "shadow")]
+    elements: BTreeMap<Handle, Vec<u8>>,
+
+    #[cfg(feature = "shadow")]
+    touched: BTreeSet<Handle>,
+
+    #[cfg(feature = "shadow")]
+    exempted: BTreeSet<Index>,
+}
+
+#[cfg_attr(
+    not(feature = "shadow"),
+    allow(
+        clippy::missing_const_for_fn,
+        clippy::needless_pass_by_ref_mut,
+        clippy::unused_self,
+        unused_mut,
+        unused_variables
+    )
+)]
+impl Shadow {
+    /// Mirror the insertion of element `handle`, whose slot must not hold a live element.
+    #[inline]
+    pub fn insert<'a>(&mut self, handle: Handle, bytes: impl FnOnce() -> &'a [u8]) {
+        #[cfg(feature = "shadow")]
+        {
+            let previous = self.elements.insert(handle, bytes().to_vec());
+            let _ = self.touched.remove(&handle);
+
+            assert!(previous.is_none(), "slot of {handle:?} handed out twice");
+        }
+    }
+
+    /// Mirror the removal of element `handle`, which must be live.
+    #[inline]
+    pub fn remove(&mut self, handle: Handle) {
+        #[cfg(feature = "shadow")]
+        {
+            let removed = self.elements.remove(&handle);
+            let _ = self.touched.remove(&handle);
+
+            assert!(removed.is_some(), "removed {handle:?}, which is not live");
+        }
+    }
+
+    /// Mirror bulk removals, keeping only elements for which `f` returns `true`.
+    #[inline]
+    pub fn retain(&mut self, mut f: impl FnMut(Handle) -> bool) {
+        #[cfg(feature = "shadow")]
+        {
+            self.elements.retain(|handle, _| f(*handle));
+            self.touched.retain(|handle| f(*handle));
+        }
+    }
+
+    /// Stop checking the bytes of element `handle`, which may be modified in place.
+    #[inline]
+    pub fn touch(&mut self, handle: Handle) {
+        #[cfg(feature = "shadow")]
+        {
+            let _ = self.touched.insert(handle);
+        }
+    }
+
+    /// Stop checking the bytes of elements of arena `index`, which relocation callbacks modify.
+    #[inline]
+    pub fn exempt(&mut self, index: usize) {
+        #[cfg(feature = "shadow")]
+        {
+            let _ = self.exempted.insert(index_of(index));
+        }
+    }
+
+    /// Check that element `handle` has the same bytes as in the model, if it is live.
+    ///
+    /// Accesses through stale handles are allowed, and left unchecked.
+    #[inline]
+    pub fn check<'a>(&self, handle: Handle, bytes: impl FnOnce() -> &'a [u8]) {
+        #[cfg(feature = "shadow")]
+        if let Some(expected) = self.elements.get(&handle) {
+            if !self.touched.contains(&handle) && !self.exempted.contains(&handle.index) {
+                assert_eq!(
+                    bytes(),
+                    expected,
+                    "bytes of {handle:?} diverged from the model"
+                );
+            }
+        }
+    }
+}
+
+impl<Trait, S: crate::Storage> crate::Hato<Trait, S>
+where
+    Trait: ?Sized + core::ptr::Pointee<Metadata = core::ptr::DynMetadata<Trait>>,
+{
+    /// Stop checking the bytes of all live elements, which are about to be handed out mutably.
+    #[inline]
+    pub(crate) fn touch_all(&mut self) {
+        for (index, arena) in self.arenas.iter().enumerate() {
+            for slot in LiveSlots::new(&arena.occupied) {
+                self.shadow.touch(Handle {
+                    index: index_of(index),
+                    offset: arena.offset(slot),
+                });
+            }
+        }
+    }
+}
+
+#[cfg(feature = "shadow")]
+impl<Trait, S: crate::Storage> crate::Hato<Trait, S>
+where
+    Trait: ?Sized + core::ptr::Pointee<Metadata = core::ptr::DynMetadata<Trait>>,
+{
+    /// Cross-check every live element of the arenas against the shadow model.
+    ///
+    /// # Panics
+    ///
+    /// This function will panic if live elements or their bytes differ from the model.
+    #[inline]
+    pub fn verify_shadow(&self) {
+        let mut live = 0;
+
+        for (index, arena) in self.arenas.iter().enumerate() {
+            for slot in (0..arena.occupied.len()).filter(|slot| arena.occupied[*slot]) {
+                let index = index_of(index);
+
+                let offset = arena.offset(slot);
+
+                let handle = Handle { index, offset };
+
+                assert!(
+                    self.shadow.elements.contains_key(&handle),
+                    "{handle:?} is live, but missing from the model"
+                );
+                self.shadow.check(handle, || arena.element(offset));
+
+                live += 1;
+            }
+        }
+
+        assert_eq!(
+            live,
+            self.shadow.elements.len(),
+            "model holds dead elements"
+        );
+    }
+}
@@ -0,0 +1,82 @@
+#[cfg(feature = "get-size")]
+use core::mem::size_of;
+use core::ptr::{DynMetadata, Pointee};
+
+#[cfg(feature = "get-size")]
+use aligned_vec::AVec;
+
+#[cfg(feature = "get-size")]
+use crate::{Arena, Index, Kind, Link};
+use crate::{Hato, Storage};
+
+#[cfg(feature = "get-size")]
+impl<Trait, S> get_size::GetSize for Hato<Trait, S>
+where
+    Trait: ?Sized + Pointee<Metadata = DynMetadata<Trait>>,
+    S: Storage,
+{
+    fn get_heap_size(&self) -> usize {
+        // Directory of arenas, including its spare capacity
+        let directory = self.arenas.capacity() * size_of::<Arena<Trait, S>>();
+
+        // Byte buffers, free lists, occupancy flags and sidecars of each arena
+        let arenas = self.arenas.iter().map(|arena| {
+            let bytes = arena.bytes.capacity();
+            let spilled = arena.spilled.capacity() * size_of::<AVec<u8>>()
+                + arena.spilled.iter().map(AVec::capacity).sum::<usize>();
+            let slots = arena.slots.capacity() * size_of::<Index>();
+            let occupied = arena.occupied.capacity() * size_of::<bool>();
+            let links = arena.links.capacity() * size_of::<Link>();
+            let tags = arena.tags.capacity();
+            let kinds =
+                (arena.types.capacity() + arena.kinds.capacity()) * size_of::<Kind<Trait>>();
+            let counts = arena.counts.capacity() * size_of::<usize>();
+            let ages = arena.ages.capacity() * size_of::<u64>();
+
+            bytes + spilled + slots + occupied + links + tags + kinds + counts + ages
+        });
+
+        directory + arenas.sum::<usize>()
+    }
+}
+
+#[cfg(feature = "malloc_size_of")]
+impl<Trait, S> malloc_size_of::MallocSizeOf for Hato<Trait, S>
+where
+    Trait: ?Sized + Pointee<Metadata = DynMetadata<Trait>>,
+    S: Storage,
+{
+    fn size_of(&self, ops: &mut malloc_size_of::MallocSizeOfOps) -> usize {
+        use malloc_size_of::MallocShallowSizeOf;
+
+        // Measure directory allocation as reported by the allocator
+        let directory = self.arenas.shallow_size_of(ops);
+
+        let arenas = self.arenas.iter().map(|arena| {
+            // Storages may not come from the allocator, such as mapped pages, so their
+            // capacity stands for the size of their allocation
+            let bytes = arena.bytes.capacity();
+
+            // Individual allocations of oversized elements, along with their pointers
+            let spilled = arena.spilled.shallow_size_of(ops)
+                + arena
+                    .spilled
+                    .iter()
+                    // ! SAFETY: Pointers come from individual allocations, or are dangling
+                    .map(|blob| unsafe { ops.malloc_size_of(blob.as_ptr()) })
+                    .sum::<usize>();
+
+            let slots = arena.slots.shallow_size_of(ops);
+            let occupied = arena.occupied.shallow_size_of(ops);
+            let links = arena.links.shallow_size_of(ops);
+            let tags = arena.tags.shallow_size_of(ops);
+            let kinds = arena.types.shallow_size_of(ops) + arena.kinds.shallow_size_of(ops);
+            let counts = arena.counts.shallow_size_of(ops);
+            let ages = arena.ages.shallow_size_of(ops);
+
+            bytes + spilled + slots + occupied + links + tags + kinds + counts + ages
+        });
+
+        directory + arenas.sum::<usize>()
+    }
+}
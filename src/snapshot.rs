@@ -0,0 +1,1410 @@
+use alloc::borrow::{Cow, ToOwned};
+use alloc::string::String;
+use alloc::vec;
+use alloc::vec::Vec;
+
+use core::fmt::{self, Display, Formatter};
+use core::ptr::{DynMetadata, Pointee};
+
+#[cfg(feature = "std")]
+use core::future::Future;
+#[cfg(feature = "std")]
+use core::pin::pin;
+#[cfg(feature = "std")]
+use core::task::{Context, Poll, Waker};
+
+#[cfg(feature = "std")]
+use std::io::{self, Read, Write};
+
+#[cfg(feature = "rayon")]
+use rayon::prelude::{IndexedParallelIterator, IntoParallelRefIterator, ParallelIterator};
+use serde::{Deserialize, Serialize};
+
+use crate::{index_of, Arena, Handle, Hato, Index, Kind, LiveSlots, Options, Storage, Types};
+
+/// Serializable copy of the elements of a [`Hato`], tagged by type name, with their slots.
+///
+/// Restoring a snapshot lays elements out in the same arenas and slots, so that handles
+/// to the original collection identify the same elements in the restored one. Only elements
+/// are captured: tag bytes, names and lists start out empty after a restore.
+///
+/// As with [`OpLog`](crate::OpLog), elements are stored as their bytes, which only make
+/// sense to builds sharing the layout of their types. Tag types with `#[repr(C)]` to keep
+/// their layout stable across compilations. Types are recorded by their name in [`Types`],
+/// whose aliases and version let applications keep loading snapshots as they evolve.
+/// Arenas carry a checksum of their contents, so that truncated or altered snapshots fail
+/// to restore with a [`SnapshotError`] naming the arena at fault, instead of yielding garbage.
+///
+/// ```rust
+/// let mut arena = hato::Hato::<dyn core::fmt::Debug>::default();
+///
+/// let x = arena.push(1_u8);
+/// let y = arena.push(2_u16);
+/// arena.remove(x);
+///
+/// // Snapshots go through any `serde` data format, to be restored later on
+/// let types = hato::Types::default().register::<u8>().register::<u16>();
+/// let snapshot = arena.snapshot(&types).unwrap();
+///
+/// let mut restored = hato::Hato::<dyn core::fmt::Debug>::default();
+/// unsafe { snapshot.restore(&mut restored, &types) }.unwrap();
+///
+/// assert!(!restored.contains(x));
+/// assert_eq!(format!("{:?}", unsafe { restored.get(y) }), "2");
+/// ```
+#[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+#[allow(clippy::unsafe_derive_deserialize)] // Restores are unsafe whatever the source of snapshots
+pub struct HatoSnapshot {
+    version: u32,
+    arenas: Vec<ArenaSnapshot>,
+
+    /// Checksum of the checksums of arenas, catching arenas lost or swapped along the way.
+    digest: u32,
+}
+
+/// Elements of a single arena, along with the free slots between them.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+struct ArenaSnapshot {
+    /// Names of the types admitted by the arena, starting with the one it was created for.
+    types: Vec<String>,
+
+    /// Distance between the offsets of consecutive slots, which handles depend on.
+    stride: usize,
+
+    /// Size of the elements, shared by all types of the arena.
+    size: usize,
+
+    /// Position of the type of each slot in `types`, or nothing for free slots.
+    slots: Vec<Option<usize>>,
+
+    /// Bytes of live elements, back to back in slot order.
+    bytes: Vec<u8>,
+
+    /// Whether `bytes` went through [`HatoSnapshot::compress`].
+    compressed: bool,
+
+    /// Whether `bytes` went through [`HatoSnapshot::seal`], along with the checksum.
+    sealed: bool,
+
+    /// Checksum of the contents of the arena, as streamed by [`Hato::write_snapshot`].
+    checksum: u32,
+}
+
+/// Reason a snapshot could not be restored, pointing at the arena at fault if any.
+///
+/// Arenas are numbered in the order they were snapshotted, which is their directory index.
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum SnapshotError {
+    /// The collection to restore into is not empty.
+    NotEmpty,
+
+    /// A type of the arena is missing from the registry, which does not skip unknown types.
+    UnknownType {
+        /// Index of the arena.
+        arena: usize,
+
+        /// Name of the type, as recorded in the snapshot.
+        name: String,
+    },
+
+    /// The arena or its elements are laid out differently in the collection restored into.
+    Layout {
+        /// Index of the arena.
+        arena: usize,
+
+        /// Names of the types admitted by the arena, as recorded in the snapshot.
+        types: Vec<String>,
+    },
+
+    /// The contents of the arena do not match their checksum, after truncation or bit rot.
+    Corrupt {
+        /// Index of the arena.
+        arena: usize,
+
+        /// Names of the types admitted by the arena, as recorded in the snapshot.
+        types: Vec<String>,
+    },
+
+    /// Arenas were lost, added or reordered since the snapshot was taken.
+    Digest,
+
+    /// The arena is sealed, or could not be sealed or opened with the given cipher.
+    #[cfg(feature = "seal")]
+    Sealed {
+        /// Index of the arena.
+        arena: usize,
+    },
+
+    /// The stream does not hold a snapshot in a format this build understands.
+    #[cfg(feature = "std")]
+    Format,
+
+    /// Reading the stream failed.
+    #[cfg(feature = "std")]
+    Io(io::Error),
+}
+
+impl Display for SnapshotError {
+    #[inline]
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NotEmpty => f.write_str("snapshots restore into empty collections only"),
+            Self::UnknownType { arena, name } => {
+                write!(f, "unknown type `{name}` in arena {arena}")
+            }
+            Self::Layout { arena, types } => {
+                write!(f, "layout mismatch of arena {arena} of {types:?}")
+            }
+            Self::Corrupt { arena, types } => write!(f, "corrupt arena {arena} of {types:?}"),
+            Self::Digest => f.write_str("arenas lost or reordered since the snapshot"),
+            #[cfg(feature = "seal")]
+            Self::Sealed { arena } => write!(f, "arena {arena} is sealed or failed to open"),
+            #[cfg(feature = "std")]
+            Self::Format => f.write_str("not a snapshot of a supported format"),
+            #[cfg(feature = "std")]
+            Self::Io(error) => write!(f, "failed to read snapshot: {error}"),
+        }
+    }
+}
+
+impl core::error::Error for SnapshotError {
+    #[inline]
+    fn source(&self) -> Option<&(dyn core::error::Error + 'static)> {
+        match self {
+            #[cfg(feature = "std")]
+            Self::Io(error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl From<io::Error> for SnapshotError {
+    #[inline]
+    fn from(error: io::Error) -> Self {
+        Self::Io(error)
+    }
+}
+
+impl<Trait: ?Sized + Pointee<Metadata = DynMetadata<Trait>>, S: Storage> Hato<Trait, S> {
+    /// Copy all elements into a [`HatoSnapshot`], arena by arena and slot by slot.
+    ///
+    /// Returns `None` if an arena admits a type missing from `types`.
+    #[inline]
+    #[must_use]
+    pub fn snapshot(&self, types: &Types<Trait>) -> Option<HatoSnapshot> {
+        let arenas = self.arenas.iter().map(|arena| snapshot_arena(arena, types));
+        let arenas = arenas.collect::<Option<Vec<_>>>()?;
+
+        Some(HatoSnapshot {
+            version: types.version(),
+            digest: digest(arenas.iter().map(|arena| arena.checksum)),
+            arenas,
+        })
+    }
+}
+
+/// Copy the elements of `arena`, or `None` if it admits a type missing from `types`.
+#[inline]
+fn snapshot_arena<Trait, S>(arena: &Arena<Trait, S>, types: &Types<Trait>) -> Option<ArenaSnapshot>
+where
+    Trait: ?Sized + Pointee<Metadata = DynMetadata<Trait>>,
+    S: Storage,
+{
+    let names = arena
+        .types
+        .iter()
+        .map(|(type_id, _)| types.name_of(*type_id));
+    let names = names.collect::<Option<Vec<_>>>()?;
+
+    let mut bytes = Vec::with_capacity(arena.live * arena.vtable.size_of());
+
+    let slots = (0..arena.occupied.len()).map(|slot| {
+        if !arena.occupied[slot] {
+            return Some(None);
+        }
+
+        let type_id = arena.kind(slot).0;
+        let position = arena.types.iter().position(|(id, _)| *id == type_id)?;
+
+        bytes.extend_from_slice(arena.element(arena.offset(slot)));
+        Some(Some(position))
+    });
+
+    let slots = slots.collect::<Option<_>>()?;
+
+    let mut snapshot = ArenaSnapshot {
+        types: names.into_iter().map(ToOwned::to_owned).collect(),
+        stride: arena.stride,
+        size: arena.vtable.size_of(),
+        slots,
+        bytes,
+        compressed: false,
+        sealed: false,
+        checksum: 0,
+    };
+
+    snapshot.checksum = snapshot.checksum_of(&snapshot.bytes);
+
+    Some(snapshot)
+}
+
+/// Bytes starting streamed snapshots, followed by the version of their format.
+#[cfg(feature = "std")]
+const MAGIC: [u8; 4] = *b"HATO";
+
+/// Version of the format of streamed snapshots, bumped on incompatible changes.
+#[cfg(feature = "std")]
+const FORMAT: u32 = 1;
+
+#[cfg(feature = "std")]
+impl<Trait: ?Sized + Pointee<Metadata = DynMetadata<Trait>>, S: Storage> Hato<Trait, S> {
+    /// Write all elements to `writer` as a snapshot, arena by arena and slot by slot.
+    ///
+    /// Unlike [`Self::snapshot`], nothing is buffered: elements go straight from their arena
+    /// to `writer`, which can be a file, a socket or a compressor. As a few bytes are written
+    /// for each slot, wrap unbuffered writers in a [`BufWriter`](std::io::BufWriter).
+    /// Read snapshots back with a [`SnapshotReader`].
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if `writer` fails, or of kind
+    /// [`InvalidInput`](io::ErrorKind::InvalidInput) if an arena admits a type missing
+    /// from `types`. Part of the snapshot may have been written by then.
+    #[inline]
+    pub fn write_snapshot(&self, types: &Types<Trait>, mut writer: impl Write) -> io::Result<()> {
+        let mut encoder = Encoder::new(self, types);
+        while encoder.step(&mut writer)? {}
+
+        Ok(())
+    }
+}
+
+/// Resumable writer of streamed snapshots, producing them piece by piece.
+#[cfg(feature = "std")]
+#[derive(Debug)]
+pub struct Encoder<'a, Trait: ?Sized + Pointee<Metadata = DynMetadata<Trait>>, S: Storage> {
+    hato: &'a Hato<Trait, S>,
+    types: &'a Types<Trait>,
+    step: Step,
+
+    /// Checksum of the contents of the arena being written.
+    crc: Crc32,
+
+    /// Checksum of the checksums of arenas written so far.
+    checksums: Crc32,
+}
+
+/// Next piece of a streamed snapshot to write.
+#[cfg(feature = "std")]
+#[derive(Clone, Copy, Debug)]
+enum Step {
+    Header,
+    Arena(usize),
+    Slot(usize, usize),
+    Done,
+}
+
+#[cfg(feature = "std")]
+impl<'a, Trait: ?Sized + Pointee<Metadata = DynMetadata<Trait>>, S: Storage> Encoder<'a, Trait, S> {
+    #[inline]
+    pub const fn new(hato: &'a Hato<Trait, S>, types: &'a Types<Trait>) -> Self {
+        Self {
+            hato,
+            types,
+            step: Step::Header,
+            crc: Crc32::new(),
+            checksums: Crc32::new(),
+        }
+    }
+
+    /// Write the next piece of the snapshot to `writer`, returning `false` once all was written.
+    ///
+    /// Pieces are the header, the head of each arena, then each of its slots in turn.
+    /// Nothing more is written after an error.
+    #[inline]
+    pub fn step(&mut self, writer: &mut impl Write) -> io::Result<bool> {
+        let step = self.step;
+
+        // Errors end the snapshot, which readers then reject
+        self.step = Step::Done;
+
+        self.step = match step {
+            Step::Header => {
+                writer.write_all(&MAGIC)?;
+                writer.write_all(&FORMAT.to_le_bytes())?;
+                writer.write_all(&self.types.version().to_le_bytes())?;
+
+                Step::Arena(0)
+            }
+            Step::Arena(index) => {
+                let Some(arena) = self.hato.arenas.get(index) else {
+                    writer.write_all(&[0])?;
+                    writer.write_all(&self.checksums.finish().to_le_bytes())?;
+
+                    return Ok(true);
+                };
+
+                let names = arena.types.iter().map(|(id, _)| self.types.name_of(*id));
+                let names = names.collect::<Option<Vec<_>>>().ok_or_else(unknown)?;
+
+                writer.write_all(&[1])?;
+
+                // Contents of arenas are followed by their checksum
+                let mut writer = Checked::new(writer);
+
+                write_len(&mut writer, names.len())?;
+
+                for name in names {
+                    write_len(&mut writer, name.len())?;
+                    writer.write_all(name.as_bytes())?;
+                }
+
+                write_len(&mut writer, arena.stride)?;
+                write_len(&mut writer, arena.vtable.size_of())?;
+                write_len(&mut writer, arena.occupied.len())?;
+
+                self.crc = writer.crc;
+
+                Step::Slot(index, 0)
+            }
+            Step::Slot(index, slot) => {
+                let arena = &self.hato.arenas[index];
+
+                if slot == arena.occupied.len() {
+                    let checksum = self.crc.finish();
+
+                    writer.write_all(&checksum.to_le_bytes())?;
+                    self.checksums.update(&checksum.to_le_bytes());
+
+                    self.step = Step::Arena(index + 1);
+                    return Ok(true);
+                }
+
+                let mut writer = Checked {
+                    inner: writer,
+                    crc: self.crc,
+                };
+
+                // Slots start with the position of their type in the arena, shifted to leave
+                // zero for free slots, and live ones continue with the bytes of their element
+                if arena.occupied[slot] {
+                    let type_id = arena.kind(slot).0;
+                    let position = arena.types.iter().position(|(id, _)| *id == type_id);
+
+                    write_len(&mut writer, position.ok_or_else(unknown)? + 1)?;
+                    writer.write_all(arena.element(arena.offset(slot)))?;
+                } else {
+                    write_len(&mut writer, 0)?;
+                }
+
+                self.crc = writer.crc;
+
+                Step::Slot(index, slot + 1)
+            }
+            Step::Done => return Ok(false),
+        };
+
+        Ok(true)
+    }
+}
+
+/// Error for arenas admitting a type missing from the registry.
+#[cfg(feature = "std")]
+#[inline]
+fn unknown() -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidInput, "unregistered type")
+}
+
+/// Reader of snapshots streamed by [`Hato::write_snapshot`], restoring them as they come.
+///
+/// Only the elements of one slot are buffered at a time, so that snapshots larger than
+/// memory can be piped from files, sockets or decompressors. The header is read first,
+/// for applications to look up the [`version`](Self::version) before picking their types.
+///
+/// ```rust
+/// let mut arena = hato::Hato::<dyn core::fmt::Debug>::default();
+///
+/// let x = arena.push(1_u8);
+/// let y = arena.push(2_u16);
+/// arena.remove(x);
+///
+/// let types = hato::Types::default().register::<u8>().register::<u16>();
+///
+/// let mut bytes = Vec::new();
+/// arena.write_snapshot(&types, &mut bytes).unwrap();
+///
+/// let reader = hato::SnapshotReader::new(bytes.as_slice()).unwrap();
+/// assert_eq!(reader.version(), 0);
+///
+/// let mut restored = hato::Hato::<dyn core::fmt::Debug>::default();
+/// unsafe { reader.restore(&mut restored, &types) }.unwrap();
+///
+/// assert!(!restored.contains(x));
+/// assert_eq!(format!("{:?}", unsafe { restored.get(y) }), "2");
+/// ```
+#[cfg(feature = "std")]
+#[derive(Debug)]
+pub struct SnapshotReader<R: Read> {
+    reader: R,
+    version: u32,
+}
+
+#[cfg(feature = "std")]
+impl<R: Read> SnapshotReader<R> {
+    /// Read the header of the snapshot streamed from `reader`.
+    ///
+    /// # Errors
+    ///
+    /// This function will return [`SnapshotError::Io`] if `reader` fails, or
+    /// [`SnapshotError::Format`] if it does not stream a snapshot in a format this build
+    /// understands.
+    #[inline]
+    pub fn new(reader: R) -> Result<Self, SnapshotError> {
+        let mut reader = Blocking(reader);
+        let version = now(read_header(&mut reader))?;
+
+        Ok(Self {
+            reader: reader.0,
+            version,
+        })
+    }
+
+    /// Version of the registry the snapshot was taken with, see [`Types::with_version`].
+    #[inline]
+    #[must_use]
+    pub const fn version(&self) -> u32 {
+        self.version
+    }
+
+    /// Recreate the streamed elements in `hato`, at the slots they were taken from.
+    ///
+    /// Follows the rules of [`HatoSnapshot::restore`], rebuilding arenas as they are read.
+    /// They only join `hato` once the whole stream is checked, which leaves it untouched
+    /// on errors. Streams cut between arenas end up failing the digest.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the reader fails, if the snapshot is malformed
+    /// or corrupt, or if it does not match `types` and the layout of `hato`.
+    ///
+    /// # Safety
+    ///
+    /// The snapshot must come from a collection of the same types, as their bytes are copied
+    /// as is.
+    #[inline]
+    pub unsafe fn restore<Trait, S>(
+        mut self,
+        hato: &mut Hato<Trait, S>,
+        types: &Types<Trait>,
+    ) -> Result<(), SnapshotError>
+    where
+        Trait: ?Sized + Pointee<Metadata = DynMetadata<Trait>>,
+        S: Storage,
+    {
+        // ! SAFETY: Caller guarantees the snapshot comes from a collection of the same types
+        now(unsafe { restore_from(&mut Blocking(&mut self.reader), hato, types) })
+    }
+}
+
+/// Read the header of a streamed snapshot, returning the version of its registry.
+#[cfg(feature = "std")]
+#[inline]
+pub async fn read_header(source: &mut impl Source) -> Result<u32, SnapshotError> {
+    let mut magic = [0; 4];
+    source.read_exact(&mut magic).await?;
+
+    if magic != MAGIC || read_u32(source).await? != FORMAT {
+        return Err(SnapshotError::Format);
+    }
+
+    Ok(read_u32(source).await?)
+}
+
+/// Recreate the elements streamed from `source` in `hato`, past the header.
+///
+/// # Safety
+///
+/// The snapshot must come from a collection of the same types, as their bytes are copied as is.
+#[cfg(feature = "std")]
+#[inline]
+pub async unsafe fn restore_from<Trait, S>(
+    source: &mut impl Source,
+    hato: &mut Hato<Trait, S>,
+    types: &Types<Trait>,
+) -> Result<(), SnapshotError>
+where
+    Trait: ?Sized + Pointee<Metadata = DynMetadata<Trait>>,
+    S: Storage,
+{
+    if !hato.arenas.is_empty() {
+        return Err(SnapshotError::NotEmpty);
+    }
+
+    let mut checksums = Crc32::new();
+    let mut buffer = Vec::new();
+
+    // Arenas join `hato` once the whole stream checks out, leaving it untouched on errors
+    let mut restored = Vec::<Restored<Trait, S>>::new();
+
+    while read_frame(source).await? {
+        let arena = restored.len();
+        let reader = &mut Checked::new(&mut *source);
+
+        let mut names = Vec::new();
+
+        for _ in 0..read_len(reader).await? {
+            let len = read_len(reader).await?;
+            read_into(reader, len, &mut buffer).await?;
+
+            names.push(String::from_utf8_lossy(&buffer).into_owned());
+        }
+
+        let kinds = resolve(arena, &names, types)?;
+
+        let (stride, size) = (read_len(reader).await?, read_len(reader).await?);
+        let rebuild = Rebuild::new(&kinds, types.any(), stride, size, hato.options);
+
+        let layout = || SnapshotError::Layout {
+            arena,
+            types: names.clone(),
+        };
+
+        let mut rebuild = rebuild.ok_or_else(layout)?;
+
+        for _ in 0..read_len(reader).await? {
+            let position = read_len(reader).await?.checked_sub(1);
+
+            if position.is_some() {
+                read_into(reader, size, &mut buffer).await?;
+            }
+
+            rebuild
+                .push(position, &buffer)
+                .ok_or(SnapshotError::Format)?;
+        }
+
+        let checksum = reader.crc.finish();
+
+        if read_u32(&mut reader.inner).await? != checksum {
+            return Err(SnapshotError::Corrupt {
+                arena,
+                types: names,
+            });
+        }
+
+        checksums.update(&checksum.to_le_bytes());
+        restored.push((rebuild.finish(), kinds));
+    }
+
+    if read_u32(source).await? != checksums.finish() {
+        return Err(SnapshotError::Digest);
+    }
+
+    for (arena, kinds) in restored {
+        hato.append_restored(arena, &kinds);
+    }
+
+    Ok(())
+}
+
+impl HatoSnapshot {
+    /// Version of the registry the snapshot was taken with, see [`Types::with_version`].
+    ///
+    /// Applications can look it up before restoring, to pick the aliases of that version.
+    #[inline]
+    #[must_use]
+    pub const fn version(&self) -> u32 {
+        self.version
+    }
+
+    /// Compress the bytes of the elements of each arena, at `level` from 0 to 10.
+    ///
+    /// Elements are plain bytes, often with long runs of zeroes, so they compress well
+    /// before going through a data format. Restores decompress them transparently.
+    /// Arenas sealed with [`Self::seal`] are left as is, as ciphertext does not compress.
+    ///
+    /// ```rust
+    /// let mut arena = hato::Hato::<dyn core::fmt::Debug>::default();
+    ///
+    /// let x = arena.push([0_u64; 512]);
+    ///
+    /// let types = hato::Types::default().register::<[u64; 512]>();
+    /// let mut snapshot = arena.snapshot(&types).unwrap();
+    ///
+    /// snapshot.compress(6);
+    ///
+    /// let mut restored = hato::Hato::<dyn core::fmt::Debug>::default();
+    /// unsafe { snapshot.restore(&mut restored, &types) }.unwrap();
+    /// assert_eq!(unsafe { restored.element_bytes(x) }, [0; 4096]);
+    /// ```
+    #[cfg(feature = "compress")]
+    #[inline]
+    pub fn compress(&mut self, level: u8) {
+        let arenas = self.arenas.iter_mut();
+
+        for arena in arenas.filter(|arena| !arena.compressed && !arena.sealed) {
+            arena.bytes = miniz_oxide::deflate::compress_to_vec(&arena.bytes, level);
+            arena.compressed = true;
+        }
+    }
+
+    /// Encrypt the bytes of the elements of each arena with `cipher`, authenticating the rest.
+    ///
+    /// The layout of arenas, their position in the snapshot and its version are authenticated
+    /// along with the elements, so that tampering with any of them fails [`Self::open`].
+    /// Checksums are sealed as well, as they would give away bits of the elements. Sealed
+    /// snapshots go through data formats as usual, but must be opened before they restore.
+    /// Compress snapshots first, as ciphertext does not compress.
+    ///
+    /// ```rust
+    /// // Illustration only, implement `Aead` on top of a vetted cipher instead
+    /// struct Xor(u8);
+    ///
+    /// impl hato::Aead for Xor {
+    ///     fn seal(&self, associated: &[u8], buffer: &mut Vec<u8>) -> bool {
+    ///         buffer.iter_mut().for_each(|byte| *byte ^= self.0);
+    ///         buffer.push(associated.iter().fold(self.0, |tag, byte| tag ^ byte));
+    ///         true
+    ///     }
+    ///
+    ///     fn open(&self, associated: &[u8], buffer: &mut Vec<u8>) -> bool {
+    ///         let tag = associated.iter().fold(self.0, |tag, byte| tag ^ byte);
+    ///         buffer.iter_mut().for_each(|byte| *byte ^= self.0);
+    ///         buffer.pop() == Some(tag ^ self.0)
+    ///     }
+    /// }
+    ///
+    /// let mut arena = hato::Hato::<dyn core::fmt::Debug>::default();
+    /// let x = arena.push(7_u32);
+    ///
+    /// let types = hato::Types::default().register::<u32>();
+    /// let mut snapshot = arena.snapshot(&types).unwrap();
+    ///
+    /// snapshot.seal(&Xor(42)).unwrap();
+    ///
+    /// let mut restored = hato::Hato::<dyn core::fmt::Debug>::default();
+    /// assert!(unsafe { snapshot.restore(&mut restored, &types) }.is_err());
+    ///
+    /// snapshot.open(&Xor(42)).unwrap();
+    /// unsafe { snapshot.restore(&mut restored, &types) }.unwrap();
+    /// assert_eq!(format!("{:?}", unsafe { restored.get(x) }), "7");
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// This function will return [`SnapshotError::Sealed`] if `cipher` fails to seal an arena,
+    /// leaving the arenas before it sealed.
+    #[cfg(feature = "seal")]
+    #[inline]
+    pub fn seal(&mut self, cipher: &impl Aead) -> Result<(), SnapshotError> {
+        let count = self.arenas.len();
+
+        for (index, arena) in self.arenas.iter_mut().enumerate() {
+            if arena.sealed {
+                continue;
+            }
+
+            let associated = arena.associated(self.version, index, count);
+            arena.bytes.extend_from_slice(&arena.checksum.to_le_bytes());
+
+            if !cipher.seal(&associated, &mut arena.bytes) {
+                return Err(SnapshotError::Sealed { arena: index });
+            }
+
+            arena.sealed = true;
+            arena.checksum = 0;
+        }
+
+        // The digest would give checksums away, arenas are authenticated with their position
+        self.digest = 0;
+
+        Ok(())
+    }
+
+    /// Decrypt arenas sealed by [`Self::seal`] with `cipher`, checking they were not tampered with.
+    ///
+    /// # Errors
+    ///
+    /// This function will return [`SnapshotError::Sealed`] if an arena fails to open, for a wrong
+    /// key or tampered contents, leaving the arenas before it opened.
+    #[cfg(feature = "seal")]
+    #[inline]
+    pub fn open(&mut self, cipher: &impl Aead) -> Result<(), SnapshotError> {
+        let count = self.arenas.len();
+
+        for (index, arena) in self.arenas.iter_mut().enumerate() {
+            if !arena.sealed {
+                continue;
+            }
+
+            let associated = arena.associated(self.version, index, count);
+            let mut bytes = arena.bytes.clone();
+
+            // Checksums were sealed at the end of the elements
+            let opened = cipher.open(&associated, &mut bytes);
+            let tail = bytes.len().checked_sub(4).filter(|_| opened);
+            let tail = tail.ok_or(SnapshotError::Sealed { arena: index })?;
+
+            let mut checksum = [0; 4];
+            checksum.copy_from_slice(&bytes[tail..]);
+            bytes.truncate(tail);
+
+            arena.bytes = bytes;
+            arena.checksum = u32::from_le_bytes(checksum);
+            arena.sealed = false;
+        }
+
+        if self.arenas.iter().all(|arena| !arena.sealed) {
+            self.digest = digest(self.arenas.iter().map(|arena| arena.checksum));
+        }
+
+        Ok(())
+    }
+
+    /// Check that the snapshot can be restored, before touching any collection.
+    #[inline]
+    fn verify(&self) -> Result<(), SnapshotError> {
+        #[cfg(feature = "seal")]
+        if let Some(arena) = self.arenas.iter().position(|arena| arena.sealed) {
+            return Err(SnapshotError::Sealed { arena });
+        }
+
+        if digest(self.arenas.iter().map(|arena| arena.checksum)) != self.digest {
+            return Err(SnapshotError::Digest);
+        }
+
+        Ok(())
+    }
+
+    /// Recreate the snapshotted elements in `hato`, at the slots they were taken from.
+    ///
+    /// The collection must be empty, and built with the same layout options as the one
+    /// the snapshot was taken from, for handles to carry over.
+    ///
+    /// Types renamed since are found through the aliases of `types`. Elements of types
+    /// it does not know are dropped instead if it was built with [`Types::skip_unknown`].
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if `hato` is not empty, if the snapshot does not
+    /// match `types` and the layout of `hato`, if it got corrupted since it was taken,
+    /// or if it is still sealed. Arenas are all rebuilt and checked before any joins `hato`,
+    /// which leaves it untouched on errors.
+    ///
+    /// # Safety
+    ///
+    /// The snapshot must come from a collection of the same types, as their bytes are copied
+    /// as is.
+    #[inline]
+    pub unsafe fn restore<Trait, S>(
+        &self,
+        hato: &mut Hato<Trait, S>,
+        types: &Types<Trait>,
+    ) -> Result<(), SnapshotError>
+    where
+        Trait: ?Sized + Pointee<Metadata = DynMetadata<Trait>>,
+        S: Storage,
+    {
+        if !hato.arenas.is_empty() {
+            return Err(SnapshotError::NotEmpty);
+        }
+
+        self.verify()?;
+
+        let arenas = self.arenas.iter().enumerate();
+        let arenas =
+            arenas.map(|(index, snapshot)| restore_arena(index, snapshot, types, hato.options));
+
+        for (arena, kinds) in arenas.collect::<Result<Vec<_>, _>>()? {
+            hato.append_restored(arena, &kinds);
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(feature = "rayon")]
+impl HatoSnapshot {
+    /// Recreate the snapshotted elements in `hato` as [`Self::restore`] does, on all threads.
+    ///
+    /// Arenas are independent, so each one is decompressed, checked and laid out by its own
+    /// task of the `rayon` pool. They join `hato` in order once all are rebuilt, which leaves
+    /// it untouched on errors.
+    ///
+    /// ```rust
+    /// let mut arena = hato::Hato::<dyn core::fmt::Debug>::default();
+    ///
+    /// let xs = (0..1000_u32).map(|i| arena.push(i)).collect::<Vec<_>>();
+    /// let y = arena.push(1_u8);
+    ///
+    /// let types = hato::Types::default().register::<u32>().register::<u8>();
+    /// let snapshot = arena.snapshot(&types).unwrap();
+    ///
+    /// let mut restored = hato::Hato::<dyn core::fmt::Debug>::default();
+    /// unsafe { snapshot.par_restore(&mut restored, &types) }.unwrap();
+    ///
+    /// assert_eq!(format!("{:?}", unsafe { restored.get(xs[999]) }), "999");
+    /// assert_eq!(format!("{:?}", unsafe { restored.get(y) }), "1");
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error in the same cases as [`Self::restore`]. When several
+    /// arenas are at fault, any of their errors may be reported.
+    ///
+    /// # Safety
+    ///
+    /// The snapshot must come from a collection of the same types, as their bytes are copied
+    /// as is.
+    #[inline]
+    pub unsafe fn par_restore<Trait, S>(
+        &self,
+        hato: &mut Hato<Trait, S>,
+        types: &Types<Trait>,
+    ) -> Result<(), SnapshotError>
+    where
+        Trait: ?Sized + Pointee<Metadata = DynMetadata<Trait>>,
+        S: Storage + Send,
+    {
+        if !hato.arenas.is_empty() {
+            return Err(SnapshotError::NotEmpty);
+        }
+
+        self.verify()?;
+
+        let options = hato.options;
+
+        let arenas = self.arenas.par_iter().enumerate();
+        let arenas = arenas.map(|(index, snapshot)| restore_arena(index, snapshot, types, options));
+
+        for (arena, kinds) in arenas.collect::<Result<Vec<_>, _>>()? {
+            hato.append_restored(arena, &kinds);
+        }
+
+        Ok(())
+    }
+}
+
+impl<Trait: ?Sized + Pointee<Metadata = DynMetadata<Trait>>, S: Storage> Hato<Trait, S> {
+    /// Append `arena` rebuilt from a snapshot, along with the bookkeeping of its elements.
+    #[inline]
+    pub(crate) fn append_restored(
+        &mut self,
+        arena: Arena<Trait, S>,
+        kinds: &[Option<Kind<Trait>>],
+    ) {
+        self.arenas.push(arena);
+
+        let index = self.arenas.len() - 1;
+        let arena = &self.arenas[index];
+
+        for slot in LiveSlots::new(&arena.occupied) {
+            let handle = Handle {
+                index: index_of(index),
+                offset: arena.offset(slot),
+            };
+
+            self.shadow.insert(handle, || arena.element(handle.offset));
+        }
+
+        for (type_id, _) in kinds.iter().flatten() {
+            self.attach_relocation(index, *type_id);
+        }
+    }
+}
+
+impl ArenaSnapshot {
+    /// Bytes of live elements, decompressed if needed, or `None` if they are corrupt.
+    #[inline]
+    fn bytes(&self) -> Option<Cow<'_, [u8]>> {
+        if !self.compressed {
+            return Some(Cow::Borrowed(&self.bytes));
+        }
+
+        #[cfg(feature = "compress")]
+        {
+            // Bound the output, so that corrupt inputs cannot exhaust memory
+            let limit = self.slots.iter().flatten().count() * self.size;
+            let bytes = miniz_oxide::inflate::decompress_to_vec_with_limit(&self.bytes, limit);
+
+            bytes.ok().map(Cow::Owned)
+        }
+
+        // Builds without compression cannot read compressed snapshots
+        #[cfg(not(feature = "compress"))]
+        None
+    }
+
+    /// Data authenticated along with the sealed bytes of the arena, at `index` of `count`.
+    #[cfg(feature = "seal")]
+    #[inline]
+    fn associated(&self, version: u32, index: usize, count: usize) -> Vec<u8> {
+        let mut data = version.to_le_bytes().to_vec();
+        let mut push_len = |len: usize| data.extend_from_slice(&(len as u64).to_le_bytes());
+
+        push_len(index);
+        push_len(count);
+        push_len(self.types.len());
+
+        for name in &self.types {
+            push_len(name.len());
+        }
+
+        push_len(self.stride);
+        push_len(self.size);
+        push_len(usize::from(self.compressed));
+        push_len(self.slots.len());
+
+        for slot in &self.slots {
+            push_len(slot.map_or(0, |position| position + 1));
+        }
+
+        for name in &self.types {
+            data.extend_from_slice(name.as_bytes());
+        }
+
+        data
+    }
+
+    /// Checksum of the arena holding the elements `bytes`, over the bytes streams write.
+    #[inline]
+    fn checksum_of(&self, bytes: &[u8]) -> u32 {
+        let mut crc = Crc32::new();
+
+        crc.update_len(self.types.len());
+
+        for name in &self.types {
+            crc.update_len(name.len());
+            crc.update(name.as_bytes());
+        }
+
+        crc.update_len(self.stride);
+        crc.update_len(self.size);
+        crc.update_len(self.slots.len());
+
+        let mut elements = bytes.chunks(self.size.max(1));
+
+        for slot in &self.slots {
+            crc.update_len(slot.map_or(0, |position| position + 1));
+
+            if slot.is_some() && self.size > 0 {
+                crc.update(elements.next().unwrap_or_default());
+            }
+        }
+
+        crc.finish()
+    }
+
+    /// Error reporting the arena at `index` as corrupt.
+    #[inline]
+    fn corrupt(&self, index: usize) -> SnapshotError {
+        SnapshotError::Corrupt {
+            arena: index,
+            types: self.types.clone(),
+        }
+    }
+}
+
+/// Checksum of the checksums of arenas, in order.
+#[inline]
+pub fn digest(checksums: impl Iterator<Item = u32>) -> u32 {
+    let mut crc = Crc32::new();
+
+    for checksum in checksums {
+        crc.update(&checksum.to_le_bytes());
+    }
+
+    crc.finish()
+}
+
+/// Kinds of the types named `names` in the arena at `index`, with `None` for skipped ones.
+#[inline]
+pub fn resolve<Trait>(
+    index: usize,
+    names: &[String],
+    types: &Types<Trait>,
+) -> Result<Vec<Option<Kind<Trait>>>, SnapshotError>
+where
+    Trait: ?Sized + Pointee<Metadata = DynMetadata<Trait>>,
+{
+    let kinds = names
+        .iter()
+        .map(|name| types.find(name))
+        .collect::<Vec<_>>();
+
+    match kinds.iter().position(Option::is_none) {
+        Some(position) if !types.skips_unknown() => Err(SnapshotError::UnknownType {
+            arena: index,
+            name: names[position].clone(),
+        }),
+        _ => Ok(kinds),
+    }
+}
+
+/// Arena rebuilt from a snapshot, along with the kinds of the types it admits.
+pub type Restored<Trait, S> = (Arena<Trait, S>, Vec<Option<Kind<Trait>>>);
+
+/// Rebuild the arena at `index` described by `snapshot`, once checked, along with its kinds.
+#[inline]
+fn restore_arena<Trait, S>(
+    index: usize,
+    snapshot: &ArenaSnapshot,
+    types: &Types<Trait>,
+    options: Options,
+) -> Result<Restored<Trait, S>, SnapshotError>
+where
+    Trait: ?Sized + Pointee<Metadata = DynMetadata<Trait>>,
+    S: Storage,
+{
+    let (size, bytes) = (snapshot.size, snapshot.bytes());
+    let bytes = bytes.ok_or_else(|| snapshot.corrupt(index))?;
+
+    let live = snapshot.slots.iter().flatten().count();
+
+    if bytes.len() != live * size || snapshot.checksum_of(&bytes) != snapshot.checksum {
+        return Err(snapshot.corrupt(index));
+    }
+
+    let kinds = resolve(index, &snapshot.types, types)?;
+
+    let rebuild = Rebuild::new(&kinds, types.any(), snapshot.stride, size, options);
+
+    let mut rebuild = rebuild.ok_or_else(|| SnapshotError::Layout {
+        arena: index,
+        types: snapshot.types.clone(),
+    })?;
+
+    let mut elements = bytes.chunks_exact(size.max(1));
+
+    for slot in &snapshot.slots {
+        let bytes = match slot {
+            Some(_) if size != 0 => elements.next().ok_or_else(|| snapshot.corrupt(index))?,
+            _ => &[],
+        };
+
+        rebuild
+            .push(*slot, bytes)
+            .ok_or_else(|| snapshot.corrupt(index))?;
+    }
+
+    let arena = rebuild.finish();
+
+    Ok((arena, kinds))
+}
+
+/// Arena being rebuilt slot by slot, from a snapshot in memory or from a stream.
+struct Rebuild<'a, Trait: ?Sized + Pointee<Metadata = DynMetadata<Trait>>, S: Storage> {
+    arena: Arena<Trait, S>,
+    kinds: &'a [Option<Kind<Trait>>],
+
+    /// Whether all types of the arena are unknown, in which case it is kept empty.
+    skipped: bool,
+
+    /// Bytes standing for free slots until all slots are laid out, to keep offsets in place.
+    placeholder: Vec<u8>,
+    free: Vec<Index>,
+}
+
+impl<'a, Trait: ?Sized + Pointee<Metadata = DynMetadata<Trait>>, S: Storage> Rebuild<'a, Trait, S> {
+    /// Start an arena admitting `kinds`, or `None` if its layout does not match the snapshot.
+    ///
+    /// Elements of unknown types, whose kind is `None`, are left out as free slots. Arenas
+    /// of unknown types only are kept empty as `any`, so that later indices stay in place.
+    #[inline]
+    fn new(
+        kinds: &'a [Option<Kind<Trait>>],
+        any: Option<Kind<Trait>>,
+        stride: usize,
+        size: usize,
+        options: Options,
+    ) -> Option<Self> {
+        let mut known = kinds.iter().flatten();
+
+        let Some(&(type_id, vtable)) = known.next() else {
+            let (type_id, vtable) = any?;
+
+            return Some(Self {
+                arena: Arena::empty(type_id, vtable, options),
+                kinds,
+                skipped: true,
+                placeholder: Vec::new(),
+                free: Vec::new(),
+            });
+        };
+
+        let mut arena = Arena::new(type_id, vtable, options);
+
+        for kind in known {
+            arena.register(*kind);
+        }
+
+        // Offsets of elements, and thus handles, depend on the stride of their arena
+        if arena.stride != stride || vtable.size_of() != size {
+            return None;
+        }
+
+        Some(Self {
+            arena,
+            kinds,
+            skipped: false,
+            placeholder: vec![0; size],
+            free: Vec::new(),
+        })
+    }
+
+    /// Lay out the next slot, holding `bytes` of the type at `position` in `kinds` if any.
+    #[inline]
+    fn push(&mut self, position: Option<usize>, bytes: &[u8]) -> Option<()> {
+        if self.skipped {
+            return Some(());
+        }
+
+        match position.map(|position| self.kinds.get(position).copied()) {
+            Some(Some(Some(kind))) => {
+                let _ = self.arena.push_bytes(bytes, kind);
+            }
+            Some(None) => return None,
+            Some(Some(None)) | None => {
+                let kind = (self.arena.type_id, self.arena.vtable);
+                self.free
+                    .push(self.arena.push_bytes(&self.placeholder, kind));
+            }
+        }
+
+        Some(())
+    }
+
+    /// Free the slots laid out as placeholders, and hand out the arena.
+    #[inline]
+    fn finish(mut self) -> Arena<Trait, S> {
+        for offset in self.free {
+            self.arena.remove(offset);
+        }
+
+        self.arena
+    }
+}
+
+/// Write `len` as a little-endian `u64`, to be read back on targets of any width.
+#[cfg(feature = "std")]
+#[inline]
+fn write_len(writer: &mut impl Write, len: usize) -> io::Result<()> {
+    writer.write_all(&(len as u64).to_le_bytes())
+}
+
+/// Read a length written by [`write_len`], failing if it does not fit in memory.
+#[cfg(feature = "std")]
+#[inline]
+async fn read_len(source: &mut impl Source) -> Result<usize, SnapshotError> {
+    let mut bytes = [0; 8];
+    source.read_exact(&mut bytes).await?;
+
+    usize::try_from(u64::from_le_bytes(bytes)).map_err(|_| SnapshotError::Format)
+}
+
+/// Read a little-endian `u32`.
+#[cfg(feature = "std")]
+#[inline]
+async fn read_u32(source: &mut impl Source) -> io::Result<u32> {
+    let mut bytes = [0; 4];
+    source.read_exact(&mut bytes).await?;
+
+    Ok(u32::from_le_bytes(bytes))
+}
+
+/// Read whether an arena follows, rather than the end of the snapshot.
+#[cfg(feature = "std")]
+#[inline]
+async fn read_frame(source: &mut impl Source) -> Result<bool, SnapshotError> {
+    let mut tag = [0];
+    source.read_exact(&mut tag).await?;
+
+    match tag {
+        [0] => Ok(false),
+        [1] => Ok(true),
+        _ => Err(SnapshotError::Format),
+    }
+}
+
+/// Largest number of bytes [`read_into`] reserves before they arrive.
+#[cfg(feature = "std")]
+const READ_CHUNK: usize = 64 * 1024;
+
+/// Replace the contents of `buffer` with the next `len` bytes of `source`.
+///
+/// The buffer grows as bytes arrive, so that corrupt lengths cannot exhaust memory.
+#[cfg(feature = "std")]
+#[inline]
+async fn read_into(source: &mut impl Source, len: usize, buffer: &mut Vec<u8>) -> io::Result<()> {
+    buffer.clear();
+
+    while buffer.len() < len {
+        let start = buffer.len();
+        buffer.resize(len.min(start + READ_CHUNK), 0);
+
+        source.read_exact(&mut buffer[start..]).await?;
+    }
+
+    Ok(())
+}
+
+/// Bytes of a streamed snapshot, whether they are read in place or arrive over time.
+#[cfg(feature = "std")]
+pub trait Source {
+    /// Fill `buffer` with the next bytes of the snapshot.
+    async fn read_exact(&mut self, buffer: &mut [u8]) -> io::Result<()>;
+}
+
+#[cfg(feature = "std")]
+impl<T: Source> Source for &mut T {
+    #[inline]
+    async fn read_exact(&mut self, buffer: &mut [u8]) -> io::Result<()> {
+        (**self).read_exact(buffer).await
+    }
+}
+
+/// Source reading from a blocking reader, whose futures are always ready.
+#[cfg(feature = "std")]
+struct Blocking<R>(R);
+
+#[cfg(feature = "std")]
+impl<R: Read> Source for Blocking<R> {
+    #[inline]
+    async fn read_exact(&mut self, buffer: &mut [u8]) -> io::Result<()> {
+        self.0.read_exact(buffer)
+    }
+}
+
+/// Run `future` to completion, when it only ever reads from a [`Blocking`] source.
+#[cfg(feature = "std")]
+#[inline]
+fn now<F: Future>(future: F) -> F::Output {
+    let mut context = Context::from_waker(Waker::noop());
+
+    match pin!(future).poll(&mut context) {
+        Poll::Ready(output) => output,
+        Poll::Pending => unreachable!("blocking sources never wait"),
+    }
+}
+
+/// Writer or reader computing the checksum of the bytes going through it.
+#[cfg(feature = "std")]
+struct Checked<T> {
+    inner: T,
+    crc: Crc32,
+}
+
+#[cfg(feature = "std")]
+impl<T> Checked<T> {
+    #[inline]
+    const fn new(inner: T) -> Self {
+        Self {
+            inner,
+            crc: Crc32::new(),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl<W: Write> Write for Checked<W> {
+    #[inline]
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let written = self.inner.write(buf)?;
+        self.crc.update(&buf[..written]);
+
+        Ok(written)
+    }
+
+    #[inline]
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+#[cfg(feature = "std")]
+impl<T: Source> Source for Checked<T> {
+    #[inline]
+    async fn read_exact(&mut self, buffer: &mut [u8]) -> io::Result<()> {
+        self.inner.read_exact(buffer).await?;
+        self.crc.update(buffer);
+
+        Ok(())
+    }
+}
+
+/// Lookup table of CRC-32 (IEEE), one entry per byte value.
+const CRC_TABLE: [u32; 256] = {
+    let mut table = [0; 256];
+    let mut byte = 0;
+
+    while byte < 256 {
+        // Entries are indexed by bytes, which fit in a `u32`
+        #[allow(clippy::cast_possible_truncation)]
+        let mut crc = byte as u32;
+        let mut bit = 0;
+
+        while bit < 8 {
+            crc = if crc & 1 == 1 {
+                0xEDB8_8320 ^ (crc >> 1)
+            } else {
+                crc >> 1
+            };
+            bit += 1;
+        }
+
+        table[byte] = crc;
+        byte += 1;
+    }
+
+    table
+};
+
+/// Running CRC-32 (IEEE), as used by zip and PNG, to detect corrupt snapshots.
+#[derive(Clone, Copy, Debug)]
+pub struct Crc32(u32);
+
+impl Crc32 {
+    #[inline]
+    pub const fn new() -> Self {
+        Self(u32::MAX)
+    }
+
+    #[inline]
+    pub fn update(&mut self, bytes: &[u8]) {
+        for &byte in bytes {
+            self.0 = CRC_TABLE[((self.0 ^ u32::from(byte)) & 0xFF) as usize] ^ (self.0 >> 8);
+        }
+    }
+
+    /// Feed `len` the way [`write_len`] writes it.
+    #[inline]
+    pub fn update_len(&mut self, len: usize) {
+        self.update(&(len as u64).to_le_bytes());
+    }
+
+    #[inline]
+    pub const fn finish(self) -> u32 {
+        !self.0
+    }
+}
+
+/// Authenticated encryption with associated data, to seal snapshots with [`HatoSnapshot::seal`].
+///
+/// The crate ships no cryptography: applications implement this trait on top of the cipher
+/// of their choice, such as AES-GCM or ChaCha20-Poly1305, and manage its key. Each call to
+/// [`seal`](Self::seal) must use a fresh nonce, typically drawn at random and stored
+/// in `buffer` along with the ciphertext and its tag, for [`open`](Self::open) to find.
+#[cfg(feature = "seal")]
+pub trait Aead {
+    /// Encrypt `buffer` in place, authenticating it along with `associated`.
+    ///
+    /// Returns `false` if the buffer could not be sealed.
+    fn seal(&self, associated: &[u8], buffer: &mut Vec<u8>) -> bool;
+
+    /// Decrypt `buffer` in place, sealed along with `associated`.
+    ///
+    /// Returns `false` if either was tampered with, or if the key does not match.
+    fn open(&self, associated: &[u8], buffer: &mut Vec<u8>) -> bool;
+}
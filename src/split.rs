@@ -0,0 +1,87 @@
+use alloc::vec::Vec;
+
+use core::any::TypeId;
+use core::ptr::{DynMetadata, Pointee};
+
+use crate::{index_of, Handle, Hato, Remap, Storage};
+
+impl<Trait: ?Sized + Pointee<Metadata = DynMetadata<Trait>>, S: Storage> Hato<Trait, S> {
+    /// Copy live elements into two new collections, depending on whether `f` returns `true`.
+    ///
+    /// Returns the collection of elements for which `f` returned `true` first, each along with
+    /// the table mapping handles of its elements from this collection. Tables list every
+    /// element of their collection, even those keeping their handle, so [`Remap::get`] tells
+    /// which side an element went to. Tag bytes and names are carried over, but elements start
+    /// out of any [`HandleList`](crate::HandleList). The original is left untouched.
+    ///
+    /// ```rust
+    /// let mut arena = hato::Hato::<dyn core::fmt::Debug>::default();
+    ///
+    /// let x = arena.push(1_u8);
+    /// let y = arena.push(2_u8);
+    ///
+    /// let ((odd, odds), (_, evens)) = arena.split_by(|x| format!("{x:?}") == "1");
+    ///
+    /// assert_eq!(format!("{:?}", unsafe { odd.get(odds.resolve(x)) }), "1");
+    /// assert!(odds.get(y).is_none() && evens.get(y).is_some());
+    /// ```
+    #[inline]
+    #[must_use]
+    pub fn split_by(&self, mut f: impl FnMut(&Trait) -> bool) -> ((Self, Remap), (Self, Remap)) {
+        self.split(|handle| {
+            // ! SAFETY: Handle identifies a live element of this collection
+            f(unsafe { self.get(handle) })
+        })
+    }
+
+    /// Copy live elements into two new collections, depending on whether `f` returns `true`
+    /// for their type.
+    ///
+    /// See [`Self::split_by`] for details. Types are identified with [`typeid::of`],
+    /// which matches [`TypeId::of`] for `'static` types.
+    #[inline]
+    #[must_use]
+    pub fn split_by_type(
+        &self,
+        mut f: impl FnMut(TypeId) -> bool,
+    ) -> ((Self, Remap), (Self, Remap)) {
+        self.split(|handle| {
+            let arena = &self.arenas[handle.index as usize];
+            f(arena.kind(arena.slot(handle.offset)).0)
+        })
+    }
+
+    /// Copy live elements into two new collections, depending on what `f` returns for them.
+    #[inline]
+    fn split(&self, mut f: impl FnMut(Handle) -> bool) -> ((Self, Remap), (Self, Remap)) {
+        let mut sides = [self.empty_like(), self.empty_like()];
+        let mut moves = [Vec::new(), Vec::new()];
+
+        for (index, arena) in self.arenas.iter().enumerate() {
+            for slot in (0..arena.occupied.len()).filter(|slot| arena.occupied[*slot]) {
+                let old = Handle {
+                    index: index_of(index),
+                    offset: arena.offset(slot),
+                };
+
+                let side = usize::from(!f(old));
+                let new = self.copy_element(old, &mut sides[side]);
+
+                moves[side].push((old, new));
+            }
+        }
+
+        let [mut kept, mut rest] = sides;
+        let [kept_moves, rest_moves] = moves.map(|moves| moves.into_iter().collect::<Remap>());
+
+        for (side, remap) in [(&mut kept, &kept_moves), (&mut rest, &rest_moves)] {
+            // Names follow their element, to whichever side it went
+            let mut names = self.names.clone();
+            names.retain(|handle| remap.get(handle).is_some());
+
+            side.names = names.remap(remap);
+        }
+
+        ((kept, kept_moves), (rest, rest_moves))
+    }
+}
@@ -0,0 +1,126 @@
+use alloc::vec::Vec;
+
+use core::any::TypeId;
+use core::ptr::{DynMetadata, Pointee};
+
+use crate::{Hato, Storage};
+
+/// Memory held by a collection for its elements, as measured by [`Hato::stats`].
+///
+/// Bookkeeping of arenas, such as free lists and tags, is left out. Bytes reserved past
+/// the used and free ones are spare capacity, which [`Hato::shrink_to_fit`] gives back.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct Stats {
+    /// Number of arenas in the directory, emptied ones included.
+    pub arenas: usize,
+
+    /// Bytes reserved by the buffers of arenas and by spilled elements.
+    pub allocated_bytes: usize,
+
+    /// Bytes taken by the slots of live elements, padding included.
+    pub used_bytes: usize,
+
+    /// Bytes taken by free slots of buffers, waiting to be reused or compacted away.
+    pub free_bytes: usize,
+
+    /// Same counts for each type stored so far, in the order of arenas.
+    pub types: Vec<TypeStats>,
+}
+
+/// Memory held for the elements of a single type.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct TypeStats {
+    /// Type measured.
+    pub type_id: TypeId,
+
+    /// Number of live elements.
+    pub live: usize,
+
+    /// Bytes taken by the slots of live elements, padding included.
+    pub used_bytes: usize,
+
+    /// Bytes taken by free slots of buffers, which last held an element of this type.
+    pub free_bytes: usize,
+}
+
+impl<Trait: ?Sized + Pointee<Metadata = DynMetadata<Trait>>, S: Storage> Hato<Trait, S> {
+    /// Measure memory reserved, used and left free by elements, overall and per type.
+    ///
+    /// Every slot is visited, so this is meant for monitoring rather than for hot paths.
+    /// A high share of free bytes hints at fragmentation, see [`Self::with_compaction_threshold`].
+    ///
+    /// ```rust
+    /// let mut arena = hato::Hato::<dyn core::fmt::Debug>::default().with_capacity_bytes(64);
+    ///
+    /// let x = arena.push(1_u32);
+    /// let _ = arena.push(2_u32);
+    /// let _ = arena.push(3_u32);
+    ///
+    /// arena.remove(x);
+    ///
+    /// let stats = arena.stats();
+    ///
+    /// assert_eq!((stats.arenas, stats.allocated_bytes), (1, 64));
+    /// assert_eq!((stats.used_bytes, stats.free_bytes), (8, 4));
+    /// assert_eq!(stats.types[0].live, 2);
+    /// ```
+    #[inline]
+    #[must_use]
+    pub fn stats(&self) -> Stats {
+        let mut stats = Stats {
+            arenas: self.arenas.len(),
+            ..Stats::default()
+        };
+
+        for arena in &self.arenas {
+            let spilled = arena.spilled.iter().map(Storage::capacity).sum::<usize>();
+            stats.allocated_bytes += arena.bytes.capacity() + spilled;
+
+            for slot in 0..arena.occupied.len() {
+                let (type_id, vtable) = arena.kind(slot);
+
+                // Spilled elements take their own allocation, while zero-sized ones take nothing
+                let bytes = match vtable.size_of() {
+                    0 => 0,
+                    size if arena.spill => size,
+                    _ => arena.stride,
+                };
+
+                let counts = stats.counts_mut(type_id);
+
+                if arena.occupied[slot] {
+                    counts.live += 1;
+                    counts.used_bytes += bytes;
+                } else if !arena.spill {
+                    counts.free_bytes += bytes;
+                }
+            }
+        }
+
+        stats.used_bytes = stats.types.iter().map(|t| t.used_bytes).sum();
+        stats.free_bytes = stats.types.iter().map(|t| t.free_bytes).sum();
+
+        stats
+    }
+}
+
+impl Stats {
+    /// Counts of type `type_id`, starting from zero on its first occurrence.
+    #[inline]
+    fn counts_mut(&mut self, type_id: TypeId) -> &mut TypeStats {
+        let position = self.types.iter().position(|t| t.type_id == type_id);
+
+        let position = position.unwrap_or_else(|| {
+            self.types.push(TypeStats {
+                type_id,
+                live: 0,
+                used_bytes: 0,
+                free_bytes: 0,
+            });
+
+            self.types.len() - 1
+        });
+
+        &mut self.types[position]
+    }
+}
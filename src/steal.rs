@@ -0,0 +1,113 @@
+use alloc::vec::Vec;
+
+use core::marker::Unsize;
+use core::ptr::{DynMetadata, Pointee};
+
+use unscrupulous::Unscrupulous;
+
+use crate::{index_of, Arena, Handle, Hato, Index, Remap, Storage};
+
+impl<Trait: ?Sized + Pointee<Metadata = DynMetadata<Trait>>, S: Storage> Hato<Trait, S> {
+    /// Move all elements of type `T` out of `other`, returning their new handles.
+    ///
+    /// Arenas of `other` dedicated to `T` are detached whole and appended to this collection,
+    /// along with their free slots, so their elements are never copied and keep their offset.
+    /// Only the arena index of their handles changes. Elements of `T` sharing an arena with
+    /// other types, see [`Self::with_size_classes`], are transferred one by one instead.
+    /// Detached arenas leave an empty one in their place, so that other handles of `other`
+    /// remain valid. Stale handles that `other` forwarded to moved elements, see
+    /// [`Self::with_forwarding`], map to their new handle in the returned table, and are
+    /// no longer forwarded by `other`. Moved elements should not belong to any
+    /// [`HandleList`](crate::HandleList).
+    ///
+    /// ```rust
+    /// let mut arena = hato::Hato::<dyn core::fmt::Debug>::default();
+    /// let mut other = hato::Hato::<dyn core::fmt::Debug>::default();
+    ///
+    /// let _ = arena.push(1_u16);
+    ///
+    /// let x = other.push(2_u8);
+    /// let y = other.push(3_u16);
+    ///
+    /// let remap = arena.steal_type::<u8>(&mut other);
+    ///
+    /// assert_eq!(format!("{:?}", unsafe { arena.get(remap.resolve(x)) }), "2");
+    /// assert_eq!(format!("{:?}", unsafe { other.get(y) }), "3");
+    /// assert!(!other.contains(x));
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// This function will panic if the number of arenas overflows the index type.
+    #[inline]
+    pub fn steal_type<T: Unsize<Trait> + Unscrupulous>(&mut self, other: &mut Self) -> Remap {
+        let type_id = typeid::of::<T>();
+        let mut moves = Vec::new();
+
+        for index in 0..other.arenas.len() {
+            let arena = &other.arenas[index];
+
+            let Some((_, vtable)) = arena.kind_of(|(id, _)| id == type_id) else {
+                continue;
+            };
+
+            let handles = (0..arena.occupied.len())
+                .filter(|slot| arena.occupied[*slot] && arena.kind(*slot).0 == type_id)
+                .map(|slot| Handle {
+                    index: index_of(index),
+                    offset: arena.offset(slot),
+                })
+                .collect::<Vec<_>>();
+
+            if handles.is_empty() {
+                continue;
+            }
+
+            if arena.types.iter().any(|(id, _)| *id != type_id) {
+                // Elements of other types stay behind, so elements of `T` are copied out
+                moves.extend(handles.iter().map(|old| (*old, other.transfer(*old, self))));
+                continue;
+            }
+
+            let new_index = Index::try_from(self.arenas.len())
+                .unwrap_or_else(|_| panic!("got more than `{}` arenas", Index::MAX));
+
+            let empty = Arena::empty(type_id, vtable, other.options);
+            let mut arena = core::mem::replace(&mut other.arenas[index], empty);
+
+            // Forwarding entries of `other` do not follow, so no slot needs to stay reserved
+            if arena.pinned > 0 && !arena.tombstones {
+                arena.pinned = 0;
+                arena.reclaim_tombstones();
+            }
+
+            // Elements leave any list of `other`, and repair themselves as this collection says
+            arena.links.clear();
+            arena.relocations.clear();
+
+            self.arenas.push(arena);
+            self.attach_relocation(new_index as usize, type_id);
+
+            for old in handles {
+                let new = Handle {
+                    index: new_index,
+                    offset: old.offset,
+                };
+
+                other.names.remove(old);
+                other.shadow.remove(old);
+
+                let arena = &self.arenas[new_index as usize];
+                self.shadow.insert(new, || arena.element(new.offset));
+
+                moves.push((old, new));
+            }
+        }
+
+        // Stale handles of `other` follow their element, its slot in `other` no longer reserved
+        let remap = moves.into_iter().collect::<Remap>();
+        let stale = other.forwards_moved_out(&remap);
+
+        remap.union(stale)
+    }
+}
@@ -0,0 +1,370 @@
+use core::convert::Infallible;
+
+use aligned_vec::{AVec, TryReserveError};
+
+use crate::Error;
+
+/// Byte buffer backing the elements of an arena, in place of the default heap allocation.
+///
+/// Backends only provide raw capacity, while arenas keep control of the growth policy.
+/// Fixed static buffers, memory-mapped regions or staging memory shared with a device
+/// can thus be plugged into [`Hato`](crate::Hato), without forking the rest of the arena logic.
+///
+/// # Safety
+///
+/// [`Self::as_ptr`] must be aligned to [`Self::align`], and valid for reads and writes
+/// of [`Self::capacity`] bytes. Growing and shrinking must preserve the first [`Self::len`] bytes.
+pub unsafe trait Storage {
+    /// Create an empty buffer, whose base address is aligned to `align` bytes.
+    fn new(align: usize) -> Self;
+
+    /// Alignment of the base address of the buffer, as requested on creation.
+    fn align(&self) -> usize;
+
+    /// Number of bytes in use, from the base address.
+    fn len(&self) -> usize;
+
+    /// Number of bytes available without growing.
+    fn capacity(&self) -> usize;
+
+    /// Base address of the buffer.
+    fn as_ptr(&self) -> *const u8;
+
+    /// Mutable base address of the buffer.
+    fn as_mut_ptr(&mut self) -> *mut u8;
+
+    /// Grow the buffer to hold at least `capacity` bytes, moving its contents if needed.
+    ///
+    /// # Panics
+    ///
+    /// Backends of bounded size may panic when running out of room.
+    fn grow(&mut self, capacity: usize);
+
+    /// Grow the buffer to hold at least `capacity` bytes, reporting failures instead of panicking.
+    ///
+    /// Defaults to [`Self::grow`], which backends should override to uphold the guarantees
+    /// of fallible methods of collections.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if memory for `capacity` bytes cannot be obtained.
+    #[inline]
+    fn try_grow(&mut self, capacity: usize) -> Result<(), Error> {
+        self.grow(capacity);
+        Ok(())
+    }
+
+    /// Release capacity beyond the bytes in use, moving its contents if needed.
+    ///
+    /// Defaults to keeping the capacity, as backends of fixed size do.
+    #[inline]
+    fn shrink_to_fit(&mut self) {}
+
+    /// Set the number of bytes in use.
+    ///
+    /// # Safety
+    ///
+    /// The length must not exceed the capacity, and bytes up to it must be initialized.
+    unsafe fn set_len(&mut self, len: usize);
+
+    /// Check whether no bytes are in use.
+    #[inline]
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Make room for `additional` more bytes, growing geometrically to amortize copies.
+    #[inline]
+    fn reserve(&mut self, additional: usize) {
+        let needed = self.len() + additional;
+
+        if needed > self.capacity() {
+            self.grow(needed.max(2 * self.capacity()));
+        }
+    }
+
+    /// Make room for exactly `additional` more bytes.
+    #[inline]
+    fn reserve_exact(&mut self, additional: usize) {
+        let needed = self.len() + additional;
+
+        if needed > self.capacity() {
+            self.grow(needed);
+        }
+    }
+
+    /// Make room for `additional` more bytes as [`Self::reserve`] does, reporting failures
+    /// instead of panicking.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the buffer cannot grow by `additional` bytes.
+    #[inline]
+    fn try_reserve(&mut self, additional: usize) -> Result<(), Error> {
+        let needed = self.len().checked_add(additional);
+        let needed = needed.ok_or(Error::CapacityOverflow)?;
+
+        if needed > self.capacity() {
+            self.try_grow(needed.max(2 * self.capacity()))?;
+        }
+
+        Ok(())
+    }
+
+    /// Make room for exactly `additional` more bytes, reporting failures instead of panicking.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the buffer cannot grow by `additional` bytes.
+    #[inline]
+    fn try_reserve_exact(&mut self, additional: usize) -> Result<(), Error> {
+        let needed = self.len().checked_add(additional);
+        let needed = needed.ok_or(Error::CapacityOverflow)?;
+
+        if needed > self.capacity() {
+            self.try_grow(needed)?;
+        }
+
+        Ok(())
+    }
+
+    /// Append the bytes of `slice` at the end of the buffer.
+    #[inline]
+    fn extend_from_slice(&mut self, slice: &[u8]) {
+        self.reserve(slice.len());
+
+        // ! SAFETY: Room was made for the slice past the bytes in use, which it initializes
+        unsafe {
+            let end = self.as_mut_ptr().add(self.len());
+            core::ptr::copy_nonoverlapping(slice.as_ptr(), end, slice.len());
+
+            self.set_len(self.len() + slice.len());
+        }
+    }
+
+    /// Grow the bytes in use up to `len`, filling new ones with zeroes, or shrink them.
+    #[inline]
+    fn resize_zeroed(&mut self, len: usize) {
+        if len > self.len() {
+            self.reserve(len - self.len());
+
+            // ! SAFETY: Room was made up to the length, and new bytes are initialized here
+            unsafe {
+                let end = self.as_mut_ptr().add(self.len());
+                end.write_bytes(0, len - self.len());
+            }
+        }
+
+        // ! SAFETY: Bytes are initialized up to the length, either before or just above
+        unsafe { self.set_len(len) };
+    }
+
+    /// Shrink the bytes in use down to `len`, keeping the capacity.
+    #[inline]
+    fn truncate(&mut self, len: usize) {
+        if len < self.len() {
+            // ! SAFETY: Bytes up to a smaller length are initialized
+            unsafe { self.set_len(len) };
+        }
+    }
+
+    /// Forget all bytes in use, keeping the capacity.
+    #[inline]
+    fn clear(&mut self) {
+        self.truncate(0);
+    }
+}
+
+// ! SAFETY: Vectors allocate their capacity at the requested alignment,
+// ! and preserve their contents on re-allocation
+unsafe impl Storage for AVec<u8> {
+    #[inline]
+    fn new(align: usize) -> Self {
+        Self::new(align)
+    }
+
+    #[inline]
+    fn align(&self) -> usize {
+        self.alignment()
+    }
+
+    #[inline]
+    fn len(&self) -> usize {
+        Self::len(self)
+    }
+
+    #[inline]
+    fn capacity(&self) -> usize {
+        Self::capacity(self)
+    }
+
+    #[inline]
+    fn as_ptr(&self) -> *const u8 {
+        Self::as_ptr(self)
+    }
+
+    #[inline]
+    fn as_mut_ptr(&mut self) -> *mut u8 {
+        Self::as_mut_ptr(self)
+    }
+
+    #[inline]
+    fn grow(&mut self, capacity: usize) {
+        self.reserve_exact(capacity.saturating_sub(Self::len(self)));
+    }
+
+    #[inline]
+    fn try_grow(&mut self, capacity: usize) -> Result<(), Error> {
+        let additional = capacity.saturating_sub(Self::len(self));
+
+        self.try_reserve_exact(additional)
+            .map_err(|error| match error {
+                TryReserveError::CapacityOverflow => Error::CapacityOverflow,
+                TryReserveError::AllocError { .. } => Error::AllocationFailure,
+            })
+    }
+
+    #[inline]
+    fn shrink_to_fit(&mut self) {
+        Self::shrink_to_fit(self);
+    }
+
+    #[inline]
+    unsafe fn set_len(&mut self, len: usize) {
+        // ! SAFETY: Caller guarantees bytes up to the length are initialized
+        unsafe { Self::set_len(self, len) };
+    }
+}
+
+/// Buffer keeping up to `N` bytes within itself, and moving them to the heap past that.
+///
+/// Tiny collections, like those nested in each entity, thus save one allocation per type,
+/// as buffers of their arenas live in the directory of arenas. The directory and bookkeeping
+/// of arenas still allocate. Elements aligned to more than 64 bytes always go to the heap.
+///
+/// Inline elements move along with the collection, which relocation callbacks do not report,
+/// see [`Hato::on_relocate`](crate::Hato::on_relocate).
+///
+/// ```rust
+/// let mut arena = hato::Hato::<dyn core::fmt::Debug, hato::InlineStorage<16>>::default();
+///
+/// let x = arena.push(1_u32);
+/// let y = arena.push(2_u64);
+///
+/// assert_eq!(format!("{:?}", unsafe { arena.get(x) }), "1");
+/// assert_eq!(format!("{:?}", unsafe { arena.get(y) }), "2");
+/// ```
+#[derive(Clone, Debug)]
+pub struct InlineStorage<const N: usize> {
+    inline: InlineBytes<N>,
+    heap: Option<AVec<u8>>,
+    len: usize,
+    align: usize,
+}
+
+/// Bytes stored inline, aligned for all elements but over-aligned ones.
+#[derive(Clone, Copy, Debug)]
+#[repr(C, align(64))]
+struct InlineBytes<const N: usize>([u8; N]);
+
+impl<const N: usize> InlineStorage<N> {
+    /// Move bytes in use to a heap buffer of at least `capacity` bytes, using `grow`.
+    #[inline]
+    fn spill<E>(
+        &mut self,
+        capacity: usize,
+        grow: impl FnOnce(&mut AVec<u8>, usize) -> Result<(), E>,
+    ) -> Result<(), E> {
+        let mut heap = AVec::new(self.align);
+        grow(&mut heap, capacity)?;
+
+        heap.extend_from_slice(&self.inline.0[..self.len]);
+        self.heap = Some(heap);
+
+        Ok(())
+    }
+}
+
+// ! SAFETY: Inline bytes are aligned to 64 bytes, and buffers of over-aligned elements
+// ! start on the heap. Spilling copies the bytes in use over
+unsafe impl<const N: usize> Storage for InlineStorage<N> {
+    #[inline]
+    fn new(align: usize) -> Self {
+        Self {
+            inline: InlineBytes([0; N]),
+            heap: (align > align_of::<InlineBytes<N>>()).then(|| AVec::new(align)),
+            len: 0,
+            align,
+        }
+    }
+
+    #[inline]
+    fn align(&self) -> usize {
+        self.align
+    }
+
+    #[inline]
+    fn len(&self) -> usize {
+        self.heap.as_ref().map_or(self.len, Storage::len)
+    }
+
+    #[inline]
+    fn capacity(&self) -> usize {
+        self.heap.as_ref().map_or(N, Storage::capacity)
+    }
+
+    #[inline]
+    fn as_ptr(&self) -> *const u8 {
+        self.heap
+            .as_ref()
+            .map_or(self.inline.0.as_ptr(), Storage::as_ptr)
+    }
+
+    #[inline]
+    fn as_mut_ptr(&mut self) -> *mut u8 {
+        match &mut self.heap {
+            Some(heap) => Storage::as_mut_ptr(heap),
+            None => self.inline.0.as_mut_ptr(),
+        }
+    }
+
+    #[inline]
+    fn grow(&mut self, capacity: usize) {
+        match &mut self.heap {
+            Some(heap) => Storage::grow(heap, capacity),
+            None if capacity > N => {
+                let Ok(()) = self.spill(capacity, |heap, capacity| {
+                    Storage::grow(heap, capacity);
+                    Ok::<(), Infallible>(())
+                });
+            }
+            None => {}
+        }
+    }
+
+    #[inline]
+    fn try_grow(&mut self, capacity: usize) -> Result<(), Error> {
+        match &mut self.heap {
+            Some(heap) => Storage::try_grow(heap, capacity),
+            None if capacity > N => self.spill(capacity, Storage::try_grow),
+            None => Ok(()),
+        }
+    }
+
+    #[inline]
+    fn shrink_to_fit(&mut self) {
+        // Bytes stay on the heap once spilled, as buffers of over-aligned elements must
+        if let Some(heap) = &mut self.heap {
+            Storage::shrink_to_fit(heap);
+        }
+    }
+
+    #[inline]
+    unsafe fn set_len(&mut self, len: usize) {
+        match &mut self.heap {
+            // ! SAFETY: Caller guarantees bytes up to the length are initialized
+            Some(heap) => unsafe { Storage::set_len(heap, len) },
+            None => self.len = len,
+        }
+    }
+}
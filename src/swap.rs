@@ -0,0 +1,106 @@
+use std::sync::Arc;
+
+use core::ptr::{DynMetadata, Pointee};
+
+use arc_swap::{ArcSwap, Guard};
+
+use crate::{Hato, HatoPersistent};
+
+/// Read-copy-update wrapper around [`Hato`], for read-mostly collections shared across threads.
+///
+/// Readers grab an immutable snapshot with a single atomic load, and are never blocked by writers.
+/// Writers derive the next version from the current one, then publish it atomically.
+/// Versions are [`HatoPersistent`] collections, so the next version shares all arenas
+/// with the current one, except those the writer modified.
+///
+/// Handles remain valid across versions, since each version derives from the previous one.
+///
+/// ```rust
+/// let swap = hato::HatoSwap::<dyn core::fmt::Debug + Send + Sync>::default();
+///
+/// // Writers derive a new version with their modifications, then publish it
+/// let x = swap.update(|arena| arena.push(4_u16));
+///
+/// // Readers access the latest published version without locking
+/// let snapshot = swap.load();
+/// assert_eq!(format!("{:?}", unsafe { snapshot.get(x) }), "4");
+/// ```
+///
+/// Versions are shared across threads, so trait objects must be [`Send`] and [`Sync`]:
+///
+/// ```rust,compile_fail
+/// let swap = hato::HatoSwap::<dyn core::fmt::Debug>::default();
+/// ```
+#[derive(Debug)]
+pub struct HatoSwap<Trait: ?Sized + Send + Sync + Pointee<Metadata = DynMetadata<Trait>>>(
+    ArcSwap<HatoPersistent<Trait>>,
+);
+
+impl<Trait> Default for HatoSwap<Trait>
+where
+    Trait: ?Sized + Send + Sync + Pointee<Metadata = DynMetadata<Trait>>,
+{
+    fn default() -> Self {
+        Self::new(Hato::default())
+    }
+}
+
+impl<Trait> HatoSwap<Trait>
+where
+    Trait: ?Sized + Send + Sync + Pointee<Metadata = DynMetadata<Trait>>,
+{
+    /// Publish `hato` as the initial version of the collection.
+    #[inline]
+    #[must_use]
+    pub fn new(hato: Hato<Trait>) -> Self {
+        Self(ArcSwap::from_pointee(HatoPersistent::from(hato)))
+    }
+
+    /// Acquire the latest published version, with a single atomic load.
+    ///
+    /// The guard is meant for short-lived accesses; prefer [`Self::load_full`] to hold onto it.
+    #[inline]
+    #[must_use]
+    pub fn load(&self) -> Guard<Arc<HatoPersistent<Trait>>> {
+        self.0.load()
+    }
+
+    /// Acquire the latest published version, as a reference-counted pointer.
+    #[inline]
+    #[must_use]
+    pub fn load_full(&self) -> Arc<HatoPersistent<Trait>> {
+        self.0.load_full()
+    }
+
+    /// Replace the published version with `hato`, without looking at the previous one.
+    #[inline]
+    pub fn store(&self, hato: Hato<Trait>) {
+        self.0.store(Arc::new(HatoPersistent::from(hato)));
+    }
+
+    /// Publish the version `f` derives from the latest one, returning the rest of its output.
+    ///
+    /// If another writer published a version in the meantime, the derived one is discarded
+    /// and `f` runs again on the newer version, so that no modification is ever lost.
+    #[inline]
+    pub fn update<R>(
+        &self,
+        mut f: impl FnMut(&HatoPersistent<Trait>) -> (HatoPersistent<Trait>, R),
+    ) -> R {
+        let mut current = self.0.load_full();
+
+        loop {
+            // Derive the next version, which only copies the arenas it modifies
+            let (next, output) = f(&current);
+
+            // Publish only if no other writer got there first
+            let previous = self.0.compare_and_swap(&current, Arc::new(next));
+
+            if Arc::ptr_eq(&previous, &current) {
+                return output;
+            }
+
+            current = Guard::into_inner(previous);
+        }
+    }
+}
@@ -1,4 +1,4 @@
-use crate::Hato;
+use crate::{Hato, HatoCache};
 
 #[test]
 fn base() {
@@ -10,3 +10,3417 @@ fn base() {
     assert_eq!(format!("{:?}", unsafe { arena.get(x) }), "9");
     assert_eq!(format!("{:?}", unsafe { arena.get(y) }), "5");
 }
+
+#[cfg(feature = "arc-swap")]
+#[test]
+fn swap() {
+    let swap = crate::HatoSwap::<dyn core::fmt::Debug + Send + Sync>::default();
+
+    let x = swap.update(|arena| arena.push(9_i32));
+    let before = swap.load_full();
+
+    let y = swap.update(|arena| arena.push(5_u16));
+    let after = swap.load();
+
+    assert_eq!(format!("{:?}", unsafe { before.get(x) }), "9");
+    assert_eq!(format!("{:?}", unsafe { after.get(x) }), "9");
+    assert_eq!(format!("{:?}", unsafe { after.get(y) }), "5");
+
+    // Arenas left alone by an update are shared with the previous version
+    let _ = swap.update(|arena| arena.push(6_u16));
+    let latest = swap.load();
+
+    let shared = |handle| unsafe { core::ptr::addr_eq(after.get(handle), latest.get(handle)) };
+    assert!(shared(x) && !shared(y));
+}
+
+#[cfg(feature = "rayon")]
+#[test]
+fn par_retain() {
+    let mut arena = Hato::<dyn core::fmt::Debug + Sync>::default();
+
+    let handles = (0..5000_u32).map(|i| arena.push(i)).collect::<Vec<_>>();
+    let y = arena.push(5_u16);
+
+    arena.par_retain(|x| format!("{x:?}").ends_with('5'));
+
+    assert_eq!(format!("{:?}", unsafe { arena.get(y) }), "5");
+    assert_eq!(format!("{:?}", unsafe { arena.get(handles[15]) }), "15");
+
+    // Exactly the slots of removed elements are handed out again
+    let mut reused = (0..4500).map(|_| arena.push(0_u32)).collect::<Vec<_>>();
+    reused.sort();
+
+    let removed = handles.iter().enumerate().filter(|(i, _)| i % 10 != 5);
+    assert!(removed.map(|(_, h)| *h).eq(reused));
+}
+
+#[cfg(feature = "get-size")]
+#[test]
+fn get_size() {
+    use get_size::GetSize;
+
+    let mut arena = Hato::<dyn core::fmt::Debug>::default();
+    let empty = arena.get_heap_size();
+
+    let x = arena.push(9_u64);
+    let _ = arena.push(5_u64);
+    let filled = arena.get_heap_size();
+
+    // Free list grows when removing elements before the end of the arena
+    arena.remove(x);
+
+    assert_eq!(empty, 0);
+    assert!(filled >= 2 * size_of::<u64>());
+    assert!(arena.get_heap_size() > filled);
+}
+
+#[cfg(feature = "malloc_size_of")]
+#[test]
+fn malloc_size_of() {
+    use core::ffi::c_void;
+
+    use malloc_size_of::{MallocSizeOf, MallocSizeOfOps};
+
+    // Stand-in for the allocator, reporting a fixed size for any allocation
+    unsafe extern "C" fn size_of_op(_: *const c_void) -> usize {
+        64
+    }
+
+    let mut ops = MallocSizeOfOps::new(size_of_op, None, None);
+
+    let mut arena = Hato::<dyn core::fmt::Debug>::default();
+    let empty = arena.size_of(&mut ops);
+
+    let x = arena.push(9_u64);
+    let _ = arena.push(5_u64);
+    let filled = arena.size_of(&mut ops);
+
+    // Free list grows when removing elements before the end of the arena
+    arena.remove(x);
+
+    assert_eq!(empty, 0);
+    assert!(filled >= 2 * size_of::<u64>());
+    assert!(arena.size_of(&mut ops) > filled);
+}
+
+#[test]
+fn push_no_drop() {
+    #[derive(Debug)]
+    struct Noisy(u8);
+
+    impl Drop for Noisy {
+        fn drop(&mut self) {
+            panic!("destructor of `Noisy({})` should not run", self.0);
+        }
+    }
+
+    unsafe impl unscrupulous::Unscrupulous for Noisy {}
+
+    let mut arena = Hato::<dyn core::fmt::Debug>::default();
+
+    let x = arena.push_no_drop(Noisy(4));
+    assert_eq!(format!("{:?}", unsafe { arena.get(x) }), "Noisy(4)");
+
+    arena.remove(x);
+}
+
+#[test]
+fn zero_sized() {
+    #[derive(Debug)]
+    struct Marker;
+
+    unsafe impl unscrupulous::Unscrupulous for Marker {}
+
+    let mut arena = Hato::<dyn core::fmt::Debug>::default();
+
+    let x = arena.push(Marker);
+    let y = arena.push(Marker);
+    let z = arena.push(5_u8);
+
+    // Each zero-sized element gets its own slot
+    assert_ne!(x, y);
+    assert_eq!(format!("{:?}", unsafe { arena.get(y) }), "Marker");
+
+    arena.remove(x);
+
+    // Slots of zero-sized elements are reused as well
+    assert_eq!(arena.push(Marker), x);
+    assert_eq!(format!("{:?}", unsafe { arena.get(z) }), "5");
+}
+
+#[test]
+fn non_static() {
+    use core::marker::PhantomData;
+
+    /// Trait objects borrow from an interner that outlives the arena.
+    trait Named<'a> {
+        fn name(&self, interner: &'a [String]) -> &'a str;
+    }
+
+    /// Plain index into the interner, tied to its lifetime without holding a reference.
+    struct Symbol<'a>(u32, PhantomData<&'a [String]>);
+
+    impl<'a> Named<'a> for Symbol<'a> {
+        fn name(&self, interner: &'a [String]) -> &'a str {
+            &interner[self.0 as usize]
+        }
+    }
+
+    unsafe impl unscrupulous::Unscrupulous for Symbol<'_> {}
+
+    fn names<'a>(
+        interner: &'a [String],
+        arena: &Hato<dyn Named<'a> + 'a>,
+        handles: &[crate::Handle],
+    ) -> Vec<&'a str> {
+        handles
+            .iter()
+            .map(|h| unsafe { arena.get(*h) }.name(interner))
+            .collect()
+    }
+
+    let interner = vec![String::from("foo"), String::from("bar")];
+
+    let mut arena = Hato::<dyn Named<'_> + '_>::default();
+
+    let x = arena.push(Symbol(1, PhantomData));
+    let y = arena.push(Symbol(0, PhantomData));
+
+    assert_eq!(names(&interner, &arena, &[x, y]), ["bar", "foo"]);
+}
+
+#[test]
+fn try_remove() {
+    let mut arena = Hato::<dyn core::fmt::Debug>::default();
+
+    let x = arena.push(9_i32);
+    let y = arena.push(5_i32);
+
+    assert!(arena.try_remove(x));
+    assert!(!arena.try_remove(x));
+
+    // Slot of `x` is handed out only once
+    let z = arena.push(7_i32);
+    let w = arena.push(3_i32);
+
+    assert_eq!(z, x);
+    assert_ne!(w, z);
+
+    assert_eq!(format!("{:?}", unsafe { arena.get(y) }), "5");
+    assert_eq!(format!("{:?}", unsafe { arena.get(z) }), "7");
+}
+
+#[test]
+fn absorb_vec() {
+    let (mut arena, handles) = Hato::<dyn core::fmt::Debug>::from_vec(vec![1_u16, 2, 3]);
+
+    let x = arena.push(4_u16);
+    let more = arena.absorb_vec(vec![5_u16, 6]);
+    let none = arena.absorb_vec(Vec::<u8>::new());
+
+    assert_eq!(none.len(), 0);
+
+    let all = handles
+        .chain([x])
+        .chain(more)
+        .map(|h| format!("{:?}", unsafe { arena.get(h) }));
+    assert!(all.eq(["1", "2", "3", "4", "5", "6"]));
+}
+
+#[test]
+fn extract_all() {
+    let mut arena = Hato::<dyn core::fmt::Debug>::default();
+
+    let xs = (0..5_u32).map(|i| arena.push(i)).collect::<Vec<_>>();
+    let y = arena.push(5_u16);
+
+    arena.remove(xs[1]);
+    arena.remove(xs[3]);
+
+    assert_eq!(arena.extract_all::<u32>(), [0, 2, 4]);
+
+    // Arena keeps being used for new elements of the same type
+    let z = arena.push(7_u32);
+
+    assert_eq!(z, xs[0]);
+    assert_eq!(arena.extract_all::<u32>(), [7]);
+    assert_eq!(format!("{:?}", unsafe { arena.get(y) }), "5");
+}
+
+#[test]
+fn retain_types() {
+    use core::any::TypeId;
+
+    let mut arena = Hato::<dyn core::fmt::Debug>::default();
+
+    let x = arena.push(1_u8);
+    let y = arena.push(2_i32);
+    let z = arena.push(3_u16);
+
+    let mut seen = Vec::new();
+
+    arena.retain_types(|id| {
+        seen.push(id);
+        id != TypeId::of::<i32>()
+    });
+
+    assert_eq!(
+        seen,
+        [TypeId::of::<u8>(), TypeId::of::<i32>(), TypeId::of::<u16>()]
+    );
+    assert_eq!(arena.extract_all::<i32>(), []);
+
+    // Handles to other arenas are left untouched, and released ones are reused
+    assert_eq!(format!("{:?}", unsafe { arena.get(x) }), "1");
+    assert_eq!(format!("{:?}", unsafe { arena.get(z) }), "3");
+    assert_eq!(arena.push(4_i32), y);
+}
+
+#[test]
+fn resolver() {
+    let mut arena = Hato::<dyn core::fmt::Debug>::default();
+
+    let xs = (0..10_u64).map(|i| arena.push(i)).collect::<Vec<_>>();
+    let y = arena.push([0_u8; 0]);
+
+    let resolver = arena.resolver(xs[0]);
+
+    for (i, x) in xs.iter().enumerate() {
+        assert_eq!(
+            format!("{:?}", unsafe { resolver.get(*x) }.unwrap()),
+            i.to_string()
+        );
+    }
+
+    assert!(unsafe { resolver.get(y) }.is_none());
+    assert_eq!(
+        format!("{:?}", unsafe { arena.resolver(y).get(y) }.unwrap()),
+        "[]"
+    );
+}
+
+#[test]
+fn cache_line_padding() {
+    let mut arena = Hato::<dyn core::fmt::Debug>::default().with_cache_line_padding();
+
+    let x = arena.push(1_u16);
+    let ys = arena.absorb_vec(vec![2_u16, 3]);
+    let z = arena.push([0_u8; 0]);
+
+    let all = core::iter::once(x).chain(ys).chain([z]);
+    assert!(all
+        .map(|h| format!("{:?}", unsafe { arena.get(h) }))
+        .eq(["1", "2", "3", "[]"]));
+
+    arena.remove(x);
+    assert_eq!(arena.extract_all::<u16>(), [2, 3]);
+}
+
+#[test]
+fn tail_truncation() {
+    let mut arena = Hato::<dyn core::fmt::Debug>::default();
+
+    let x = arena.push(1_u32);
+    let y = arena.push(2_u32);
+    let z = arena.push(3_u32);
+
+    // Removing the last element also releases free slots right before it
+    arena.remove(y);
+    arena.remove(z);
+
+    assert!(!arena.try_remove(y));
+
+    // Buffer grows back from its new end, in order
+    assert_eq!(arena.push(4_u32), y);
+    assert_eq!(arena.push(5_u32), z);
+    assert_eq!(arena.extract_all::<u32>(), [1, 4, 5]);
+
+    assert!(!arena.try_remove(x));
+}
+
+#[test]
+fn tail_truncation_free_list() {
+    let mut arena = Hato::<dyn core::fmt::Debug>::default();
+
+    let handles: Vec<_> = (0..8_u32).map(|i| arena.push(i)).collect();
+
+    for i in [1, 3, 5, 7, 6] {
+        arena.remove(handles[i]);
+    }
+
+    // Slots cut off with the tail are skipped, even if the free list still holds them
+    assert_eq!(arena.push(10_u32), handles[3]);
+    assert_eq!(arena.push(11_u32), handles[1]);
+    assert_eq!(arena.push(12_u32), handles[5]);
+    assert_eq!(arena.push(13_u32), handles[6]);
+    assert_eq!(arena.extract_all::<u32>(), [0, 11, 2, 10, 4, 12, 13]);
+}
+
+#[test]
+#[should_panic = "stale handle past the end of its arena"]
+fn tail_truncation_stale() {
+    let mut arena = Hato::<dyn core::fmt::Debug>::default();
+
+    let _ = arena.push(1_u32);
+    let x = arena.push(2_u32);
+
+    // Memory of the removed element is given back, so the stale handle cannot read it
+    arena.remove(x);
+    arena.shrink_to_fit();
+
+    let _ = unsafe { arena.get(x) };
+}
+
+#[test]
+fn exact_fit() {
+    let mut exact = Hato::<dyn core::fmt::Debug>::default().with_exact_fit();
+    let mut amortized = Hato::<dyn core::fmt::Debug>::default();
+
+    for i in 0..100_u16 {
+        let _ = exact.push(i);
+        let _ = amortized.push(i);
+    }
+
+    let _ = exact.absorb_vec(vec![0_u16; 10]);
+    let _ = exact.push([0_u8; 0]);
+
+    assert!(exact.slack().eq([0, 0]));
+    assert!(amortized.slack().all(|bytes| bytes > 0));
+}
+
+#[test]
+fn handle_list() {
+    let mut arena = Hato::<dyn core::fmt::Debug>::default();
+    let mut list = crate::HandleList::new();
+
+    let xs = (0..4_u32).map(|i| arena.push(i)).collect::<Vec<_>>();
+    let y = arena.push(4_u8);
+
+    list.push_back(&mut arena, xs[1]);
+    list.push_back(&mut arena, y);
+    list.push_front(&mut arena, xs[3]);
+    list.push_back(&mut arena, xs[0]);
+
+    list.unlink(&mut arena, y);
+
+    let all = list
+        .iter(&arena)
+        .map(|h| format!("{:?}", unsafe { arena.get(h) }));
+    assert!(all.eq(["3", "1", "0"]));
+
+    assert_eq!(list.pop_back(&mut arena), Some(xs[0]));
+    assert_eq!(list.pop_front(&mut arena), Some(xs[3]));
+    assert_eq!(list.len(), 1);
+
+    // Elements that left the list can join another one
+    let mut other = crate::HandleList::default();
+    other.push_back(&mut arena, y);
+
+    assert!(other.iter(&arena).eq([y]));
+    assert!(list.iter(&arena).eq([xs[1]]));
+}
+
+#[test]
+fn persistent() {
+    let empty = crate::HatoPersistent::<dyn core::fmt::Debug>::default();
+
+    let (first, x) = empty.push(1_u32);
+    let (second, y) = first.push(2_u32);
+    let third = second.remove(x);
+
+    // Slot freed in a later version is reused without affecting earlier ones
+    let (fourth, z) = third.push(3_u32);
+
+    assert_eq!(z, x);
+    assert_eq!(format!("{:?}", unsafe { first.get(x) }), "1");
+    assert_eq!(format!("{:?}", unsafe { second.get(x) }), "1");
+    assert_eq!(format!("{:?}", unsafe { fourth.get(z) }), "3");
+    assert_eq!(format!("{:?}", unsafe { fourth.get(y) }), "2");
+
+    // Frozen collections keep their handles
+    let mut arena = Hato::<dyn core::fmt::Debug>::default();
+    let w = arena.push(4_u8);
+
+    let frozen = crate::HatoPersistent::from(arena);
+    assert_eq!(format!("{:?}", unsafe { frozen.get(w) }), "4");
+
+    // Versions only copy the chunk of slots they modify, and share the others
+    let mut version = crate::HatoPersistent::<dyn core::fmt::Debug>::default();
+    let mut xs = Vec::new();
+
+    for i in 0..1000_u32 {
+        let (next, x) = version.push(i);
+
+        version = next;
+        xs.push(x);
+    }
+
+    let next = version.remove(xs[999]);
+    let (next, _) = next.push(5_u32);
+
+    let shares = |x| core::ptr::addr_eq(unsafe { version.get(x) }, unsafe { next.get(x) });
+
+    assert!(shares(xs[0]) && shares(xs[900]));
+    assert!(!shares(xs[998]));
+}
+
+#[test]
+fn pool() {
+    let mut pool = crate::Pool::<u64>::default();
+
+    let x = pool.push(1);
+    let ys = pool.absorb_vec(vec![2, 3, 4]).collect::<Vec<_>>();
+
+    pool.remove(ys[1]);
+    unsafe { *pool.get_mut(x) += 10 };
+
+    // Slot of the removed element is handed out again
+    assert_eq!(pool.push(5), ys[1]);
+    assert_eq!(unsafe { *pool.get(x) }, 11);
+    assert_eq!(pool.clone().extract_all(), [11, 2, 5, 4]);
+
+    let mut markers = crate::Pool::<[u8; 0]>::default();
+    let zs = markers.absorb_vec(vec![[]; 3]).collect::<Vec<_>>();
+
+    assert_ne!(zs[0], zs[2]);
+    assert_eq!(markers.extract_all().len(), 3);
+}
+
+#[test]
+fn hato_macro() {
+    let (arena, handles) = crate::hato![dyn core::fmt::Debug; 1_u8, 2.5_f32, [3_u16; 2],];
+
+    let all = handles.map(|h| format!("{:?}", unsafe { arena.get(h) }));
+    assert_eq!(all, ["1", "2.5", "[3, 3]"]);
+
+    let (_, none) = crate::hato![dyn core::fmt::Debug;];
+    assert!(none.is_empty());
+}
+
+#[test]
+fn remap() {
+    use std::collections::HashMap;
+
+    let mut arena = Hato::<dyn core::fmt::Debug>::default();
+
+    let xs = (0..4_u32).map(|i| arena.push(i)).collect::<Vec<_>>();
+
+    // Last two elements moved to the first two slots
+    let remap = crate::Remap::from_iter([(xs[2], xs[0]), (xs[3], xs[1])]);
+
+    let mut handles = vec![xs[3], xs[2]];
+    remap.apply(&mut handles);
+
+    let mut names = HashMap::from([(xs[2], "two"), (xs[3], "three")]);
+    remap.apply_map(&mut names);
+
+    assert_eq!(handles, [xs[1], xs[0]]);
+    assert_eq!(names, HashMap::from([(xs[0], "two"), (xs[1], "three")]));
+    assert_eq!(remap.resolve(xs[0]), xs[0]);
+    assert_eq!(remap.len(), 2);
+}
+
+#[test]
+fn get_batch() {
+    let mut arena = Hato::<dyn core::fmt::Debug>::default();
+
+    let xs = (0..3_u32).map(|i| arena.push(i)).collect::<Vec<_>>();
+    let y = arena.push(3_u8);
+
+    let mut out = vec![unsafe { arena.get(y) }];
+    unsafe { arena.get_batch(&[xs[2], y, xs[0], xs[1]], &mut out) };
+
+    let all = out.iter().map(|x| format!("{x:?}"));
+    assert!(all.eq(["3", "2", "3", "0", "1"]));
+}
+
+#[test]
+fn tag_bytes() {
+    let mut arena = Hato::<dyn core::fmt::Debug>::default().with_tag_bytes(2);
+
+    let x = arena.push(1_u32);
+    let ys = arena.absorb_vec(vec![2_u32, 3]).collect::<Vec<_>>();
+    let z = arena.push([0_u8; 0]);
+
+    arena.tag_mut(x).copy_from_slice(&[1, 2]);
+    arena.tag_mut(z)[1] = 7;
+
+    assert_eq!(arena.tag(x), [1, 2]);
+    assert_eq!(arena.tag(ys[1]), [0, 0]);
+    assert_eq!(arena.tag(z), [0, 7]);
+
+    // Tags of reused slots start zeroed again
+    arena.remove(x);
+    assert_eq!(arena.push(4_u32), x);
+    assert_eq!(arena.tag(x), [0, 0]);
+
+    let untagged = Hato::<dyn core::fmt::Debug>::from_vec(vec![1_u8]);
+    assert!(untagged.1.map(|h| untagged.0.tag(h).len()).eq([0]));
+}
+
+#[test]
+fn transfer() {
+    let mut active = Hato::<dyn core::fmt::Debug>::default().with_tag_bytes(1);
+    let mut archived = Hato::<dyn core::fmt::Debug>::default().with_tag_bytes(2);
+
+    let x = active.push(1_u32);
+    let y = active.push(2_u32);
+    let z = active.push([0_u8; 0]);
+    let w = archived.push(3_u8);
+
+    active.tag_mut(y)[0] = 9;
+
+    let moved = [y, z].map(|h| active.transfer(h, &mut archived));
+
+    assert!(!active.try_remove(y));
+    assert_eq!(archived.tag(moved[0]), [9, 0]);
+
+    let all = core::iter::once(w).chain(moved);
+    assert!(all
+        .map(|h| format!("{:?}", unsafe { archived.get(h) }))
+        .eq(["3", "2", "[]"]));
+    assert_eq!(format!("{:?}", unsafe { active.get(x) }), "1");
+}
+
+#[test]
+fn spill_threshold() {
+    let mut arena = Hato::<dyn core::fmt::Debug>::default().with_spill_threshold(16);
+
+    let xs = (0..3_u64).map(|i| arena.push([i; 4])).collect::<Vec<_>>();
+    let y = arena.push(4_u64);
+
+    arena.remove(xs[1]);
+    assert_eq!(arena.push([5_u64; 4]), xs[1]);
+
+    let resolver = arena.resolver(xs[0]);
+    let all = xs
+        .iter()
+        .map(|h| format!("{:?}", unsafe { resolver.get(*h) }.unwrap()));
+    assert!(all.eq(["[0, 0, 0, 0]", "[5, 5, 5, 5]", "[2, 2, 2, 2]"]));
+
+    // Spilled elements keep their own allocation, and are not in the byte buffer
+    assert_eq!(arena.slack().next(), Some(0));
+    assert_eq!(format!("{:?}", unsafe { arena.get(y) }), "4");
+
+    arena.remove(xs[2]);
+    assert_eq!(arena.extract_all::<[u64; 4]>(), [[0; 4], [5; 4]]);
+}
+
+#[test]
+#[should_panic = "stale handle to a removed spilled element"]
+fn spill_threshold_stale() {
+    let mut arena = Hato::<dyn core::fmt::Debug>::default().with_spill_threshold(16);
+
+    let x = arena.push([1_u64; 4]);
+    let _ = arena.push([2_u64; 4]);
+
+    // Allocation of the spilled element is freed on removal, so the stale handle cannot reach it
+    arena.remove(x);
+
+    let _ = arena.get_mut(x);
+}
+
+#[test]
+fn split_one_mut() {
+    let mut arena = Hato::<dyn core::any::Any>::default();
+
+    let xs = (1..=4_u32).map(|i| arena.push(i)).collect::<Vec<_>>();
+
+    // Each element becomes the sum of its neighbors
+    for (i, x) in xs.iter().enumerate() {
+        let (element, others) = arena.split_one_mut(*x);
+
+        let neighbors = [i.wrapping_sub(1), i + 1].into_iter().filter_map(|j| {
+            let other = unsafe { others.get(*xs.get(j)?) }?;
+            other.downcast_ref::<u32>().copied()
+        });
+
+        *element.downcast_mut::<u32>().unwrap() = neighbors.sum();
+        assert!(unsafe { others.get(*x) }.is_none());
+    }
+
+    assert_eq!(arena.extract_all::<u32>(), [2, 5, 9, 9]);
+}
+
+#[test]
+fn convert() {
+    use core::fmt::{Debug, Display};
+
+    let mut arena = Hato::<dyn Debug>::default();
+
+    let x = arena.push(1_u8);
+    let y = arena.push(2.5_f32);
+
+    // Types missing from the registry leave the collection untouched
+    let partial = crate::Conversion::<dyn Debug, dyn Display>::default().register::<u8>();
+    let Err(arena) = arena.convert(&partial) else {
+        panic!("`f32` is not registered");
+    };
+
+    let full = partial.register::<f32>();
+    let Ok(arena) = arena.convert(&full) else {
+        panic!("all types are registered");
+    };
+
+    assert_eq!(unsafe { arena.get(x) }.to_string(), "1");
+    assert_eq!(unsafe { arena.get(y) }.to_string(), "2.5");
+}
+
+#[test]
+fn push_dyn_clone() {
+    use core::any::Any;
+
+    let mut prototypes = Hato::<dyn Any>::default();
+
+    let x = prototypes.push(1_u32);
+    let y = prototypes.push([0_u8; 0]);
+
+    let mut arena = Hato::<dyn Any>::default();
+    let _ = arena.absorb_vec(vec![0_u32; 2]);
+    let _ = arena.push([0_u8; 0]);
+
+    let clones = [x, y].map(|h| arena.push_dyn_clone(unsafe { prototypes.get(h) }));
+    let [Some(cx), Some(cy)] = clones else {
+        panic!("types of prototypes are stored");
+    };
+
+    // Clones are independent from their prototype
+    *prototypes.get_mut(x).downcast_mut::<u32>().unwrap() = 2;
+
+    assert_eq!(unsafe { arena.get(cx) }.downcast_ref::<u32>(), Some(&1));
+    assert!(unsafe { arena.get(cy) }.is::<[u8; 0]>());
+    assert!(arena.push_dyn_clone(&3_u16).is_none());
+}
+
+#[test]
+fn tombstones() {
+    let mut arena = Hato::<dyn core::fmt::Debug>::default().with_tombstones();
+
+    let xs = (0..4_u32).map(|i| arena.push(i)).collect::<Vec<_>>();
+
+    arena.remove(xs[1]);
+    arena.remove(xs[3]);
+
+    // Stale handles are detected, as their slots are never handed out
+    let y = arena.push(4_u32);
+
+    assert!(!xs.contains(&y));
+    assert!(!arena.contains(xs[1]));
+    assert!(!arena.try_remove(xs[3]));
+
+    arena.remove(y);
+    arena.reclaim_tombstones();
+
+    // Trailing tombstones are released, others are reused
+    assert_eq!(arena.push(5_u32), xs[1]);
+    assert_eq!(arena.push(6_u32), xs[3]);
+    assert_eq!(arena.extract_all::<u32>(), [0, 5, 2, 6]);
+}
+
+#[test]
+fn compact_clone() {
+    let mut arena = Hato::<dyn core::fmt::Debug>::default().with_tag_bytes(1);
+
+    let _ = arena.push(0_u8);
+    let xs = (0..4_u32).map(|i| arena.push(i)).collect::<Vec<_>>();
+
+    arena.retain_types(|id| id != core::any::TypeId::of::<u8>());
+    arena.remove(xs[0]);
+    arena.remove(xs[2]);
+    arena.tag_mut(xs[3])[0] = 7;
+
+    let (compacted, remap) = arena.compact_clone();
+
+    // Live elements are packed in order, in arenas following the same directory order
+    let mut handles = vec![xs[1], xs[3]];
+    remap.apply(&mut handles);
+
+    assert_eq!(handles[0].offset, 0);
+    assert_eq!(handles[1].offset, 4);
+    assert_eq!(compacted.tag(handles[1]), [7]);
+    assert!(handles
+        .iter()
+        .map(|h| format!("{:?}", unsafe { compacted.get(*h) }))
+        .eq(["1", "3"]));
+
+    // Original stays valid
+    assert_eq!(format!("{:?}", unsafe { arena.get(xs[3]) }), "3");
+}
+
+#[test]
+fn capacity_bytes() {
+    let mut arena = Hato::<dyn core::fmt::Debug>::default().with_capacity_bytes(40);
+
+    arena.reserve_bytes::<u64>(800);
+    arena.reserve_bytes::<[u8; 0]>(800);
+
+    let capacities = arena.slack().collect::<Vec<_>>();
+    assert!(capacities[0] >= 800);
+    assert_eq!(capacities[1], 0);
+
+    // Reserved storage is used without reallocating
+    let xs = (0..100_u64).map(|i| arena.push(i)).collect::<Vec<_>>();
+    let first = (&raw const *unsafe { arena.get(xs[0]) }).addr();
+
+    for x in &xs[..50] {
+        arena.remove(*x);
+    }
+
+    assert_eq!(arena.slack().next(), Some(capacities[0] - 800));
+    assert_eq!((&raw const *unsafe { arena.get(xs[0]) }).addr(), first);
+
+    // Arenas created later start with the default reservation
+    let _ = arena.push(1_u16);
+    assert_eq!(arena.slack().last(), Some(38));
+}
+
+#[cfg(feature = "oplog")]
+#[test]
+fn oplog() {
+    let mut recorder = crate::Recorder::new(Hato::<dyn core::any::Any>::default());
+
+    let xs = (0..3_u32).map(|i| recorder.push(i)).collect::<Vec<_>>();
+    let y = recorder.push(3_u16);
+
+    recorder.remove(xs[1]);
+    recorder.update(xs[2], |x| *x.downcast_mut::<u32>().unwrap() = 7);
+    let z = recorder.push(4_u32);
+
+    let Some(log) = crate::OpLog::decode(&recorder.log().encode()) else {
+        panic!("encoded log should decode");
+    };
+    assert_eq!(&log, recorder.log());
+
+    // Replays stop at types missing from the registry
+    let types = crate::Types::default().register::<u32>();
+    assert!(!unsafe { log.replay(&mut Hato::default(), &types) });
+
+    let types = types.register::<u16>();
+    let mut replayed = Hato::<dyn core::any::Any>::default();
+    assert!(unsafe { log.replay(&mut replayed, &types) });
+
+    assert_eq!(z, xs[1]);
+    assert!(replayed.contains(z));
+    assert_eq!(unsafe { replayed.get(y) }.downcast_ref::<u16>(), Some(&3));
+    assert_eq!(replayed.extract_all::<u32>(), [0, 4, 7]);
+
+    assert!(crate::OpLog::decode(&[1, 0]).is_none());
+}
+
+#[cfg(feature = "wal")]
+#[test]
+fn wal() {
+    let mut wal = crate::Wal::new(Hato::<dyn core::any::Any>::default(), Vec::new());
+
+    let xs = (0..3_u32).map(|i| wal.push(i).unwrap()).collect::<Vec<_>>();
+    wal.remove(xs[0]).unwrap();
+    wal.update(xs[2], |x| *x.downcast_mut::<u32>().unwrap() = 7)
+        .unwrap();
+
+    let (mut hato, mut bytes) = wal.into_parts();
+    let intact = bytes.len();
+
+    // Crash while appending the next modification
+    bytes.extend_from_slice(&[0, 9, 0]);
+
+    let (log, len) = crate::OpLog::decode_intact(&bytes);
+    assert_eq!((log.len(), len), (5, intact));
+
+    let types = crate::Types::default().register::<u32>();
+    let mut recovered = Hato::<dyn core::any::Any>::default();
+    assert!(unsafe { log.replay(&mut recovered, &types) });
+
+    assert!(!recovered.contains(xs[0]));
+    assert_eq!(recovered.extract_all::<u32>(), hato.extract_all::<u32>());
+}
+
+#[test]
+fn prewarm() {
+    let mut arena = Hato::<dyn core::fmt::Debug>::default();
+
+    arena.reserve_bytes::<u64>(1 << 16);
+    arena.reserve_bytes::<u8>(1 << 16);
+
+    let x = arena.push(1_u64);
+
+    arena.prewarm();
+    arena.prewarm_type::<u8>();
+
+    // Prewarming leaves elements and reservations untouched
+    assert_eq!(format!("{:?}", unsafe { arena.get(x) }), "1");
+    assert!(arena.slack().all(|bytes| bytes >= (1 << 16) - 8));
+}
+
+#[test]
+fn named() {
+    let mut arena = Hato::<dyn core::fmt::Debug>::default();
+
+    let x = arena.push(1_u32);
+    let y = arena.push(2_u32);
+    let z = arena.push(3_u8);
+
+    assert_eq!(arena.insert_named("player", x), None);
+    assert_eq!(arena.insert_named("player", y), Some(x));
+    let _ = arena.insert_named("boss", y);
+    let _ = arena.insert_named("flag", z);
+
+    // Each element keeps a single name
+    assert_eq!(arena.get_named("player"), None);
+    assert_eq!(arena.name_of(y), Some("boss"));
+    assert_eq!(arena.name_of(x), None);
+
+    // Names follow their element through compaction, and are forgotten on removal
+    let _ = arena.insert_named("player", x);
+    arena.remove(x);
+
+    let (compacted, remap) = arena.compact_clone();
+    assert_eq!(compacted.get_named("boss"), Some(remap.resolve(y)));
+    assert_eq!(compacted.get_named("player"), None);
+
+    arena.retain_types(|id| id != core::any::TypeId::of::<u8>());
+    assert_eq!(arena.get_named("flag"), None);
+    assert_eq!(arena.remove_named("boss"), Some(y));
+}
+
+#[cfg(feature = "shadow")]
+#[test]
+fn shadow() {
+    let mut arena = Hato::<dyn core::fmt::Debug>::default().with_cache_line_padding();
+
+    let xs = (0..10_u32).map(|i| arena.push(i)).collect::<Vec<_>>();
+    let ys = arena.absorb_vec(vec![[1_u8; 3]; 4]).collect::<Vec<_>>();
+
+    arena.remove(xs[3]);
+    arena.remove(ys[3]);
+    assert!(!arena.try_remove(xs[3]));
+
+    let _ = arena.push(10_u32);
+    assert_eq!(format!("{:?}", arena.get_mut(xs[0])), "0");
+    assert_eq!(arena.extract_all::<[u8; 3]>(), [[1; 3]; 3]);
+
+    arena.verify_shadow();
+    let (compacted, _) = arena.compact_clone();
+    compacted.verify_shadow();
+}
+
+#[cfg(feature = "shadow")]
+#[test]
+#[should_panic = "handed out twice"]
+fn shadow_double_removal() {
+    let mut arena = Hato::<dyn core::fmt::Debug>::default();
+
+    let x = arena.push(1_u32);
+    let _ = arena.push(2_u32);
+
+    // Free the slot behind the model's back, as a bug in the core would
+    arena.arenas[0].remove(x.offset);
+
+    let _ = arena.push(3_u32);
+}
+
+#[cfg(feature = "egui")]
+#[test]
+fn inspector() {
+    let mut arena = Hato::<dyn core::fmt::Debug>::default();
+
+    let x = arena.push(1_u32);
+    let _ = arena.push([2_u8; 3]);
+    arena.remove(x);
+
+    // Render a frame headlessly
+    let ctx = egui::Context::default();
+    let mut output = ctx.run_ui(egui::RawInput::default(), |ui| {
+        drop(ui.add(crate::Inspector::new(&arena)));
+    });
+
+    // Font atlas uploads are left to the integration, and must be acknowledged explicitly
+    output.textures_delta.clear();
+}
+
+#[cfg(feature = "bevy_reflect")]
+#[test]
+fn reflect() {
+    use bevy_reflect::{Reflect, TypeRegistry};
+
+    #[derive(Debug, Reflect)]
+    struct Position {
+        x: f32,
+        y: f32,
+    }
+
+    unsafe impl unscrupulous::Unscrupulous for Position {}
+
+    let mut registry = TypeRegistry::empty();
+    registry.register::<Position>();
+
+    let mut arena = Hato::<dyn core::fmt::Debug>::default();
+
+    let x = arena.push(Position { x: 1.0, y: 2.0 });
+    let y = arena.push(3_u64);
+
+    // Fields are reachable generically, without knowing the concrete type
+    let Some(reflected) = arena.get_reflect_mut(x, &registry) else {
+        panic!("registered type should be reflected");
+    };
+    let bevy_reflect::ReflectMut::Struct(position) = reflected.reflect_mut() else {
+        panic!("position should be reflected as a struct");
+    };
+    *position
+        .field_mut("y")
+        .unwrap()
+        .try_downcast_mut::<f32>()
+        .unwrap() = 5.0;
+
+    assert_eq!(
+        format!("{:?}", unsafe { arena.get(x) }),
+        "Position { x: 1.0, y: 5.0 }"
+    );
+
+    // Unregistered types and removed elements are not reflected
+    assert!(arena.get_reflect(y, &registry).is_none());
+    arena.remove(x);
+    assert!(arena.get_reflect(x, &registry).is_none());
+}
+
+#[test]
+fn storage() {
+    use crate::Storage;
+
+    // Buffer of fixed size, never re-allocated
+    #[derive(Clone, Debug)]
+    struct Fixed {
+        words: Box<[u64; 32]>,
+        len: usize,
+    }
+
+    unsafe impl Storage for Fixed {
+        fn new(align: usize) -> Self {
+            assert!(align <= align_of::<u64>(), "over-aligned element");
+
+            Self {
+                words: Box::new([0; 32]),
+                len: 0,
+            }
+        }
+
+        fn align(&self) -> usize {
+            align_of::<u64>()
+        }
+
+        fn len(&self) -> usize {
+            self.len
+        }
+
+        fn capacity(&self) -> usize {
+            size_of::<[u64; 32]>()
+        }
+
+        fn as_ptr(&self) -> *const u8 {
+            self.words.as_ptr().cast()
+        }
+
+        fn as_mut_ptr(&mut self) -> *mut u8 {
+            self.words.as_mut_ptr().cast()
+        }
+
+        fn grow(&mut self, capacity: usize) {
+            assert!(capacity <= self.capacity(), "fixed buffer is full");
+        }
+
+        unsafe fn set_len(&mut self, len: usize) {
+            self.len = len;
+        }
+    }
+
+    let mut arena = Hato::<dyn core::fmt::Debug, Fixed>::default();
+
+    let xs = (0..10_u32).map(|i| arena.push(i)).collect::<Vec<_>>();
+    let y = arena.push([7_u8; 3]);
+
+    arena.remove(xs[4]);
+    arena.remove(xs[9]);
+    let z = arena.push(10_u32);
+
+    assert_eq!(z, xs[4]);
+    assert_eq!(format!("{:?}", unsafe { arena.get(xs[8]) }), "8");
+    assert_eq!(format!("{:?}", unsafe { arena.get(y) }), "[7, 7, 7]");
+    assert_eq!(arena.extract_all::<u32>(), [0, 1, 2, 3, 10, 5, 6, 7, 8]);
+}
+
+#[test]
+fn poisoning() {
+    // Sanitized builds poison free slots, which must never be touched by the crate itself
+    let mut arena = Hato::<dyn core::fmt::Debug>::default().with_tombstones();
+
+    let xs = (0..64_u64).map(|i| arena.push(i)).collect::<Vec<_>>();
+
+    for x in xs.iter().step_by(2) {
+        arena.remove(*x);
+    }
+
+    // Grow the buffer past free slots, then copy it whole
+    let ys = (64..256_u64).map(|i| arena.push(i)).collect::<Vec<_>>();
+    let mut copy = arena.clone();
+
+    arena.reclaim_tombstones();
+    let _ = arena.push(256_u64);
+
+    // Tombstones are skipped on extraction, even though they are not in the free list
+    assert_eq!(format!("{:?}", unsafe { copy.get(ys[0]) }), "64");
+    assert_eq!(copy.extract_all::<u64>().len(), 32 + 192);
+    assert_eq!(arena.extract_all::<u64>().len(), 32 + 192 + 1);
+}
+
+#[test]
+fn handle_text() {
+    let mut arena = Hato::<dyn core::fmt::Debug>::default();
+
+    let _ = arena.push(1_u8);
+    let x = arena.push(2_u32);
+    let y = arena.push(3_u32);
+
+    assert_eq!(y.to_string(), "h1:00004");
+    assert_eq!(x.to_string().parse(), Ok(x));
+
+    // Leading zeroes are optional, but nothing else is accepted
+    assert_eq!("h1:4".parse(), Ok(y));
+
+    for invalid in [
+        "",
+        "h1",
+        "1:4",
+        "h1:",
+        "h:4",
+        "h+1:4",
+        "h1: 4",
+        "h1:4:0",
+        "h1:4294967296",
+    ] {
+        assert_eq!(
+            invalid.parse::<crate::Handle>(),
+            Err(crate::ParseHandleError)
+        );
+    }
+}
+
+#[test]
+fn group_handles_by_type() {
+    use core::any::TypeId;
+
+    let mut arena = Hato::<dyn core::fmt::Debug>::default();
+
+    let xs = (0..6_u32).map(|i| arena.push(i)).collect::<Vec<_>>();
+    let ys = (0..3_u8).map(|i| arena.push(i)).collect::<Vec<_>>();
+    let _ = arena.push(7_u16);
+
+    let handles = [ys[2], xs[5], xs[0], ys[0], xs[3]];
+    let groups = arena.group_handles_by_type(&handles);
+
+    assert_eq!(
+        groups,
+        [
+            (TypeId::of::<u32>(), vec![xs[5], xs[0], xs[3]]),
+            (TypeId::of::<u8>(), vec![ys[2], ys[0]]),
+        ]
+    );
+}
+
+#[test]
+fn iter_ptrs() {
+    let mut arena = Hato::<dyn core::fmt::Debug>::default();
+
+    let xs = (0..5_u32).map(|i| arena.push(i)).collect::<Vec<_>>();
+    let _ = arena.push([9_u8; 3]);
+    let _ = arena.push([0_u8; 0]);
+
+    arena.remove(xs[1]);
+
+    // Pointers are those of the elements, and skip free slots
+    let ptrs = arena
+        .iter_ptrs()
+        .map(Iterator::collect)
+        .collect::<Vec<Vec<_>>>();
+
+    assert_eq!(ptrs.iter().map(Vec::len).collect::<Vec<_>>(), [4, 1, 1]);
+    assert!(core::ptr::addr_eq(ptrs[0][1], unsafe { arena.get(xs[2]) }));
+
+    let seen = ptrs
+        .concat()
+        .into_iter()
+        .map(|ptr| format!("{:?}", unsafe { &*ptr }));
+    assert_eq!(
+        seen.collect::<Vec<_>>(),
+        ["0", "2", "3", "4", "[9, 9, 9]", "[]"]
+    );
+}
+
+#[test]
+fn par_for_each() {
+    use core::num::NonZeroUsize;
+    use core::sync::atomic::{AtomicU64, Ordering};
+
+    trait Counter: Send + Sync {
+        fn increment(&mut self);
+        fn value(&self) -> u64;
+    }
+
+    impl Counter for u64 {
+        fn increment(&mut self) {
+            *self += 1;
+        }
+
+        fn value(&self) -> u64 {
+            *self
+        }
+    }
+
+    impl Counter for u16 {
+        fn increment(&mut self) {
+            *self += 1;
+        }
+
+        fn value(&self) -> u64 {
+            u64::from(*self)
+        }
+    }
+
+    let mut arena = Hato::<dyn Counter>::default();
+
+    let xs = (0..5000_u64).map(|i| arena.push(i)).collect::<Vec<_>>();
+    let _ = arena.push(7_u16);
+
+    for x in xs.iter().step_by(3) {
+        arena.remove(*x);
+    }
+
+    let threads = NonZeroUsize::new(4).unwrap();
+    arena.par_for_each_mut(threads, Counter::increment);
+
+    // Each live element is visited exactly once
+    let sum = AtomicU64::new(0);
+    arena.par_for_each(threads, |x| {
+        let _ = sum.fetch_add(x.value(), Ordering::Relaxed);
+    });
+
+    let expected = (0..5000).filter(|i| i % 3 != 0).map(|i| i + 1).sum::<u64>() + 8;
+    assert_eq!(sum.into_inner(), expected);
+}
+
+#[test]
+fn clone_out() {
+    let mut arena = Hato::<dyn core::fmt::Debug>::default().with_spill_threshold(8);
+
+    let x = arena.push(3_u128);
+    let y = arena.push([0_u8; 0]);
+    let z = arena.push(5_u32);
+
+    let boxes = unsafe { [arena.clone_out(x), arena.clone_out(y), arena.clone_out(z)] };
+
+    // Copies are independent from the collection, and keep the layout of their type
+    arena.remove(x);
+    let _ = arena.push(4_u128);
+
+    assert_eq!(format!("{boxes:?}"), "[3, [], 5]");
+    assert_eq!((&raw const *boxes[0]).addr() % align_of::<u128>(), 0);
+}
+
+#[test]
+fn retain_of() {
+    let mut arena = Hato::<dyn core::fmt::Debug>::default();
+
+    let xs = (0..10_u32).map(|i| arena.push(i)).collect::<Vec<_>>();
+    let y = arena.push(4_u64);
+    let _ = arena.insert_named("eighth", xs[8]);
+
+    arena.retain_of::<u32>(|x| *x % 3 == 0);
+
+    let live = xs.iter().map(|x| arena.contains(*x)).collect::<Vec<_>>();
+    assert_eq!(live.iter().filter(|live| **live).count(), 4);
+    assert!(live[0] && live[3] && !live[4] && arena.contains(y));
+
+    // Names of removed elements are forgotten, and their slots reused
+    assert_eq!(arena.get_named("eighth"), None);
+    assert_eq!(arena.push(11_u32), xs[8]);
+}
+
+#[test]
+fn fallible() {
+    let mut arena = Hato::<dyn core::fmt::Debug>::default().with_spill_threshold(8);
+
+    let x = arena.try_push(3_u16).unwrap();
+    let y = arena.try_push(7_u128).unwrap();
+
+    assert!(arena.try_reserve_bytes::<u32>(64).is_ok());
+    assert_eq!(format!("{:?}", arena.checked_get(y)), "Ok(7)");
+
+    // Stale and forged handles are reported instead of panicking
+    assert_eq!(arena.checked_remove(x), Ok(()));
+    assert!(matches!(
+        arena.checked_get(x),
+        Err(crate::Error::InvalidHandle)
+    ));
+    assert!(matches!(
+        arena.checked_get_mut(x),
+        Err(crate::Error::InvalidHandle)
+    ));
+
+    let forged = crate::Handle {
+        index: 9,
+        offset: 0,
+    };
+    assert_eq!(
+        arena.checked_remove(forged),
+        Err(crate::Error::InvalidHandle)
+    );
+
+    // Impossible requests are surfaced as errors
+    let huge = arena.try_reserve_bytes::<u64>(usize::MAX);
+    assert!(matches!(huge, Err(crate::Error::CapacityOverflow)));
+}
+
+#[test]
+fn element_bytes() {
+    let mut arena = Hato::<dyn core::fmt::Debug>::default()
+        .with_cache_line_padding()
+        .with_spill_threshold(8);
+
+    let x = arena.push(7_u32);
+    let y = arena.push(9_u32);
+    let z = arena.push(5_u128);
+    let w = arena.push([0_u8; 0]);
+
+    // Spans stop at the end of elements, whatever the stride or storage of their arena
+    unsafe {
+        assert_eq!(arena.element_bytes(x), 7_u32.to_ne_bytes());
+        assert_eq!(arena.element_bytes(y), 9_u32.to_ne_bytes());
+        assert_eq!(arena.element_bytes(z), 5_u128.to_ne_bytes());
+        assert!(arena.element_bytes(w).is_empty());
+    }
+}
+
+#[test]
+fn compaction() {
+    use std::sync::{Arc, Mutex};
+
+    let mut arena = Hato::<dyn core::fmt::Debug>::default()
+        .with_tag_bytes(1)
+        .with_compaction_threshold(25);
+
+    let moved = Arc::new(Mutex::new(Vec::new()));
+    let observed = Arc::clone(&moved);
+    arena.on_remap(move |remap| observed.lock().unwrap().push(remap.clone()));
+
+    let xs = (0..8_u32).map(|i| arena.push(i)).collect::<Vec<_>>();
+    arena.tag_mut(xs[7])[0] = 7;
+    let _ = arena.insert_named("last", xs[7]);
+
+    // Staying under the threshold leaves elements in place
+    arena.remove(xs[1]);
+    arena.maintain();
+    assert!(moved.lock().unwrap().is_empty());
+
+    // Crossing it on removal compacts on the next push
+    arena.remove(xs[2]);
+    arena.remove(xs[4]);
+    let y = arena.push(8_u32);
+
+    let remaps = moved.lock().unwrap().clone();
+    assert_eq!(remaps.len(), 1);
+
+    let last = remaps[0].resolve(xs[7]);
+    assert_eq!(arena.get_named("last"), Some(last));
+    assert_eq!(arena.tag(last), [7]);
+
+    let mut handles = xs;
+    remaps[0].apply(&mut handles);
+    let live = [0, 3, 5, 6, 7].map(|i| format!("{:?}", unsafe { arena.get(handles[i]) }));
+    assert_eq!(live, ["0", "3", "5", "6", "7"]);
+
+    // Elements end up densely packed, the new one right after the others
+    assert_eq!(y.offset, 5 * 4);
+
+    // Fallible insertions compact first as well, so that their handle stays valid
+    arena.remove(handles[0]);
+    arena.remove(handles[3]);
+
+    let z = arena.try_push(9_u32).unwrap();
+    assert_eq!(moved.lock().unwrap().len(), 2);
+
+    let _ = arena.push(10_u32);
+    assert_eq!(moved.lock().unwrap().len(), 2);
+    assert_eq!(format!("{:?}", arena.checked_get(z)), "Ok(9)");
+}
+
+#[test]
+fn partitions_mut() {
+    trait Counter {
+        fn increment(&mut self);
+        fn value(&self) -> u64;
+    }
+
+    impl Counter for u16 {
+        fn increment(&mut self) {
+            *self += 1;
+        }
+
+        fn value(&self) -> u64 {
+            u64::from(*self)
+        }
+    }
+
+    impl Counter for u64 {
+        fn increment(&mut self) {
+            *self += 1;
+        }
+
+        fn value(&self) -> u64 {
+            *self
+        }
+    }
+
+    let mut arena = Hato::<dyn Counter + Send>::default().with_spill_threshold(4);
+
+    let xs = (0..10_u16).map(|i| arena.push(i)).collect::<Vec<_>>();
+    let ys = (0..5_u64).map(|i| arena.push(i)).collect::<Vec<_>>();
+    arena.remove(xs[3]);
+
+    let count = core::num::NonZeroUsize::new(4).unwrap();
+    let mut partitions = arena.partitions_mut(count);
+
+    // Slots are split evenly, each live element landing in exactly one partition
+    assert_eq!(partitions.len(), 4);
+    assert_eq!(
+        partitions
+            .iter()
+            .map(crate::HatoPartition::len)
+            .sum::<usize>(),
+        14
+    );
+    assert!(partitions[0].get_mut(xs[0]).is_some() && partitions[1].get_mut(xs[0]).is_none());
+    assert!(partitions[0].get_mut(xs[3]).is_none());
+
+    std::thread::scope(|scope| {
+        for mut partition in partitions {
+            drop(scope.spawn(move || partition.for_each_mut(Counter::increment)));
+        }
+    });
+
+    let live = xs.iter().chain(&ys).filter(|x| arena.contains(**x));
+    let sum = live.map(|x| unsafe { arena.get(*x) }.value()).sum::<u64>();
+    assert_eq!(sum, (1..=10).sum::<u64>() - 4 + (1..=5).sum::<u64>());
+}
+
+#[test]
+fn push_raw() {
+    use core::any::TypeId;
+
+    let mut arena = Hato::<dyn core::fmt::Debug>::default().with_spill_threshold(8);
+
+    arena.reserve_bytes::<u32>(0);
+    arena.reserve_bytes::<u128>(0);
+
+    let x = unsafe { arena.push_raw(&5_u32.to_ne_bytes(), TypeId::of::<u32>()) };
+    let y = unsafe { arena.push_raw(&9_u128.to_ne_bytes(), TypeId::of::<u128>()) };
+    let z = arena.push(6_u32);
+
+    // Raw insertions land in the arena of their type, next to typed ones
+    assert_eq!((x.index, z.index), (0, 0));
+    assert_eq!(
+        format!("{:?}", unsafe { [arena.get(x), arena.get(y)] }),
+        "[5, 9]"
+    );
+}
+
+#[test]
+fn double_ended_iterators() {
+    let mut arena = Hato::<dyn core::fmt::Debug>::default();
+
+    let xs = arena.absorb_vec(vec![1_u8, 2, 3, 4]).collect::<Vec<_>>();
+    let _ = arena.push(5_u16);
+    arena.remove(xs[1]);
+
+    // Ranges of handles walk back from their end
+    let range = arena.absorb_vec(vec![6_u32, 7, 8]);
+    let offsets = range.clone().rev().map(|x| x.offset).collect::<Vec<_>>();
+    assert_eq!((range.len(), offsets), (3, vec![8, 4, 0]));
+
+    // Live elements are counted exactly, skipping free slots from both ends
+    {
+        let mut ptrs = arena.iter_ptrs();
+        let mut bytes = ptrs.next().unwrap();
+        assert_eq!((ptrs.len(), bytes.len()), (2, 3));
+
+        let ends =
+            [bytes.next_back(), bytes.next()].map(|x| format!("{:?}", unsafe { &*x.unwrap() }));
+        assert_eq!((ends, bytes.len()), (["4".into(), "1".into()], 1));
+        assert_eq!(
+            format!("{:?}", unsafe { &*bytes.next_back().unwrap() }),
+            "3"
+        );
+        assert!(bytes.next().is_none());
+    }
+
+    // Lists are walked from their tail through links
+    let mut list = crate::HandleList::new();
+    list.push_back(&mut arena, xs[0]);
+    list.push_back(&mut arena, xs[2]);
+    list.push_back(&mut arena, xs[3]);
+
+    let mut handles = list.iter(&arena);
+    assert_eq!((handles.next_back(), handles.len()), (Some(xs[3]), 2));
+    assert!(handles.rev().eq([xs[2], xs[0]]));
+}
+
+#[test]
+fn for_each_dispatch() {
+    let mut arena = Hato::<dyn core::fmt::Debug>::default();
+
+    let _ = arena.push(1_u8);
+    let _ = arena.push(20_u16);
+    let _ = arena.push(300_i64);
+    let _ = arena.push(4_u8);
+
+    // Listed types are visited first, with their concrete type
+    let mut seen = Vec::new();
+    crate::for_each_dispatch!(arena, [u16, u8], |x| seen.push(format!("{x:?}")));
+    assert_eq!(seen, ["20", "1", "4", "300"]);
+
+    let mut sum = 0_u64;
+    crate::for_each_dispatch!(arena, [], |x| sum += format!("{x:?}").len() as u64);
+    assert_eq!(sum, 7);
+}
+
+#[cfg(target_pointer_width = "16")]
+#[test]
+fn index_u16() {
+    let mut arena = Hato::<dyn core::fmt::Debug>::default();
+
+    // Offsets of arenas are bounded by the index type, further elements landing in a new arena
+    let xs = (0..70_000_u32)
+        .map(|i| arena.push(i.to_le_bytes()[0]))
+        .collect::<Vec<_>>();
+
+    assert_eq!(size_of::<crate::Handle>(), 4);
+    assert_eq!((xs[65_534].index, xs[65_535].index), (0, 1));
+    assert_eq!(format!("{:?}", unsafe { arena.get(xs[69_999]) }), "111");
+}
+
+#[test]
+fn sequence() {
+    let mut sequence = crate::Sequence::<dyn core::any::Any>::default();
+
+    let x = sequence.push(1_u8);
+    let y = sequence.push(2_u64);
+    let _ = sequence.push([0_u8; 0]);
+    let _ = sequence.push(3_u16);
+
+    sequence.remove(x);
+    sequence.for_each_mut(|z| {
+        if let Some(z) = z.downcast_mut::<u16>() {
+            *z += 1;
+        }
+    });
+
+    assert_eq!(sequence.len(), 3);
+    assert_eq!(unsafe { sequence.get(y) }.downcast_ref::<u64>(), Some(&2));
+
+    // Elements come back in insertion order, whatever their type
+    let ids = sequence
+        .iter()
+        .map(core::any::Any::type_id)
+        .collect::<Vec<_>>();
+    let expected = [
+        core::any::TypeId::of::<u64>(),
+        core::any::TypeId::of::<[u8; 0]>(),
+        core::any::TypeId::of::<u16>(),
+    ];
+    assert_eq!(ids, expected);
+
+    assert_eq!(sequence.iter().len(), 3);
+
+    let z = sequence.handles().last().unwrap();
+    assert_eq!(unsafe { sequence.get(z) }.downcast_ref::<u16>(), Some(&4));
+
+    sequence.clear();
+    assert!(sequence.is_empty());
+    assert_eq!(sequence.iter().count(), 0);
+}
+
+#[test]
+fn size_classes() {
+    use core::any::{Any, TypeId};
+
+    let mut arena = Hato::<dyn Any>::default().with_size_classes();
+
+    let x = arena.push(1_u32);
+    let y = arena.push(2.5_f32);
+    let z = arena.push(3_i32);
+    let w = arena.push(4_u16);
+
+    // Types of the same size and alignment share a single arena
+    assert_eq!(arena.slack().len(), 2);
+    assert_eq!(unsafe { arena.get(y) }.downcast_ref::<f32>(), Some(&2.5));
+    assert_eq!(arena.get_mut(z).downcast_mut::<i32>(), Some(&mut 3));
+
+    let resolver = arena.resolver(x);
+    let element = unsafe { resolver.get(y) }.unwrap();
+    assert_eq!(element.downcast_ref::<f32>(), Some(&2.5));
+
+    let mut sum = 0;
+    arena.for_each_of::<u32>(|x| sum += x);
+    assert_eq!(sum, 1);
+
+    let groups = arena.group_handles_by_type(&[z, x, w, y]);
+    let ids = groups.iter().map(|(id, _)| *id).collect::<Vec<_>>();
+    let expected = [
+        TypeId::of::<i32>(),
+        TypeId::of::<u32>(),
+        TypeId::of::<f32>(),
+    ];
+    assert_eq!(ids[..3], expected);
+
+    let boxed = unsafe { arena.clone_out(y) };
+    assert_eq!(boxed.downcast_ref::<f32>(), Some(&2.5));
+
+    let raw = unsafe { arena.push_raw(&5_i32.to_ne_bytes(), TypeId::of::<i32>()) };
+    assert_eq!(unsafe { arena.get(raw) }.downcast_ref::<i32>(), Some(&5));
+
+    // Extraction leaves other types of the shared arena alone
+    assert_eq!(arena.extract_all::<i32>(), [3, 5]);
+    assert_eq!(unsafe { arena.get(x) }.downcast_ref::<u32>(), Some(&1));
+
+    let mut other = Hato::<dyn Any>::default();
+    let moved = arena.transfer(y, &mut other);
+    assert_eq!(
+        unsafe { other.get(moved) }.downcast_ref::<f32>(),
+        Some(&2.5)
+    );
+
+    let (compacted, remap) = arena.compact_clone();
+    let x = remap.resolve(x);
+    assert_eq!(unsafe { compacted.get(x) }.downcast_ref::<u32>(), Some(&1));
+
+    arena.retain_types(|id| id != TypeId::of::<u32>());
+    assert_eq!(arena.extract_all::<u32>(), []);
+    assert_eq!(arena.extract_all::<u16>(), [4]);
+}
+
+#[test]
+fn sort_arenas_by_key() {
+    use core::any::TypeId;
+
+    let mut arena = Hato::<dyn core::fmt::Debug>::default();
+
+    let x = arena.push(1_u8);
+    let y = arena.push(2_u16);
+    let z = arena.push(3_u32);
+
+    assert!(arena.insert_named("x", x).is_none());
+
+    let order = [TypeId::of::<u32>(), TypeId::of::<u8>(), TypeId::of::<u16>()];
+    let remap = arena.sort_arenas_by_key(|id| order.iter().position(|o| *o == id));
+
+    let mut seen = Vec::new();
+    arena.for_each_excluding(&[], |x| seen.push(format!("{x:?}")));
+    assert_eq!(seen, ["3", "1", "2"]);
+
+    let [x, y, z] = [x, y, z].map(|handle| remap.resolve(handle));
+    assert_eq!((x.index, y.index, z.index), (1, 2, 0));
+    assert_eq!(arena.get_named("x"), Some(x));
+
+    // Sorting again by the same key leaves everything in place
+    assert!(arena
+        .sort_arenas_by_key(|id| order.iter().position(|o| *o == id))
+        .is_empty());
+}
+
+#[test]
+fn forwarding() {
+    let mut arena = Hato::<dyn core::fmt::Debug>::default()
+        .with_compaction_threshold(25)
+        .with_forwarding();
+
+    let xs = (0..8_u32).map(|i| arena.push(i)).collect::<Vec<_>>();
+
+    for i in [1, 2, 4] {
+        arena.remove(xs[i]);
+    }
+
+    arena.maintain();
+
+    // Stale handles reach their element, and slots they point to are not reused
+    let live = [0, 3, 5, 6, 7].map(|i| format!("{:?}", unsafe { arena.get(xs[i]) }));
+    assert_eq!(live, ["0", "3", "5", "6", "7"]);
+    assert_eq!(arena.push(8_u32).offset, 8 * 4);
+
+    // Forwarding entries chain across successive compactions
+    for i in [0, 3, 5] {
+        arena.remove(xs[i]);
+    }
+
+    arena.maintain();
+    assert_eq!(format!("{:?}", unsafe { arena.get(xs[7]) }), "7");
+
+    arena.remove(xs[7]);
+    assert!(!arena.contains(xs[7]));
+
+    // Flushing releases the slots elements moved out of, along with stale handles
+    assert!(arena.contains(xs[6]));
+    arena.flush_forwarding();
+    assert!(!arena.contains(xs[6]));
+}
+
+#[test]
+fn forwarding_arenas() {
+    use core::any::{Any, TypeId};
+
+    let mut arena = Hato::<dyn Any>::default()
+        .with_compaction_threshold(25)
+        .with_forwarding();
+
+    let y = arena.push(100_u16);
+    let xs = (0..10_u32).map(|i| arena.push(i)).collect::<Vec<_>>();
+
+    for x in &xs[..4] {
+        arena.remove(*x);
+    }
+
+    arena.maintain();
+
+    // Resolvers and views follow forwarding entries too
+    let resolver = arena.resolver(xs[9]);
+    assert_eq!(
+        unsafe { resolver.get(xs[9]) }.unwrap().downcast_ref(),
+        Some(&9_u32)
+    );
+
+    let (element, others) = arena.split_one_mut(xs[9]);
+    assert_eq!(element.downcast_ref(), Some(&9_u32));
+    assert!(unsafe { others.get(xs[9]) }.is_none());
+    assert_eq!(
+        unsafe { others.get(y) }.unwrap().downcast_ref(),
+        Some(&100_u16)
+    );
+
+    let count = core::num::NonZeroUsize::new(1).unwrap();
+    let mut partitions = arena.partitions_mut(count);
+    assert_eq!(
+        partitions[0].get_mut(xs[9]).unwrap().downcast_ref(),
+        Some(&9_u32)
+    );
+    drop(partitions);
+
+    // Stale handles follow their arena to its new index
+    let order = [TypeId::of::<u32>(), TypeId::of::<u16>()];
+    let remap = arena.sort_arenas_by_key(|id| order.iter().position(|o| *o == id));
+
+    let x = remap.resolve(xs[9]);
+    assert_eq!(unsafe { arena.get(x) }.downcast_ref(), Some(&9_u32));
+    assert_eq!(
+        unsafe { arena.get(remap.resolve(y)) }.downcast_ref(),
+        Some(&100_u16)
+    );
+
+    // Removing elements drops the entries forwarding to them
+    arena.retain_of::<u32>(|x| *x != 9);
+    assert!(!arena.contains(x));
+
+    // Stale handles of stolen elements are part of the returned table
+    let stale = crate::Handle {
+        index: 0,
+        offset: xs[8].offset,
+    };
+
+    let mut thief = Hato::<dyn Any>::default();
+    let remap = thief.steal_type::<u32>(&mut arena);
+
+    assert_eq!(
+        unsafe { thief.get(remap.resolve(stale)) }.downcast_ref(),
+        Some(&8_u32)
+    );
+    assert!(!arena.contains(stale));
+}
+
+#[test]
+fn relocation() {
+    #[derive(Debug)]
+    #[allow(dead_code)] // Read through the `Debug` implementation only
+    struct Anchored(usize);
+
+    unsafe impl unscrupulous::Unscrupulous for Anchored {}
+
+    let mut arena = Hato::<dyn core::fmt::Debug>::default()
+        .with_compaction_threshold(25)
+        .with_forwarding();
+
+    arena.on_relocate::<Anchored>(|_, new, bytes| {
+        bytes.copy_from_slice(&(new as usize).to_ne_bytes());
+    });
+
+    let anchored = |arena: &Hato<dyn core::fmt::Debug>, x| {
+        let address = unsafe { arena.element_bytes(x) }.as_ptr() as usize;
+        format!("{:?}", unsafe { arena.get(x) }) == format!("Anchored({address})")
+    };
+
+    // Elements moved by growth of the buffer are reported, new ones are not
+    let xs = (0..64).map(|_| arena.push(Anchored(0))).collect::<Vec<_>>();
+    assert!(anchored(&arena, xs[0]));
+    assert!(!anchored(&arena, xs[63]));
+
+    // Elements moved by compaction are reported, from the slot they left
+    for x in &xs[..32] {
+        arena.remove(*x);
+    }
+
+    arena.maintain();
+    assert!(anchored(&arena, xs[63]));
+}
+
+#[test]
+fn cache() {
+    use std::sync::{Arc, Mutex};
+
+    let mut cache = HatoCache::<dyn core::fmt::Debug>::new(12);
+
+    let evicted = Arc::new(Mutex::new(Vec::new()));
+    let observed = Arc::clone(&evicted);
+    cache.on_evict(move |handle, bytes| observed.lock().unwrap().push((handle, bytes.to_vec())));
+
+    // Costly elements outlive cheap ones, even when used less recently
+    let x = cache.push_with_cost(1_u32, 10);
+    let y = cache.push(2_u32);
+    let z = cache.push(3_u32);
+
+    assert!(cache.get(y).is_some());
+    let w = cache.push(4_u32);
+
+    // Evicted elements are reported with the handle their owners hold
+    assert_eq!(
+        *evicted.lock().unwrap(),
+        [(z, 3_u32.to_ne_bytes().to_vec())]
+    );
+    assert_eq!(format!("{:?}", cache.peek(x)), "Some(1)");
+
+    // Handles of evicted elements stay invalid, although their slot was reused
+    assert!(cache.peek(z).is_none());
+    assert!(cache.remove(w));
+    assert!(!cache.remove(w));
+
+    // Shrinking the budget evicts right away, leaving elements that fit
+    cache.set_budget(4);
+    assert_eq!((cache.len(), cache.used_bytes()), (1, 4));
+    assert_eq!(format!("{:?}", cache.get(x)), "Some(1)");
+}
+
+#[cfg(feature = "compress")]
+#[test]
+fn tiered() {
+    let mut tiered = crate::HatoTiered::<dyn core::any::Any>::new(4096).with_level(1);
+
+    let xs = (0..64_u64)
+        .map(|i| tiered.push([i; 64]))
+        .collect::<Vec<_>>();
+    let y = tiered.push(7_u8);
+
+    // Only the most recent elements stay hot, the others compress well
+    assert_eq!((tiered.len(), tiered.hot_len()), (65, 8));
+    assert!(tiered.hot_bytes() <= 4096 && tiered.cold_bytes() < 57 * 512 / 8);
+    assert!(!tiered.is_hot(xs[0]) && tiered.is_hot(y));
+
+    for (i, x) in xs.iter().enumerate().rev() {
+        let value = tiered
+            .get_mut(*x)
+            .unwrap()
+            .downcast_mut::<[u64; 64]>()
+            .unwrap();
+
+        assert_eq!(value[0], i as u64);
+        value[1] = 1000;
+    }
+
+    // Modifications of promoted elements survive another round trip through the cold tier
+    tiered.set_resident(0);
+    assert_eq!(tiered.hot_len(), 1);
+
+    let value = tiered.get(xs[63]).unwrap().downcast_ref::<[u64; 64]>();
+    assert_eq!(value.map(|value| value[1]), Some(1000));
+
+    // Removed elements are detected, even once their slot is reused
+    assert!(tiered.remove(xs[5]) && !tiered.remove(xs[5]));
+
+    let z = tiered.push(9_u8);
+
+    assert!(tiered.get(xs[5]).is_none());
+    assert_eq!(tiered.get(z).unwrap().downcast_ref(), Some(&9_u8));
+    assert_eq!(tiered.len(), 65);
+}
+
+#[test]
+fn paged() {
+    use std::io::Cursor;
+
+    let file = Cursor::new(Vec::new());
+    let mut paged = crate::HatoPaged::<dyn core::any::Any, _>::new(file, 1024).with_pinned::<u8>();
+
+    let xs = (0..16_u64)
+        .map(|i| paged.push([i; 16]).unwrap())
+        .collect::<Vec<_>>();
+    let ys = (0..16_u32)
+        .map(|i| paged.push([i; 16]).unwrap())
+        .collect::<Vec<_>>();
+    let z = paged.push(7_u8).unwrap();
+
+    // Arenas of older elements were paged out, while pinned ones stay in memory
+    assert_eq!(paged.len(), 33);
+    assert!(!paged.is_resident(xs[0]) && !paged.is_resident(ys[0]) && paged.is_resident(z));
+    assert_eq!(
+        (paged.resident_bytes(), paged.paged_bytes()),
+        (1, 2048 + 1024)
+    );
+
+    for (i, x) in xs.iter().enumerate() {
+        let value = paged
+            .get_mut(*x)
+            .unwrap()
+            .downcast_mut::<[u64; 16]>()
+            .unwrap();
+
+        assert_eq!(value[0], i as u64);
+        value[1] = 1000;
+    }
+
+    assert!(paged.is_resident(xs[0]) && !paged.is_resident(ys[0]));
+
+    // Arenas only read since they came back are not written again
+    let value = unsafe { paged.get(ys[3]) }
+        .unwrap()
+        .downcast_ref::<[u32; 16]>();
+    assert_eq!(value.map(|value| value[0]), Some(3));
+
+    let _ = unsafe { paged.get(xs[0]) }.unwrap();
+    paged.remove(ys[0]).unwrap();
+    paged.set_resident(0).unwrap();
+
+    let (hato, file) = paged.into_inner().unwrap();
+
+    assert_eq!(file.get_ref().len(), 2048 + 1024);
+    assert_eq!(hato.len(), 32);
+    assert_eq!(
+        unsafe { hato.get(xs[15]) }
+            .downcast_ref::<[u64; 16]>()
+            .map(|x| x[1]),
+        Some(1000)
+    );
+}
+
+#[test]
+fn iter_since() {
+    let mut arena = Hato::<dyn core::fmt::Debug>::default().with_tombstones();
+
+    let x = arena.push(1_u8);
+    let first = arena.checkpoint();
+
+    let y = arena.push(2_u8);
+    let second = arena.checkpoint();
+
+    // Arenas created after the checkpoint are scanned from their start
+    let z = arena.push(3_u16);
+    arena.remove(x);
+    let w = arena.push(4_u8);
+
+    let since = |checkpoint| {
+        let new = arena.iter_since(checkpoint);
+        new.map(|(handle, x)| (handle, format!("{x:?}")))
+            .collect::<Vec<_>>()
+    };
+
+    assert_eq!(
+        since(&first),
+        [(y, "2".into()), (w, "4".into()), (z, "3".into())]
+    );
+    assert_eq!(since(&second), [(w, "4".into()), (z, "3".into())]);
+    assert_eq!(arena.iter_since(&first).len(), 3);
+    assert_eq!(since(&arena.checkpoint()), []);
+}
+
+#[test]
+fn split_by() {
+    use core::any::TypeId;
+
+    let mut arena = Hato::<dyn core::fmt::Debug>::default().with_tag_bytes(1);
+
+    let x = arena.push(1_u8);
+    let y = arena.push(2_u16);
+    let z = arena.push(3_u8);
+
+    arena.tag_mut(z)[0] = 7;
+    let _ = arena.insert_named("z", z);
+
+    let ((small, smalls), (large, larges)) = arena.split_by_type(|id| id == TypeId::of::<u8>());
+
+    // Every element goes to exactly one side, with its tag and name
+    assert_eq!((smalls.len(), larges.len()), (2, 1));
+    assert!(larges.get(x).is_none() && smalls.get(y).is_none());
+
+    assert_eq!(
+        format!("{:?}", unsafe { small.get(smalls.resolve(z)) }),
+        "3"
+    );
+    assert_eq!(
+        format!("{:?}", unsafe { large.get(larges.resolve(y)) }),
+        "2"
+    );
+
+    assert_eq!(small.tag(smalls.resolve(z)), [7]);
+    assert_eq!(small.get_named("z"), smalls.get(z));
+    assert_eq!(large.get_named("z"), None);
+
+    // Predicates over elements see them through the trait
+    let ((ones, found), (_, rest)) = arena.split_by(|x| format!("{x:?}") == "1");
+
+    assert_eq!((found.len(), rest.len()), (1, 2));
+    assert_eq!(format!("{:?}", unsafe { ones.get(found.resolve(x)) }), "1");
+}
+
+#[test]
+fn try_for_each() {
+    use core::ops::ControlFlow;
+
+    let mut arena = Hato::<dyn core::fmt::Debug>::default();
+
+    let _ = arena.push(1_u8);
+    let x = arena.push(2_u16);
+    let _ = arena.push(3_u16);
+
+    // Visitation stops at the first break, leaving later elements untouched
+    let mut seen = Vec::new();
+    let found = arena.try_for_each(|handle, element| {
+        seen.push(format!("{element:?}"));
+        if handle == x {
+            ControlFlow::Break(handle)
+        } else {
+            ControlFlow::Continue(())
+        }
+    });
+
+    assert_eq!(found, ControlFlow::Break(x));
+    assert_eq!(seen, ["1", "2"]);
+
+    let mut visited = 0;
+    let done = arena.try_for_each_mut(|_, _| {
+        visited += 1;
+        ControlFlow::<()>::Continue(())
+    });
+
+    assert_eq!((done, visited), (ControlFlow::Continue(()), 3));
+}
+
+#[test]
+fn steal_type() {
+    let mut arena = Hato::<dyn core::fmt::Debug>::default();
+    let mut other = Hato::<dyn core::fmt::Debug>::default().with_tag_bytes(1);
+
+    let _ = arena.push(1_u8);
+
+    let x = other.push(2_u8);
+    let y = other.push(3_u16);
+    let z = other.push(4_u8);
+
+    other.tag_mut(z)[0] = 7;
+    other.remove(x);
+
+    // The whole arena moves, keeping offsets and tags of its elements
+    let remap = arena.steal_type::<u8>(&mut other);
+
+    assert_eq!(remap.len(), 1);
+    assert_eq!(remap.resolve(z).offset, z.offset);
+    assert_eq!(arena.tag(remap.resolve(z)), [7]);
+    assert_eq!(format!("{:?}", unsafe { arena.get(remap.resolve(z)) }), "4");
+
+    // Other handles of the original stay valid
+    assert!(!other.contains(z));
+    assert_eq!(format!("{:?}", unsafe { other.get(y) }), "3");
+
+    // Elements sharing an arena with other types are copied out instead
+    let mut shared = Hato::<dyn core::fmt::Debug>::default().with_size_classes();
+
+    let unsigned = shared.push(6_u16);
+    let signed = shared.push(7_i16);
+
+    let remap = arena.steal_type::<u16>(&mut shared);
+
+    assert_eq!(
+        format!("{:?}", unsafe { arena.get(remap.resolve(unsigned)) }),
+        "6"
+    );
+    assert_eq!(format!("{:?}", unsafe { shared.get(signed) }), "7");
+}
+
+#[test]
+fn collect_garbage() {
+    trait Object {
+        fn references(&self) -> &[crate::Handle];
+    }
+
+    struct Node([crate::Handle; 1]);
+
+    impl Object for Node {
+        fn references(&self) -> &[crate::Handle] {
+            &self.0
+        }
+    }
+
+    unsafe impl unscrupulous::Unscrupulous for Node {}
+
+    let mut heap = Hato::<dyn Object>::default();
+
+    // Nodes point to the next slot, closing cycles of two elements
+    let next = |i: usize| crate::Handle {
+        index: 0,
+        offset: (i * size_of::<Node>()).try_into().unwrap(),
+    };
+
+    let nodes = [1, 0, 3, 2, 5, 4].map(|i| heap.push(Node([next(i)])));
+    let _ = heap.insert_named("garbage", nodes[2]);
+
+    heap.remove(nodes[4]);
+
+    // Cycles are kept whole when reachable, and freed whole otherwise
+    let freed = heap.collect_garbage([nodes[0], nodes[4]], |object, visit| {
+        object.references().iter().copied().for_each(visit);
+    });
+
+    assert_eq!(freed, 3);
+    assert_eq!(heap.handles().collect::<Vec<_>>(), nodes[..2]);
+    assert_eq!(heap.get_named("garbage"), None);
+}
+
+#[test]
+fn quota() {
+    let mut arena = Hato::<dyn core::fmt::Debug>::default().with_size_classes();
+
+    arena.set_quota::<u16>(Some(2));
+    arena.set_byte_quota::<u32>(Some(5));
+
+    let xs = [arena.push(1_u16), arena.push(2_u16)];
+    let _ = arena.push(3_i16);
+    let _ = arena.push(4_u32);
+
+    // Quotas count elements of their own type only, even in shared arenas
+    assert_eq!(arena.try_push(5_u16), Err(crate::Error::QuotaExceeded));
+    assert_eq!(arena.try_push(6_u32), Err(crate::Error::QuotaExceeded));
+    assert!(arena.try_push(7_i16).is_ok());
+    assert_eq!(arena.count_of::<u16>(), 2);
+
+    arena.remove(xs[0]);
+    assert!(arena.try_push(8_u16).is_ok());
+
+    // Lifted quotas no longer limit insertions
+    arena.set_quota::<u16>(None);
+    let _ = arena.push(9_u16);
+    assert_eq!(arena.count_of::<u16>(), 3);
+}
+
+#[test]
+fn diagnose() {
+    use core::any::TypeId;
+
+    let mut arena = Hato::<dyn core::fmt::Debug>::default()
+        .with_diagnostics()
+        .with_compaction_threshold(30)
+        .with_forwarding();
+
+    let xs = [1_u8, 2, 3, 4].map(|x| arena.push(x));
+    let y = arena.push(5_u16);
+
+    arena.remove(xs[1]);
+
+    let report = arena.diagnose([xs[0], xs[3]]);
+
+    // Orphans are reported in handle order, with increasing ages here
+    let orphans = report.orphans.iter().map(|o| o.handle).collect::<Vec<_>>();
+    assert_eq!(orphans, [xs[2], y]);
+    assert!(report.orphans[0].age < report.orphans[1].age);
+
+    assert_eq!(report.types[0].type_id, TypeId::of::<u8>());
+    assert_eq!((report.types[0].live, report.types[0].free), (3, 1));
+    assert_eq!(report.types[0].oldest_orphan, report.orphans[0].age);
+    assert_eq!(report.types[1].orphans, 1);
+
+    // Compaction carries ages along with elements, and stale handles are forwarded
+    let age = report.orphans[0].age;
+
+    arena.remove(xs[0]);
+    arena.maintain();
+
+    let report = arena.diagnose([xs[3], y]);
+    assert_eq!(report.orphans.len(), 1);
+    assert_eq!(report.orphans[0].age, age);
+
+    // Collections without diagnostics still report orphans, without their age
+    let mut plain = Hato::<dyn core::fmt::Debug>::default();
+    let _ = plain.push(6_u8);
+    assert_eq!(plain.diagnose([]).orphans[0].age, None);
+}
+
+#[test]
+fn push_copy() {
+    #[derive(Clone, Copy, Debug)]
+    #[allow(dead_code)] // Read through the `Debug` implementation only
+    struct Prototype([u32; 4]);
+
+    unsafe impl unscrupulous::Unscrupulous for Prototype {}
+
+    let mut arena = Hato::<dyn core::fmt::Debug>::default();
+    let prototype = Prototype([1, 2, 3, 4]);
+
+    let xs = [(); 3].map(|()| arena.push_copy(&prototype));
+    let y = arena.push(prototype);
+
+    // Copies are independent elements, sharing the arena of moved values
+    arena.remove(xs[1]);
+    assert_eq!(arena.push_copy(&prototype), xs[1]);
+    assert_eq!(y.index, xs[0].index);
+
+    for x in xs {
+        assert_eq!(
+            format!("{:?}", unsafe { arena.get(x) }),
+            format!("{prototype:?}")
+        );
+    }
+}
+
+#[test]
+fn inline_storage() {
+    use crate::{InlineStorage, Storage};
+
+    #[derive(Clone, Copy, Debug)]
+    #[repr(align(128))]
+    #[allow(dead_code)] // Read through the `Debug` implementation only
+    struct Wide(u8);
+
+    unsafe impl unscrupulous::Unscrupulous for Wide {}
+
+    let mut arena = Hato::<dyn core::fmt::Debug, InlineStorage<16>>::default();
+
+    let xs = (0..4_u32).map(|i| arena.push(i)).collect::<Vec<_>>();
+    let bytes = &arena.arenas[0].bytes;
+
+    // Elements fitting in the inline bytes are stored within the directory
+    let directory = arena.arenas.as_ptr_range();
+    assert!(directory.contains(&bytes.as_ptr().cast()));
+    assert_eq!(bytes.capacity(), 16);
+
+    // Growing past them moves elements to the heap, contents included
+    let y = arena.push(4_u32);
+    let clone = arena.clone();
+
+    assert!(!directory.contains(&arena.arenas[0].bytes.as_ptr().cast()));
+
+    for (i, x) in xs.into_iter().chain([y]).enumerate() {
+        assert_eq!(format!("{:?}", unsafe { clone.get(x) }), i.to_string());
+    }
+
+    // Over-aligned elements start on the heap, at their alignment
+    let z = arena.push(Wide(5));
+    let address = unsafe { arena.element_bytes(z) }.as_ptr() as usize;
+    assert_eq!(address % align_of::<Wide>(), 0);
+}
+
+#[cfg(all(feature = "protect", unix))]
+#[test]
+fn protected_storage() {
+    use crate::{Error, ProtectedStorage, Storage};
+
+    let mut arena = Hato::<dyn core::any::Any, ProtectedStorage>::default();
+
+    let xs = (0..4_u64).map(|i| arena.push(i)).collect::<Vec<_>>();
+    let bytes = &arena.arenas[0].bytes;
+
+    // Buffers span whole pages, starting on their boundary
+    assert_eq!(bytes.as_ptr() as usize % bytes.capacity(), 0);
+
+    let sealed = arena.seal();
+    assert!(sealed.hato().arenas[0].bytes.is_sealed());
+
+    // Clones of sealed collections stay sealed, with the same elements
+    let clone = sealed.clone();
+    assert!(clone.hato().arenas[0].bytes.is_sealed());
+
+    for (i, x) in xs.iter().enumerate() {
+        assert_eq!(unsafe { clone.get(*x) }.downcast_ref(), Some(&(i as u64)));
+    }
+
+    // Sealed buffers refuse to grow instead of panicking
+    let mut bytes = clone.hato().arenas[0].bytes.clone();
+    assert_eq!(bytes.try_grow(1 << 20), Err(Error::Sealed));
+
+    let mut arena = sealed.unseal();
+    assert!(!arena.arenas[0].bytes.is_sealed());
+
+    *arena.get_mut(xs[1]).downcast_mut::<u64>().unwrap() = 7;
+
+    // Unsealed buffers grow to more pages, moving elements over
+    let ys = (0..1024_u64).map(|i| arena.push(i)).collect::<Vec<_>>();
+
+    assert_eq!(unsafe { arena.get(xs[1]) }.downcast_ref(), Some(&7_u64));
+    assert_eq!(
+        unsafe { arena.get(ys[1023]) }.downcast_ref(),
+        Some(&1023_u64)
+    );
+}
+
+#[test]
+fn hato_pool() {
+    use crate::{HatoPool, Storage};
+
+    let pool = HatoPool::default();
+    let mut arena = Hato::<dyn core::fmt::Debug>::default().with_pool(&pool);
+
+    for i in 0..100_u32 {
+        let _ = arena.push(i);
+    }
+
+    let _ = arena.push(1_u8);
+    let capacity = arena.arenas[0].bytes.capacity();
+
+    // Clones share the pool, and hand their own buffers back
+    pool.recycle(arena.clone());
+    pool.recycle(arena);
+    assert_eq!(pool.len(), 4);
+
+    // New arenas take the largest buffer aligned enough for their type
+    let mut arena = Hato::<dyn core::fmt::Debug>::default().with_pool(&pool.clone());
+    let x = arena.push(7_u64);
+
+    assert_eq!(arena.arenas[0].bytes.capacity(), capacity);
+    assert!(arena.arenas[0].bytes.align() >= align_of::<u64>());
+    assert_eq!(format!("{:?}", unsafe { arena.get(x) }), "7");
+    assert_eq!(pool.len(), 3);
+}
+
+#[test]
+fn global_hato() {
+    use crate::GlobalHato;
+
+    static REGISTRY: GlobalHato<dyn core::fmt::Debug + Send + Sync> = GlobalHato::new();
+
+    // Threads insert concurrently, each getting the handle of its own element
+    let handles = std::thread::scope(|scope| {
+        let workers = [0, 1, 2, 3_u32].map(|i| scope.spawn(move || REGISTRY.push(i)));
+        workers.map(|worker| worker.join().unwrap())
+    });
+
+    assert_eq!(REGISTRY.with(|hato| hato.handles().count()), 4);
+
+    for (i, x) in handles.iter().enumerate() {
+        assert_eq!(REGISTRY.get(*x, |x| format!("{x:?}")), Ok(i.to_string()));
+    }
+
+    assert!(REGISTRY.remove(handles[0]));
+    assert!(!REGISTRY.remove(handles[0]));
+    assert_eq!(
+        REGISTRY.get(handles[0], |_| ()),
+        Err(crate::Error::InvalidHandle)
+    );
+}
+
+#[test]
+fn apply_deferred() {
+    let mut arena = Hato::<dyn core::fmt::Debug>::default();
+
+    let xs = (0..6_u32).map(|i| arena.push(i)).collect::<Vec<_>>();
+    let _ = arena.insert_named("x", xs[1]);
+
+    // Duplicates and removed elements are only counted once, when live
+    for x in [xs[1], xs[3], xs[1], xs[5]] {
+        arena.defer_remove(x);
+    }
+
+    arena.remove(xs[5]);
+
+    // Clones carry pending removals along
+    let mut clone = arena.clone();
+
+    assert!(arena.contains(xs[1]));
+    assert_eq!(arena.apply_deferred(), 2);
+    assert_eq!(arena.apply_deferred(), 0);
+
+    assert_eq!(arena.handles().collect::<Vec<_>>(), [xs[0], xs[2], xs[4]]);
+    assert_eq!(arena.get_named("x"), None);
+
+    assert_eq!(clone.apply_deferred(), 2);
+}
+
+#[test]
+fn clone_empty() {
+    let mut arena = Hato::<dyn core::fmt::Debug>::default()
+        .with_size_classes()
+        .with_tag_bytes(2);
+
+    let xs = (0..40_u32).map(|i| arena.push(i)).collect::<Vec<_>>();
+    let y = arena.push(5_i32);
+    let z = arena.push([1_u64; 8]);
+
+    arena.remove(xs[0]);
+
+    let mut empty = arena.clone_empty();
+    assert!(empty.handles().next().is_none());
+
+    // Arenas keep their capacity, and admit the same types
+    for (old, new) in arena.arenas.iter().zip(&empty.arenas) {
+        assert!(new.bytes.capacity() >= old.bytes.capacity());
+        assert_eq!(new.types, old.types);
+    }
+
+    // Replaying insertions yields the same handles, with cleared tags
+    assert_eq!(empty.push([2_u64; 8]), z);
+    assert_eq!(empty.push(6_u32), xs[0]);
+    assert_eq!(empty.push(7_i32), xs[1]);
+    assert_eq!(empty.tag(xs[1]), [0, 0]);
+    assert_eq!(empty.push(8_i32).index, y.index);
+}
+
+#[test]
+fn hato_commands() {
+    let mut arena = Hato::<dyn core::fmt::Debug>::default();
+    let xs = (0..3_u8).map(|i| arena.push(i)).collect::<Vec<_>>();
+
+    let mut commands = crate::HatoCommands::default();
+
+    // Commands are recorded while the collection is borrowed
+    for (value, _) in (10_u16..).zip(arena.handles()) {
+        let _ = commands.push(value);
+    }
+
+    commands.remove(xs[0]);
+    commands.remove(xs[0]);
+    assert_eq!(commands.push(20_u8), 3);
+    assert_eq!(commands.len(), 6);
+
+    // Removals take effect in order, so the last push reuses the freed slot
+    let handles = commands.apply(&mut arena);
+
+    assert!(commands.is_empty());
+    assert_eq!(handles.len(), 4);
+    assert_eq!(handles[3], xs[0]);
+
+    let values = handles
+        .iter()
+        .map(|h| format!("{:?}", unsafe { arena.get(*h) }));
+    assert_eq!(values.collect::<Vec<_>>(), ["10", "11", "12", "20"]);
+}
+
+#[test]
+fn owned_handle() {
+    let mut arena = Hato::<dyn core::fmt::Debug>::default();
+
+    let x = arena.push_owned(1_u32);
+    let y = arena.push_owned(2_u32);
+    let z = arena.push(3_u32);
+    let z = arena.own(z);
+
+    let handles = [x.handle(), y.handle(), z.handle()];
+
+    // Guards may be dropped while the collection is borrowed
+    let borrowed = &arena;
+    drop(x);
+    assert!(borrowed.contains(handles[0]));
+
+    y.forget();
+    assert_eq!(arena.apply_deferred(), 1);
+
+    assert!(!arena.contains(handles[0]));
+    assert!(arena.contains(handles[1]) && arena.contains(handles[2]));
+
+    // Guards stay tied to the collection they came from, not to its clones
+    let clone = arena.clone();
+    drop(z);
+    assert_eq!(arena.apply_deferred(), 1);
+    assert!(clone.contains(handles[2]));
+}
+
+#[test]
+fn query() {
+    let mut arena = Hato::<dyn core::fmt::Debug>::default().with_size_classes();
+
+    let a = arena.push(1_u16);
+    let b = arena.push(2_i16);
+    let c = arena.push(3_u64);
+    let d = arena.push(4_u16);
+    let _ = arena.push('e');
+
+    arena.remove(d);
+
+    // Elements of other types sharing an arena are skipped
+    let found = arena
+        .query::<(u16, u64)>()
+        .map(|(handle, x)| (handle, format!("{x:?}")));
+    assert_eq!(
+        found.collect::<Vec<_>>(),
+        [(a, "1".to_owned()), (c, "3".to_owned())]
+    );
+
+    assert_eq!(
+        arena.query::<(i16,)>().map(|(h, _)| h).collect::<Vec<_>>(),
+        [b]
+    );
+    assert_eq!(arena.query::<(u32,)>().count(), 0);
+
+    // Sizes come from counts of live elements, and listing a type twice yields it once
+    assert_eq!(arena.query::<(u16, u64, u16)>().len(), 2);
+    assert_eq!(
+        arena.query::<(u16, u64)>().next_back().map(|(h, _)| h),
+        Some(c)
+    );
+}
+
+#[test]
+fn iter() {
+    let mut arena = Hato::<dyn core::fmt::Debug>::default().with_size_classes();
+
+    let xs = (0..4_u32).map(|i| arena.push(i)).collect::<Vec<_>>();
+    let y = arena.push(4_i32);
+    let z = arena.push(5_u8);
+
+    arena.remove(xs[1]);
+
+    let seen = arena.iter().map(|(h, x)| (h, format!("{x:?}")));
+    let expected = [(xs[0], "0"), (xs[2], "2"), (xs[3], "3"), (y, "4"), (z, "5")];
+
+    assert!(seen.eq(expected.map(|(h, x)| (h, x.to_owned()))));
+    assert_eq!(arena.iter().next_back().map(|(h, _)| h), Some(z));
+    assert!(arena.iter().map(|(h, _)| h).eq(arena.handles()));
+
+    // Sizes come from counts of live elements, and shrink as elements are yielded
+    let mut elements = arena.iter();
+    let _ = elements.next_back();
+
+    assert_eq!((arena.iter().len(), elements.len()), (5, 4));
+    assert_eq!(arena.handles().len(), 5);
+}
+
+#[test]
+fn iter_mut() {
+    use core::any::Any;
+
+    let mut arena = Hato::<dyn Any>::default()
+        .with_size_classes()
+        .with_spill_threshold(256);
+
+    let xs = (0..4_u32).map(|i| arena.push(i)).collect::<Vec<_>>();
+    let y = arena.push(4_i32);
+    let z = arena.push([5_u8; 300]);
+
+    arena.remove(xs[1]);
+
+    // References to distinct elements, across arenas, are held together
+    assert_eq!(arena.iter_mut().len(), 5);
+
+    let mut elements = arena.iter_mut().collect::<Vec<_>>();
+    assert_eq!(elements.len(), 5);
+
+    for (_, x) in &mut elements {
+        if let Some(x) = x.downcast_mut::<u32>() {
+            *x *= 10;
+        } else if let Some(x) = x.downcast_mut::<[u8; 300]>() {
+            x[299] = 6;
+        }
+    }
+
+    let (_, last) = arena.iter_mut().next_back().unwrap();
+    *last
+        .downcast_mut::<[u8; 300]>()
+        .unwrap()
+        .first_mut()
+        .unwrap() = 7;
+
+    let get = |handle| unsafe { arena.get(handle) };
+
+    assert_eq!(get(xs[3]).downcast_ref::<u32>(), Some(&30));
+    assert_eq!(get(y).downcast_ref::<i32>(), Some(&4));
+    assert_eq!(
+        get(z).downcast_ref::<[u8; 300]>().map(|x| (x[0], x[299])),
+        Some((7, 6))
+    );
+
+    let handles = arena.handles().collect::<Vec<_>>();
+    assert!(arena.iter_mut().map(|(h, _)| h).eq(handles));
+}
+
+#[test]
+fn versioned() {
+    use core::any::Any;
+
+    let mut arena = crate::HatoVersioned::<dyn Any>::default();
+
+    let x = arena.push(1_u32);
+    let y = arena.push(2_u32);
+
+    assert!(arena.remove(x));
+    assert!(!arena.remove(x));
+
+    // Slot of `x` is handed to `z`, one generation later
+    let z = arena.push(3_u32);
+    assert_eq!(z.handle(), x.handle());
+    assert_eq!((x.generation(), z.generation()), (0, 1));
+
+    assert!(!arena.contains(x) && arena.contains(z));
+    assert!(arena.get(x).is_none() && arena.get_mut(x).is_none());
+
+    *arena.get_mut(z).unwrap().downcast_mut::<u32>().unwrap() = 4;
+    assert_eq!(arena.get(z).unwrap().downcast_ref::<u32>(), Some(&4));
+
+    // Removing through a stale handle leaves the new element in place
+    assert!(!arena.remove(x));
+    assert!(arena.remove(z) && arena.contains(y));
+    assert_eq!(arena.hato().handles().count(), 1);
+}
+
+#[test]
+fn typed_handle() {
+    let mut arena = Hato::<dyn core::fmt::Debug>::default().with_size_classes();
+
+    let x = arena.push_typed([1_u16, 2]);
+    let y = arena.push_typed(3_u32);
+
+    // Types sharing an arena are told apart through their handle
+    unsafe { arena.get_typed_mut(x)[1] = 5 };
+    *unsafe { arena.get_typed_mut(y) } *= 2;
+
+    assert_eq!(unsafe { arena.get_typed(x) }, &[1, 5]);
+    assert_eq!(unsafe { arena.get_typed(y) }, &6);
+
+    let handle = crate::Handle::from(y);
+    assert_eq!(handle, y.handle());
+    assert_eq!(format!("{:?}", unsafe { arena.get(handle) }), "6");
+
+    arena.remove(x.into());
+    assert!(!arena.contains(x.handle()) && arena.contains(y.handle()));
+}
+
+#[test]
+fn hato_drop() {
+    use core::sync::atomic::{AtomicUsize, Ordering};
+
+    static DROPS: AtomicUsize = AtomicUsize::new(0);
+
+    #[derive(Debug)]
+    struct Counted(u8);
+
+    impl Drop for Counted {
+        fn drop(&mut self) {
+            let _ = DROPS.fetch_add(usize::from(self.0), Ordering::Relaxed);
+        }
+    }
+
+    unsafe impl unscrupulous::Unscrupulous for Counted {}
+
+    #[derive(Debug)]
+    struct Panicky;
+
+    impl Drop for Panicky {
+        fn drop(&mut self) {
+            panic!("destructor panicked");
+        }
+    }
+
+    unsafe impl unscrupulous::Unscrupulous for Panicky {}
+
+    let drops = || DROPS.load(Ordering::Relaxed);
+
+    let mut arena = crate::HatoDrop::<dyn core::fmt::Debug>::default();
+
+    let xs = (0..4).map(|_| arena.push(Counted(1))).collect::<Vec<_>>();
+    let y = arena.push([Counted(10), Counted(10)]);
+
+    // Removing twice only drops the element once
+    assert!(arena.remove(xs[0]) && !arena.remove(xs[0]));
+    assert!(arena.remove(y));
+    assert_eq!(drops(), 21);
+
+    arena.clear();
+    assert_eq!(drops(), 24);
+    assert!(arena.hato().handles().next().is_none());
+
+    // Collection is usable after clearing, and drops its elements along with itself
+    let z = arena.push(Counted(100));
+    assert_eq!(format!("{:?}", unsafe { arena.get(z) }), "Counted(100)");
+
+    drop(arena);
+    assert_eq!(drops(), 124);
+
+    let mut arena = crate::HatoDrop::<dyn core::fmt::Debug>::default();
+    let x = arena.push(Panicky);
+
+    // Elements with a panicking destructor are removed anyway, and never dropped twice
+    let removal = std::panic::catch_unwind(core::panic::AssertUnwindSafe(|| arena.remove(x)));
+    assert!(removal.is_err() && !arena.hato().contains(x));
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn snapshot() {
+    use core::any::Any;
+
+    const fn serializable<T: serde::Serialize + serde::de::DeserializeOwned>(_: &T) {}
+
+    let build = || {
+        Hato::<dyn Any>::default()
+            .with_size_classes()
+            .with_spill_threshold(16)
+    };
+
+    let mut arena = build();
+
+    let xs = (0..4_u32).map(|i| arena.push(i)).collect::<Vec<_>>();
+    let y = arena.push(5_i32);
+    let z = arena.push([6_u8; 32]);
+
+    arena.remove(xs[1]);
+
+    // Snapshots need every admitted type to be registered
+    let types = crate::Types::default().register::<u32>().register::<i32>();
+    assert!(arena.snapshot(&types).is_none());
+
+    let types = types.register::<[u8; 32]>();
+    let snapshot = arena.snapshot(&types).unwrap();
+
+    // Snapshots go through any data format
+    serializable(&snapshot);
+
+    let mut restored = build();
+    unsafe { snapshot.restore(&mut restored, &types) }.unwrap();
+
+    assert!(restored.handles().eq(arena.handles()));
+    assert_eq!(unsafe { restored.get(y) }.downcast_ref::<i32>(), Some(&5));
+    assert_eq!(unsafe { restored.get(z) }.downcast_ref(), Some(&[6_u8; 32]));
+
+    // Free slots are handed out again, as in the original collection
+    assert_eq!(restored.push(7_u32), arena.push(7_u32));
+
+    // Restores need an empty collection, laid out the same way
+    let error = unsafe { snapshot.restore(&mut restored, &types) };
+    assert!(matches!(error, Err(crate::SnapshotError::NotEmpty)));
+
+    let mut padded = build().with_cache_line_padding();
+    let error = unsafe { snapshot.restore(&mut padded, &types) };
+    assert!(matches!(
+        error,
+        Err(crate::SnapshotError::Layout { arena: 0, .. })
+    ));
+
+    // Arenas past a mismatched one are not restored either
+    let mut unspilled = Hato::<dyn Any>::default().with_size_classes();
+    let error = unsafe { snapshot.restore(&mut unspilled, &types) };
+
+    assert!(matches!(error, Err(crate::SnapshotError::Layout { arena, .. }) if arena > 0));
+    assert!(unspilled.arenas.is_empty());
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn snapshot_evolution() {
+    use core::any::Any;
+
+    let mut arena = Hato::<dyn Any>::default();
+
+    let x = arena.push(1_u32);
+    let y = arena.push(2_i64);
+    let z = arena.push(3_u32);
+    let w = arena.push(4_u8);
+
+    let types = crate::Types::default()
+        .register_as::<u32>("id")
+        .register_as::<i64>("position")
+        .register_as::<u8>("flag")
+        .with_version(1);
+
+    let snapshot = arena.snapshot(&types).unwrap();
+    assert_eq!(snapshot.version(), 1);
+
+    // Later versions rename a type and drop another
+    let types = crate::Types::default()
+        .register_as::<u32>("entity")
+        .register_as::<u8>("flag")
+        .alias("id", "entity");
+
+    let mut restored = Hato::<dyn Any>::default();
+    let error = unsafe { snapshot.restore(&mut restored, &types) };
+    assert!(
+        matches!(error, Err(crate::SnapshotError::UnknownType { arena: 1, name }) if name == "position")
+    );
+
+    let types = types.skip_unknown();
+
+    let mut restored = Hato::<dyn Any>::default();
+    unsafe { snapshot.restore(&mut restored, &types) }.unwrap();
+
+    assert_eq!(unsafe { restored.get(z) }.downcast_ref(), Some(&3_u32));
+    assert_eq!(unsafe { restored.get(w) }.downcast_ref(), Some(&4_u8));
+    assert!(restored.contains(x) && !restored.contains(y));
+
+    // Arenas of dropped types stay in place, empty
+    assert_eq!(restored.arena_lens().collect::<Vec<_>>(), [2, 0, 1]);
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn snapshot_stream() {
+    use core::any::Any;
+
+    let build = || Hato::<dyn Any>::default().with_spill_threshold(16);
+
+    let mut arena = build();
+
+    let xs = (0..8_u32).map(|i| arena.push(i)).collect::<Vec<_>>();
+    let y = arena.push([6_u8; 32]);
+    let z = arena.push([0_u8; 0]);
+
+    arena.remove(xs[2]);
+
+    let types = crate::Types::default().register::<u32>().with_version(3);
+
+    let mut bytes = Vec::new();
+    assert!(arena.write_snapshot(&types, &mut bytes).is_err());
+
+    let types = types.register::<[u8; 32]>().register::<[u8; 0]>();
+
+    bytes.clear();
+    arena.write_snapshot(&types, &mut bytes).unwrap();
+
+    let reader = crate::SnapshotReader::new(bytes.as_slice()).unwrap();
+    assert_eq!(reader.version(), 3);
+
+    let mut restored = build();
+    unsafe { reader.restore(&mut restored, &types) }.unwrap();
+
+    assert!(restored.handles().eq(arena.handles()));
+    assert_eq!(unsafe { restored.get(xs[7]) }.downcast_ref(), Some(&7_u32));
+    assert_eq!(unsafe { restored.get(y) }.downcast_ref(), Some(&[6_u8; 32]));
+    assert_eq!(unsafe { restored.get(z) }.downcast_ref(), Some(&[0_u8; 0]));
+
+    // Truncated and foreign streams fail instead of restoring garbage
+    let reader = crate::SnapshotReader::new(&bytes[..bytes.len() - 1]).unwrap();
+    assert!(unsafe { reader.restore(&mut build(), &types) }.is_err());
+
+    assert!(crate::SnapshotReader::new(&bytes[1..]).is_err());
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn snapshot_corruption() {
+    use crate::SnapshotError;
+
+    let types = crate::Types::default().register::<u32>().register::<u64>();
+
+    let write = |hato: &Hato<dyn core::any::Any>| {
+        let mut bytes = Vec::new();
+        hato.write_snapshot(&types, &mut bytes).unwrap();
+        bytes
+    };
+
+    let restore = |bytes: &[u8]| {
+        let reader = crate::SnapshotReader::new(bytes)?;
+        let mut hato = Hato::<dyn core::any::Any>::default();
+
+        let result = unsafe { reader.restore(&mut hato, &types) };
+
+        // Failed restores leave the collection untouched, even past intact arenas
+        assert_eq!(result.is_ok(), !hato.arenas.is_empty());
+        result
+    };
+
+    let mut arena = Hato::<dyn core::any::Any>::default();
+    let _ = arena.push(1_u32);
+
+    let single = write(&arena);
+
+    let _ = arena.push(2_u64);
+    let double = write(&arena);
+
+    assert!(restore(&double).is_ok());
+
+    // Bit rot in the last element of the first arena, right before its checksum
+    let mut rotten = single.clone();
+    rotten[single.len() - 10] ^= 1;
+
+    let error = restore(&rotten);
+    assert!(matches!(error, Err(SnapshotError::Corrupt { arena: 0, types }) if types == ["u32"]));
+
+    // Arenas cut off at the end, with an end frame carrying the digest of all of them
+    let mut cut = single[..single.len() - 5].to_vec();
+    cut.extend_from_slice(&double[double.len() - 5..]);
+
+    assert!(matches!(restore(&cut), Err(SnapshotError::Digest)));
+
+    let error = restore(&double[..double.len() - 1]);
+    assert!(
+        matches!(error, Err(SnapshotError::Io(error)) if error.kind() == std::io::ErrorKind::UnexpectedEof)
+    );
+}
+
+#[cfg(all(feature = "rayon", feature = "serde"))]
+#[test]
+fn snapshot_par_restore() {
+    use core::any::Any;
+
+    let mut arena = Hato::<dyn Any>::default();
+
+    let xs = (0..4096_u32).map(|i| arena.push(i)).collect::<Vec<_>>();
+    let ys = (0..64_u64).map(|i| arena.push([i; 8])).collect::<Vec<_>>();
+    let z = arena.push(3_i16);
+
+    arena.remove(xs[7]);
+    arena.remove(ys[0]);
+
+    let types = crate::Types::default()
+        .register::<u32>()
+        .register::<[u64; 8]>()
+        .register::<i16>();
+
+    let snapshot = arena.snapshot(&types).unwrap();
+
+    let mut restored = Hato::<dyn Any>::default();
+    unsafe { snapshot.par_restore(&mut restored, &types) }.unwrap();
+
+    assert!(restored.handles().eq(arena.handles()));
+    assert_eq!(
+        unsafe { restored.get(xs[4095]) }.downcast_ref(),
+        Some(&4095_u32)
+    );
+    assert_eq!(
+        unsafe { restored.get(ys[63]) }.downcast_ref(),
+        Some(&[63_u64; 8])
+    );
+    assert_eq!(unsafe { restored.get(z) }.downcast_ref(), Some(&3_i16));
+
+    // Failures leave the collection untouched, unlike sequential restores
+    let types = crate::Types::default().register::<u32>().register::<i16>();
+    let mut partial = Hato::<dyn Any>::default();
+
+    let error = unsafe { snapshot.par_restore(&mut partial, &types) };
+    assert!(matches!(
+        error,
+        Err(crate::SnapshotError::UnknownType { arena: 1, .. })
+    ));
+    assert_eq!(partial.arena_lens().count(), 0);
+}
+
+#[cfg(feature = "futures")]
+#[test]
+fn snapshot_frames() {
+    use core::any::Any;
+    use core::pin::{pin, Pin};
+    use core::task::{Context, Poll, Waker};
+
+    use futures_core::Stream;
+
+    /// Stream handing frames out every other poll, as a socket would.
+    struct Trickle(Vec<Vec<u8>>, bool);
+
+    impl Stream for Trickle {
+        type Item = std::io::Result<Vec<u8>>;
+
+        fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+            self.1 = !self.1;
+
+            if self.1 {
+                cx.waker().wake_by_ref();
+                return Poll::Pending;
+            }
+
+            Poll::Ready((!self.0.is_empty()).then(|| Ok(self.0.remove(0))))
+        }
+    }
+
+    fn block_on<F: core::future::Future>(future: F) -> F::Output {
+        let mut future = pin!(future);
+        let mut context = Context::from_waker(Waker::noop());
+
+        loop {
+            if let Poll::Ready(output) = future.as_mut().poll(&mut context) {
+                return output;
+            }
+        }
+    }
+
+    let mut arena = Hato::<dyn Any>::default();
+
+    let xs = (0..100_u32).map(|i| arena.push(i)).collect::<Vec<_>>();
+    let y = arena.push([7_u64; 4]);
+
+    arena.remove(xs[3]);
+
+    let types = crate::Types::default()
+        .register::<u32>()
+        .register::<[u64; 4]>()
+        .with_version(2);
+
+    // Frames of odd sizes split lengths and elements alike
+    let mut stream = pin!(arena.snapshot_frames(&types, 7));
+    let mut context = Context::from_waker(Waker::noop());
+    let mut frames = Vec::new();
+
+    while let Poll::Ready(Some(frame)) = stream.as_mut().poll_next(&mut context) {
+        frames.push(frame.unwrap());
+    }
+
+    assert!(frames[..frames.len() - 1]
+        .iter()
+        .all(|frame| frame.len() >= 7));
+
+    let reader = block_on(crate::AsyncSnapshotReader::new(Trickle(
+        frames.clone(),
+        false,
+    )));
+    let reader = reader.unwrap();
+    assert_eq!(reader.version(), 2);
+
+    let mut restored = Hato::<dyn Any>::default();
+    block_on(unsafe { reader.restore(&mut restored, &types) }).unwrap();
+
+    assert!(restored.handles().eq(arena.handles()));
+    assert_eq!(
+        unsafe { restored.get(xs[99]) }.downcast_ref(),
+        Some(&99_u32)
+    );
+    assert_eq!(unsafe { restored.get(y) }.downcast_ref(), Some(&[7_u64; 4]));
+
+    // Streams ending early fail instead of leaving a restore waiting forever
+    frames.truncate(frames.len() - 1);
+
+    let reader = block_on(crate::AsyncSnapshotReader::new(Trickle(frames, false))).unwrap();
+    let error = block_on(unsafe { reader.restore(&mut Hato::<dyn Any>::default(), &types) });
+    assert!(matches!(error, Err(crate::SnapshotError::Io(_))));
+
+    // Unregistered types end the stream with an error
+    let types = crate::Types::default().register::<u32>();
+    let mut stream = pin!(arena.snapshot_frames(&types, 7));
+
+    let items = core::iter::from_fn(|| match stream.as_mut().poll_next(&mut context) {
+        Poll::Ready(item) => item,
+        Poll::Pending => None,
+    });
+    assert!(items.last().is_some_and(|item| item.is_err()));
+}
+
+#[cfg(all(feature = "compress", feature = "serde"))]
+#[test]
+fn snapshot_compress() {
+    use core::any::Any;
+
+    let mut arena = Hato::<dyn Any>::default();
+
+    let xs = (0..64_u32).map(|i| arena.push([i; 16])).collect::<Vec<_>>();
+    let y = arena.push(5_u8);
+
+    for x in xs.iter().step_by(3) {
+        arena.remove(*x);
+    }
+
+    let types = crate::Types::default()
+        .register::<[u32; 16]>()
+        .register::<u8>();
+
+    let plain = arena.snapshot(&types).unwrap();
+    let mut snapshot = plain.clone();
+
+    snapshot.compress(6);
+    assert_ne!(snapshot, plain);
+
+    // Compressing twice leaves bytes as they are
+    let compressed = snapshot.clone();
+    snapshot.compress(10);
+    assert_eq!(snapshot, compressed);
+
+    let mut restored = Hato::<dyn Any>::default();
+    unsafe { snapshot.restore(&mut restored, &types) }.unwrap();
+
+    assert!(restored.handles().eq(arena.handles()));
+    assert_eq!(
+        unsafe { restored.get(xs[1]) }.downcast_ref(),
+        Some(&[1_u32; 16])
+    );
+    assert_eq!(unsafe { restored.get(y) }.downcast_ref(), Some(&5_u8));
+}
+
+#[cfg(feature = "seal")]
+#[test]
+fn snapshot_seal() {
+    use core::any::Any;
+    use core::hash::{Hash, Hasher};
+    use std::collections::hash_map::DefaultHasher;
+
+    /// Keystream and tag derived from a hasher, standing in for a real cipher.
+    struct Toy(u64);
+
+    impl Toy {
+        fn tag(&self, associated: &[u8], buffer: &[u8]) -> [u8; 8] {
+            let mut hasher = DefaultHasher::new();
+            (self.0, associated, buffer).hash(&mut hasher);
+            hasher.finish().to_le_bytes()
+        }
+
+        fn apply(&self, buffer: &mut [u8]) {
+            let key = self.0.to_le_bytes();
+            buffer
+                .iter_mut()
+                .zip(key.iter().cycle())
+                .for_each(|(b, k)| *b ^= k);
+        }
+    }
+
+    impl crate::Aead for Toy {
+        fn seal(&self, associated: &[u8], buffer: &mut Vec<u8>) -> bool {
+            self.apply(buffer);
+            let tag = self.tag(associated, buffer);
+            buffer.extend_from_slice(&tag);
+            true
+        }
+
+        fn open(&self, associated: &[u8], buffer: &mut Vec<u8>) -> bool {
+            let Some(len) = buffer.len().checked_sub(8) else {
+                return false;
+            };
+
+            let valid = buffer[len..] == self.tag(associated, &buffer[..len]);
+            buffer.truncate(len);
+            self.apply(buffer);
+            valid
+        }
+    }
+
+    let mut arena = Hato::<dyn Any>::default();
+
+    let x = arena.push(0xDEAD_BEEF_u32);
+    let y = arena.push([9_u16; 3]);
+
+    let types = crate::Types::default()
+        .register::<u32>()
+        .register::<[u16; 3]>();
+
+    let original = arena.snapshot(&types).unwrap();
+
+    let mut snapshot = original.clone();
+    snapshot.seal(&Toy(7)).unwrap();
+
+    // Neither elements nor their checksums show through
+    assert_ne!(snapshot, original);
+    assert!(!format!("{snapshot:?}").contains(&format!("{:?}", 0xDEAD_BEEF_u32.to_ne_bytes())));
+
+    let mut restored = Hato::<dyn Any>::default();
+    let error = unsafe { snapshot.restore(&mut restored, &types) };
+    assert!(matches!(
+        error,
+        Err(crate::SnapshotError::Sealed { arena: 0 })
+    ));
+
+    // Wrong keys fail to open, leaving the snapshot sealed
+    let mut wrong = snapshot.clone();
+    assert!(wrong.open(&Toy(8)).is_err());
+    assert_eq!(wrong, snapshot);
+
+    snapshot.open(&Toy(7)).unwrap();
+    assert_eq!(snapshot, original);
+
+    unsafe { snapshot.restore(&mut restored, &types) }.unwrap();
+    assert_eq!(
+        unsafe { restored.get(x) }.downcast_ref(),
+        Some(&0xDEAD_BEEF_u32)
+    );
+    assert_eq!(unsafe { restored.get(y) }.downcast_ref(), Some(&[9_u16; 3]));
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn archive() {
+    use core::any::Any;
+
+    let build = || {
+        Hato::<dyn Any>::default()
+            .with_size_classes()
+            .with_spill_threshold(16)
+            .with_tombstones()
+    };
+
+    let mut arena = build();
+
+    let xs = (0..8_u32).map(|i| arena.push(i)).collect::<Vec<_>>();
+    let y = arena.push(5_i32);
+    let zs = [arena.push([6_u8; 32]), arena.push([7_u8; 32])];
+    let w = arena.push([0_u8; 0]);
+
+    arena.remove(xs[1]);
+    arena.remove(zs[0]);
+
+    let types = crate::Types::default().register::<u32>().register::<i32>();
+    assert!(arena.archive(&types).is_none());
+
+    let types = types.register::<[u8; 32]>().register::<[u8; 0]>();
+    let archive = arena.archive(&types).unwrap();
+
+    let mut restored = build();
+    unsafe { archive.restore(&mut restored, &types) }.unwrap();
+
+    // Shared arenas keep the type of each slot, and oversized elements their allocation
+    assert!(restored.handles().eq(arena.handles()));
+    assert_eq!(restored.count_of::<u32>(), 7);
+    assert_eq!(unsafe { restored.get(y) }.downcast_ref::<i32>(), Some(&5));
+    assert_eq!(
+        unsafe { restored.get(zs[1]) }.downcast_ref(),
+        Some(&[7_u8; 32])
+    );
+    assert_eq!(unsafe { restored.get(w) }.downcast_ref(), Some(&[0_u8; 0]));
+
+    // Tombstones stay out of circulation, as in the original collection
+    assert_eq!(restored.push(8_u32), arena.push(8_u32));
+    assert!(!restored.contains(xs[1]));
+
+    // Restores need an empty collection, laid out the same way
+    let error = unsafe { archive.restore(&mut restored, &types) };
+    assert!(matches!(error, Err(crate::SnapshotError::NotEmpty)));
+
+    let mut padded = build().with_cache_line_padding();
+    let error = unsafe { archive.restore(&mut padded, &types) };
+    assert!(matches!(
+        error,
+        Err(crate::SnapshotError::Layout { arena: 0, .. })
+    ));
+
+    // Unknown types cannot be left out of whole buffers
+    let partial = crate::Types::default()
+        .register::<u32>()
+        .register::<[u8; 32]>()
+        .register::<[u8; 0]>()
+        .skip_unknown();
+
+    let error = unsafe { archive.restore(&mut build(), &partial) };
+    assert!(
+        matches!(error, Err(crate::SnapshotError::UnknownType { arena: 0, name }) if name.contains("i32"))
+    );
+}
+
+#[test]
+fn len() {
+    let mut arena = Hato::<dyn core::fmt::Debug>::default().with_size_classes();
+    assert!(arena.is_empty());
+
+    let x = arena.push(1_u8);
+    let _ = arena.push(2_i8);
+    let _ = arena.push(3_u32);
+    let _ = arena.absorb_vec(vec![4_u16, 5, 6]);
+
+    assert_eq!(arena.len(), 6);
+    assert_eq!(arena.arena_lens().collect::<Vec<_>>(), [2, 1, 3]);
+
+    // Removing twice only counts once
+    arena.remove(x);
+    assert!(!arena.try_remove(x));
+    assert_eq!(arena.arena_lens().collect::<Vec<_>>(), [1, 1, 3]);
+
+    let (compacted, _) = arena.compact_clone();
+    assert_eq!(compacted.len(), 5);
+
+    arena.retain_types(|_| false);
+    assert!(arena.is_empty());
+}
+
+#[test]
+fn clear() {
+    let mut arena = Hato::<dyn core::fmt::Debug>::default()
+        .with_compaction_threshold(30)
+        .with_forwarding();
+
+    let xs = (0..16_u32).map(|i| arena.push(i)).collect::<Vec<_>>();
+    let y = arena.push(1_u8);
+    assert!(arena.insert_named("y", y).is_none());
+
+    for x in &xs[..8] {
+        arena.remove(*x);
+    }
+
+    arena.maintain();
+    arena.clear();
+
+    assert!(arena.is_empty());
+    assert_eq!(arena.get_named("y"), None);
+
+    // Memory is kept, with forwarding entries dropped
+    assert_eq!(arena.slack().collect::<Vec<_>>(), [16 * 4, 1]);
+    assert_eq!(arena.push(2_u8), y);
+    assert_eq!(arena.push(3_u32), xs[0]);
+    assert_eq!(format!("{:?}", unsafe { arena.get(xs[0]) }), "3");
+}
+
+#[test]
+fn shrink_to_fit() {
+    let mut arena = Hato::<dyn core::fmt::Debug>::default()
+        .with_capacity_bytes(1024)
+        .with_tombstones();
+
+    let xs = (0..8_u32).map(|i| arena.push(i)).collect::<Vec<_>>();
+    let y = arena.push(1_u8);
+    let _ = arena.push(2_u16);
+
+    arena.remove(xs[7]);
+    arena.remove(y);
+    arena.shrink_to_fit();
+
+    // Tombstones keep their slots, even past the last element
+    assert_eq!(arena.slack().collect::<Vec<_>>(), [0, 0, 0]);
+    assert_ne!(arena.push(3_u32), xs[7]);
+
+    let mut arena = Hato::<dyn core::fmt::Debug>::default().with_capacity_bytes(1024);
+
+    let x = arena.push(1_u32);
+    let y = arena.push(2_u8);
+
+    arena.remove(y);
+    arena.shrink_to_fit();
+
+    // Emptied arenas stay in the directory without any memory
+    assert_eq!(arena.slack().collect::<Vec<_>>(), [0, 0]);
+    assert_eq!(arena.push(3_u8), y);
+    assert_eq!(format!("{:?}", unsafe { arena.get(x) }), "1");
+}
+
+#[test]
+fn stats() {
+    use core::any::TypeId;
+
+    let mut arena = Hato::<dyn core::fmt::Debug>::default()
+        .with_size_classes()
+        .with_spill_threshold(16);
+
+    let x = arena.push(1_u8);
+    let _ = arena.push(2_i8);
+    let _ = arena.push([0_u8; 0]);
+    let y = arena.push([3_u8; 32]);
+    let _ = arena.push([4_u8; 32]);
+
+    arena.remove(x);
+    arena.remove(y);
+
+    let stats = arena.stats();
+    let of = |type_id| *stats.types.iter().find(|t| t.type_id == type_id).unwrap();
+
+    assert_eq!(stats.arenas, 3);
+    assert_eq!((stats.used_bytes, stats.free_bytes), (1 + 32, 1));
+
+    // Free slots of spilled elements give their allocation back
+    assert_eq!(of(TypeId::of::<[u8; 32]>()).free_bytes, 0);
+    assert_eq!(of(TypeId::of::<u8>()).live, 0);
+    assert_eq!(of(TypeId::of::<[u8; 0]>()).live, 1);
+
+    // Buffers hold the two slots of bytes, the spilled element its own allocation
+    let reserved = arena.slack().sum::<usize>() + 2;
+    assert_eq!(stats.allocated_bytes, reserved + 32);
+}
+
+#[cfg(feature = "rayon")]
+#[test]
+fn par_iter() {
+    use core::any::Any;
+
+    use rayon::iter::ParallelIterator;
+
+    let mut arena = Hato::<dyn Any + Send + Sync>::default()
+        .with_size_classes()
+        .with_spill_threshold(16);
+
+    let xs = (0..3000_u32).map(|i| arena.push(i)).collect::<Vec<_>>();
+    let y = arena.push([1_u8; 32]);
+    let _ = arena.push(2_i32);
+
+    for x in xs.iter().step_by(7) {
+        arena.remove(*x);
+    }
+
+    let mut seen = arena.par_iter().map(|(h, _)| h).collect::<Vec<_>>();
+    seen.sort();
+    assert!(seen.into_iter().eq(arena.handles()));
+
+    arena.par_iter_mut().for_each(|(_, x)| {
+        if let Some(x) = x.downcast_mut::<u32>() {
+            *x += 1;
+        } else if let Some(x) = x.downcast_mut::<[u8; 32]>() {
+            x[0] = 3;
+        }
+    });
+
+    assert_eq!(
+        unsafe { arena.get(xs[2999]) }.downcast_ref(),
+        Some(&3000_u32)
+    );
+    assert_eq!(
+        unsafe { arena.get(y) }.downcast_ref::<[u8; 32]>().unwrap()[..2],
+        [3, 1]
+    );
+}
+
+#[test]
+fn try_get() {
+    let mut arena = Hato::<dyn core::any::Any>::default().with_tombstones();
+
+    let x = arena.push(1_u32);
+    let y = arena.push(2_u32);
+
+    arena.remove(x);
+    assert!(arena.try_get(x).is_none());
+    assert!(arena.try_get_mut(x).is_none());
+
+    *arena.try_get_mut(y).unwrap().downcast_mut::<u32>().unwrap() = 3;
+    assert_eq!(arena.try_get(y).unwrap().downcast_ref(), Some(&3_u32));
+
+    // Handles of other collections may point past the arenas or their slots
+    let mut other = Hato::<dyn core::any::Any>::default();
+    let _ = other.push(4_u8);
+    let z = other.push(5_u32);
+
+    assert!(arena.try_get(other.push(6_u16)).is_none());
+    assert!(arena.try_get(crate::Handle { offset: 64, ..z }).is_none());
+}
+
+#[test]
+fn take() {
+    use core::sync::atomic::{AtomicUsize, Ordering};
+
+    static DROPPED: AtomicUsize = AtomicUsize::new(0);
+
+    #[derive(Debug)]
+    struct Counted(u32);
+
+    impl Drop for Counted {
+        fn drop(&mut self) {
+            let _ = DROPPED.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    unsafe impl unscrupulous::Unscrupulous for Counted {}
+
+    let mut arena = Hato::<dyn core::fmt::Debug>::default()
+        .with_size_classes()
+        .with_spill_threshold(16);
+
+    let x = arena.push_no_drop(Counted(1));
+    let y = arena.push(2_i32);
+    let z = arena.push([3_u8; 32]);
+
+    // Taken elements are owned again, running their destructor once
+    assert_eq!(arena.take::<Counted>(x).0, 1);
+    assert_eq!(DROPPED.load(Ordering::Relaxed), 1);
+
+    assert_eq!(arena.take::<[u8; 32]>(z), [3; 32]);
+    assert_eq!(arena.handles().collect::<Vec<_>>(), [y]);
+
+    // Slots are handed out again, and mistyped takes are caught
+    let w = arena.push(4_u32);
+    assert_eq!(w, x);
+
+    assert!(std::panic::catch_unwind(core::panic::AssertUnwindSafe(|| {
+        let _ = arena.take::<i32>(w);
+    }))
+    .is_err());
+}
@@ -0,0 +1,65 @@
+use core::fmt::{self, Display, Formatter};
+use core::str::FromStr;
+
+use crate::{Handle, Index};
+
+/// Textual form of handles, `h<index>:<offset>`, with offsets padded to five digits.
+///
+/// The form is stable across versions, so handles can be logged, searched for,
+/// and pasted back into debugging or replay tools.
+///
+/// ```rust
+/// let mut arena = hato::Hato::<dyn core::fmt::Debug>::default();
+///
+/// let _ = arena.push(1_u8);
+/// let x = arena.push(2_u8);
+///
+/// assert_eq!(x.to_string(), "h0:00001");
+/// assert_eq!("h0:00001".parse(), Ok(x));
+/// ```
+impl Display for Handle {
+    #[inline]
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "h{}:{:05}", self.index, self.offset)
+    }
+}
+
+impl FromStr for Handle {
+    type Err = ParseHandleError;
+
+    #[inline]
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (index, offset) = s
+            .strip_prefix('h')
+            .and_then(|s| s.split_once(':'))
+            .ok_or(ParseHandleError)?;
+
+        Ok(Self {
+            index: parse_decimal(index)?,
+            offset: parse_decimal(offset)?,
+        })
+    }
+}
+
+/// Error returned when parsing a string that is not in the textual form of handles.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct ParseHandleError;
+
+impl Display for ParseHandleError {
+    #[inline]
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str("invalid handle, expected `h<index>:<offset>`")
+    }
+}
+
+impl core::error::Error for ParseHandleError {}
+
+/// Parse a number made of decimal digits only, rejecting signs and whitespace.
+#[inline]
+fn parse_decimal(s: &str) -> Result<Index, ParseHandleError> {
+    if s.is_empty() || !s.bytes().all(|byte| byte.is_ascii_digit()) {
+        return Err(ParseHandleError);
+    }
+
+    s.parse().map_err(|_| ParseHandleError)
+}
@@ -0,0 +1,116 @@
+use core::num::NonZeroUsize;
+use core::ops::Range;
+use core::ptr::{DynMetadata, Pointee};
+use core::sync::atomic::{AtomicUsize, Ordering};
+
+use crate::{Arena, Hato, Storage};
+
+/// Number of consecutive slots visited by a single task, to amortize scheduling costs.
+const CHUNK: usize = 1024;
+
+impl<Trait: ?Sized + Pointee<Metadata = DynMetadata<Trait>>, S: Storage> Hato<Trait, S> {
+    /// Call `f` on every element, spreading work over `threads` scoped threads.
+    ///
+    /// Work is split per arena and by chunks of slots, which threads pick up as they go.
+    /// Unlike the methods of the `rayon` feature, this only relies on the standard library.
+    ///
+    /// ```rust
+    /// use core::sync::atomic::{AtomicUsize, Ordering};
+    ///
+    /// let mut arena = hato::Hato::<dyn core::fmt::Debug + Sync>::default();
+    ///
+    /// let _ = arena.push(1_u8);
+    /// let _ = arena.push(20_u32);
+    ///
+    /// let digits = AtomicUsize::new(0);
+    /// let threads = std::thread::available_parallelism().unwrap();
+    ///
+    /// arena.par_for_each(threads, |x| {
+    ///     let _ = digits.fetch_add(format!("{x:?}").len(), Ordering::Relaxed);
+    /// });
+    ///
+    /// assert_eq!(digits.into_inner(), 3);
+    /// ```
+    #[inline]
+    pub fn par_for_each(&self, threads: NonZeroUsize, f: impl Fn(&Trait) + Sync)
+    where
+        Trait: Sync,
+        S: Sync,
+    {
+        let arenas = &self.arenas;
+
+        visit(&self.tasks(), threads, |index, slot| {
+            let arena = &arenas[index];
+
+            if arena.occupied[slot] {
+                f(arena.get(arena.offset(slot)));
+            }
+        });
+    }
+
+    /// Call `f` on every element mutably, spreading work over `threads` scoped threads.
+    ///
+    /// Each element is visited by exactly one thread, so no synchronization is needed in `f`.
+    #[inline]
+    pub fn par_for_each_mut(&mut self, threads: NonZeroUsize, f: impl Fn(&mut Trait) + Sync)
+    where
+        Trait: Send,
+        S: Sync,
+    {
+        let tasks = self.tasks();
+
+        // Elements may be modified through the references, past what the model can follow
+        self.touch_all();
+
+        // Take mutable addresses up front, so that threads only share the arenas afterwards
+        let bases = self.arenas.iter_mut().map(Arena::bases).collect::<Vec<_>>();
+
+        let arenas = &self.arenas;
+
+        visit(&tasks, threads, |index, slot| {
+            let arena = &arenas[index];
+
+            if arena.occupied[slot] {
+                // ! SAFETY: Slot holds a valid element, visited by this thread only
+                f(unsafe { arena.element_mut(&bases[index], slot) });
+            }
+        });
+    }
+
+    /// Chunks of slots of each arena, as pairs of directory index and range of slots.
+    #[inline]
+    fn tasks(&self) -> Vec<(usize, Range<usize>)> {
+        let chunks = self.arenas.iter().enumerate().flat_map(|(index, arena)| {
+            let len = arena.occupied.len();
+            (0..len)
+                .step_by(CHUNK)
+                .map(move |first| (index, first..len.min(first + CHUNK)))
+        });
+
+        chunks.collect()
+    }
+}
+
+/// Call `f` on the directory index and slot of each slot of `tasks`, on `threads` threads.
+#[inline]
+fn visit(tasks: &[(usize, Range<usize>)], threads: NonZeroUsize, f: impl Fn(usize, usize) + Sync) {
+    let next = AtomicUsize::new(0);
+
+    // Pick tasks one at a time, so that threads finishing early help with the rest
+    let work = || {
+        while let Some((index, slots)) = tasks.get(next.fetch_add(1, Ordering::Relaxed)) {
+            for slot in slots.clone() {
+                f(*index, slot);
+            }
+        }
+    };
+
+    std::thread::scope(|scope| {
+        for _ in 1..threads.get().min(tasks.len()) {
+            drop(scope.spawn(work));
+        }
+
+        // Put the calling thread to work as well
+        work();
+    });
+}
@@ -0,0 +1,326 @@
+use alloc::collections::BTreeSet;
+use alloc::vec::Vec;
+
+use core::marker::Unsize;
+use core::mem::size_of;
+use core::ptr::{DynMetadata, Pointee};
+
+use miniz_oxide::deflate::compress_to_vec;
+use miniz_oxide::inflate::decompress_to_vec_with_limit;
+use unscrupulous::Unscrupulous;
+
+use crate::{Handle, Hato, Kind};
+
+/// Wrapper around [`Hato`] keeping recently used elements in memory, and compressing the others.
+///
+/// Elements start out hot, in the underlying collection. Once hot elements take more than
+/// a resident budget of bytes, the least recently used ones are compressed with DEFLATE
+/// into a cold region, and removed from the collection. Accessing a cold element decompresses
+/// it back into the collection, cooling others down in turn. The budget counts the bytes
+/// of elements only, without padding nor bookkeeping.
+///
+/// Elements move between tiers, so they are identified by their own [`TieredHandle`],
+/// which stays valid until their removal, and detects it afterwards.
+///
+/// ```rust
+/// let mut tiered = hato::HatoTiered::<dyn core::fmt::Debug>::new(8);
+///
+/// let x = tiered.push([1_u32; 2]);
+/// let y = tiered.push([2_u32; 2]);
+///
+/// // Inserting `y` compressed `x`, which comes back on access, compressing `y` instead
+/// assert_eq!(tiered.hot_len(), 1);
+/// assert_eq!(format!("{:?}", tiered.get(x)), "Some([1, 1])");
+/// assert_eq!(format!("{:?}", tiered.get(y)), "Some([2, 2])");
+/// ```
+#[derive(Debug)]
+pub struct HatoTiered<Trait: ?Sized + Pointee<Metadata = DynMetadata<Trait>>> {
+    hato: Hato<Trait>,
+    entries: Vec<Entry<Trait>>,
+    free: Vec<usize>,
+    resident: usize,
+    hot_bytes: usize,
+    cold_bytes: usize,
+    level: u8,
+    tick: u64,
+
+    /// Hot elements by last use, coldest first.
+    queue: BTreeSet<(u64, usize)>,
+}
+
+/// Bookkeeping of an element, identified by the stamp of its insertion.
+#[derive(Debug)]
+struct Entry<Trait: ?Sized + Pointee<Metadata = DynMetadata<Trait>>> {
+    stamp: u64,
+    tier: Tier<Trait>,
+}
+
+/// Location of an element, or nothing once it was removed.
+#[derive(Debug)]
+enum Tier<Trait: ?Sized + Pointee<Metadata = DynMetadata<Trait>>> {
+    Hot { handle: Handle, used: u64 },
+    Cold { kind: Kind<Trait>, bytes: Vec<u8> },
+    Removed,
+}
+
+impl<Trait: ?Sized + Pointee<Metadata = DynMetadata<Trait>>> HatoTiered<Trait> {
+    /// Create an empty collection, keeping at most `resident` bytes worth of elements hot.
+    #[inline]
+    #[must_use]
+    pub fn new(resident: usize) -> Self {
+        Self {
+            hato: Hato::default(),
+            entries: Vec::new(),
+            free: Vec::new(),
+            resident,
+            hot_bytes: 0,
+            cold_bytes: 0,
+            level: 6,
+            tick: 0,
+            queue: BTreeSet::new(),
+        }
+    }
+
+    /// Compress cold elements at `level`, from 0 to 10, instead of the default of 6.
+    #[inline]
+    #[must_use]
+    pub const fn with_level(mut self, level: u8) -> Self {
+        self.level = level;
+        self
+    }
+
+    /// Collection of hot elements, to traverse them without affecting their tier.
+    #[inline]
+    #[must_use]
+    pub const fn hato(&self) -> &Hato<Trait> {
+        &self.hato
+    }
+
+    /// Number of elements, hot or cold.
+    #[inline]
+    #[must_use]
+    pub const fn len(&self) -> usize {
+        self.entries.len() - self.free.len()
+    }
+
+    /// Check whether the collection holds no element.
+    #[inline]
+    #[must_use]
+    pub const fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Number of hot elements.
+    #[inline]
+    #[must_use]
+    pub fn hot_len(&self) -> usize {
+        self.queue.len()
+    }
+
+    /// Bytes of hot elements, at most the resident budget unless a single element exceeds it.
+    #[inline]
+    #[must_use]
+    pub const fn hot_bytes(&self) -> usize {
+        self.hot_bytes
+    }
+
+    /// Bytes of cold elements, once compressed.
+    #[inline]
+    #[must_use]
+    pub const fn cold_bytes(&self) -> usize {
+        self.cold_bytes
+    }
+
+    /// Change the resident budget to `resident` bytes, compressing cold elements past it.
+    #[inline]
+    pub fn set_resident(&mut self, resident: usize) {
+        self.resident = resident;
+        self.cool_down();
+    }
+
+    /// Insert `x` as a hot element, compressing the least recently used ones past the budget.
+    ///
+    /// # Panics
+    ///
+    /// This function will panic if the number of arenas overflows the index type.
+    #[inline]
+    pub fn push<T: Unsize<Trait> + Unscrupulous>(&mut self, x: T) -> TieredHandle {
+        let handle = self.hato.push(x);
+        let (stamp, used) = (self.next_tick(), self.next_tick());
+
+        let entry = Entry {
+            stamp,
+            tier: Tier::Hot { handle, used },
+        };
+
+        let slot = if let Some(slot) = self.free.pop() {
+            self.entries[slot] = entry;
+            slot
+        } else {
+            self.entries.push(entry);
+            self.entries.len() - 1
+        };
+
+        let _ = self.queue.insert((used, slot));
+        self.hot_bytes += size_of::<T>();
+        self.cool_down();
+
+        TieredHandle { slot, stamp }
+    }
+
+    /// Retrieve the element identified by `handle`, decompressing it first if it is cold.
+    ///
+    /// The element becomes the most recently used one, which may compress others.
+    /// Returns `None` if it was removed.
+    #[inline]
+    pub fn get(&mut self, handle: TieredHandle) -> Option<&Trait> {
+        let handle = self.warm_up(handle)?;
+
+        // ! SAFETY: Handle identifies a live element of the underlying collection
+        Some(unsafe { self.hato.get(handle) })
+    }
+
+    /// Retrieve the element identified by `handle` mutably, decompressing it first if needed.
+    ///
+    /// The element becomes the most recently used one, which may compress others.
+    /// Returns `None` if it was removed.
+    #[inline]
+    pub fn get_mut(&mut self, handle: TieredHandle) -> Option<&mut Trait> {
+        let handle = self.warm_up(handle)?;
+        Some(self.hato.get_mut(handle))
+    }
+
+    /// Check whether the element identified by `handle` is hot, without affecting its tier.
+    #[inline]
+    #[must_use]
+    pub fn is_hot(&self, handle: TieredHandle) -> bool {
+        let entry = self.entries.get(handle.slot);
+        let entry = entry.filter(|entry| entry.stamp == handle.stamp);
+
+        entry.is_some_and(|entry| matches!(entry.tier, Tier::Hot { .. }))
+    }
+
+    /// Remove the element identified by `handle`, returning whether it was still present.
+    #[inline]
+    pub fn remove(&mut self, handle: TieredHandle) -> bool {
+        let Some(entry) = self.entry_mut(handle) else {
+            return false;
+        };
+
+        match core::mem::replace(&mut entry.tier, Tier::Removed) {
+            Tier::Hot {
+                handle: inner,
+                used,
+            } => {
+                self.forget_hot(inner, used, handle.slot);
+                self.hato.remove(inner);
+            }
+            Tier::Cold { bytes, .. } => self.cold_bytes -= bytes.len(),
+            Tier::Removed => unreachable!("removed entries are rejected by their stamp"),
+        }
+
+        self.free.push(handle.slot);
+        true
+    }
+
+    /// Make the element identified by `handle` hot and most recently used, and return
+    /// its handle in the underlying collection.
+    #[inline]
+    fn warm_up(&mut self, handle: TieredHandle) -> Option<Handle> {
+        let tick = self.next_tick();
+        let entry = self.entry_mut(handle)?;
+
+        let inner = match core::mem::replace(&mut entry.tier, Tier::Removed) {
+            Tier::Hot {
+                handle: inner,
+                used,
+            } => {
+                let _ = self.queue.remove(&(used, handle.slot));
+                inner
+            }
+            Tier::Cold { kind, bytes } => {
+                let size = kind.1.size_of();
+
+                // Cold bytes never leave the collection, so they decompress to their exact size
+                let element = decompress_to_vec_with_limit(&bytes, size);
+                let element = element.expect("cold bytes should decompress");
+
+                self.cold_bytes -= bytes.len();
+                self.hot_bytes += size;
+
+                self.hato.push_kind(&element, kind)
+            }
+            Tier::Removed => unreachable!("removed entries are rejected by their stamp"),
+        };
+
+        self.entries[handle.slot].tier = Tier::Hot {
+            handle: inner,
+            used: tick,
+        };
+
+        let _ = self.queue.insert((tick, handle.slot));
+        self.cool_down();
+
+        Some(inner)
+    }
+
+    /// Compress the least recently used hot elements until they fit within the budget.
+    ///
+    /// The most recently used element always stays hot, so that it can be accessed.
+    #[inline]
+    fn cool_down(&mut self) {
+        while self.hot_bytes > self.resident && self.queue.len() > 1 {
+            let Some((used, slot)) = self.queue.first().copied() else {
+                return;
+            };
+
+            let Tier::Hot { handle, .. } = self.entries[slot].tier else {
+                unreachable!("queued entries are hot");
+            };
+
+            let arena = &self.hato.arenas[handle.index as usize];
+            let kind = arena.kind(arena.slot(handle.offset));
+
+            // ! SAFETY: Handle identifies a live element of the underlying collection
+            let bytes = compress_to_vec(unsafe { self.hato.element_bytes(handle) }, self.level);
+
+            self.cold_bytes += bytes.len();
+            self.entries[slot].tier = Tier::Cold { kind, bytes };
+
+            self.forget_hot(handle, used, slot);
+            self.hato.remove(handle);
+        }
+    }
+
+    /// Drop the bookkeeping of the hot element `handle`, last used at `used`.
+    #[inline]
+    fn forget_hot(&mut self, handle: Handle, used: u64, slot: usize) {
+        let arena = &self.hato.arenas[handle.index as usize];
+
+        let _ = self.queue.remove(&(used, slot));
+        self.hot_bytes -= arena.vtable.size_of();
+    }
+
+    /// Bookkeeping of the element identified by `handle`, unless it was removed.
+    #[inline]
+    fn entry_mut(&mut self, handle: TieredHandle) -> Option<&mut Entry<Trait>> {
+        let entry = self.entries.get_mut(handle.slot)?;
+
+        // Slots of removed elements may hold newer ones, inserted at a later tick
+        (entry.stamp == handle.stamp && !matches!(entry.tier, Tier::Removed)).then_some(entry)
+    }
+
+    /// Advance the logical clock of insertions and accesses.
+    #[inline]
+    const fn next_tick(&mut self) -> u64 {
+        self.tick += 1;
+        self.tick
+    }
+}
+
+/// Index to access an element stored in a [`HatoTiered`], whichever its tier.
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct TieredHandle {
+    slot: usize,
+    stamp: u64,
+}
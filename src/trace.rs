@@ -0,0 +1,125 @@
+use alloc::vec;
+use alloc::vec::Vec;
+
+use core::ptr::{DynMetadata, Pointee};
+
+use crate::{index_of, Counted, Handle, Hato, LiveSlots, Storage};
+
+impl<Trait: ?Sized + Pointee<Metadata = DynMetadata<Trait>>, S: Storage> Hato<Trait, S> {
+    /// Iterate over the handles of all live elements, arena by arena.
+    ///
+    /// ```rust
+    /// let mut arena = hato::Hato::<dyn core::fmt::Debug>::default();
+    ///
+    /// let x = arena.push(1_u8);
+    /// let y = arena.push(2_u16);
+    ///
+    /// assert_eq!(arena.handles().collect::<Vec<_>>(), [x, y]);
+    /// ```
+    #[inline]
+    #[must_use]
+    pub fn handles(&self) -> impl DoubleEndedIterator<Item = Handle> + ExactSizeIterator + '_ {
+        let handles = self.arenas.iter().enumerate().flat_map(|(index, arena)| {
+            let index = index_of(index);
+
+            LiveSlots::new(&arena.occupied).map(move |slot| Handle {
+                index,
+                offset: arena.offset(slot),
+            })
+        });
+
+        Counted::new(handles, self.len())
+    }
+
+    /// Remove all elements unreachable from `roots`, returning how many were freed.
+    ///
+    /// Turns the collection into a traced heap, as for objects of a scripting language.
+    /// Elements report the handles they reference to `edges`, which calls its second argument
+    /// once per handle. Elements reachable from `roots` through these references are kept,
+    /// while slots of all others go back to the free list. Handles of removed elements,
+    /// among roots or references, are skipped rather than followed.
+    ///
+    /// ```rust
+    /// trait Object {
+    ///     fn references(&self) -> &[hato::Handle];
+    /// }
+    ///
+    /// struct Cell(u32);
+    /// struct Cons([hato::Handle; 2]);
+    ///
+    /// impl Object for Cell {
+    ///     fn references(&self) -> &[hato::Handle] {
+    ///         &[]
+    ///     }
+    /// }
+    ///
+    /// impl Object for Cons {
+    ///     fn references(&self) -> &[hato::Handle] {
+    ///         &self.0
+    ///     }
+    /// }
+    ///
+    /// unsafe impl unscrupulous::Unscrupulous for Cell {}
+    /// unsafe impl unscrupulous::Unscrupulous for Cons {}
+    ///
+    /// let mut heap = hato::Hato::<dyn Object>::default();
+    ///
+    /// let [x, y] = [heap.push(Cell(1)), heap.push(Cell(2))];
+    /// let root = heap.push(Cons([x, x]));
+    /// let garbage = heap.push(Cons([y, root]));
+    ///
+    /// let freed = heap.collect_garbage([root], |object, visit| {
+    ///     object.references().iter().copied().for_each(visit);
+    /// });
+    ///
+    /// assert_eq!(freed, 2);
+    /// assert!(heap.contains(x) && !heap.contains(y) && !heap.contains(garbage));
+    /// ```
+    #[inline]
+    pub fn collect_garbage(
+        &mut self,
+        roots: impl IntoIterator<Item = Handle>,
+        mut edges: impl FnMut(&Trait, &mut dyn FnMut(Handle)),
+    ) -> usize {
+        let mut marks = self
+            .arenas
+            .iter()
+            .map(|arena| vec![false; arena.occupied.len()])
+            .collect::<Vec<_>>();
+
+        let mut pending = roots.into_iter().collect::<Vec<_>>();
+
+        // Mark elements reachable from the roots, depth first
+        while let Some(handle) = pending.pop() {
+            let handle = self.forward(handle);
+
+            if !self.contains(handle) {
+                continue;
+            }
+
+            let arena = &self.arenas[handle.index as usize];
+            let mark = &mut marks[handle.index as usize][arena.slot(handle.offset)];
+
+            if core::mem::replace(mark, true) {
+                continue;
+            }
+
+            edges(arena.get(handle.offset), &mut |handle| pending.push(handle));
+        }
+
+        let garbage = self
+            .handles()
+            .filter(|handle| {
+                let arena = &self.arenas[handle.index as usize];
+                !marks[handle.index as usize][arena.slot(handle.offset)]
+            })
+            .collect::<Vec<_>>();
+
+        // Free unmarked slots as regular removals, keeping free lists and names consistent
+        for handle in &garbage {
+            self.remove(*handle);
+        }
+
+        garbage.len()
+    }
+}
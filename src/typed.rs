@@ -0,0 +1,164 @@
+use core::cmp::Ordering;
+use core::fmt::{self, Debug, Formatter};
+use core::hash::{Hash, Hasher};
+use core::marker::{PhantomData, Unsize};
+use core::ptr::{DynMetadata, Pointee};
+
+use unscrupulous::Unscrupulous;
+
+use crate::{Handle, Hato, Storage};
+
+/// Handle to an element of concrete type `T`, accessed without going through its vtable.
+///
+/// Typed handles convert to plain ones with [`From`], for APIs that only take erased handles.
+///
+/// ```rust
+/// let mut arena = hato::Hato::<dyn core::fmt::Debug>::default();
+///
+/// let x = arena.push_typed(4_u16);
+/// *unsafe { arena.get_typed_mut(x) } += 1;
+///
+/// assert_eq!(unsafe { arena.get_typed(x) }, &5);
+/// assert_eq!(format!("{:?}", unsafe { arena.get(x.into()) }), "5");
+/// ```
+pub struct TypedHandle<T> {
+    handle: Handle,
+    marker: PhantomData<fn() -> T>,
+}
+
+impl<T> TypedHandle<T> {
+    /// Plain handle of the element, with its type erased.
+    #[inline]
+    #[must_use]
+    pub const fn handle(&self) -> Handle {
+        self.handle
+    }
+}
+
+impl<T> From<TypedHandle<T>> for Handle {
+    #[inline]
+    fn from(handle: TypedHandle<T>) -> Self {
+        handle.handle
+    }
+}
+
+// Traits are implemented by hand, as derives would require `T` to implement them too
+impl<T> Clone for TypedHandle<T> {
+    #[inline]
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T> Copy for TypedHandle<T> {}
+
+impl<T> Debug for TypedHandle<T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("TypedHandle").field(&self.handle).finish()
+    }
+}
+
+impl<T> PartialEq for TypedHandle<T> {
+    #[inline]
+    fn eq(&self, other: &Self) -> bool {
+        self.handle == other.handle
+    }
+}
+
+impl<T> Eq for TypedHandle<T> {}
+
+impl<T> PartialOrd for TypedHandle<T> {
+    #[inline]
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<T> Ord for TypedHandle<T> {
+    #[inline]
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.handle.cmp(&other.handle)
+    }
+}
+
+impl<T> Hash for TypedHandle<T> {
+    #[inline]
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.handle.hash(state);
+    }
+}
+
+impl<Trait: ?Sized + Pointee<Metadata = DynMetadata<Trait>>, S: Storage> Hato<Trait, S> {
+    /// Insert `x`, returning a handle that remembers its concrete type.
+    ///
+    /// See [`TypedHandle`] for details.
+    ///
+    /// # Panics
+    ///
+    /// This function will panic if the number of arenas overflows the index type,
+    /// or if the quota of `T` is exhausted, see [`Self::set_quota`].
+    #[inline]
+    pub fn push_typed<T: Unsize<Trait> + Unscrupulous>(&mut self, x: T) -> TypedHandle<T> {
+        TypedHandle {
+            handle: self.push(x),
+            marker: PhantomData,
+        }
+    }
+
+    /// Retrieve the element identified by `handle` as its concrete type.
+    ///
+    /// # Safety
+    ///
+    /// The handle must originate from the same instance of `Hato`, and its element must not
+    /// have been removed, as its slot may since hold an element of another type.
+    #[inline]
+    #[must_use]
+    pub unsafe fn get_typed<T: Unsize<Trait> + Unscrupulous>(&self, handle: TypedHandle<T>) -> &T {
+        let handle = self.forward(handle.handle);
+
+        let arena = &self.arenas[handle.index as usize];
+        self.shadow.check(handle, || arena.element(handle.offset));
+
+        // Catch handles to slots reused by elements of other types, in debug builds
+        let slot = arena.slot(handle.offset);
+        debug_assert_eq!(
+            arena.kind(slot).0,
+            typeid::of::<T>(),
+            "element type mismatch"
+        );
+
+        // ! SAFETY: Slot holds a valid element of type `T`, as required from the caller
+        unsafe { &*arena.ptr(handle.offset).cast::<T>() }
+    }
+
+    /// Retrieve the element identified by `handle` mutably, as its concrete type.
+    ///
+    /// # Safety
+    ///
+    /// The handle must originate from the same instance of `Hato`, and its element must not
+    /// have been removed, as its slot may since hold an element of another type.
+    #[inline]
+    #[must_use]
+    pub unsafe fn get_typed_mut<T: Unsize<Trait> + Unscrupulous>(
+        &mut self,
+        handle: TypedHandle<T>,
+    ) -> &mut T {
+        let handle = self.forward(handle.handle);
+
+        // Element may be modified through the reference, past what the model can follow
+        self.shadow.touch(handle);
+
+        let arena = &mut self.arenas[handle.index as usize];
+
+        // Catch handles to slots reused by elements of other types, in debug builds
+        let slot = arena.slot(handle.offset);
+        debug_assert_eq!(
+            arena.kind(slot).0,
+            typeid::of::<T>(),
+            "element type mismatch"
+        );
+
+        // ! SAFETY: Slot holds a valid element of type `T`, as required from the caller
+        unsafe { &mut *arena.ptr_mut(handle.offset).cast::<T>() }
+    }
+}
@@ -0,0 +1,136 @@
+use alloc::vec::Vec;
+
+use core::any::{type_name, TypeId};
+use core::marker::Unsize;
+use core::ptr::{DynMetadata, Pointee};
+
+use unscrupulous::Unscrupulous;
+
+use crate::{get_metadata_of, Kind};
+
+/// Registry of concrete types by name, to recreate their elements from logs or snapshots.
+///
+/// Names default to [`type_name`], which changes along with module paths and may differ
+/// across compiler versions. Types registered with [`Self::register_as`] get a stable name
+/// instead, and [`Self::alias`] keeps loading elements saved under former names.
+#[derive(Debug)]
+pub struct Types<Trait: ?Sized + Pointee<Metadata = DynMetadata<Trait>>> {
+    registered: Vec<(&'static str, TypeId, DynMetadata<Trait>)>,
+    aliases: Vec<(&'static str, &'static str)>,
+    #[cfg(feature = "serde")]
+    skip_unknown: bool,
+    #[cfg(feature = "serde")]
+    version: u32,
+}
+
+impl<Trait: ?Sized + Pointee<Metadata = DynMetadata<Trait>>> Default for Types<Trait> {
+    fn default() -> Self {
+        Self {
+            registered: Vec::new(),
+            aliases: Vec::new(),
+            #[cfg(feature = "serde")]
+            skip_unknown: false,
+            #[cfg(feature = "serde")]
+            version: 0,
+        }
+    }
+}
+
+impl<Trait: ?Sized + Pointee<Metadata = DynMetadata<Trait>>> Types<Trait> {
+    /// Register type `T`, so that its elements can be recreated.
+    #[inline]
+    #[must_use]
+    pub fn register<T: Unsize<Trait> + Unscrupulous>(self) -> Self {
+        self.register_as::<T>(type_name::<T>())
+    }
+
+    /// Register type `T` under `name`, which stays valid as the application evolves.
+    ///
+    /// ```rust
+    /// let types = hato::Types::<dyn core::fmt::Debug>::default()
+    ///     .register_as::<u32>("score")
+    ///     .alias("points", "score");
+    /// ```
+    #[inline]
+    #[must_use]
+    pub fn register_as<T: Unsize<Trait> + Unscrupulous>(mut self, name: &'static str) -> Self {
+        let vtable = get_metadata_of::<T, Trait>();
+
+        self.registered.push((name, typeid::of::<T>(), vtable));
+        self
+    }
+
+    /// Recreate elements recorded under the former name `old` as the type registered as `name`.
+    ///
+    /// Renamed or moved types thus keep loading, as long as their layout is unchanged.
+    #[inline]
+    #[must_use]
+    pub fn alias(mut self, old: &'static str, name: &'static str) -> Self {
+        self.aliases.push((old, name));
+        self
+    }
+
+    /// Kind of the registered type named `name`.
+    #[inline]
+    pub(crate) fn find(&self, name: &str) -> Option<Kind<Trait>> {
+        // Former names resolve to the current one first
+        let alias = self.aliases.iter().find(|(old, _)| *old == name);
+        let name = alias.map_or(name, |(_, current)| *current);
+
+        let found = self.registered.iter().find(|(n, ..)| *n == name);
+        found.map(|(_, type_id, vtable)| (*type_id, *vtable))
+    }
+
+    /// Name of the registered type identified by `type_id`.
+    #[cfg(feature = "serde")]
+    #[inline]
+    pub(crate) fn name_of(&self, type_id: TypeId) -> Option<&'static str> {
+        let found = self.registered.iter().find(|(_, id, _)| *id == type_id);
+        found.map(|(name, ..)| *name)
+    }
+
+    /// Drop elements of types missing from the registry when restoring snapshots, instead of failing.
+    ///
+    /// Their slots are left free, so that handles to other elements stay valid.
+    #[cfg(feature = "serde")]
+    #[inline]
+    #[must_use]
+    pub const fn skip_unknown(mut self) -> Self {
+        self.skip_unknown = true;
+        self
+    }
+
+    /// Tag snapshots taken with this registry with `version`, see [`HatoSnapshot::version`].
+    ///
+    /// [`HatoSnapshot::version`]: crate::HatoSnapshot::version
+    #[cfg(feature = "serde")]
+    #[inline]
+    #[must_use]
+    pub const fn with_version(mut self, version: u32) -> Self {
+        self.version = version;
+        self
+    }
+
+    /// Whether elements of unknown types are dropped on restore.
+    #[cfg(feature = "serde")]
+    #[inline]
+    pub(crate) const fn skips_unknown(&self) -> bool {
+        self.skip_unknown
+    }
+
+    /// Version of the schema described by this registry.
+    #[cfg(feature = "serde")]
+    #[inline]
+    pub(crate) const fn version(&self) -> u32 {
+        self.version
+    }
+
+    /// Any registered type, to stand for arenas whose types are all unknown.
+    #[cfg(feature = "serde")]
+    #[inline]
+    pub(crate) fn any(&self) -> Option<Kind<Trait>> {
+        self.registered
+            .first()
+            .map(|(_, type_id, vtable)| (*type_id, *vtable))
+    }
+}
@@ -0,0 +1,144 @@
+use alloc::collections::BTreeMap;
+
+use core::marker::Unsize;
+use core::ptr::{DynMetadata, Pointee};
+
+use unscrupulous::Unscrupulous;
+
+use crate::{Handle, Hato};
+
+/// Wrapper around [`Hato`] whose handles carry the generation of their slot.
+///
+/// Each slot counts the removals of its elements. Handles embed the count at insertion,
+/// so accesses through handles of removed elements are detected, even once their slot holds
+/// another element. This lifts the [ABA problem](https://en.wikipedia.org/wiki/ABA_problem)
+/// of plain handles, at the cost of a lookup per access. Counters wrap around after
+/// `u32::MAX` removals from the same slot.
+///
+/// ```rust
+/// let mut arena = hato::HatoVersioned::<dyn core::fmt::Debug>::default();
+///
+/// let x = arena.push(5_u8);
+/// assert!(arena.remove(x));
+///
+/// // The slot of `x` is reused, yet its handle stays stale
+/// let y = arena.push(9_u8);
+///
+/// assert!(arena.get(x).is_none());
+/// assert_eq!(format!("{:?}", arena.get(y)), "Some(9)");
+/// ```
+#[derive(Clone, Debug)]
+pub struct HatoVersioned<Trait: ?Sized + Pointee<Metadata = DynMetadata<Trait>>> {
+    hato: Hato<Trait>,
+    generations: BTreeMap<Handle, u32>,
+}
+
+/// Handle to an element of a [`HatoVersioned`], along with the generation of its slot.
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct VersionedHandle {
+    handle: Handle,
+    generation: u32,
+}
+
+impl VersionedHandle {
+    /// Plain handle of the slot, regardless of its generation.
+    #[inline]
+    #[must_use]
+    pub const fn handle(&self) -> Handle {
+        self.handle
+    }
+
+    /// Number of removals from the slot before the element was inserted, modulo `2^32`.
+    #[inline]
+    #[must_use]
+    pub const fn generation(&self) -> u32 {
+        self.generation
+    }
+}
+
+impl<Trait: ?Sized + Pointee<Metadata = DynMetadata<Trait>>> Default for HatoVersioned<Trait> {
+    fn default() -> Self {
+        Self::new(Hato::default())
+    }
+}
+
+impl<Trait: ?Sized + Pointee<Metadata = DynMetadata<Trait>>> HatoVersioned<Trait> {
+    /// Track generations of the slots of `hato`, all starting at zero.
+    #[inline]
+    #[must_use]
+    pub const fn new(hato: Hato<Trait>) -> Self {
+        Self {
+            hato,
+            generations: BTreeMap::new(),
+        }
+    }
+
+    /// Wrapped collection, whose plain handles are subject to the ABA problem.
+    #[inline]
+    #[must_use]
+    pub const fn hato(&self) -> &Hato<Trait> {
+        &self.hato
+    }
+
+    /// Insert `x`, returning a handle to the current generation of its slot.
+    ///
+    /// # Panics
+    ///
+    /// This function will panic if the number of arenas overflows the index type.
+    #[inline]
+    pub fn push<T: Unsize<Trait> + Unscrupulous>(&mut self, x: T) -> VersionedHandle {
+        let handle = self.hato.push(x);
+        let generation = self.generation(handle);
+
+        VersionedHandle { handle, generation }
+    }
+
+    /// Check whether `handle` identifies a live element, rather than a removed one.
+    #[inline]
+    #[must_use]
+    pub fn contains(&self, handle: VersionedHandle) -> bool {
+        // Removals bump the generation, so matching slots hold the element of the handle
+        self.generation(handle.handle) == handle.generation && self.hato.contains(handle.handle)
+    }
+
+    /// Retrieve the element identified by `handle`, unless it was removed.
+    #[inline]
+    #[must_use]
+    pub fn get(&self, handle: VersionedHandle) -> Option<&Trait> {
+        // ! SAFETY: Handle identifies a live element of the wrapped collection
+        self.contains(handle)
+            .then(|| unsafe { self.hato.get(handle.handle) })
+    }
+
+    /// Retrieve the element identified by `handle` mutably, unless it was removed.
+    #[inline]
+    #[must_use]
+    pub fn get_mut(&mut self, handle: VersionedHandle) -> Option<&mut Trait> {
+        self.contains(handle)
+            .then(|| self.hato.get_mut(handle.handle))
+    }
+
+    /// Remove the element identified by `handle`, returning whether it was live.
+    ///
+    /// Its slot moves on to the next generation, so that the handle is stale from now on.
+    #[inline]
+    pub fn remove(&mut self, handle: VersionedHandle) -> bool {
+        if !self.contains(handle) {
+            return false;
+        }
+
+        self.hato.remove(handle.handle);
+
+        let generation = self.generations.entry(handle.handle).or_default();
+        *generation = generation.wrapping_add(1);
+
+        true
+    }
+
+    /// Current generation of the slot of `handle`.
+    #[inline]
+    fn generation(&self, handle: Handle) -> u32 {
+        // Only slots that saw removals are tracked, others are at generation zero
+        self.generations.get(&handle).copied().unwrap_or(0)
+    }
+}
@@ -0,0 +1,58 @@
+use core::ptr::{DynMetadata, Pointee};
+
+use crate::{Handle, Hato};
+
+/// Read access to all elements of a collection but one, borrowed mutably on the side.
+///
+/// Obtained from [`Hato::split_one_mut`], so that an element can read its neighbors
+/// while updating itself, without cloning nor unsafe aliasing.
+#[derive(Clone, Copy, Debug)]
+pub struct ReadOnlyView<'a, Trait: ?Sized + Pointee<Metadata = DynMetadata<Trait>>> {
+    hato: &'a Hato<Trait>,
+    excluded: Handle,
+}
+
+impl<Trait: ?Sized + Pointee<Metadata = DynMetadata<Trait>>> Hato<Trait> {
+    /// Retrieve the element identified by `handle` mutably, along with read access to the others.
+    ///
+    /// ```rust
+    /// let mut arena = hato::Hato::<dyn core::fmt::Debug>::default();
+    ///
+    /// let x = arena.push(1_u8);
+    /// let y = arena.push(2_u8);
+    ///
+    /// let (element, others) = arena.split_one_mut(x);
+    ///
+    /// assert_eq!(format!("{element:?}"), "1");
+    /// assert_eq!(format!("{:?}", unsafe { others.get(y) }.unwrap()), "2");
+    /// assert!(unsafe { others.get(x) }.is_none());
+    /// ```
+    #[inline]
+    #[must_use]
+    pub fn split_one_mut(&mut self, handle: Handle) -> (&mut Trait, ReadOnlyView<'_, Trait>) {
+        let element: *mut Trait = self.get_mut(handle);
+
+        let view = ReadOnlyView {
+            hato: self,
+            excluded: self.forward(handle),
+        };
+
+        // ! SAFETY: Element lives in an arena buffer, out of the directory borrowed by the view,
+        // ! and the view never hands out the excluded element
+        (unsafe { &mut *element }, view)
+    }
+}
+
+impl<'a, Trait: ?Sized + Pointee<Metadata = DynMetadata<Trait>>> ReadOnlyView<'a, Trait> {
+    /// Retrieve the element identified by `handle`, unless it is the one borrowed mutably.
+    ///
+    /// # Safety
+    ///
+    /// The handle must originate from the same instance of `Hato` as the view.
+    #[inline]
+    #[must_use]
+    pub unsafe fn get(&self, handle: Handle) -> Option<&'a Trait> {
+        // ! SAFETY: Caller guarantees the handle comes from the viewed collection
+        (self.hato.forward(handle) != self.excluded).then(|| unsafe { self.hato.get(handle) })
+    }
+}
@@ -0,0 +1,139 @@
+use core::any::type_name;
+use core::marker::Unsize;
+use core::ptr::{DynMetadata, Pointee};
+use std::io::{self, Write};
+
+use unscrupulous::{as_slice_of_bytes, Unscrupulous};
+
+use crate::oplog::Op;
+use crate::{Handle, Hato};
+
+/// Wrapper around [`Hato`] appending every modification to a write-ahead log.
+///
+/// Modifications are encoded as in an [`OpLog`](crate::OpLog), and flushed to the writer
+/// before they apply. After a crash, [`OpLog::decode_intact`](crate::OpLog::decode_intact)
+/// recovers all whole modifications from the log, which replay onto a collection in the same
+/// initial state to rebuild it with identical handles. Writers should persist data on flush
+/// for the log to survive power losses, like a [`File`](std::fs::File) opened to append,
+/// through a wrapper calling [`sync_data`](std::fs::File::sync_data).
+///
+/// ```rust
+/// let mut wal = hato::Wal::new(hato::Hato::<dyn core::fmt::Debug>::default(), Vec::new());
+///
+/// let x = wal.push(1_u8).unwrap();
+/// wal.update(x, |_| {}).unwrap();
+///
+/// let (_, bytes) = wal.into_parts();
+///
+/// let (log, _) = hato::OpLog::decode_intact(&bytes);
+/// let types = hato::Types::default().register::<u8>();
+///
+/// let mut recovered = hato::Hato::<dyn core::fmt::Debug>::default();
+/// assert!(unsafe { log.replay(&mut recovered, &types) });
+/// assert_eq!(format!("{:?}", unsafe { recovered.get(x) }), "1");
+/// ```
+#[derive(Debug)]
+pub struct Wal<Trait: ?Sized + Pointee<Metadata = DynMetadata<Trait>>, W: Write> {
+    hato: Hato<Trait>,
+    writer: W,
+    buffer: Vec<u8>,
+}
+
+impl<Trait: ?Sized + Pointee<Metadata = DynMetadata<Trait>>, W: Write> Wal<Trait, W> {
+    /// Start logging modifications of `hato` to `writer`.
+    ///
+    /// Recovered collections match `hato` if replays start from its current state, either
+    /// by starting from an empty collection and log, or from one recovered from `writer`.
+    #[inline]
+    #[must_use]
+    pub const fn new(hato: Hato<Trait>, writer: W) -> Self {
+        Self {
+            hato,
+            writer,
+            buffer: Vec::new(),
+        }
+    }
+
+    /// Logged collection.
+    #[inline]
+    #[must_use]
+    pub const fn hato(&self) -> &Hato<Trait> {
+        &self.hato
+    }
+
+    /// Writer the log is appended to.
+    #[inline]
+    #[must_use]
+    pub const fn writer(&self) -> &W {
+        &self.writer
+    }
+
+    /// Stop logging, giving back the collection and the writer.
+    #[inline]
+    #[must_use]
+    pub fn into_parts(self) -> (Hato<Trait>, W) {
+        (self.hato, self.writer)
+    }
+
+    /// Log the insertion of `x`, then insert it into the collection.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the log cannot be written, leaving the collection
+    /// untouched.
+    ///
+    /// # Panics
+    ///
+    /// This function will panic if the number of arenas overflows the index type.
+    #[inline]
+    pub fn push<T: Unsize<Trait> + Unscrupulous>(&mut self, x: T) -> io::Result<Handle> {
+        self.append(&Op::Push {
+            type_name: type_name::<T>().to_owned(),
+            bytes: as_slice_of_bytes(&x).to_vec(),
+        })?;
+
+        Ok(self.hato.push(x))
+    }
+
+    /// Log the removal of the element identified by `handle`, then remove it.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the log cannot be written, leaving the collection
+    /// untouched.
+    #[inline]
+    pub fn remove(&mut self, handle: Handle) -> io::Result<()> {
+        self.append(&Op::Remove { handle })?;
+        self.hato.remove(handle);
+
+        Ok(())
+    }
+
+    /// Apply `f` to the element identified by `handle`, then log its bytes.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the log cannot be written, in which case
+    /// the modification of the element is not durable.
+    #[inline]
+    pub fn update<R>(&mut self, handle: Handle, f: impl FnOnce(&mut Trait) -> R) -> io::Result<R> {
+        let output = f(self.hato.get_mut(handle));
+
+        let arena = &self.hato.arenas[handle.index as usize];
+        let bytes = arena.element(handle.offset).to_vec();
+
+        self.append(&Op::Write { handle, bytes })?;
+
+        Ok(output)
+    }
+
+    /// Write `op` at the end of the log in a single call, and flush it.
+    #[inline]
+    fn append(&mut self, op: &Op) -> io::Result<()> {
+        self.buffer.clear();
+        op.encode(&mut self.buffer);
+
+        self.writer.write_all(&self.buffer)?;
+        self.writer.flush()
+    }
+}